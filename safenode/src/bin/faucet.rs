@@ -0,0 +1,291 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode::{
+    client::{CaptchaVerifier, Client, Error as ClientError, Faucet, FaucetClaim, FaucetConfig},
+    log::init_node_logging,
+    network::NetworkContacts,
+    protocol::wallet::{LocalWallet, Wallet},
+};
+
+use async_trait::async_trait;
+use clap::Parser;
+use eyre::{eyre, Result};
+use hyper::{
+    body::HttpBody,
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sn_dbc::{PublicAddress, Token};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    process::Command,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+// The largest body of a claim, which is only an address and a captcha answer.
+const MAX_CLAIM_SIZE: u64 = 4096;
+
+#[derive(Parser, Debug)]
+#[clap(name = "safe faucet")]
+struct Opt {
+    /// The address to serve claims on.
+    #[clap(long, default_value = "127.0.0.1:8000")]
+    listen: SocketAddr,
+
+    /// The dir of the funded wallet to dispense from.
+    #[clap(long)]
+    wallet_dir: PathBuf,
+
+    /// The amount sent for every claim, in tokens.
+    #[clap(long, default_value = "1")]
+    amount: Token,
+
+    /// How many seconds an address has to wait between two claims.
+    #[clap(long, default_value_t = FaucetConfig::default().address_cooldown.as_secs())]
+    address_cooldown: u64,
+
+    /// The number of claims an IP address can make within --ip-window.
+    #[clap(long, default_value_t = FaucetConfig::default().ip_claims)]
+    ip_claims: u32,
+
+    /// The number of seconds over which the claims of an IP address are counted.
+    #[clap(long, default_value_t = FaucetConfig::default().ip_window.as_secs())]
+    ip_window: u64,
+
+    /// Append every claim, dispensed or not, to this json lines file.
+    #[clap(long)]
+    audit_log: Option<PathBuf>,
+
+    /// Check the captcha answer of every claim with this program, which is run with the IP
+    /// address of the claimant and the answer as arguments, and must exit with success.
+    #[clap(long)]
+    captcha_cmd: Option<PathBuf>,
+
+    /// Take the IP address of claimants from the X-Forwarded-For header, when the faucet
+    /// is behind a reverse proxy. Only set this if the proxy sets the header.
+    #[clap(long)]
+    trust_forwarded_for: bool,
+
+    /// Join the private network of the given contacts file.
+    #[clap(long, env = "SAFE_NETWORK_CONTACTS")]
+    network_contacts: Option<PathBuf>,
+
+    /// The dir to write the logs to, instead of stdout.
+    #[clap(long)]
+    log_dir: Option<PathBuf>,
+}
+
+/// A claim, as posted to `/claim`.
+#[derive(Debug, Deserialize)]
+struct ClaimBody {
+    /// The hex encoded public address to send the tokens to.
+    address: String,
+    #[serde(default)]
+    captcha: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Status {
+    address: String,
+    balance: String,
+    amount: String,
+}
+
+// Checks captcha answers by running a program of the operator's choice.
+struct CommandCaptcha {
+    program: PathBuf,
+}
+
+#[async_trait]
+impl CaptchaVerifier for CommandCaptcha {
+    async fn verify(&self, answer: Option<&str>, ip: IpAddr) -> bool {
+        let answer = match answer {
+            Some(answer) => answer.to_string(),
+            None => return false,
+        };
+        let program = self.program.clone();
+        let status = tokio::task::spawn_blocking(move || {
+            Command::new(program)
+                .arg(ip.to_string())
+                .arg(answer)
+                .status()
+        })
+        .await;
+        match status {
+            Ok(Ok(status)) => status.success(),
+            Ok(Err(error)) => {
+                warn!("Could not run the captcha command: {error}");
+                false
+            }
+            Err(error) => {
+                warn!("The captcha command panicked: {error}");
+                false
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opt = Opt::parse();
+    let _log_appender_guard = init_node_logging(&opt.log_dir)?;
+
+    let wallet = LocalWallet::load_from(&opt.wallet_dir).await?;
+    let address = hex::encode(wallet.address().to_bytes());
+    let signer = Arc::new(bls::SecretKey::random());
+    let client = match &opt.network_contacts {
+        Some(path) => Client::private(signer, &NetworkContacts::load(path)?)?,
+        None => Client::with_signer(signer)?,
+    };
+    while !client.is_connected() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    info!("Faucet connected to the network");
+
+    let config = FaucetConfig {
+        amount: opt.amount,
+        address_cooldown: Duration::from_secs(opt.address_cooldown),
+        ip_claims: opt.ip_claims,
+        ip_window: Duration::from_secs(opt.ip_window),
+    };
+    let mut faucet = Faucet::new(client, wallet, config);
+    if let Some(program) = opt.captcha_cmd {
+        faucet = faucet.with_captcha(Arc::new(CommandCaptcha { program }));
+    }
+    if let Some(path) = opt.audit_log {
+        faucet = faucet.with_audit_log(path);
+    }
+    let faucet = Arc::new(faucet);
+    let trust_forwarded_for = opt.trust_forwarded_for;
+    println!(
+        "Faucet of the wallet {address} serving on http://{}",
+        opt.listen
+    );
+
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let faucet = faucet.clone();
+        let address = address.clone();
+        let remote = conn.remote_addr().ip();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let faucet = faucet.clone();
+                let address = address.clone();
+                async move {
+                    let ip = claimant_ip(&req, remote, trust_forwarded_for);
+                    Ok::<_, Infallible>(handle(&faucet, &address, ip, req).await)
+                }
+            }))
+        }
+    });
+    Server::bind(&opt.listen)
+        .serve(make_service)
+        .await
+        .map_err(|err| eyre!("The faucet server failed: {err}"))
+}
+
+async fn handle(
+    faucet: &Faucet<LocalWallet>,
+    address: &str,
+    ip: IpAddr,
+    req: Request<Body>,
+) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/status") => {
+            let status = Status {
+                address: address.to_string(),
+                balance: faucet.balance().await.to_string(),
+                amount: faucet.amount().to_string(),
+            };
+            json(StatusCode::OK, &status)
+        }
+        (&Method::POST, "/claim") => {
+            let claim = match read_claim(req, ip).await {
+                Ok(claim) => claim,
+                Err(error) => return error_response(StatusCode::BAD_REQUEST, &error.to_string()),
+            };
+            match faucet.claim(claim).await {
+                Ok(amount) => json(
+                    StatusCode::OK,
+                    &serde_json::json!({ "amount": amount.to_string() }),
+                ),
+                Err(ClientError::FaucetRateLimited { retry_after }) => {
+                    let mut response = error_response(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        &format!("Too many claims, try again in {}s", retry_after.as_secs()),
+                    );
+                    if let Ok(value) = retry_after.as_secs().to_string().parse() {
+                        let _ = response.headers_mut().insert(RETRY_AFTER, value);
+                    }
+                    response
+                }
+                Err(ClientError::CaptchaRejected) => {
+                    error_response(StatusCode::FORBIDDEN, "The captcha answer is not valid")
+                }
+                Err(error) => {
+                    warn!("Could not dispense a claim: {error}");
+                    error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "The faucet could not send the tokens",
+                    )
+                }
+            }
+        }
+        _ => error_response(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+async fn read_claim(req: Request<Body>, ip: IpAddr) -> Result<FaucetClaim> {
+    match req.body().size_hint().upper() {
+        Some(size) if size <= MAX_CLAIM_SIZE => {}
+        _ => return Err(eyre!("The claim must be at most {MAX_CLAIM_SIZE} bytes")),
+    }
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body: ClaimBody = serde_json::from_slice(&body)?;
+    let public_key = bls::PublicKey::from_hex(body.address.trim())
+        .map_err(|_| eyre!("The address is not a hex encoded public key"))?;
+    Ok(FaucetClaim {
+        to: PublicAddress::new(public_key),
+        ip,
+        captcha: body.captcha,
+    })
+}
+
+// The IP address of the claimant, which is that of the proxy if the faucet is behind one.
+fn claimant_ip(req: &Request<Body>, remote: IpAddr, trust_forwarded_for: bool) -> IpAddr {
+    if !trust_forwarded_for {
+        return remote;
+    }
+    req.headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .and_then(|first| first.trim().parse().ok())
+        .unwrap_or(remote)
+}
+
+fn json<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    if let Ok(value) = "application/json".parse() {
+        let _ = response.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    response
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response<Body> {
+    json(status, &serde_json::json!({ "error": error }))
+}
@@ -7,109 +7,1370 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use safenode::{
-    client::{Client, ClientEvent, Error as ClientError, Files, WalletClient},
+    client::{
+        AuditOutcome, AuditTrail, BenchConfig, ChunkAudit, Client, ClientEvent, CommandSigner,
+        ConsistencyPolicy, ErasureCoding, Error as ClientError, ErrorKind, FileUpload, Files,
+        Journal, Manifest, OperationState, PinList, PinStatus, PolicyFile, PolicyTemplate, Profile,
+        Profiles, RecoveryStore, RetryConfig, RetryPolicy, Signer, SiteChanges, SitePublish,
+        SpendStatus, Telemetry, TrailVerification, WalletClient, DEFAULT_JOURNAL_SIZE,
+        DEFAULT_TELEMETRY_SIZE,
+    },
     log::init_node_logging,
-    protocol::{address::ChunkAddress, wallet::LocalWallet},
+    network::{check_network, NetworkContacts, NetworkId},
+    protocol::{
+        address::{
+            decode_name, encoding_vectors, verify_vectors, AddressBase, ChunkAddress, DbcAddress,
+            EncodingVector,
+        },
+        wallet::{
+            migrate_main_key, verify_detached, CredentialStore, EncryptedFileStore,
+            Error as WalletError, FileStore, KeyringStore, LocalWallet, Wallet,
+        },
+    },
 };
 
-use bytes::Bytes;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use dirs_next::home_dir;
 use eyre::Result;
-use std::{fs, path::PathBuf};
+use libp2p::Multiaddr;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing::info;
-use walkdir::WalkDir;
 use xor_name::XorName;
 
 #[derive(Parser, Debug)]
-#[clap(name = "safeclient cli")]
+#[clap(name = "safeclient cli", after_help = EXIT_CODES_HELP)]
 struct Opt {
+    /// Use the settings of the given profile, or of the one last switched to if not set.
+    /// Settings given on the command line take precedence over those of the profile.
+    #[clap(long, env = "SAFE_PROFILE")]
+    profile: Option<String>,
+
+    /// Create a profile with the given name, from the --client-dir, --key-store, --gateway,
+    /// --network-contacts, --address-base and --json settings given. Each profile gets its own client dir, and so its own
+    /// wallet, unless --client-dir is set.
+    #[clap(long, value_name = "NAME")]
+    profile_create: Option<String>,
+
+    /// List the profiles, marking the one last switched to.
+    #[clap(long)]
+    profile_list: bool,
+
+    /// Use the profile with the given name when no --profile is set.
+    #[clap(long, value_name = "NAME")]
+    profile_switch: Option<String>,
+
     #[clap(long)]
     client_dir: Option<PathBuf>,
 
+    /// The base to print addresses in. Defaults to hex.
+    /// Addresses in any base are accepted as arguments.
+    #[clap(long, value_enum)]
+    address_base: Option<Base>,
+
+    /// Print the given address in the base set by --address-base.
+    #[clap(long, value_name = "ADDRESS")]
+    recode_address: Option<String>,
+
+    /// Print, as json, the corpus of test vectors of how names are written in every base,
+    /// for other implementations to check their encoding against, and exit.
+    #[clap(long)]
+    encoding_vectors: bool,
+
+    /// Check the encoding of this client against the json test vectors in the given file,
+    /// e.g. written by another implementation, and exit.
+    #[clap(long, value_name = "FILE")]
+    verify_encoding_vectors: Option<PathBuf>,
+
     #[clap(long)]
     log_dir: Option<PathBuf>,
 
     #[clap(long)]
     upload_chunks: Option<PathBuf>,
 
+    /// Together with --upload-chunks, also store parity chunks, as `DATA:PARITY`, e.g. `8:2`
+    /// for 2 parity chunks per 8 content chunks. The files stay retrievable as long as no
+    /// more than PARITY chunks of any group are unavailable.
+    #[clap(long, value_name = "DATA:PARITY", value_parser = parse_erasure_coding)]
+    erasure_coding: Option<ErasureCoding>,
+
+    /// Together with --upload-chunks or --recover-resume, the number of files uploaded at once.
+    /// The chunks of all the files share the same limit of chunks in flight.
+    #[clap(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
+
+    /// List the operations that were interrupted, e.g. by a crash, and can be resumed
+    /// with --recover-resume or cleaned up with --recover-discard, and exit.
+    #[clap(long)]
+    recover_list: bool,
+
+    /// Resume the interrupted operation with the given id, as listed by --recover-list.
+    #[clap(long, value_name = "ID", value_parser = parse_operation_id)]
+    recover_resume: Option<u64>,
+
+    /// Drop the record of the interrupted operation with the given id, and exit.
+    #[clap(long, value_name = "ID", value_parser = parse_operation_id)]
+    recover_discard: Option<u64>,
+
     #[clap(long)]
     get_chunk: Option<String>,
 
     #[clap(long)]
     create_register: Option<String>,
 
+    /// Together with --create-register or --publish-site, set who can write to the Register
+    /// created as per the given toml policy file, e.g. `template = "team-write"` and
+    /// `writers = ["<hex encoded public key>"]`. See also --policy-template.
+    #[clap(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
+
+    /// Together with --create-register or --publish-site, set who can write to the Register
+    /// created as per the given template. Only the owner can write unless this is set,
+    /// e.g. to public-append.
+    #[clap(long, value_enum, conflicts_with = "policy")]
+    policy_template: Option<Template>,
+
     #[clap(long)]
     entry: Option<String>,
 
     #[clap(long)]
     query_register: Vec<String>,
+
+    /// Show the owner, permissions, branches and entries of the Register with the given nickname.
+    #[clap(long)]
+    inspect_register: Option<String>,
+
+    /// Show the size and the chunks of the data at the given address, and how many
+    /// data maps lead to them.
+    #[clap(long, value_name = "ADDRESS")]
+    inspect_data: Option<String>,
+
+    /// Together with --inspect-register, --inspect-data or --network-map, print the details as json.
+    #[clap(long)]
+    json: bool,
+
+    /// Print the records of --audit, --archive, --show-site, --show-audit-trail and
+    /// --journal-show as json, one per line, as soon as each is produced, for tools
+    /// such as jq to process them as they come.
+    #[clap(long)]
+    stream: bool,
+
+    /// Estimate the fee for spending a single input dbc of the wallet.
+    /// This is not the cost of storing data, which is not estimated.
+    #[clap(long)]
+    estimate_spend_fees: bool,
+
+    #[clap(long)]
+    check_network: bool,
+
+    /// Show how the records stored on the network are distributed across the xor namespace,
+    /// as reported by the nodes that can be reached.
+    #[clap(long)]
+    network_map: bool,
+
+    /// The number of leading bits of names to bucket the records of the network map by.
+    #[clap(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(0..=8))]
+    network_map_bits: u8,
+
+    /// Check that none of the dbcs in the wallet are implicated in a double spend.
+    #[clap(long)]
+    check_wallet: bool,
+
+    /// Audit the chunks listed in the given file, one hex encoded address per line.
+    #[clap(long)]
+    audit: Option<PathBuf>,
+
+    /// Together with --upload-chunks, write a manifest of the uploaded files and their chunks
+    /// to the given file, signed with the key of the wallet.
+    #[clap(long, requires = "upload_chunks")]
+    manifest: Option<PathBuf>,
+
+    /// Check that the manifest in the given file is validly signed, that its files are
+    /// stored in the chunks it lists, and that those chunks are intact.
+    #[clap(long)]
+    verify_manifest: Option<PathBuf>,
+
+    /// Pin the content with the given hex encoded address, recording the chunks it is stored in.
+    #[clap(long)]
+    pin: Vec<String>,
+
+    /// Unpin the content with the given hex encoded address.
+    #[clap(long)]
+    unpin: Vec<String>,
+
+    /// Check that all pinned content can still be retrieved, and show the status of each pin.
+    #[clap(long)]
+    verify_pins: bool,
+
+    /// Together with --verify-pins, keep verifying the pinned content every given number of seconds.
+    #[clap(long, requires = "verify_pins")]
+    verify_pins_every: Option<u64>,
+
+    /// Store the pin list on the network, so that it can be restored with --restore-pins.
+    #[clap(long)]
+    upload_pins: bool,
+
+    /// Add the pins of the pin list with the given hex encoded address to the local pin list.
+    #[clap(long)]
+    restore_pins: Option<String>,
+
+    /// Get the spend of the dbc with the given hex encoded address.
+    #[clap(long)]
+    get_spend: Option<String>,
+
+    /// Sign the given file with the wallet key, writing the hex encoded signature to <FILE>.sig.
+    #[clap(long)]
+    sign: Option<PathBuf>,
+
+    /// Verify the given file against the detached signature in --signature, by --public-key.
+    #[clap(long, requires_all = ["signature", "public_key"])]
+    verify: Option<PathBuf>,
+
+    /// The file holding the hex encoded signature to verify.
+    #[clap(long)]
+    signature: Option<PathBuf>,
+
+    /// The hex encoded public address that made the signature to verify.
+    #[clap(long)]
+    public_key: Option<String>,
+
+    /// Export the payment receipts of the wallet, as json, to the given file.
+    #[clap(long)]
+    export_receipts: Option<PathBuf>,
+
+    /// Export the wallet, with its key and history, to the given file, as json encrypted
+    /// with the passphrase read from the SAFE_WALLET_EXPORT_PASSPHRASE env var.
+    #[clap(long)]
+    export_wallet: Option<PathBuf>,
+
+    /// Import the wallet exported to the given file with --export-wallet, merging it into
+    /// the wallet in the client dir. The passphrase is read from SAFE_WALLET_EXPORT_PASSPHRASE.
+    #[clap(long)]
+    import_wallet: Option<PathBuf>,
+
+    /// Where the secret key of the wallet is kept.
+    /// The passphrase of the encrypted store is read from the SAFE_KEY_PASSPHRASE env var.
+    /// Defaults to a plain file in the client dir.
+    #[clap(long, value_enum)]
+    key_store: Option<KeyStore>,
+
+    /// Move the secret key of the wallet from the given store to the one set by --key-store.
+    #[clap(long, value_enum)]
+    migrate_keys_from: Option<KeyStore>,
+
+    /// Pack the files of the given directory into an archive, and upload it.
+    #[clap(long)]
+    upload_archive: Option<PathBuf>,
+
+    /// List the files of the archive with the given hex encoded address.
+    #[clap(long)]
+    archive: Option<String>,
+
+    /// Together with --archive, get only the file at the given path in the archive.
+    /// A json file can be followed by a JSON pointer, e.g. `config.json#/servers/0`,
+    /// to get only that part of it.
+    #[clap(long, requires = "archive")]
+    archive_file: Option<String>,
+
+    /// Publish the containers and names given with --site-container, --site-link and
+    /// --site-unlink to the site with the given name, all at once.
+    #[clap(long)]
+    publish_site: Option<String>,
+
+    /// Together with --publish-site, upload the given directory as an archive, as the
+    /// container with the given name. Can be given several times.
+    #[clap(long, value_name = "CONTAINER=DIR", requires = "publish_site")]
+    site_container: Vec<String>,
+
+    /// Together with --publish-site, point the name at the given container.
+    /// Can be given several times.
+    #[clap(long, value_name = "NAME=CONTAINER", requires = "publish_site")]
+    site_link: Vec<String>,
+
+    /// Together with --publish-site, remove the name from the site. Can be given several times.
+    #[clap(long, value_name = "NAME", requires = "publish_site")]
+    site_unlink: Vec<String>,
+
+    /// Together with --publish-site, add, update and remove the names listed in the given
+    /// json changes file, all or none of them. See `SiteChanges` for the format.
+    #[clap(long, value_name = "FILE", requires = "publish_site")]
+    site_changes: Option<PathBuf>,
+
+    /// List the names of the site with the given name, and the archives they point to.
+    #[clap(long)]
+    show_site: Option<String>,
+
+    /// Snapshot the given directory to the network.
+    #[clap(long)]
+    backup: Option<PathBuf>,
+
+    /// Together with --backup, the hex encoded address of the previous snapshot,
+    /// so that unchanged files are not uploaded again.
+    #[clap(long, requires = "backup")]
+    backup_parent: Option<String>,
+
+    /// List the snapshot with the given hex encoded address, and all its ancestors.
+    #[clap(long)]
+    list_backups: Option<String>,
+
+    /// Restore the snapshot with the given hex encoded address, into --restore-to.
+    #[clap(long, requires = "restore_to")]
+    restore: Option<String>,
+
+    /// The directory to restore a snapshot into.
+    #[clap(long)]
+    restore_to: Option<PathBuf>,
+
+    /// Benchmark putting and getting random payloads, printing the results to stdout.
+    #[clap(long)]
+    bench: bool,
+
+    /// The size in bytes of each benchmark payload.
+    #[clap(long, default_value_t = BenchConfig::default().payload_size)]
+    bench_size: usize,
+
+    /// The number of payloads to put, and then get, in the benchmark.
+    #[clap(long, default_value_t = BenchConfig::default().operations)]
+    bench_ops: usize,
+
+    /// The number of benchmark operations in flight at any time.
+    #[clap(long, default_value_t = BenchConfig::default().concurrency)]
+    bench_concurrency: usize,
+
+    /// The format of the benchmark results.
+    #[clap(long, value_enum, default_value_t = BenchFormat::Json)]
+    bench_format: BenchFormat,
+
+    /// Record every operation sent to the network in a journal in the client dir,
+    /// to attach to bug reports.
+    #[clap(long)]
+    journal: bool,
+
+    /// Mirror every mutation to the audit trail of the signer, in Registers only it can write.
+    /// The trail continues across runs only with the same signer, see --signer-cmd.
+    #[clap(long)]
+    audit_trail: bool,
+
+    /// Show the audit trail of the given hex encoded public key, or of the signer if not set,
+    /// and check that no entry is missing or was altered.
+    #[clap(long, value_name = "PUBLIC_KEY", num_args = 0..=1, default_missing_value = "")]
+    show_audit_trail: Option<String>,
+
+    /// The number of most recent operations the journal keeps.
+    #[clap(long, default_value_t = DEFAULT_JOURNAL_SIZE)]
+    journal_size: usize,
+
+    /// Print the given number of most recent operations in the journal, and exit.
+    #[clap(long, value_name = "LAST")]
+    journal_show: Option<usize>,
+
+    /// Record the kind, duration and outcome of every operation sent to the network in the
+    /// client dir, without addresses, keys or times, to share with --telemetry-report.
+    #[clap(long)]
+    telemetry: bool,
+
+    /// Write a json summary of the recorded telemetry to the given file, or print it if not
+    /// set, to attach to reports of performance issues, and exit.
+    #[clap(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+    telemetry_report: Option<PathBuf>,
+
+    /// Sign data ownership proofs with the given external program instead of a local key.
+    /// See `CommandSigner` for the interface the program must provide.
+    #[clap(long)]
+    signer_cmd: Option<PathBuf>,
+
+    /// Run as a light client, reaching the network only through the gateway at this address.
+    /// The address must end with the peer id of the gateway, e.g. `/ip4/1.2.3.4/udp/12000/quic-v1/p2p/<peer id>`.
+    #[clap(long)]
+    gateway: Option<Multiaddr>,
+
+    /// Join the private network of the given contacts file, first connecting to its contacts.
+    /// A client dir is only ever used on the network it was first used on.
+    #[clap(long, env = "SAFE_NETWORK_CONTACTS", conflicts_with = "gateway")]
+    network_contacts: Option<PathBuf>,
+
+    /// The maximum number of times a request is sent when no peer can be reached.
+    #[clap(long, default_value_t = RetryPolicy::default().max_attempts)]
+    max_attempts: u32,
+
+    /// Run from a script: never draw progress on the terminal, and give up connecting to the
+    /// network after --connect-timeout, which defaults to 60 seconds in this mode.
+    #[clap(long)]
+    non_interactive: bool,
+
+    /// The number of seconds to wait for a connection to the network before failing.
+    /// Waits indefinitely by default, unless --non-interactive is set.
+    #[clap(long)]
+    connect_timeout: Option<u64>,
+
+    /// How the answers of the close group to a spend query must agree to be accepted.
+    #[clap(long, value_enum, default_value_t = Consistency::Quorum)]
+    consistency: Consistency,
+
+    /// The number of peers that must agree with `--consistency quorum`.
+    /// Defaults to a majority of the close group.
+    #[clap(long)]
+    quorum: Option<usize>,
+}
+
+/// The places the secret key of the wallet can be kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum KeyStore {
+    /// A plain file in the client dir.
+    File,
+    /// A file in the client dir, encrypted with a passphrase.
+    Encrypted,
+    /// The credential store of the OS.
+    Keyring,
+}
+
+/// The bases addresses can be printed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Base {
+    /// Lowercase hex.
+    Hex,
+    /// Human-oriented base32, as a multibase string.
+    Base32z,
+    /// Bitcoin's base58, as a multibase string.
+    Base58Btc,
+    /// Url-safe base64, as a multibase string.
+    Base64Url,
+}
+
+impl From<Base> for AddressBase {
+    fn from(base: Base) -> Self {
+        match base {
+            Base::Hex => Self::Hex,
+            Base::Base32z => Self::Base32z,
+            Base::Base58Btc => Self::Base58Btc,
+            Base::Base64Url => Self::Base64Url,
+        }
+    }
+}
+
+/// The formats benchmark results can be printed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BenchFormat {
+    /// A json report.
+    Json,
+    /// A csv table, one row per operation.
+    Csv,
+}
+
+/// The consistency policies answers can be accepted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Consistency {
+    /// Every peer asked must return the same answer.
+    AllEqual,
+    /// A quorum of the peers must return the same answer.
+    Quorum,
+    /// The first valid answer is accepted.
+    FirstValid,
+}
+
+/// The templates of the permissions of Registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Template {
+    /// Only the owner can write.
+    Private,
+    /// Anyone can write.
+    PublicAppend,
+    /// Only the owner and the writers of a policy file can write, see --policy.
+    TeamWrite,
+}
+
+impl From<Template> for PolicyTemplate {
+    fn from(template: Template) -> Self {
+        match template {
+            Template::Private => Self::Private,
+            Template::PublicAppend => Self::PublicAppend,
+            Template::TeamWrite => Self::TeamWrite,
+        }
+    }
+}
+
+/// An audit of a chunk, as printed by --audit --stream.
+#[derive(Serialize)]
+struct AuditRecord {
+    address: String,
+    outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    latency_ms: u128,
+}
+
+impl AuditRecord {
+    fn new(audit: &ChunkAudit, base: AddressBase) -> Self {
+        let (outcome, reason) = match &audit.outcome {
+            AuditOutcome::Intact => ("intact", None),
+            AuditOutcome::Corrupted => ("corrupted", None),
+            AuditOutcome::Missing(reason) => ("missing", Some(reason.clone())),
+        };
+        Self {
+            address: base.encode(audit.address.name()),
+            outcome,
+            reason,
+            latency_ms: audit.latency.as_millis(),
+        }
+    }
+}
+
+// Prints the record as a line of json, which is flushed right away, stdout being line buffered.
+fn emit<T: Serialize>(record: &T) -> Result<()> {
+    println!("{}", serde_json::to_string(record)?);
+    Ok(())
+}
+
+/// Env var to read the passphrase of the encrypted key store from.
+const PASSPHRASE_ENV_VAR: &str = "SAFE_KEY_PASSPHRASE";
+/// Env var to read the passphrase of wallet exports from.
+const EXPORT_PASSPHRASE_ENV_VAR: &str = "SAFE_WALLET_EXPORT_PASSPHRASE";
+
+/// How long to wait for a connection to the network with --non-interactive, by default.
+const NON_INTERACTIVE_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  unexpected failure
+  2  invalid input, e.g. bad arguments, or files and keys that cannot be read
+  3  the data asked for was not found on the network
+  4  the network could not be reached, or did not answer in time
+  5  data returned by the network, or a signature, did not verify
+  6  the network refused the operation, e.g. for lack of permissions or fees";
+
+/// The exit codes of the cli, as listed in `EXIT_CODES_HELP`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Exit {
+    Failure = 1,
+    InvalidInput = 2,
+    NotFound = 3,
+    NetworkUnavailable = 4,
+    VerificationFailed = 5,
+    Refused = 6,
+}
+
+/// A failure detected by the cli itself, with the code it exits with.
+#[derive(Debug)]
+struct Failure {
+    exit: Exit,
+    message: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn fail(exit: Exit, message: impl Into<String>) -> eyre::Report {
+    eyre::Report::new(Failure {
+        exit,
+        message: message.into(),
+    })
+}
+
+// The exit code for the error, from the first cause in its chain that tells what went wrong.
+fn exit_code(report: &eyre::Report) -> Exit {
+    for cause in report.chain() {
+        if let Some(failure) = cause.downcast_ref::<Failure>() {
+            return failure.exit;
+        }
+        if let Some(error) = cause.downcast_ref::<ClientError>() {
+            return client_exit(error);
+        }
+        if let Some(error) = cause.downcast_ref::<WalletError>() {
+            return match error {
+                WalletError::FailedToParseBlsKey
+                | WalletError::FailedToDecodeHexToKey
+                | WalletError::FailedToDecryptKey
+                | WalletError::NoKeyToMigrate
+                | WalletError::SameKeyStore
+                | WalletError::InvalidWalletExport(_)
+                | WalletError::WalletKeyMismatch => Exit::InvalidInput,
+                WalletError::InvalidSignature | WalletError::ReceiptSignatureInvalid => {
+                    Exit::VerificationFailed
+                }
+                _ => Exit::Failure,
+            };
+        }
+        if cause.is::<hex::FromHexError>() || cause.is::<std::num::ParseIntError>() {
+            return Exit::InvalidInput;
+        }
+    }
+    Exit::Failure
+}
+
+fn client_exit(error: &ClientError) -> Exit {
+    match error.kind() {
+        ErrorKind::NotFound => Exit::NotFound,
+        ErrorKind::NetworkUnavailable => Exit::NetworkUnavailable,
+        ErrorKind::InvalidInput => Exit::InvalidInput,
+        ErrorKind::VerificationFailed => Exit::VerificationFailed,
+        ErrorKind::Refused => Exit::Refused,
+        ErrorKind::Other => Exit::Failure,
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let opt = Opt::parse();
+    match run(opt).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => {
+            eprintln!("Error: {report:?}");
+            ExitCode::from(exit_code(&report) as u8)
+        }
+    }
+}
+
+async fn run(opt: Opt) -> Result<()> {
     let _log_appender_guard = init_node_logging(&opt.log_dir)?;
 
+    let profiles = Profiles::open(&get_profiles_dir().await?)?;
+    if let Some(name) = &opt.profile_create {
+        let profile = Profile {
+            client_dir: Some(
+                opt.client_dir
+                    .clone()
+                    .unwrap_or_else(|| profiles.dir().join(name)),
+            ),
+            key_store: opt.key_store.and_then(|store| {
+                store
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_string())
+            }),
+            gateway: opt.gateway.as_ref().map(|gateway| gateway.to_string()),
+            network_contacts: opt.network_contacts.clone(),
+            address_base: opt.address_base.and_then(|base| {
+                base.to_possible_value()
+                    .map(|value| value.get_name().to_string())
+            }),
+            json: opt.json.then_some(true),
+        };
+        profiles.create(name, &profile)?;
+        println!("Created the profile {name}.");
+        return Ok(());
+    }
+    if opt.profile_list {
+        let current = profiles.current()?;
+        for name in profiles.list()? {
+            let marker = if Some(&name) == current.as_ref() {
+                "*"
+            } else {
+                " "
+            };
+            println!("{marker} {name}");
+        }
+        return Ok(());
+    }
+    if let Some(name) = &opt.profile_switch {
+        profiles.switch(name)?;
+        println!("Switched to the profile {name}.");
+        return Ok(());
+    }
+    let opt = with_profile(opt, &profiles)?;
+    let base = opt.address_base.map(AddressBase::from).unwrap_or_default();
+
+    if let Some(address) = &opt.recode_address {
+        println!("{}", base.encode(&parse_name(address)?));
+        return Ok(());
+    }
+    if opt.encoding_vectors {
+        println!("{}", serde_json::to_string_pretty(&encoding_vectors())?);
+        return Ok(());
+    }
+    if let Some(path) = &opt.verify_encoding_vectors {
+        let vectors: Vec<EncodingVector> = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| fail(Exit::InvalidInput, format!("{path:?}: {err}")))?;
+        let mismatches = verify_vectors(&vectors);
+        for mismatch in &mismatches {
+            println!("MISMATCH {mismatch}");
+        }
+        if !mismatches.is_empty() {
+            return Err(fail(
+                Exit::VerificationFailed,
+                format!(
+                    "{} of {} vectors do not match",
+                    mismatches.len(),
+                    vectors.len()
+                ),
+            ));
+        }
+        println!("All {} vectors match.", vectors.len());
+        return Ok(());
+    }
+
     info!("Instantiating a SAFE client...");
 
     let client_dir = opt.client_dir.unwrap_or(get_client_dir().await?);
-    let wallet = LocalWallet::load_from(&client_dir).await?;
+    if let Some(last) = opt.journal_show {
+        for entry in Journal::read_last(&client_dir, last)? {
+            if opt.stream {
+                emit(&entry)?;
+                continue;
+            }
+            let outcome = match &entry.error {
+                Some(error) => format!("failed: {error}"),
+                None => format!("ok ({}/{} peers)", entry.succeeded, entry.peers),
+            };
+            println!(
+                "{} {:016x} {:<26} {:>6}ms x{} {} {outcome}",
+                entry.started_at,
+                entry.id,
+                entry.operation,
+                entry.duration_ms,
+                entry.attempts,
+                entry.target
+            );
+        }
+        return Ok(());
+    }
+    if let Some(path) = opt.telemetry_report {
+        let report = serde_json::to_string_pretty(&Telemetry::report(&client_dir)?)?;
+        if path.as_os_str().is_empty() {
+            println!("{report}");
+        } else {
+            fs::write(&path, report)?;
+            println!("Wrote the telemetry report to {path:?}");
+        }
+        return Ok(());
+    }
+
+    let recovery = RecoveryStore::open(&client_dir)?;
+    if let Some(id) = opt.recover_discard {
+        recovery.discard(id)?;
+        println!("Discarded the interrupted operation {id:016x}");
+        return Ok(());
+    }
+    let interrupted = recovery.interrupted()?;
+    if opt.recover_list {
+        for operation in &interrupted {
+            let OperationState::UploadDir { dir, uploaded, .. } = &operation.state;
+            println!(
+                "{:016x} started at {}: upload of {dir:?}, {} files uploaded",
+                operation.id,
+                operation.started_at,
+                uploaded.len()
+            );
+        }
+        return Ok(());
+    }
+    if !interrupted.is_empty() && opt.recover_resume.is_none() {
+        println!(
+            "{} interrupted operations can be resumed or discarded, see --recover-list",
+            interrupted.len()
+        );
+    }
+
+    let key_store_kind = opt.key_store.unwrap_or(KeyStore::File);
+    let mut key_store = credential_store(key_store_kind, &client_dir)?;
+    if let Some(from) = opt.migrate_keys_from {
+        if from == key_store_kind {
+            return Err(fail(
+                Exit::InvalidInput,
+                format!(
+                    "The wallet key is already in the {from:?} store, pick another --key-store"
+                ),
+            ));
+        }
+        migrate_main_key(
+            credential_store(from, &client_dir)?.as_ref(),
+            key_store.as_ref(),
+        )
+        .await?;
+        println!("Moved the wallet key from the {from:?} store to the {key_store_kind:?} store.");
+    } else if key_store_kind == KeyStore::Keyring {
+        if let Err(WalletError::Keyring(error)) = key_store.load().await {
+            println!("The OS keyring is not available ({error}), using the key file instead.");
+            key_store = Box::new(FileStore::new(&client_dir));
+        }
+    }
+    let contacts = opt
+        .network_contacts
+        .as_ref()
+        .map(|path| NetworkContacts::load(path))
+        .transpose()
+        .map_err(|err| fail(Exit::InvalidInput, err.to_string()))?;
+    let network_id = contacts
+        .as_ref()
+        .map(|contacts| contacts.network_id.clone())
+        .unwrap_or(NetworkId::Public);
+    check_network(&client_dir, &network_id).map_err(|err| {
+        fail(
+            Exit::InvalidInput,
+            format!("The client dir {client_dir:?} cannot be used: {err}"),
+        )
+    })?;
 
-    let secret_key = bls::SecretKey::random();
-    let client = Client::new(secret_key)?;
+    if let Some(path) = &opt.import_wallet {
+        let export = fs::read_to_string(path)?;
+        let wallet = LocalWallet::import(
+            &client_dir,
+            key_store.as_ref(),
+            &export,
+            &export_passphrase()?,
+        )
+        .await?;
+        println!(
+            "Imported the wallet from {path:?}, its balance is now {}",
+            wallet.balance()
+        );
+    }
+    let wallet = LocalWallet::load_with(&client_dir, key_store.as_ref()).await?;
+    if let Some(path) = &opt.export_wallet {
+        fs::write(path, wallet.export(&export_passphrase()?)?)?;
+        println!("Exported the wallet to {path:?}, keep it as safe as its passphrase");
+    }
+
+    if let Some(path) = &opt.sign {
+        let signature = wallet.sign(&fs::read(path)?);
+        let mut signature_path = path.clone().into_os_string();
+        signature_path.push(".sig");
+        fs::write(&signature_path, hex::encode(signature.to_bytes()))?;
+        println!("Wrote the signature to {signature_path:?}");
+        println!("Public key: {}", hex::encode(wallet.address().to_bytes()));
+    }
+
+    if let (Some(path), Some(signature_path), Some(public_key)) =
+        (&opt.verify, &opt.signature, &opt.public_key)
+    {
+        let signature = fs::read_to_string(signature_path)?;
+        match verify_detached(&fs::read(path)?, &signature, public_key) {
+            Ok(()) => println!("The signature of {path:?} is valid."),
+            Err(error) => {
+                return Err(fail(
+                    Exit::VerificationFailed,
+                    format!("The signature of {path:?} is not valid: {error}"),
+                ))
+            }
+        }
+    }
+
+    if let Some(path) = &opt.export_receipts {
+        let receipts = wallet.receipts();
+        fs::write(path, serde_json::to_string_pretty(receipts)?)?;
+        println!("Exported {} payment receipts to {path:?}", receipts.len());
+    }
+
+    let signer: Arc<dyn Signer> = match opt.signer_cmd {
+        Some(program) => Arc::new(CommandSigner::new(&program)?),
+        None => Arc::new(bls::SecretKey::random()),
+    };
+    let client = match (opt.gateway, contacts) {
+        (Some(gateway), _) => Client::light(signer, gateway)?,
+        (None, Some(contacts)) => Client::private(signer, &contacts)?,
+        (None, None) => Client::with_signer(signer)?,
+    };
+    let client = client.with_retry_config(RetryConfig {
+        default: RetryPolicy {
+            max_attempts: opt.max_attempts,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let client = client.with_consistency_policy(match opt.consistency {
+        Consistency::AllEqual => ConsistencyPolicy::AllEqual,
+        Consistency::Quorum => opt
+            .quorum
+            .map(ConsistencyPolicy::Quorum)
+            .unwrap_or_default(),
+        Consistency::FirstValid => ConsistencyPolicy::FirstValid,
+    });
+    let client = if opt.journal {
+        client.with_journal(Journal::open(&client_dir, opt.journal_size)?)
+    } else {
+        client
+    };
+    let client = if opt.telemetry {
+        client.with_telemetry(Telemetry::open(&client_dir, DEFAULT_TELEMETRY_SIZE)?)
+    } else {
+        client
+    };
+    let client = if opt.audit_trail {
+        client.with_audit_trail(AuditTrail::new())
+    } else {
+        client
+    };
     let file_api = Files::new(client.clone());
-    let _wallet_client = WalletClient::new(client.clone(), wallet);
+    let wallet_client = WalletClient::new(client.clone(), wallet);
+
+    let bootstrap_start = Instant::now();
+    let connect_timeout = match opt.connect_timeout {
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None if opt.non_interactive => Some(NON_INTERACTIVE_CONNECT_TIMEOUT),
+        None => None,
+    };
+    let render = !opt.non_interactive && std::io::stderr().is_terminal();
+    let waiting = wait_for_network(&client, bootstrap_start, render);
+    match connect_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, waiting).await.map_err(|_| {
+            fail(
+                Exit::NetworkUnavailable,
+                format!("Could not connect to the network within {timeout:?}"),
+            )
+        })?,
+        None => waiting.await,
+    }
+
+    if opt.check_network {
+        println!(
+            "Connected to the network in {}ms, running checks...",
+            bootstrap_start.elapsed().as_millis()
+        );
+        let report = client.check_network().await;
+        print!("{report}");
+        if !report.passed() {
+            return Err(fail(
+                Exit::NetworkUnavailable,
+                "One or more network checks failed",
+            ));
+        }
+    }
 
-    let mut client_events_rx = client.events_channel();
-    if let Ok(event) = client_events_rx.recv().await {
-        match event {
-            ClientEvent::ConnectedToNetwork => {
-                info!("Client connected to the Network");
+    if opt.network_map {
+        let map = client.network_map(opt.network_map_bits).await;
+        if opt.json {
+            println!("{}", serde_json::to_string_pretty(&map)?);
+        } else {
+            print!("{map}");
+        }
+    }
+
+    if opt.bench {
+        let config = BenchConfig {
+            payload_size: opt.bench_size,
+            operations: opt.bench_ops,
+            concurrency: opt.bench_concurrency,
+        };
+        let report = file_api.bench(config).await;
+        match opt.bench_format {
+            BenchFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            BenchFormat::Csv => print!("{}", report.to_csv()),
+        }
+    }
+
+    if opt.estimate_spend_fees {
+        match wallet_client.estimate_spend_fees().await {
+            Ok(estimate) => println!(
+                "Fee per input dbc (from {} quotes): min {}, median {}, max {}",
+                estimate.samples, estimate.min, estimate.median, estimate.max
+            ),
+            Err(error) => {
+                return Err(fail(
+                    Exit::NetworkUnavailable,
+                    format!("Could not estimate spend fees: {error}"),
+                ))
             }
         }
     }
 
-    let mut chunks_to_fetch = Vec::new();
+    if opt.check_wallet {
+        let implicated = wallet_client.implicated_dbcs().await?;
+        for (dbc_id, status) in &implicated {
+            println!("!!! WARNING: dbc {dbc_id:?} is implicated in a DOUBLE SPEND and must not be trusted !!!");
+            if let SpendStatus::Conflicted(a, b) = status {
+                println!(
+                    "    Conflicting spends of {:?}:\n    {a:?}\n    {b:?}",
+                    a.dbc_id()
+                );
+            }
+        }
+        if !implicated.is_empty() {
+            return Err(fail(
+                Exit::VerificationFailed,
+                format!(
+                    "{} dbcs in the wallet are implicated in a double spend",
+                    implicated.len()
+                ),
+            ));
+        }
+        println!("No dbc in the wallet is implicated in a double spend.");
+    }
 
-    if let Some(files_path) = opt.upload_chunks {
-        for entry in WalkDir::new(files_path).into_iter().flatten() {
-            if entry.file_type().is_file() {
-                let file = fs::read(entry.path())?;
-                let bytes = Bytes::from(file);
-                let file_name = entry.file_name();
-
-                info!("Storing file {file_name:?} of {} bytes..", bytes.len());
-                println!("Storing file {file_name:?}.");
-
-                match file_api.upload(bytes).await {
-                    Ok(address) => {
-                        info!("Successfully stored file to {address:?}");
-                        chunks_to_fetch.push(*address.name());
+    if let Some(audit_list) = opt.audit {
+        let mut addresses = Vec::new();
+        for line in fs::read_to_string(audit_list)?.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            addresses.push(ChunkAddress::new(parse_name(line)?));
+        }
+
+        let passed = if opt.stream {
+            let mut passed = true;
+            let mut emitted = Ok(());
+            client
+                .audit_chunks_with(&addresses, |audit| {
+                    passed &= audit.outcome == AuditOutcome::Intact;
+                    if emitted.is_ok() {
+                        emitted = emit(&AuditRecord::new(&audit, base));
+                    }
+                })
+                .await;
+            emitted?;
+            passed
+        } else {
+            println!("Auditing {} chunks...", addresses.len());
+            let report = client.audit_chunks(addresses).await;
+            print!("{report}");
+            report.passed()
+        };
+        if !passed {
+            return Err(fail(
+                Exit::VerificationFailed,
+                "Not all audited chunks are intact",
+            ));
+        }
+    }
+
+    if let Some(path) = &opt.verify_manifest {
+        let manifest: Manifest = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| fail(Exit::InvalidInput, format!("{path:?}: {err}")))?;
+        println!(
+            "Verifying the {} files of the manifest signed by {}...",
+            manifest.files.len(),
+            manifest.signer
+        );
+        let report = client.verify_manifest(&manifest).await?;
+        print!("{}", report.audit);
+        for file in &report.mismatched {
+            println!("{file} is not stored in the chunks of the manifest");
+        }
+        if !report.passed() {
+            return Err(fail(
+                Exit::VerificationFailed,
+                "The content on the network does not match the manifest",
+            ));
+        }
+        println!("All files of the manifest are intact.");
+    }
+
+    if !opt.pin.is_empty()
+        || !opt.unpin.is_empty()
+        || opt.verify_pins
+        || opt.upload_pins
+        || opt.restore_pins.is_some()
+    {
+        let mut pins = PinList::load(&client_dir).await?;
+        if let Some(address) = opt.restore_pins {
+            pins.merge(file_api.get_pins(parse_chunk_address(&address)?).await?);
+            println!("Restored the pins of pin list {address}");
+        }
+        for address in &opt.pin {
+            file_api
+                .pin(&mut pins, parse_chunk_address(address)?)
+                .await?;
+            println!("Pinned {address}");
+        }
+        for address in &opt.unpin {
+            if pins.remove(&parse_chunk_address(address)?).is_none() {
+                println!("{address} was not pinned");
+            }
+        }
+        pins.store(&client_dir).await?;
+
+        if opt.upload_pins {
+            let address = file_api.upload_pins(&pins).await?;
+            println!("Stored the pin list at {}", base.encode(address.name()));
+        }
+
+        if opt.verify_pins {
+            loop {
+                let report = client.verify_pins(&mut pins).await;
+                pins.store(&client_dir).await?;
+                for (address, pin) in pins.iter() {
+                    let address = base.encode(address.name());
+                    match &pin.status {
+                        PinStatus::Unverified => println!("{address} UNVERIFIED"),
+                        PinStatus::Retrievable => println!("{address} RETRIEVABLE"),
+                        PinStatus::Degraded(lost) => println!(
+                            "{address} DEGRADED, {} of {} chunks lost",
+                            lost.len(),
+                            pin.chunks.len()
+                        ),
                     }
-                    Err(error) => {
-                        panic!("Did not store file {file_name:?} to all nodes in the close group! {error}")
+                }
+                match opt.verify_pins_every {
+                    Some(secs) => tokio::time::sleep(Duration::from_secs(secs)).await,
+                    None if !report.passed() => {
+                        return Err(fail(
+                            Exit::NotFound,
+                            "Not all pinned content is retrievable",
+                        ))
                     }
-                };
+                    None => break,
+                }
             }
         }
     }
 
+    if let Some(address_str) = opt.get_spend {
+        let xorname = parse_name(&address_str)?;
+        let address = DbcAddress::new(xorname);
+        match client.get_spend(address).await {
+            Ok(signed_spend) => {
+                println!(
+                    "Spend of dbc {:?} at {}:",
+                    signed_spend.dbc_id(),
+                    base.encode(&xorname)
+                );
+                println!("  source tx hash:      {:?}", signed_spend.src_tx_hash());
+                println!("  destination tx hash: {:?}", signed_spend.dst_tx_hash());
+            }
+            Err(error) => {
+                return Err(fail(
+                    client_exit(&error),
+                    format!("Could not get the spend at {address_str}: {error}"),
+                ))
+            }
+        }
+    }
+
+    if let Some(dir) = opt.upload_archive {
+        let address = file_api.upload_archive(&dir).await?;
+        println!(
+            "Uploaded archive of {dir:?} to {}",
+            base.encode(address.name())
+        );
+    }
+
+    if let Some(address_str) = opt.archive {
+        let archive = file_api
+            .get_archive(parse_chunk_address(&address_str)?)
+            .await?;
+        match opt.archive_file {
+            Some(path) => {
+                let bytes = archive.resolve(&file_api, &path).await?;
+                println!("Got {path:?} of {} bytes from the archive", bytes.len());
+            }
+            None => {
+                for (path, entry) in &archive.entries {
+                    if opt.stream {
+                        emit(&serde_json::json!({ "path": path, "entry": entry }))?;
+                    } else {
+                        println!("{path} ({} bytes)", entry.len);
+                    }
+                }
+            }
+        }
+    }
+
+    let policy = match (&opt.policy, opt.policy_template) {
+        (Some(path), _) => Some(PolicyFile::load(path)?),
+        (None, Some(template)) => Some(PolicyFile::from(PolicyTemplate::from(template))),
+        (None, None) => None,
+    };
+    // Checks the policy before creating anything with it.
+    if let Some(policy) = &policy {
+        let _ = policy.compile(client.signer_pk())?;
+    }
+
+    if let Some(site) = opt.publish_site {
+        let mut publish = SitePublish::new(site.clone());
+        if let Some(policy) = policy.clone() {
+            publish = publish.with_policy(policy);
+        }
+        for container in &opt.site_container {
+            let (container, dir) = split_pair(container, "CONTAINER=DIR")?;
+            publish = publish.stage_container(container, PathBuf::from(dir));
+        }
+        for link in &opt.site_link {
+            let (name, container) = split_pair(link, "NAME=CONTAINER")?;
+            publish = publish.link(name, container);
+        }
+        for name in opt.site_unlink {
+            publish = publish.unlink(name);
+        }
+        if let Some(path) = &opt.site_changes {
+            publish = publish.apply_changes(&SiteChanges::load(path)?)?;
+        }
+        let report = publish.commit(&file_api).await?;
+        println!(
+            "Published the site {site} at {}, {} names changed",
+            base.encode(report.current.name()),
+            report.changed_names.len()
+        );
+        for (container, address) in &report.containers {
+            println!("{container}: {}", base.encode(address.name()));
+        }
+    }
+
+    if let Some(site) = opt.show_site {
+        let site_map = file_api.current_site(&site).await?;
+        for (name, address) in &site_map.names {
+            if opt.stream {
+                emit(&serde_json::json!({ "name": name, "address": base.encode(address.name()) }))?;
+            } else {
+                println!("{name}: {}", base.encode(address.name()));
+            }
+        }
+    }
+
+    if let Some(owner) = &opt.show_audit_trail {
+        let owner = if owner.is_empty() {
+            client.signer_pk()
+        } else {
+            parse_public_key(owner)?
+        };
+        let entries = client.audit_trail(owner).await?;
+        for entry in &entries {
+            if opt.stream {
+                emit(entry)?;
+                continue;
+            }
+            let version = entry
+                .version
+                .map(|version| format!(" version {}", hex::encode(version.0)))
+                .unwrap_or_default();
+            println!(
+                "{:>6} {} {} {:?}{version} hash {}",
+                entry.seq,
+                entry.timestamp,
+                entry.operation,
+                entry.target,
+                hex::encode(entry.hash)
+            );
+        }
+        let verification = TrailVerification::check(&owner, &entries);
+        if opt.stream {
+            eprint!("{verification}");
+        } else {
+            print!("{verification}");
+        }
+        if !verification.is_continuous() {
+            return Err(fail(
+                Exit::VerificationFailed,
+                "The audit trail is not continuous",
+            ));
+        }
+    }
+
+    if let Some(dir) = opt.backup {
+        let parent = opt
+            .backup_parent
+            .map(|parent| parse_chunk_address(&parent))
+            .transpose()?;
+        let (address, snapshot) = file_api.create_snapshot(&dir, parent).await?;
+        let stats = snapshot.stats;
+        println!(
+            "Snapshot of {dir:?} stored at {}: {} files, {} uploaded ({} bytes), {} unchanged, \
+            {} bytes of changed files reused",
+            base.encode(address.name()),
+            stats.files,
+            stats.uploaded_files,
+            stats.uploaded_bytes,
+            stats.reused_files,
+            stats.reused_bytes
+        );
+    }
+
+    if let Some(address) = opt.list_backups {
+        let history = file_api
+            .snapshot_history(parse_chunk_address(&address)?)
+            .await?;
+        for (address, snapshot) in history {
+            println!(
+                "{} created at {}, {} files",
+                base.encode(address.name()),
+                snapshot.created,
+                snapshot.stats.files
+            );
+        }
+    }
+
+    if let (Some(address), Some(target)) = (opt.restore, opt.restore_to) {
+        file_api
+            .restore_snapshot(parse_chunk_address(&address)?, &target)
+            .await?;
+        println!("Restored snapshot {address} to {target:?}");
+    }
+
+    let mut chunks_to_fetch = Vec::new();
+    let mut uploaded_files = BTreeMap::new();
+
+    let mut failed = 0;
+    let mut on_upload = |upload: FileUpload| match upload.result {
+        Ok(address) => {
+            info!("Successfully stored file {} to {address:?}", upload.path);
+            println!(
+                "Stored file {:?} of {} bytes at {} in {}ms",
+                upload.path,
+                upload.size,
+                base.encode(address.name()),
+                upload.elapsed.as_millis()
+            );
+            chunks_to_fetch.push(*address.name());
+            let _ = uploaded_files.insert(upload.path, address);
+        }
+        Err(error) => {
+            println!("Did not store file {:?}: {error}", upload.path);
+            failed += 1;
+        }
+    };
+    if let Some(files_path) = opt.upload_chunks {
+        file_api
+            .upload_dir_recoverable(
+                &recovery,
+                &files_path,
+                usize::from(opt.jobs),
+                opt.erasure_coding,
+                &mut on_upload,
+            )
+            .await?;
+    }
+    if let Some(id) = opt.recover_resume {
+        file_api
+            .resume_operation(&recovery, id, usize::from(opt.jobs), &mut on_upload)
+            .await?;
+    }
+    if failed > 0 {
+        return Err(fail(
+            Exit::Failure,
+            format!(
+                "{failed} files were not stored to all nodes in their close group, \
+                resume the upload with --recover-resume"
+            ),
+        ));
+    }
+
+    if let (Some(path), false) = (&opt.manifest, uploaded_files.is_empty()) {
+        let mut files = BTreeMap::new();
+        for (file, address) in uploaded_files {
+            let _ = files.insert(file, file_api.manifest_entry(address).await?);
+        }
+        let manifest = Manifest::sign(files, wallet_client.wallet())?;
+        fs::write(path, serde_json::to_string_pretty(&manifest)?)?;
+        println!(
+            "Wrote the manifest of {} files to {path:?}, signed by {}",
+            manifest.files.len(),
+            manifest.signer
+        );
+    }
+
     if let Some(input_str) = opt.get_chunk {
         println!("String passed in via get_chunk is {input_str}...");
-        if input_str.len() == 64 {
-            let vec = hex::decode(input_str).expect("Failed to decode xorname!");
-            let mut xorname = XorName::default();
-            xorname.0.copy_from_slice(vec.as_slice());
-            chunks_to_fetch.push(xorname);
-        }
+        chunks_to_fetch.push(parse_name(&input_str)?);
 
         for xorname in chunks_to_fetch.iter() {
             println!("Downloading file {xorname:?}");
             match file_api.read_bytes(ChunkAddress::new(*xorname)).await {
                 Ok(bytes) => info!("Successfully got file {xorname} of {} bytes!", bytes.len()),
                 Err(error) => {
-                    panic!("Did not get file {xorname:?} from the network! {error}")
+                    return Err(fail(
+                        client_exit(&error),
+                        format!("Did not get file {xorname:?} from the network! {error}"),
+                    ))
                 }
             };
         }
@@ -118,16 +1379,31 @@ async fn main() -> Result<()> {
     if let Some(reg_nickname) = opt.create_register {
         let xorname = XorName::from_content(reg_nickname.as_bytes());
         let tag = 3006;
-        println!("Creating Register with '{reg_nickname}' at xorname: {xorname:x} and tag {tag}");
+        println!(
+            "Creating Register with '{reg_nickname}' at xorname: {} and tag {tag}",
+            base.encode(&xorname)
+        );
 
-        let mut reg_replica = match client.create_register(xorname, tag).await {
+        let created = match &policy {
+            Some(policy) => {
+                let permissions = policy.compile(client.signer_pk())?;
+                client
+                    .create_register_with_permissions(xorname, tag, permissions)
+                    .await
+            }
+            None => client.create_register(xorname, tag).await,
+        };
+        let mut reg_replica = match created {
             Ok(replica) => {
                 info!("Successfully created register '{reg_nickname}' at {xorname:?}, {tag}!");
                 replica
             }
-            Err(error) => panic!(
-                "Did not create register '{reg_nickname}' on all nodes in the close group! {error}"
-            ),
+            Err(error) => {
+                return Err(fail(
+                    client_exit(&error),
+                    format!("Did not create register '{reg_nickname}' on all nodes in the close group! {error}"),
+                ))
+            }
         };
 
         if let Some(entry) = opt.entry {
@@ -161,15 +1437,244 @@ async fn main() -> Result<()> {
                     register.tag()
                 ),
                 Err(error) => {
-                    panic!("Did not retrieve Register '{reg_nickname}' from all nodes in the close group! {error}")
+                    return Err(fail(
+                        client_exit(&error),
+                        format!("Did not retrieve Register '{reg_nickname}' from all nodes in the close group! {error}"),
+                    ))
                 }
             }
         }
     }
 
+    if let Some(reg_nickname) = &opt.inspect_register {
+        let xorname = XorName::from_content(reg_nickname.as_bytes());
+        let info = client.register_info(xorname, 3006).await?;
+        if opt.json {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        } else {
+            println!("Register '{reg_nickname}' at {:?}", info.address);
+            println!("Owner: {:?}", info.owner);
+            for (user, permissions) in &info.permissions {
+                println!("Permissions of {user:?}: {permissions:?}");
+            }
+            println!("Entries: {}", info.size);
+            println!("Branches: {}", info.heads.len());
+            for entry in &info.entries {
+                let head = if info.heads.contains(&entry.hash) {
+                    " (head)"
+                } else {
+                    ""
+                };
+                println!(
+                    "  {}{head}: {} bytes, atop {:?}",
+                    entry.hash, entry.size, entry.children
+                );
+            }
+        }
+    }
+
+    if let Some(address) = &opt.inspect_data {
+        let info = file_api.data_info(parse_chunk_address(address)?).await?;
+        if opt.json {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        } else {
+            println!("Data at {}", base.encode(info.address.name()));
+            println!("Size: {} bytes", info.size);
+            println!("Data map levels: {}", info.data_map_levels);
+            println!("Data map chunks: {}", info.data_map_chunks.len());
+            for address in &info.data_map_chunks {
+                println!("  {}", base.encode(address.name()));
+            }
+            println!("Content chunks: {}", info.content_chunks.len());
+            for (address, size) in &info.content_chunks {
+                println!("  {}: {size} bytes", base.encode(address.name()));
+            }
+            if !info.parity_chunks.is_empty() {
+                println!("Parity chunks: {}", info.parity_chunks.len());
+                for address in &info.parity_chunks {
+                    println!("  {}", base.encode(address.name()));
+                }
+            }
+        }
+    }
+
+    let retries = client.retry_stats();
+    if retries != Default::default() {
+        println!(
+            "Retried {} queries, {} cmds and {} spends due to unreachable peers.",
+            retries.queries, retries.cmds, retries.spends
+        );
+    }
+    let rejected = client.verification_stats();
+    if rejected != Default::default() {
+        println!(
+            "Rejected {} chunks, {} registers and {} spends returned by the network, \
+            as they did not verify.",
+            rejected.chunks, rejected.registers, rejected.spends
+        );
+    }
+
     Ok(())
 }
 
+fn parse_chunk_address(address: &str) -> Result<ChunkAddress> {
+    Ok(ChunkAddress::new(parse_name(address)?))
+}
+
+fn parse_erasure_coding(value: &str) -> Result<ErasureCoding, String> {
+    let (data, parity) = value
+        .split_once(':')
+        .ok_or_else(|| format!("{value:?} is not of the form DATA:PARITY"))?;
+    let parse = |shards: &str| {
+        shards
+            .parse::<usize>()
+            .map_err(|err| format!("{shards:?} is not a number of chunks: {err}"))
+    };
+    ErasureCoding::new(parse(data)?, parse(parity)?).map_err(|err| err.to_string())
+}
+
+fn parse_operation_id(value: &str) -> Result<u64, String> {
+    u64::from_str_radix(value, 16).map_err(|err| format!("{value:?} is not an operation id: {err}"))
+}
+
+// Splits a `KEY=VALUE` argument.
+fn split_pair<'a>(arg: &'a str, form: &str) -> Result<(&'a str, &'a str)> {
+    arg.split_once('=').ok_or_else(|| {
+        fail(
+            Exit::InvalidInput,
+            format!("{arg:?} is not of the form {form}"),
+        )
+    })
+}
+
+fn parse_public_key(hex_key: &str) -> Result<bls::PublicKey> {
+    hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|bytes| <[u8; bls::PK_SIZE]>::try_from(bytes.as_slice()).ok())
+        .and_then(|bytes| bls::PublicKey::from_bytes(bytes).ok())
+        .ok_or_else(|| {
+            fail(
+                Exit::InvalidInput,
+                format!("{hex_key:?} is not a hex encoded public key"),
+            )
+        })
+}
+
+fn parse_name(address: &str) -> Result<XorName> {
+    decode_name(address).map_err(|error| fail(Exit::InvalidInput, error.to_string()))
+}
+
+fn export_passphrase() -> Result<String> {
+    env::var(EXPORT_PASSPHRASE_ENV_VAR).map_err(|_| {
+        fail(
+            Exit::InvalidInput,
+            format!("{EXPORT_PASSPHRASE_ENV_VAR} must be set to export or import a wallet"),
+        )
+    })
+}
+
+fn credential_store(kind: KeyStore, client_dir: &Path) -> Result<Box<dyn CredentialStore>> {
+    Ok(match kind {
+        KeyStore::File => Box::new(FileStore::new(client_dir)),
+        KeyStore::Encrypted => {
+            let passphrase = env::var(PASSPHRASE_ENV_VAR).map_err(|_| {
+                fail(
+                    Exit::InvalidInput,
+                    format!("{PASSPHRASE_ENV_VAR} must be set to use the encrypted key store"),
+                )
+            })?;
+            Box::new(EncryptedFileStore::new(client_dir, passphrase))
+        }
+        KeyStore::Keyring => Box::new(KeyringStore::new(client_dir)),
+    })
+}
+
+// Waits until the client is connected to the network, rendering the progress
+// on stderr, with a spinner and the time taken so far, if `render` is set.
+async fn wait_for_network(client: &Client, start: Instant, render: bool) {
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+    let mut events = client.events_channel();
+    let mut ticks = tokio::time::interval(Duration::from_millis(100));
+    let mut status = "waiting for peers".to_string();
+    let mut frame = 0;
+    while !client.is_connected() {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            event = events.recv() => match event {
+                Ok(ClientEvent::Bootstrap(progress)) => {
+                    info!("Bootstrap progress: {progress}");
+                    status = progress.to_string();
+                }
+                Ok(ClientEvent::ConnectedToNetwork) | Err(_) => {}
+            }
+        }
+        if render {
+            frame = (frame + 1) % SPINNER.len();
+            let elapsed = start.elapsed().as_millis();
+            eprint!("\r{} [{elapsed:>6}ms] {status:<72.72}", SPINNER[frame]);
+        }
+    }
+    info!("Client connected to the Network");
+    if render {
+        let elapsed = start.elapsed().as_millis();
+        eprintln!("\rConnected to the network in {elapsed}ms{:<72}", "");
+    }
+}
+
+// Takes the settings not given on the command line from the named profile, if any.
+fn with_profile(mut opt: Opt, profiles: &Profiles) -> Result<Opt> {
+    let name = match opt.profile.clone() {
+        Some(name) => name,
+        None => match profiles.current()? {
+            Some(name) => name,
+            None => return Ok(opt),
+        },
+    };
+    let profile = profiles.load(&name)?;
+    info!("Using the profile {name}");
+
+    if opt.client_dir.is_none() {
+        opt.client_dir = profile.client_dir;
+    }
+    if let (None, Some(store)) = (opt.key_store, &profile.key_store) {
+        opt.key_store = Some(KeyStore::from_str(store, true).map_err(|_| {
+            fail(
+                Exit::InvalidInput,
+                format!("The key store {store:?} of the profile {name} is not known"),
+            )
+        })?);
+    }
+    if let (None, Some(gateway)) = (&opt.gateway, &profile.gateway) {
+        opt.gateway = Some(gateway.parse().map_err(|err| {
+            fail(
+                Exit::InvalidInput,
+                format!("The gateway {gateway} of the profile {name} is not valid: {err}"),
+            )
+        })?);
+    }
+    if opt.network_contacts.is_none() && opt.gateway.is_none() {
+        opt.network_contacts = profile.network_contacts;
+    }
+    if let (None, Some(base)) = (opt.address_base, &profile.address_base) {
+        opt.address_base = Some(Base::from_str(base, true).map_err(|_| {
+            fail(
+                Exit::InvalidInput,
+                format!("The address base {base:?} of the profile {name} is not known"),
+            )
+        })?);
+    }
+    opt.json |= profile.json.unwrap_or_default();
+    Ok(opt)
+}
+
+async fn get_profiles_dir() -> Result<PathBuf> {
+    let mut home_dirs = home_dir().expect("A homedir to exist.");
+    home_dirs.push(".safe");
+    home_dirs.push("profiles");
+    tokio::fs::create_dir_all(home_dirs.as_path()).await?;
+    Ok(home_dirs)
+}
+
 async fn get_client_dir() -> Result<PathBuf> {
     let mut home_dirs = home_dir().expect("A homedir to exist.");
     home_dirs.push(".safe");
@@ -8,60 +8,680 @@
 
 use safenode::{
     log::init_node_logging,
-    network::Network,
-    node::{Node, NodeEvent},
+    network::{check_network, Network, NetworkBootstrapBuilder},
+    node::{
+        preflight, CheckStatus, DeletionLog, IdentityLock, MaintenanceWindow, Node, NodeConfig,
+        NodeCtl, NodeEvent, NodeIdentity, PreflightReport,
+    },
 };
 
+#[cfg(unix)]
+use safenode::{
+    node::{
+        check_replication, list_connections, node_lanes, node_metrics, node_rejections,
+        serve_events,
+    },
+    protocol::address::{decode_name, DataAddress, DbcAddress},
+};
+#[cfg(unix)]
+use std::path::Path;
+
 use clap::Parser;
 use eyre::{eyre, Result};
 use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
-use std::{
-    net::{IpAddr, Ipv4Addr, SocketAddr},
-    path::PathBuf,
-    thread, time,
-};
-use tracing::info;
+use std::{env, net::IpAddr, path::PathBuf, time::Duration};
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+// How long to wait for the node to connect to the network before checking it against peers.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let opt = Opt::parse();
-    let _log_appender_guard = init_node_logging(&opt.log_dir)?;
+    let config = effective_config(&opt)?;
+    if opt.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if opt.list_connections {
+        let path = config
+            .events_socket
+            .as_ref()
+            .ok_or_else(|| eyre!("--list-connections needs the --events-socket of the node"))?;
+        print_connections(path).await?;
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if opt.metrics {
+        let path = config
+            .events_socket
+            .as_ref()
+            .ok_or_else(|| eyre!("--metrics needs the --events-socket of the node"))?;
+        print_metrics(path).await?;
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if let Some(address) = opt.check_replication {
+        let path = config
+            .events_socket
+            .as_ref()
+            .ok_or_else(|| eyre!("--check-replication needs the --events-socket of the node"))?;
+        print_replication(path, address).await?;
+        return Ok(());
+    }
+    if let Some(name) = &opt.create_network {
+        create_network(name, &opt)?;
+        return Ok(());
+    }
+    let _log_appender_guard = init_node_logging(&config.log_dir)?;
+
+    let root_dir = config
+        .root_dir
+        .clone()
+        .ok_or_else(|| eyre!("There is no home dir, so --root-dir must be set"))?;
+    if let Some(path) = &opt.export_identity {
+        let identity = NodeIdentity::load_or_create(&root_dir)?;
+        identity.export(path, &identity_passphrase()?)?;
+        println!(
+            "Exported the identity of node {} to {path:?}",
+            identity.peer_id()
+        );
+        return Ok(());
+    }
+    if opt.list_deletions {
+        print_deletions(&DeletionLog::open(&root_dir)?)?;
+        return Ok(());
+    }
+    if let Some(path) = &opt.import_identity {
+        let identity = NodeIdentity::import(path, &identity_passphrase()?)?;
+        let _lock = IdentityLock::acquire(&root_dir)?;
+        identity.store(&root_dir)?;
+        println!(
+            "Imported the identity of node {} into {root_dir:?}",
+            identity.peer_id()
+        );
+        return Ok(());
+    }
 
-    let socket_addr = SocketAddr::new(opt.ip, opt.port);
+    // Refuse to run a second node with the same identity.
+    let _identity_lock = IdentityLock::acquire(&root_dir)?;
+    let identity = NodeIdentity::load_or_create(&root_dir)?;
+    // Refuse to take the data of the node to another network.
+    let contacts = config.network_contacts()?;
+    check_network(&root_dir, &contacts.network_id)?;
+
+    // Fail fast on an environment the node cannot run in.
+    let report = preflight(&root_dir, config.socket_addr());
+    if opt.check {
+        print!("{report}");
+    }
+    log_warnings(&report);
+    if !report.passed() {
+        return Err(eyre!(
+            "The node cannot start, fix the failed checks:\n{report}"
+        ));
+    }
+
+    info!(
+        "Starting node {} on the {} network with {config:?}",
+        identity.peer_id(),
+        contacts.network_id
+    );
+    let (node_events_channel, node_ctl) = Node::run(
+        identity,
+        config.socket_addr(),
+        &contacts,
+        config.rate_limit(),
+        config.query_cache_ttl(),
+        config.ban_config(),
+        config.maintenance(),
+    )
+    .await?;
+
+    if opt.check {
+        let connected = wait_for_connection(node_events_channel.subscribe()).await;
+        if !connected {
+            println!("[FAIL] {:<12} Not connected to the network after {}s. Check that the contacts are reachable from this machine.", "network", CONNECT_TIMEOUT.as_secs());
+            return Err(eyre!("The node failed its checks"));
+        }
+        let report = node_ctl.preflight_network().await;
+        print!("{report}");
+        if !report.passed() {
+            return Err(eyre!("The node failed its checks"));
+        }
+        println!("All checks passed.");
+        return Ok(());
+    }
+
+    // Check the clock against those of the peers once connected, a skewed clock
+    // only being reported, as the node may still be useful to the network.
+    {
+        let node_ctl = node_ctl.clone();
+        let node_events_rx = node_events_channel.subscribe();
+        let _handle = tokio::spawn(async move {
+            if wait_for_connection(node_events_rx).await {
+                let report = node_ctl.preflight_network().await;
+                log_warnings(&report);
+            }
+        });
+    }
+
+    if let Some(prune_interval) = config.prune_interval() {
+        let node_ctl = node_ctl.clone();
+        let log = DeletionLog::open(&root_dir)?;
+        let _handle = tokio::spawn(async move {
+            let mut pruning = tokio::time::interval(prune_interval);
+            // The first tick completes right away, while the node is still joining.
+            let _ = pruning.tick().await;
+            loop {
+                let _ = pruning.tick().await;
+                if let Err(err) = node_ctl.prune(&log).await {
+                    warn!("Could not prune records: {err}");
+                }
+            }
+        });
+    }
+
+    #[cfg(unix)]
+    {
+        let mut hangups = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        let node_ctl = node_ctl.clone();
+        let opt = opt.clone();
+        let _handle = tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                match effective_config(&opt) {
+                    Ok(config) => {
+                        info!("Reloaded the config, now running with {config:?}");
+                        node_ctl.reconfigure(config.rate_limit(), config.ban_config());
+                    }
+                    Err(err) => warn!("Could not reload the config: {err}"),
+                }
+            }
+        });
+    }
 
-    info!("Starting a node...");
-    let node_events_channel = Node::run(socket_addr).await?;
+    #[cfg(unix)]
+    if let Some(path) = config.events_socket.clone() {
+        let node_ctl = node_ctl.clone();
+        let _handle = tokio::spawn(async move {
+            if let Err(err) = serve_events(&path, node_ctl).await {
+                warn!("Stopped serving node events: {err}");
+            }
+        });
+    }
 
+    // Log the events the node runs into, alerting on those that need the operator.
     let mut node_events_rx = node_events_channel.subscribe();
-    if let Ok(event) = node_events_rx.recv().await {
-        match event {
-            NodeEvent::ConnectedToNetwork => {
-                info!("Connected to the Network");
+    let _handle = tokio::spawn(async move {
+        loop {
+            let event = match node_events_rx.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Skipped {skipped} node events, as they came in too fast");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            match event {
+                NodeEvent::ConnectedToNetwork => {
+                    info!("Connected to the Network");
+                }
+                NodeEvent::RegistersMaintained(report) => {
+                    info!("Registers maintained: {report:?}");
+                }
+                NodeEvent::DoubleSpendDetected { new, existing } => {
+                    warn!(
+                        "Double spend detected of dbc {:?}! New: {new:?}. Existing: {existing:?}",
+                        new.dbc_id()
+                    );
+                }
+                NodeEvent::PeerBanned {
+                    peer,
+                    misbehaviour,
+                    duration,
+                } => {
+                    warn!("Banned peer {peer} for {duration:?} due to {misbehaviour:?}");
+                }
+                NodeEvent::ChunkStored(_)
+                | NodeEvent::RegisterStored(_)
+                | NodeEvent::RepublishProgress { .. }
+                | NodeEvent::MaintenanceStarted { .. }
+                | NodeEvent::MaintenanceEnded => {}
             }
         }
-    }
+    });
 
-    // Keep the node running.
-    loop {
-        thread::sleep(time::Duration::from_millis(100));
+    if opt.republish_on_exit {
+        tokio::signal::ctrl_c().await?;
+        let mut node_events_rx = node_events_channel.subscribe();
+        let _progress = tokio::spawn(async move {
+            while let Ok(event) = node_events_rx.recv().await {
+                if let NodeEvent::RepublishProgress { done, total } = event {
+                    if done % 100 == 0 || done == total {
+                        println!("Republished {done}/{total} records");
+                    }
+                }
+            }
+        });
+        republish(&node_ctl, config.republish_rate()).await;
+        return Ok(());
     }
+
+    // Keep the node running until Ctrl-C, releasing the identity lock on the way out.
+    tokio::signal::ctrl_c().await?;
+    Ok(())
 }
 
-#[derive(Parser, Debug)]
+/// Settings are taken from, in order of precedence: flags, env vars, the config file,
+/// and then their defaults. On SIGHUP, the config file is read again, and the new
+/// rate limits and ban settings are applied to the running node.
+#[derive(Parser, Debug, Clone)]
 #[clap(name = "safenode cli")]
 struct Opt {
+    /// The toml config file to read settings from, see `NodeConfig` for the settings.
+    #[clap(long, env = "SAFENODE_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Print the effective config, as toml, and exit.
+    #[clap(long)]
+    print_config: bool,
+
+    /// Create a private network with the given name, writing its genesis key and its
+    /// contacts file to --genesis-dir, and exit. Hand the contacts file out to the nodes
+    /// and clients of the network, and keep the genesis key secret.
+    #[clap(long, requires = "genesis_dir")]
+    create_network: Option<String>,
+
+    /// A peer for the nodes and clients of the network created with --create-network
+    /// to first connect to, its address ending with `/p2p/<peer id>`.
+    #[clap(long, requires = "create_network")]
+    contact: Vec<Multiaddr>,
+
+    /// The dir to write the genesis key and the contacts file of --create-network to.
     #[clap(long)]
+    genesis_dir: Option<PathBuf>,
+
+    /// The contacts file of the private network to join. Defaults to the public network.
+    #[clap(long, env = "SAFENODE_NETWORK_CONTACTS")]
+    network_contacts: Option<PathBuf>,
+
+    /// The network the node must be on, e.g. `public` or the `<name>-<id>` of a private
+    /// network, the node refusing to start with the contacts of any other one.
+    #[clap(long, env = "SAFENODE_NETWORK_ID")]
+    network_id: Option<String>,
+
+    /// The dir the identity of the node is kept in. Defaults to ~/.safe/node.
+    #[clap(long, env = "SAFENODE_ROOT_DIR")]
+    root_dir: Option<PathBuf>,
+
+    /// Write the identity of the node to the given file, and exit.
+    /// The file is encrypted with the passphrase in the SAFENODE_IDENTITY_PASSPHRASE env var.
+    #[clap(long, conflicts_with = "import_identity")]
+    export_identity: Option<PathBuf>,
+
+    /// Replace the identity of the node with the one exported to the given file, and exit.
+    /// The file is decrypted with the passphrase in the SAFENODE_IDENTITY_PASSPHRASE env var.
+    #[clap(long)]
+    import_identity: Option<PathBuf>,
+
+    #[clap(long, env = "SAFENODE_LOG_DIR")]
     log_dir: Option<PathBuf>,
 
     /// Specify specific port to listen on.
     /// Defaults to 0, which means any available port.
-    #[clap(long, default_value_t = 0)]
-    port: u16,
+    #[clap(long, env = "SAFENODE_PORT")]
+    port: Option<u16>,
 
     /// Specify specific IP to listen on.
     /// Defaults to 0.0.0.0, which will bind to all network interfaces.
-    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
-    ip: IpAddr,
+    #[clap(long, env = "SAFENODE_IP")]
+    ip: Option<IpAddr>,
+
+    /// The maximum number of requests any single client can send in a burst.
+    #[clap(long, env = "SAFENODE_MAX_REQUEST_BURST")]
+    max_request_burst: Option<u32>,
+
+    /// The number of requests per second any single client can sustain.
+    #[clap(long, env = "SAFENODE_MAX_REQUESTS_PER_SEC")]
+    max_requests_per_sec: Option<u32>,
+
+    /// The maximum number of requests any single node in the routing table can send in a burst.
+    #[clap(long, env = "SAFENODE_MAX_NODE_REQUEST_BURST")]
+    max_node_request_burst: Option<u32>,
+
+    /// The number of requests per second any single node in the routing table can sustain.
+    #[clap(long, env = "SAFENODE_MAX_NODE_REQUESTS_PER_SEC")]
+    max_node_requests_per_sec: Option<u32>,
+
+    /// The maximum number of requests the clients at any single IP can send in a burst.
+    #[clap(long, env = "SAFENODE_MAX_IP_REQUEST_BURST")]
+    max_ip_request_burst: Option<u32>,
+
+    /// The number of requests per second the clients at any single IP can sustain.
+    #[clap(long, env = "SAFENODE_MAX_IP_REQUESTS_PER_SEC")]
+    max_ip_requests_per_sec: Option<u32>,
+
+    /// How long, in milliseconds, the responses to queries are kept to answer retries.
+    /// Set to 0 to disable the cache. Defaults to 2000.
+    #[clap(long, env = "SAFENODE_QUERY_CACHE_TTL")]
+    query_cache_ttl: Option<u64>,
+
+    /// The total weight of misbehaviours, such as invalid signatures, at which a peer is banned.
+    #[clap(long, env = "SAFENODE_BAN_THRESHOLD")]
+    ban_threshold: Option<u32>,
+
+    /// How long, in seconds, a misbehaving peer is banned for.
+    #[clap(long, env = "SAFENODE_BAN_DURATION")]
+    ban_duration: Option<u64>,
+
+    /// Check the environment of the node, i.e. its root dir, disk space, port, clock and CPU,
+    /// then join the network to check that peers answer and agree with the clock of the
+    /// node, print the results, and exit with an error if any check failed.
+    #[clap(long)]
+    check: bool,
+
+    /// A window in which the node does its heavy upkeep and takes less load, in UTC,
+    /// e.g. `02:00-04:00` every day, or `sun 02:00-04:00` once a week.
+    /// Can be given several times.
+    #[clap(long, value_name = "WINDOW")]
+    maintenance_window: Vec<MaintenanceWindow>,
+
+    /// The share of its usual rate limits the node takes in maintenance windows,
+    /// in percent. Defaults to 50.
+    #[clap(long, env = "SAFENODE_MAINTENANCE_LOAD")]
+    maintenance_load: Option<u8>,
+
+    /// On Ctrl-C, push all records held by this node to their current closest peers
+    /// before exiting, so that retiring the node does not lose any data.
+    #[clap(long)]
+    republish_on_exit: bool,
+
+    /// The number of records per second to push when republishing. Defaults to 50.
+    #[clap(long, env = "SAFENODE_REPUBLISH_RATE")]
+    republish_rate: Option<u32>,
+
+    /// How often, in seconds, to delete the records the node is no longer responsible for,
+    /// once enough closer peers hold them. 0 disables pruning. Defaults to an hour.
+    #[clap(long, env = "SAFENODE_PRUNE_INTERVAL")]
+    prune_interval: Option<u64>,
+
+    /// Print the records of the data the node in --root-dir pruned, checking their
+    /// signatures, and exit.
+    #[clap(long)]
+    list_deletions: bool,
+
+    /// Serve the events of the node to local apps, on a unix socket at the given path.
+    /// A subscriber sends a json `EventFilter` line, and then receives the selected events.
+    #[cfg(unix)]
+    #[clap(long, env = "SAFENODE_EVENTS_SOCKET")]
+    events_socket: Option<PathBuf>,
+
+    /// Print the peers that the node serving events on --events-socket talks to, and exit.
+    #[cfg(unix)]
+    #[clap(long)]
+    list_connections: bool,
+
+    /// Print the latencies of the operations handled by the node serving events on
+    /// --events-socket, per operation and source, the state of its lanes, and the requests it rejected, and exit.
+    #[cfg(unix)]
+    #[clap(long)]
+    metrics: bool,
+
+    /// Have the node serving events on --events-socket check which peers of the close group
+    /// of a record hold it, replicate it to those that do not if it holds it, and print the
+    /// report. The record is given as `chunk:NAME`, `register:NAME:TAG` or `spend:NAME`.
+    #[cfg(unix)]
+    #[clap(long, value_name = "ADDRESS", value_parser = parse_data_address)]
+    check_replication: Option<DataAddress>,
+}
+
+#[cfg(unix)]
+fn parse_data_address(value: &str) -> Result<DataAddress, String> {
+    let name = |name: &str| decode_name(name).map_err(|err| err.to_string());
+    match value.split(':').collect::<Vec<_>>()[..] {
+        ["chunk", chunk] => Ok(DataAddress::chunk(name(chunk)?)),
+        ["register", register, tag] => {
+            let tag = tag
+                .parse()
+                .map_err(|err| format!("{tag:?} is not a register tag: {err}"))?;
+            Ok(DataAddress::register(name(register)?, tag))
+        }
+        ["spend", spend] => Ok(DataAddress::Spend(DbcAddress::new(name(spend)?))),
+        _ => Err(format!(
+            "{value:?} is not of the form chunk:NAME, register:NAME:TAG or spend:NAME"
+        )),
+    }
+}
+
+#[cfg(unix)]
+async fn print_replication(events_socket: &Path, address: DataAddress) -> Result<()> {
+    let report = check_replication(events_socket, address).await?;
+    println!("{address:?}");
+    let this_node = match (report.should_hold, report.holds) {
+        (true, true) => "in the close group, holds it",
+        (true, false) => "in the close group, does not hold it",
+        (false, true) => "not in the close group, holds it",
+        (false, false) => "not in the close group, does not hold it",
+    };
+    println!("this node: {this_node}");
+    for peer in &report.peers {
+        let holds = match peer.holds {
+            _ if report.replicated_to.contains(&peer.peer) => "replicated",
+            Some(true) => "holds",
+            Some(false) => "missing",
+            None => "no answer",
+        };
+        println!("{:<52} {holds}", peer.peer);
+    }
+    let under = if report.is_under_replicated() {
+        ", under-replicated"
+    } else {
+        ""
+    };
+    println!(
+        "{} peers of the close group hold it{under}",
+        report.holders()
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn print_metrics(events_socket: &Path) -> Result<()> {
+    let metrics = node_metrics(events_socket).await?;
+    println!(
+        "{:<14} {:<7} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "operation", "source", "count", "mean (us)", "p50 (us)", "p95 (us)", "p99 (us)"
+    );
+    for summary in metrics {
+        println!(
+            "{:<14} {:<7} {:>10} {:>12} {:>12} {:>12} {:>12}",
+            format!("{:?}", summary.operation),
+            format!("{:?}", summary.source),
+            summary.count,
+            summary.mean_micros,
+            summary.p50_micros,
+            summary.p95_micros,
+            summary.p99_micros
+        );
+    }
+    let lanes = node_lanes(events_socket).await?;
+    println!(
+        "lanes: fast {} queued, {} shed; bulk {} queued, {} shed",
+        lanes.fast_depth, lanes.fast_shed, lanes.bulk_depth, lanes.bulk_shed
+    );
+    let rejections = node_rejections(events_socket).await?;
+    println!(
+        "rejected: {} banned, {} throttled, {} throttled by ip, {} muted",
+        rejections.banned, rejections.throttled, rejections.ip_throttled, rejections.muted
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn print_connections(events_socket: &Path) -> Result<()> {
+    let connections = list_connections(events_socket).await?;
+    println!(
+        "{:<52} {:>9} {:>12} {:>12} {:>7}  {:<40} last error",
+        "peer", "connected", "bytes sent", "bytes recv", "faults", "address"
+    );
+    for info in connections {
+        let connected = match info.connected_secs {
+            Some(secs) => format!("{secs}s"),
+            None => "-".to_string(),
+        };
+        // Deprioritised peers are marked with a '*'.
+        let faults = if info.deprioritised {
+            format!("{}*", info.faults)
+        } else {
+            info.faults.to_string()
+        };
+        println!(
+            "{:<52} {connected:>9} {:>12} {:>12} {faults:>7}  {:<40} {}",
+            info.peer,
+            info.bytes_sent,
+            info.bytes_received,
+            info.address.unwrap_or_else(|| "-".to_string()),
+            info.last_error.unwrap_or_else(|| "-".to_string())
+        );
+    }
+    Ok(())
+}
+
+fn print_deletions(log: &DeletionLog) -> Result<()> {
+    for record in log.records()? {
+        let signature = if record.verify() { "valid" } else { "INVALID" };
+        println!(
+            "{} {:?} pruned by {} with {} holders, signature {signature}",
+            record.pruned_at,
+            record.address,
+            record.node,
+            record.holders.len()
+        );
+    }
+    Ok(())
+}
+
+// Logs the checks that did not pass.
+fn log_warnings(report: &PreflightReport) {
+    for check in &report.checks {
+        match check.status {
+            CheckStatus::Pass => {}
+            CheckStatus::Warn => warn!("Preflight check {}: {}", check.name, check.details),
+            CheckStatus::Fail => error!("Preflight check {} failed: {}", check.name, check.details),
+        }
+    }
+}
+
+// Waits for the node to connect to the network, returning false if it did not in time.
+async fn wait_for_connection(mut node_events_rx: broadcast::Receiver<NodeEvent>) -> bool {
+    let connected = async {
+        loop {
+            match node_events_rx.recv().await {
+                Ok(NodeEvent::ConnectedToNetwork) => return true,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return false,
+            }
+        }
+    };
+    tokio::time::timeout(CONNECT_TIMEOUT, connected)
+        .await
+        .unwrap_or(false)
+}
+
+/// Env var to read the passphrase of exported identities from.
+const IDENTITY_PASSPHRASE_ENV_VAR: &str = "SAFENODE_IDENTITY_PASSPHRASE";
+
+fn identity_passphrase() -> Result<String> {
+    env::var(IDENTITY_PASSPHRASE_ENV_VAR).map_err(|_| {
+        eyre!("{IDENTITY_PASSPHRASE_ENV_VAR} must be set to export or import an identity")
+    })
+}
+
+// Layers the settings of the flags and env vars over those of the config file.
+fn effective_config(opt: &Opt) -> Result<NodeConfig> {
+    let file = match &opt.config {
+        Some(path) => NodeConfig::load(path)?,
+        None => NodeConfig::default(),
+    };
+    let given = NodeConfig {
+        root_dir: opt.root_dir.clone(),
+        ip: opt.ip,
+        port: opt.port,
+        log_dir: opt.log_dir.clone(),
+        max_request_burst: opt.max_request_burst,
+        max_requests_per_sec: opt.max_requests_per_sec,
+        max_node_request_burst: opt.max_node_request_burst,
+        max_node_requests_per_sec: opt.max_node_requests_per_sec,
+        max_ip_request_burst: opt.max_ip_request_burst,
+        max_ip_requests_per_sec: opt.max_ip_requests_per_sec,
+        query_cache_ttl: opt.query_cache_ttl,
+        ban_threshold: opt.ban_threshold,
+        ban_duration: opt.ban_duration,
+        republish_rate: opt.republish_rate,
+        #[cfg(unix)]
+        events_socket: opt.events_socket.clone(),
+        #[cfg(not(unix))]
+        events_socket: None,
+        network_contacts: opt.network_contacts.clone(),
+        network_id: opt.network_id.as_deref().map(str::parse).transpose()?,
+        maintenance_windows: (!opt.maintenance_window.is_empty())
+            .then(|| opt.maintenance_window.clone()),
+        maintenance_load: opt.maintenance_load,
+        prune_interval: opt.prune_interval,
+    };
+    Ok(given.or(file).effective())
+}
+
+fn create_network(name: &str, opt: &Opt) -> Result<()> {
+    let dir = opt
+        .genesis_dir
+        .as_ref()
+        .ok_or_else(|| eyre!("--create-network needs the --genesis-dir to write to"))?;
+    let bundle = opt
+        .contact
+        .iter()
+        .fold(NetworkBootstrapBuilder::new(name), |builder, contact| {
+            builder.contact(contact.clone())
+        })
+        .build()?;
+    bundle.write(dir)?;
+    println!(
+        "Created the {} network in {dir:?}, with {} contacts",
+        bundle.network_id(),
+        bundle.contacts.contacts.len()
+    );
+    Ok(())
+}
+
+async fn republish(node_ctl: &NodeCtl, rate: u32) {
+    println!("Republishing all records before exiting...");
+    let manifest = node_ctl.republish(rate).await;
+
+    for addr in &manifest.chunks {
+        println!("Republished chunk {:x}", addr.name());
+    }
+    for addr in &manifest.registers {
+        println!("Republished register {:x} {}", addr.name(), addr.tag());
+    }
+    for addr in &manifest.failed_chunks {
+        println!("FAILED to republish chunk {:x}", addr.name());
+    }
+    for addr in &manifest.failed_registers {
+        println!(
+            "FAILED to republish register {:x} {}",
+            addr.name(),
+            addr.tag()
+        );
+    }
+    println!(
+        "Republished {} chunks and {} registers, {} chunks and {} registers failed",
+        manifest.chunks.len(),
+        manifest.registers.len(),
+        manifest.failed_chunks.len(),
+        manifest.failed_registers.len()
+    );
 }
 
 // Todo: Implement node bootstrapping to connect to peers from outside the local network
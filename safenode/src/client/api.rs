@@ -8,37 +8,133 @@
 
 use super::{
     error::{Error, Result},
-    Client, ClientEvent, ClientEventsChannel, ClientEventsReceiver, Register, RegisterOffline,
+    journal::is_success,
+    verification::{verify_spend, VerificationCounters},
+    AuditTrail, BootstrapProgress, Client, ClientEvent, ClientEventsChannel, ClientEventsReceiver,
+    ConsistencyPolicy, FeeCache, Journal, JournalEntry, Register, RegisterOffline, RetryConfig,
+    RetryOperation, RetryStats, Signer, SiteCache, SiteCacheStats, SpendStatus, Telemetry,
+    TelemetryRecord, VerificationStats,
 };
 
 use crate::{
-    network::{NetworkEvent, SwarmDriver},
+    network::{Network, NetworkContacts, NetworkEvent, NetworkId, SwarmDriver, CLOSE_GROUP_SIZE},
+    network_transfers::Error as TransferError,
     protocol::{
-        address::ChunkAddress,
+        address::{dbc_address, ChunkAddress, DbcAddress},
         chunk::Chunk,
         error::Error as ProtocolError,
-        messages::{Cmd, CmdResponse, Query, QueryResponse, Request, Response},
+        messages::{Cmd, CmdResponse, Query, QueryResponse, Request, Response, SpendQuery},
+        register::{Permissions, RegisterInfo, User},
     },
 };
 
+use sn_dbc::{DbcTransaction, SignedSpend};
+
 use bls::{PublicKey, SecretKey, Signature};
 use futures::future::select_all;
-use libp2p::PeerId;
-use std::time::Duration;
-use tokio::task::spawn;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::spawn,
+};
 use xor_name::XorName;
 
 impl Client {
     /// Instantiate a new client.
     pub fn new(signer: SecretKey) -> Result<Self> {
+        Self::with_signer(Arc::new(signer))
+    }
+
+    /// Instantiate a new client, which proves ownership of its data with the given signer.
+    #[instrument(skip_all, name = "connect", level = "debug")]
+    pub fn with_signer(signer: Arc<dyn Signer>) -> Result<Self> {
         info!("Starting Kad swarm in client mode...");
-        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new_client()?;
+        Ok(Self::start(
+            signer,
+            SwarmDriver::new_client(&NetworkId::Public)?,
+        ))
+    }
+
+    /// Instantiate a client of the network of the given contacts, which it first connects to.
+    ///
+    /// Peers of any other network, e.g. found on the local network, are not talked to.
+    #[instrument(skip_all, name = "connect", level = "debug")]
+    pub fn private(signer: Arc<dyn Signer>, contacts: &NetworkContacts) -> Result<Self> {
+        let peers = contacts.peers()?;
+        info!(
+            "Starting Kad swarm in client mode, on the {} network...",
+            contacts.network_id
+        );
+        let client = Self::start(signer, SwarmDriver::new_client(&contacts.network_id)?);
+        for (peer_id, address) in peers {
+            let network = client.network.clone();
+            let _dial = spawn(async move {
+                if let Err(err) = network.dial(peer_id, address).await {
+                    warn!("Could not connect to the contact {peer_id:?}: {err}");
+                }
+            });
+        }
+        Ok(client)
+    }
+
+    /// Instantiate a light client, which reaches the network through the given gateway only.
+    ///
+    /// The address of the gateway must end with its peer id, i.e. `/p2p/<peer id>`.
+    /// A light client keeps no routing table of its own: it does not discover peers on the
+    /// local network, and looks up the peers to send each request to through the gateway.
+    /// It is connected as soon as the gateway is, at the cost of trusting the gateway to
+    /// point it to the right peers. The data returned by those peers is verified all the same.
+    #[instrument(skip_all, name = "connect", level = "debug")]
+    pub fn light(signer: Arc<dyn Signer>, gateway: Multiaddr) -> Result<Self> {
+        let mut address = gateway.clone();
+        let peer_id = match address.pop() {
+            Some(Protocol::P2p(hash)) => PeerId::from_multihash(hash).ok(),
+            _ => None,
+        }
+        .ok_or_else(|| Error::InvalidGateway(gateway.to_string()))?;
+
+        info!("Starting Kad swarm in light client mode, through gateway {peer_id:?}...");
+        let client = Self::start(signer, SwarmDriver::new_light_client(&NetworkId::Public)?);
+        let gateway_client = client.clone();
+        let _dial = spawn(async move {
+            match gateway_client.network.dial(peer_id, address).await {
+                Ok(()) => gateway_client.connected_to_network(),
+                Err(err) => error!("Could not connect to the gateway {gateway}: {err}"),
+            }
+        });
+        Ok(client)
+    }
+
+    // Starts the swarm driver and the handling of its events.
+    fn start(
+        signer: Arc<dyn Signer>,
+        (network, mut network_event_receiver, swarm_driver): (
+            Network,
+            mpsc::Receiver<NetworkEvent>,
+            SwarmDriver,
+        ),
+    ) -> Self {
         info!("Client constructed network and swarm_driver");
         let events_channel = ClientEventsChannel::default();
         let client = Self {
             network,
             events_channel,
+            connected: Arc::new(watch::channel(false).0),
             signer,
+            retry_config: Arc::new(RetryConfig::default()),
+            retries: Arc::default(),
+            verifications: Arc::default(),
+            fee_cache: Arc::default(),
+            site_cache: Arc::default(),
+            journal: None,
+            telemetry: None,
+            audit_trail: None,
+            consistency: ConsistencyPolicy::default(),
         };
         let mut client_clone = client.clone();
 
@@ -63,29 +159,141 @@ impl Client {
             }
         });
 
-        Ok(client)
+        client
+    }
+
+    /// Retry failed requests according to the given config, instead of the default one.
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Arc::new(retry_config);
+        self
+    }
+
+    /// Reuse the fees quoted for spending a dbc for the given time, instead of the default one.
+    /// A zero ttl disables the reuse of quoted fees.
+    pub fn with_fee_quote_ttl(mut self, ttl: Duration) -> Self {
+        self.fee_cache = Arc::new(FeeCache::new(ttl));
+        self
+    }
+
+    /// Reuse the site map a site resolves to for at most the given time, instead of the
+    /// default one. A zero ttl disables the reuse of resolved sites.
+    pub fn with_site_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.site_cache = Arc::new(SiteCache::new(ttl));
+        self
+    }
+
+    /// Record every operation sent to the network in the given journal.
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(Arc::new(journal));
+        self
+    }
+
+    /// Record how every operation sent to the network performed in the given telemetry.
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = Some(Arc::new(telemetry));
+        self
+    }
+
+    /// Mirror every mutation acknowledged by the network to the given audit trail,
+    /// owned by the signer of the client.
+    pub fn with_audit_trail(mut self, audit_trail: AuditTrail) -> Self {
+        self.audit_trail = Some(Arc::new(audit_trail));
+        self
+    }
+
+    /// Accept the answers of the peers to queries for signed data as per the given policy,
+    /// instead of requiring a majority of the close group to agree.
+    pub fn with_consistency_policy(mut self, consistency: ConsistencyPolicy) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    pub(crate) fn fee_cache(&self) -> &FeeCache {
+        &self.fee_cache
+    }
+
+    pub(crate) fn site_cache(&self) -> &SiteCache {
+        &self.site_cache
+    }
+
+    /// How often the resolution of sites by this client was answered from its cache.
+    pub fn site_cache_stats(&self) -> SiteCacheStats {
+        self.site_cache.stats()
+    }
+
+    /// The number of retries this client has made so far, per operation.
+    pub fn retry_stats(&self) -> RetryStats {
+        self.retries.stats()
+    }
+
+    /// The number of answers this client has rejected so far as they did not verify,
+    /// per kind of data.
+    pub fn verification_stats(&self) -> VerificationStats {
+        self.verifications.stats()
+    }
+
+    pub(super) fn verifications(&self) -> &VerificationCounters {
+        &self.verifications
     }
 
     fn handle_network_event(&mut self, event: NetworkEvent) -> Result<()> {
         match event {
             // Clients do not handle requests.
             NetworkEvent::RequestReceived { .. } => {}
-            NetworkEvent::PeerAdded => {
-                self.events_channel
-                    .broadcast(ClientEvent::ConnectedToNetwork);
+            NetworkEvent::PeerAdded { routing_table_size } => {
+                self.events_channel.broadcast(ClientEvent::Bootstrap(
+                    BootstrapProgress::PeersKnown {
+                        known: routing_table_size,
+                        needed: CLOSE_GROUP_SIZE,
+                    },
+                ));
+                self.connected_to_network();
             }
+            NetworkEvent::Dialing(peer) => self
+                .events_channel
+                .broadcast(ClientEvent::Bootstrap(BootstrapProgress::Dialing(peer))),
+            NetworkEvent::PeerConnected(peer) => self
+                .events_channel
+                .broadcast(ClientEvent::Bootstrap(BootstrapProgress::Connected(peer))),
+            NetworkEvent::ConnectionFailed { peer, error } => self.events_channel.broadcast(
+                ClientEvent::Bootstrap(BootstrapProgress::ConnectionFailed { peer, error }),
+            ),
         }
 
         Ok(())
     }
 
+    fn connected_to_network(&self) {
+        let _ = self.connected.send_replace(true);
+        self.events_channel
+            .broadcast(ClientEvent::ConnectedToNetwork);
+    }
+
     /// Get the client events channel.
     pub fn events_channel(&self) -> ClientEventsReceiver {
         self.events_channel.subscribe()
     }
 
+    /// Whether the client has connected to the network.
+    pub fn is_connected(&self) -> bool {
+        *self.connected.borrow()
+    }
+
+    /// Waits until the client has connected to the network, returning at once if it already has.
+    ///
+    /// Unlike the events channel, this does not miss a connection made before it was called,
+    /// so tasks handed a clone of the client at any time can wait on it.
+    pub async fn wait_until_connected(&self) {
+        let mut connected = self.connected.subscribe();
+        while !*connected.borrow() {
+            if connected.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
     /// Sign the given data
-    pub fn sign(&self, data: &[u8]) -> Signature {
+    pub fn sign(&self, data: &[u8]) -> Result<Signature> {
         self.signer.sign(data)
     }
 
@@ -100,12 +308,29 @@ impl Client {
         Register::retrieve(self.clone(), xorname, tag).await
     }
 
+    /// Retrieve a Register from the network, and summarise its state for inspection.
+    pub async fn register_info(&self, xorname: XorName, tag: u64) -> Result<RegisterInfo> {
+        Ok(self.get_register(xorname, tag).await?.info())
+    }
+
     /// Create a new Register.
     pub async fn create_register(&self, xorname: XorName, tag: u64) -> Result<Register> {
         info!("Instantiating a new Register replica with name {xorname} and tag {tag}");
         Register::create(self.clone(), xorname, tag).await
     }
 
+    /// Create a new Register, with the given permissions for users other than the client,
+    /// which owns it, e.g. as compiled from a [`PolicyFile`](super::PolicyFile).
+    pub async fn create_register_with_permissions(
+        &self,
+        xorname: XorName,
+        tag: u64,
+        permissions: BTreeMap<User, Permissions>,
+    ) -> Result<Register> {
+        info!("Instantiating a new Register replica with name {xorname} and tag {tag}, with {permissions:?}");
+        Register::create_with_permissions(self.clone(), xorname, tag, permissions).await
+    }
+
     /// Create a new offline Register instance.
     /// It returns a Rgister instance which can be used to apply operations offline,
     /// and publish them all to the network on a ad hoc basis.
@@ -115,6 +340,7 @@ impl Client {
     }
 
     /// Store `Chunk` to its close group.
+    #[instrument(skip_all, level = "debug", fields(address = ?chunk.address(), bytes = chunk.value().len()))]
     pub(super) async fn store_chunk(&self, chunk: Chunk) -> Result<()> {
         info!("Store chunk: {:?}", chunk.address());
         let request = Request::Cmd(Cmd::StoreChunk(chunk));
@@ -145,25 +371,99 @@ impl Client {
     }
 
     /// Retrieve a `Chunk` from the closest peers.
+    #[instrument(skip(self), level = "debug", fields(bytes))]
     pub(super) async fn get_chunk(&self, address: ChunkAddress) -> Result<Chunk> {
         info!("Get chunk: {address:?}");
         let request = Request::Query(Query::GetChunk(address));
         let responses = self.send_to_closest(request).await?;
 
-        // We will return the first chunk we get.
+        // We will return the first chunk we get that is of the address asked for.
+        let mut rejection = None;
         for resp in responses.iter().flatten() {
             if let Response::Query(QueryResponse::GetChunk(Ok(chunk))) = resp {
+                if let Err(err) = self.verifications.chunk(&address, chunk) {
+                    rejection = Some(err);
+                    continue;
+                }
+                let _ = tracing::Span::current().record("bytes", chunk.value().len());
                 return Ok(chunk.clone());
             };
         }
 
         // If no chunk was found, we will return the first error sent to us.
         for resp in responses.iter().flatten() {
-            if let Response::Query(QueryResponse::GetChunk(result)) = resp {
+            if let Response::Query(QueryResponse::GetChunk(Err(err))) = resp {
+                return Err(err.clone().into());
+            };
+        }
+
+        // Otherwise, all chunks returned were rejected, or we check if there were any send errors.
+        if let Some(err) = rejection {
+            return Err(err);
+        }
+        for resp in responses {
+            let _ = resp?;
+        }
+
+        // If there was none of the above, then we had unexpected responses.
+        Err(Error::Protocol(ProtocolError::UnexpectedResponses))
+    }
+
+    /// Retrieve the `SignedSpend` of a dbc from the closest peers to its address.
+    ///
+    /// The spends returned must agree as per the consistency policy of the client, by default
+    /// a majority of the close group, so that a single rogue node cannot pass off a bogus spend.
+    /// Spends are compared by what was spent, and those not signed by the owner of the dbc
+    /// are rejected.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_spend(&self, address: DbcAddress) -> Result<SignedSpend> {
+        info!("Get spend: {address:?}");
+        let request = Request::Query(Query::Spend(SpendQuery::GetDbcSpend(address)));
+        let responses = self.send_to_closest(request).await?;
+
+        // Get all Ok results of the expected response type `GetDbcSpend`.
+        let spends: Vec<_> = responses
+            .iter()
+            .flatten()
+            .filter_map(|resp| {
+                if let Response::Query(QueryResponse::GetDbcSpend(Ok(signed_spend))) = resp {
+                    Some(signed_spend.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut rejection = None;
+        let spends: Vec<_> = spends
+            .into_iter()
+            .filter(|spend| match self.verifications.spend(address, spend) {
+                Ok(()) => true,
+                Err(err) => {
+                    let _ = rejection.get_or_insert(err);
+                    false
+                }
+            })
+            .collect();
+        if let Some(spend) = self
+            .consistency
+            .resolve(responses.len(), spends, |spend| spend.spend.hash())
+        {
+            return Ok(spend);
+        }
+
+        // If the spends did not agree, we will return the first error sent to us.
+        for resp in responses.iter().flatten() {
+            if let Response::Query(QueryResponse::GetDbcSpend(result)) = resp {
                 let _ = result.clone()?;
             };
         }
 
+        // Otherwise, if spends were rejected, the remaining ones could not agree.
+        if let Some(err) = rejection {
+            return Err(err);
+        }
+
         // If there were no success or fail to the expected query,
         // we check if there were any send errors.
         for resp in responses {
@@ -174,14 +474,160 @@ impl Client {
         Err(Error::Protocol(ProtocolError::UnexpectedResponses))
     }
 
+    /// Retrieve the `SignedSpend`s of all the inputs of the given transaction,
+    /// i.e. the spends through which the outputs of that transaction were created.
+    pub async fn get_tx_spends(&self, tx: &DbcTransaction) -> Result<Vec<SignedSpend>> {
+        let mut spends = Vec::with_capacity(tx.inputs.len());
+        for input in &tx.inputs {
+            spends.push(self.get_spend(dbc_address(&input.dbc_id())).await?);
+        }
+        Ok(spends)
+    }
+
+    /// Get the state of the dbc at the given address, according to its close group, whose
+    /// answers must agree as per the consistency policy of the client.
+    ///
+    /// Unlike [`get_spend`](Self::get_spend), a double spend of the dbc is not an error,
+    /// but returned as [`SpendStatus::Conflicted`], once both spends have been verified.
+    pub async fn spend_status(&self, address: DbcAddress) -> Result<SpendStatus> {
+        info!("Get spend status: {address:?}");
+        let request = Request::Query(Query::Spend(SpendQuery::GetDbcSpend(address)));
+        let responses = self.send_to_closest(request).await?;
+
+        let mut spends = Vec::new();
+        let mut not_found = 0;
+        for resp in responses.iter().flatten() {
+            match resp {
+                Response::Query(QueryResponse::GetDbcSpend(Ok(spend))) => {
+                    spends.push(spend.clone());
+                }
+                // A peer could lie about a double spend, so we only trust valid spends.
+                Response::Query(QueryResponse::GetDbcSpend(Err(ProtocolError::Transfers(
+                    TransferError::DoubleSpendAttempt { new, existing },
+                )))) if is_double_spend(address, new, existing) => {
+                    warn!("Double spend of {address:?} reported by the network!");
+                    return Ok(SpendStatus::Conflicted(existing.clone(), new.clone()));
+                }
+                Response::Query(QueryResponse::GetDbcSpend(Err(ProtocolError::Transfers(
+                    TransferError::SpendNotFound(_),
+                )))) => not_found += 1,
+                _ => {}
+            }
+        }
+
+        // Peers that have not yet been notified of a double spend can each return a different spend.
+        let valid_spends: BTreeSet<_> = spends
+            .iter()
+            .filter(|spend| is_valid_spend(address, spend))
+            .collect();
+        if let [a, b, ..] = Vec::from_iter(valid_spends).as_slice() {
+            warn!("Double spend of {address:?} found on the network!");
+            return Ok(SpendStatus::Conflicted(
+                Box::new((*a).clone()),
+                Box::new((*b).clone()),
+            ));
+        }
+
+        let asked = responses.len();
+        let spends = spends
+            .into_iter()
+            .filter(|spend| is_valid_spend(address, spend))
+            .collect();
+        if let Some(spend) = self
+            .consistency
+            .resolve(asked, spends, |spend| spend.spend.hash())
+        {
+            return Ok(SpendStatus::Spent(Box::new(spend)));
+        }
+
+        // The peers that do not know of a spend have to agree as per the policy as well.
+        if self
+            .consistency
+            .resolve(asked, vec![(); not_found], |_| ())
+            .is_some()
+        {
+            return Ok(SpendStatus::Unspent);
+        }
+
+        // If the peers did not agree, we will return the first error sent to us.
+        for resp in responses.iter().flatten() {
+            if let Response::Query(QueryResponse::GetDbcSpend(result)) = resp {
+                let _ = result.clone()?;
+            };
+        }
+
+        // If there were no success or fail to the expected query,
+        // we check if there were any send errors.
+        for resp in responses {
+            let _ = resp?;
+        }
+
+        // If there was none of the above, then we had unexpected responses.
+        Err(Error::Protocol(ProtocolError::UnexpectedResponses))
+    }
+
+    // Sends the request to the closest peers, retrying as per the retry config
+    // for as long as none of them could be reached.
+    #[instrument(skip_all, level = "debug", fields(dst = ?request.dst(), operation = ?RetryOperation::of(&request), attempts))]
     pub(crate) async fn send_to_closest(&self, request: Request) -> Result<Vec<Result<Response>>> {
+        let operation = RetryOperation::of(&request);
+        let policy = *self.retry_config.policy(operation);
+        let (started_at, start) = (SystemTime::now(), Instant::now());
+
+        let mut attempt = 1;
+        loop {
+            let result = self.try_send_to_closest(&request).await;
+            let failed = match &result {
+                Ok(responses) => responses.iter().all(|resp| resp.is_err()),
+                Err(_) => true,
+            };
+            if !failed || attempt >= policy.max_attempts {
+                let _ = tracing::Span::current().record("attempts", attempt);
+                if let Some(journal) = &self.journal {
+                    let entry =
+                        JournalEntry::new(&request, started_at, attempt, start.elapsed(), &result);
+                    if let Err(err) = journal.record(&entry) {
+                        warn!("Could not record {:?} in the journal: {err}", request.dst());
+                    }
+                }
+                if let Some(telemetry) = &self.telemetry {
+                    let record = TelemetryRecord::new(&request, attempt, start.elapsed(), &result);
+                    if let Err(err) = telemetry.record(&record) {
+                        warn!("Could not record an operation in the telemetry: {err}");
+                    }
+                }
+                if let (Some(trail), Ok(responses)) = (&self.audit_trail, &result) {
+                    let acknowledged = responses
+                        .iter()
+                        .any(|resp| matches!(resp, Ok(resp) if is_success(resp)));
+                    if acknowledged {
+                        if let Err(err) = trail.append(self, &request).await {
+                            error!(
+                                "Could not record {:?} in the audit trail: {err}",
+                                request.dst()
+                            );
+                        }
+                    }
+                }
+                return result;
+            }
+
+            let backoff = policy.backoff(attempt);
+            warn!(
+                "Sending {:?} to the closest peers failed on attempt {attempt}, retrying in {backoff:?}",
+                request.dst()
+            );
+            self.retries.increment(operation);
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    async fn try_send_to_closest(&self, request: &Request) -> Result<Vec<Result<Response>>> {
         info!("Sending {:?} to the closest peers.", request.dst());
-        let closest_peers = self
-            .network
-            .client_get_closest_peers(*request.dst().name())
-            .await?;
+        let closest_peers = self.network.client_get_targets(request).await?;
         Ok(self
-            .send_and_get_responses(closest_peers, &request, true)
+            .send_and_get_responses(closest_peers, request, true)
             .await)
     }
 
@@ -227,3 +673,13 @@ impl Client {
         responses
     }
 }
+
+// Whether the spend is of the dbc at the given address, and signed by its owner.
+fn is_valid_spend(address: DbcAddress, spend: &SignedSpend) -> bool {
+    verify_spend(address, spend).is_ok()
+}
+
+// Whether the two spends are different, valid spends of the dbc at the given address.
+fn is_double_spend(address: DbcAddress, a: &SignedSpend, b: &SignedSpend) -> bool {
+    a.spend.hash() != b.spend.hash() && is_valid_spend(address, a) && is_valid_spend(address, b)
+}
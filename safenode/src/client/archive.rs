@@ -0,0 +1,248 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Files,
+};
+
+use crate::protocol::address::ChunkAddress;
+
+use bincode::{deserialize, serialize};
+use bytes::{Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+use walkdir::WalkDir;
+use xor_name::XorName;
+
+/// Maximum size of a single pack. Files are packed together until this size is reached.
+pub(super) const MAX_PACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Where the content of a file is found within the packs of an archive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// The index of the pack holding the content.
+    pub pack: usize,
+    /// The position of the content within the pack.
+    pub offset: usize,
+    /// The length of the content.
+    pub len: usize,
+}
+
+/// A directory packed into a handful of large blobs, so that storing many small
+/// files does not take (at least) one chunk per file.
+///
+/// Files with identical content are only packed once.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Archive {
+    /// The addresses of the uploaded packs.
+    pub packs: Vec<ChunkAddress>,
+    /// The files in the archive, by their path relative to the archived directory.
+    pub entries: BTreeMap<String, ArchiveEntry>,
+}
+
+impl Archive {
+    /// Returns the content of the file at the given path in the archive,
+    /// reading only the part of the pack that holds it.
+    pub async fn read_file(&self, files: &Files, path: &str) -> Result<Bytes> {
+        let entry = self
+            .entries
+            .get(path)
+            .ok_or_else(|| Error::ArchiveEntryNotFound(path.to_string()))?;
+        let address = self
+            .packs
+            .get(entry.pack)
+            .ok_or_else(|| Error::ArchiveEntryNotFound(path.to_string()))?;
+        files.read_from(*address, entry.offset, entry.len).await
+    }
+
+    /// Like [`Archive::read_file`], but a json file can be followed by a
+    /// [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901) fragment, e.g.
+    /// `config.json#/servers/0`, to only return that part of the document, as json.
+    pub async fn resolve(&self, files: &Files, path: &str) -> Result<Bytes> {
+        if self.entries.contains_key(path) {
+            return self.read_file(files, path).await;
+        }
+        match path.split_once('#') {
+            Some((file, pointer)) if self.entries.contains_key(file) => {
+                let content = self.read_file(files, file).await?;
+                select_json(file, &content, pointer)
+            }
+            _ => Err(Error::ArchiveEntryNotFound(path.to_string())),
+        }
+    }
+}
+
+impl Files {
+    /// Packs all files under the given directory, uploads the packs and then the archive
+    /// index, and returns the address of the index.
+    pub async fn upload_archive(&self, dir: &Path) -> Result<ChunkAddress> {
+        let mut files = Vec::new();
+        for entry in WalkDir::new(dir).into_iter().flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = match entry.path().strip_prefix(dir) {
+                Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                Err(_) => continue,
+            };
+            let content = tokio::fs::read(entry.path()).await?;
+            files.push((path, Bytes::from(content)));
+        }
+
+        let (entries, packs) = pack_files(files, MAX_PACK_SIZE);
+        info!(
+            "Uploading archive of {} files in {} packs",
+            entries.len(),
+            packs.len()
+        );
+
+        let mut pack_addresses = Vec::with_capacity(packs.len());
+        for pack in packs {
+            pack_addresses.push(self.upload(pack).await?);
+        }
+
+        let archive = Archive {
+            packs: pack_addresses,
+            entries,
+        };
+        self.upload(Bytes::from(serialize(&archive)?)).await
+    }
+
+    /// Retrieves the index of the archive at the given address.
+    pub async fn get_archive(&self, address: ChunkAddress) -> Result<Archive> {
+        let bytes = self.read_bytes(address).await?;
+        Ok(deserialize(&bytes)?)
+    }
+}
+
+// Packs the files into blobs of at most `max_pack_size` bytes (unless a single file is larger),
+// storing each distinct content only once.
+pub(super) fn pack_files(
+    files: Vec<(String, Bytes)>,
+    max_pack_size: usize,
+) -> (BTreeMap<String, ArchiveEntry>, Vec<Bytes>) {
+    let mut entries = BTreeMap::new();
+    let mut packed: BTreeMap<XorName, ArchiveEntry> = BTreeMap::new();
+    let mut packs = Vec::new();
+    let mut current = BytesMut::new();
+
+    for (path, content) in files {
+        let name = XorName::from_content(&content);
+        if let Some(entry) = packed.get(&name) {
+            let _ = entries.insert(path, *entry);
+            continue;
+        }
+
+        if !current.is_empty() && current.len() + content.len() > max_pack_size {
+            packs.push(current.split().freeze());
+        }
+        let entry = ArchiveEntry {
+            pack: packs.len(),
+            offset: current.len(),
+            len: content.len(),
+        };
+        current.extend_from_slice(&content);
+        let _ = packed.insert(name, entry);
+        let _ = entries.insert(path, entry);
+    }
+
+    if !current.is_empty() {
+        packs.push(current.freeze());
+    }
+
+    (entries, packs)
+}
+
+// Returns the part of the json document the pointer points to.
+fn select_json(file: &str, content: &[u8], pointer: &str) -> Result<Bytes> {
+    if !file.ends_with(".json") {
+        return Err(Error::InvalidJson(format!(
+            "{file} is not a json file, so it cannot be resolved with a pointer"
+        )));
+    }
+    let document: serde_json::Value = serde_json::from_slice(content)
+        .map_err(|err| Error::InvalidJson(format!("{file}: {err}")))?;
+    let selected = document
+        .pointer(pointer)
+        .ok_or_else(|| Error::JsonPointerNotFound(format!("{file}#{pointer}")))?;
+    let bytes =
+        serde_json::to_vec(selected).map_err(|err| Error::InvalidJson(format!("{file}: {err}")))?;
+    Ok(Bytes::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &'static [u8]) -> (String, Bytes) {
+        (path.to_string(), Bytes::from_static(content))
+    }
+
+    #[test]
+    fn files_are_packed_back_to_back() {
+        let (entries, packs) = pack_files(vec![file("a", b"hello"), file("b/c", b"world")], 100);
+
+        assert_eq!(packs, vec![Bytes::from_static(b"helloworld")]);
+        let entry = entries["b/c"];
+        assert_eq!(
+            &packs[entry.pack][entry.offset..entry.offset + entry.len],
+            b"world"
+        );
+    }
+
+    #[test]
+    fn json_pointers_select_part_of_the_document() -> Result<()> {
+        let content = br#"{"servers": [{"port": 12000}, {"port": 13000}]}"#;
+
+        let selected = select_json("config.json", content, "/servers/1")?;
+        assert_eq!(selected, Bytes::from_static(br#"{"port":13000}"#));
+        assert_eq!(
+            select_json("config.json", content, "/servers/0/port")?,
+            Bytes::from_static(b"12000")
+        );
+
+        assert!(matches!(
+            select_json("config.json", content, "/servers/2"),
+            Err(Error::JsonPointerNotFound(_))
+        ));
+        assert!(matches!(
+            select_json("config.txt", content, "/servers"),
+            Err(Error::InvalidJson(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn identical_content_is_packed_once() {
+        let (entries, packs) = pack_files(vec![file("a", b"same"), file("b", b"same")], 100);
+
+        assert_eq!(packs, vec![Bytes::from_static(b"same")]);
+        assert_eq!(entries["a"], entries["b"]);
+    }
+
+    #[test]
+    fn packs_are_split_at_max_size() {
+        let (entries, packs) = pack_files(
+            vec![file("a", b"1234"), file("b", b"5678"), file("c", b"9")],
+            6,
+        );
+
+        assert_eq!(packs.len(), 2);
+        assert_eq!(entries["a"].pack, 0);
+        assert_eq!(
+            entries["b"],
+            ArchiveEntry {
+                pack: 1,
+                offset: 0,
+                len: 4
+            }
+        );
+        assert_eq!(entries["c"].pack, 1);
+    }
+}
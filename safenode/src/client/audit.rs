@@ -0,0 +1,173 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+
+use crate::protocol::{
+    address::ChunkAddress,
+    messages::{Query, QueryResponse, Request, Response},
+};
+
+use futures::future::{join_all, select_all};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+// Maximum number of chunks audited concurrently.
+const AUDIT_BATCH_SIZE: usize = 5;
+// How long to wait for any single peer to respond to an audit query.
+const AUDIT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What an audit found out about a chunk.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditOutcome {
+    /// A copy of the chunk was retrieved, and its content matches its address.
+    Intact,
+    /// Copies of the chunk were retrieved, but the content of none of them matches its address.
+    Corrupted,
+    /// No copy of the chunk could be retrieved.
+    Missing(String),
+}
+
+/// The audit of a single chunk.
+#[derive(Clone, Debug)]
+pub struct ChunkAudit {
+    /// The audited chunk.
+    pub address: ChunkAddress,
+    /// What the audit found.
+    pub outcome: AuditOutcome,
+    /// How long it took until the outcome was known.
+    pub latency: Duration,
+}
+
+/// The audits of a set of chunks, as returned by [`Client::audit_chunks`].
+#[derive(Clone, Debug, Default)]
+pub struct AuditReport {
+    /// One audit per chunk, in the order the chunks were given.
+    pub audits: Vec<ChunkAudit>,
+}
+
+impl AuditReport {
+    /// Returns true if every audited chunk is intact.
+    pub fn passed(&self) -> bool {
+        self.audits
+            .iter()
+            .all(|audit| audit.outcome == AuditOutcome::Intact)
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for audit in &self.audits {
+            let outcome = match &audit.outcome {
+                AuditOutcome::Intact => "INTACT".to_string(),
+                AuditOutcome::Corrupted => "CORRUPTED".to_string(),
+                AuditOutcome::Missing(reason) => format!("MISSING ({reason})"),
+            };
+            writeln!(
+                f,
+                "{:x} {:>6}ms {outcome}",
+                audit.address.name(),
+                audit.latency.as_millis()
+            )?;
+        }
+        let intact = self
+            .audits
+            .iter()
+            .filter(|audit| audit.outcome == AuditOutcome::Intact)
+            .count();
+        writeln!(f, "{intact} of {} chunks intact", self.audits.len())
+    }
+}
+
+impl Client {
+    /// Checks that each of the given chunks can still be retrieved from the network,
+    /// and that the retrieved content matches the chunk's address.
+    ///
+    /// Chunks are audited in small concurrent batches. Each chunk is requested from its
+    /// whole close group at once, and the first intact copy to arrive settles the audit,
+    /// so a single slow or faulty peer does not make a chunk look lost.
+    pub async fn audit_chunks(&self, addresses: Vec<ChunkAddress>) -> AuditReport {
+        let mut audits = Vec::with_capacity(addresses.len());
+        self.audit_chunks_with(&addresses, |audit| audits.push(audit))
+            .await;
+        AuditReport { audits }
+    }
+
+    /// Like [`Client::audit_chunks`], but hands each audit to `on_audit` as soon as its
+    /// batch is done, in the order the chunks were given, instead of collecting them.
+    pub async fn audit_chunks_with(
+        &self,
+        addresses: &[ChunkAddress],
+        mut on_audit: impl FnMut(ChunkAudit),
+    ) {
+        for batch in addresses.chunks(AUDIT_BATCH_SIZE) {
+            let batch_audits =
+                join_all(batch.iter().map(|address| self.audit_chunk(*address))).await;
+            batch_audits.into_iter().for_each(&mut on_audit);
+        }
+    }
+
+    async fn audit_chunk(&self, address: ChunkAddress) -> ChunkAudit {
+        let start = Instant::now();
+        let outcome = self.hedged_chunk_outcome(address).await;
+        debug!("Audit of chunk {address:?}: {outcome:?}");
+        ChunkAudit {
+            address,
+            outcome,
+            latency: start.elapsed(),
+        }
+    }
+
+    async fn hedged_chunk_outcome(&self, address: ChunkAddress) -> AuditOutcome {
+        let peers = match self.network.client_get_closest_peers(*address.name()).await {
+            Ok(peers) => peers,
+            Err(error) => return AuditOutcome::Missing(error.to_string()),
+        };
+
+        let request = Request::Query(Query::GetChunk(address));
+        let mut pending: Vec<_> = peers
+            .into_iter()
+            .map(|peer| {
+                Box::pin(tokio::time::timeout(
+                    AUDIT_RESPONSE_TIMEOUT,
+                    self.network.send_request(request.clone(), peer),
+                ))
+            })
+            .collect();
+
+        let mut corrupted = false;
+        let mut last_error = "no peers to query".to_string();
+        while !pending.is_empty() {
+            let (result, _, remaining) = select_all(pending).await;
+            pending = remaining;
+            match result {
+                Ok(Ok(Response::Query(QueryResponse::GetChunk(Ok(chunk))))) => {
+                    // The address of a chunk is derived from its content on deserialisation.
+                    if chunk.address() == &address {
+                        return AuditOutcome::Intact;
+                    }
+                    corrupted = true;
+                }
+                Ok(Ok(Response::Query(QueryResponse::GetChunk(Err(error))))) => {
+                    last_error = error.to_string();
+                }
+                Ok(Ok(other)) => last_error = format!("unexpected response {other:?}"),
+                Ok(Err(error)) => last_error = error.to_string(),
+                Err(_elapsed) => last_error = "timed out".to_string(),
+            }
+        }
+
+        if corrupted {
+            AuditOutcome::Corrupted
+        } else {
+            AuditOutcome::Missing(last_error)
+        }
+    }
+}
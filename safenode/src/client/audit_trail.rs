@@ -0,0 +1,347 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, journal::operation_name, Client, ErrorKind, Register};
+
+use crate::protocol::{
+    address::DataAddress,
+    authority::PublicKey,
+    messages::{Cmd, RegisterCmd, Request},
+    register::{EntryHash, Permissions, User},
+};
+
+use bincode::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+use xor_name::XorName;
+
+/// The tag of the Registers holding the audit trails of users.
+pub const AUDIT_TRAIL_TAG: u64 = 3008;
+
+// The number of entries of each Register of a trail, which must be under the
+// maximum number of entries of a Register.
+const ENTRIES_PER_SEGMENT: u64 = 1000;
+
+/// A mutation recorded in an audit trail.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TrailEntry {
+    /// The position of the entry in the trail, starting at zero.
+    pub seq: u64,
+    /// The kind of mutation, e.g. `StoreChunk`.
+    pub operation: String,
+    /// The address of the data that was mutated.
+    pub target: DataAddress,
+    /// For Register edits, the hash of the entry written, i.e. the new version of the Register.
+    pub version: Option<EntryHash>,
+    /// The hash of the mutation, as sent to the network.
+    pub hash: XorName,
+    /// When the mutation was acknowledged by the network, in seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The key the mutation was signed with.
+    pub signer: PublicKey,
+    /// The hash of the previous entry of the trail, which chains the entries together.
+    pub previous: Option<XorName>,
+}
+
+impl TrailEntry {
+    /// The hash of the entry, which the next entry of the trail refers to.
+    pub fn hash(&self) -> Result<XorName> {
+        Ok(XorName::from_content(&serialize(self)?))
+    }
+}
+
+/// Whether an audit trail is continuous, as returned by [`Client::verify_audit_trail`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrailVerification {
+    /// The number of entries of the trail.
+    pub entries: usize,
+    /// The positions the trail is broken at, with why, e.g. a missing entry.
+    pub breaks: Vec<(u64, String)>,
+}
+
+impl TrailVerification {
+    /// Checks that the entries of the trail of the given owner, sorted by position,
+    /// chain up and were all made by the owner.
+    pub fn check(owner: &PublicKey, entries: &[TrailEntry]) -> Self {
+        let mut breaks = vec![];
+        let mut previous: Option<&TrailEntry> = None;
+        for entry in entries {
+            let expected_seq = previous.map(|prev| prev.seq + 1).unwrap_or_default();
+            if entry.seq != expected_seq {
+                breaks.push((
+                    entry.seq,
+                    format!(
+                        "expected entry {expected_seq}, some entries are missing or duplicated"
+                    ),
+                ));
+            }
+            let expected_previous = previous.and_then(|prev| prev.hash().ok());
+            if entry.previous != expected_previous {
+                breaks.push((
+                    entry.seq,
+                    "it does not refer to the previous entry".to_string(),
+                ));
+            }
+            if &entry.signer != owner {
+                breaks.push((entry.seq, "it was signed by another key".to_string()));
+            }
+            previous = Some(entry);
+        }
+        Self {
+            entries: entries.len(),
+            breaks,
+        }
+    }
+
+    /// Whether the trail has no missing, reordered or altered entries.
+    pub fn is_continuous(&self) -> bool {
+        self.breaks.is_empty()
+    }
+}
+
+impl fmt::Display for TrailVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_continuous() {
+            return writeln!(f, "The trail of {} entries is continuous.", self.entries);
+        }
+        writeln!(
+            f,
+            "The trail of {} entries is broken in {} places:",
+            self.entries,
+            self.breaks.len()
+        )?;
+        for (seq, reason) in &self.breaks {
+            writeln!(f, "  at entry {seq}: {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An append-only record of the mutations a client made, kept on the network.
+///
+/// Every mutation acknowledged by the network is mirrored to an entry of Registers
+/// owned by the signer of the client, which only it can write to. Each entry refers
+/// to the hash of the previous one, so that a missing or altered entry is detected
+/// by [`Client::verify_audit_trail`]. The trail spans as many Registers as needed,
+/// each holding up to a thousand entries.
+#[derive(Default)]
+pub struct AuditTrail {
+    state: Mutex<Option<TrailState>>,
+}
+
+// The Register the next entry is appended to, and the last entry appended.
+struct TrailState {
+    segment: u64,
+    register: Option<Register>,
+    last: Option<(u64, XorName)>,
+}
+
+impl AuditTrail {
+    /// A trail to be appended to by the client it is given to, see [`Client::with_audit_trail`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the mutation to the trail.
+    ///
+    /// The future is boxed, as appending is itself a mutation sent by the client.
+    pub(super) fn append<'a>(
+        &'a self,
+        client: &'a Client,
+        request: &'a Request,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let cmd = match request {
+                Request::Cmd(cmd) if !is_trail_cmd(cmd) => cmd,
+                _ => return Ok(()),
+            };
+            let mut guard = self.state.lock().await;
+            // The state is only put back once the entry is appended, so that it is
+            // loaded from the network again after a failure.
+            let mut state = match guard.take() {
+                Some(state) => state,
+                None => load_state(client).await?,
+            };
+
+            let seq = state.last.map(|(seq, _)| seq + 1).unwrap_or_default();
+            let entry = TrailEntry {
+                seq,
+                operation: operation_name(request).to_string(),
+                target: request.dst(),
+                version: match cmd {
+                    Cmd::Register(RegisterCmd::Edit(edit)) => {
+                        Some(EntryHash(edit.op.edit.crdt_op.hash()))
+                    }
+                    _ => None,
+                },
+                hash: XorName::from_content(&serialize(cmd)?),
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|since| since.as_secs())
+                    .unwrap_or_default(),
+                signer: client.signer_pk(),
+                previous: state.last.map(|(_, hash)| hash),
+            };
+
+            let segment = seq / ENTRIES_PER_SEGMENT;
+            if state.segment != segment || state.register.is_none() {
+                let permissions = BTreeMap::from([(User::Anyone, Permissions::new(false))]);
+                let register = client
+                    .create_register_with_permissions(
+                        segment_name(&client.signer_pk(), segment),
+                        AUDIT_TRAIL_TAG,
+                        permissions,
+                    )
+                    .await?;
+                state.segment = segment;
+                state.register = Some(register);
+            }
+            if let Some(register) = &mut state.register {
+                register.write_merging_branches(&serialize(&entry)?).await?;
+            }
+            state.last = Some((seq, entry.hash()?));
+            *guard = Some(state);
+            Ok(())
+        })
+    }
+}
+
+impl Client {
+    /// Reads the audit trail of the given user, oldest entry first.
+    pub async fn audit_trail(&self, owner: PublicKey) -> Result<Vec<TrailEntry>> {
+        let mut entries = vec![];
+        let mut segment = 0;
+        while let Some(register) = get_segment(self, &owner, segment).await? {
+            for info in register.info().entries {
+                entries.push(deserialize::<TrailEntry>(register.get(info.hash)?)?);
+            }
+            segment += 1;
+        }
+        entries.sort_by_key(|entry| entry.seq);
+        Ok(entries)
+    }
+
+    /// Reads the audit trail of the given user, and checks that it has no missing,
+    /// reordered or altered entries.
+    pub async fn verify_audit_trail(&self, owner: PublicKey) -> Result<TrailVerification> {
+        Ok(TrailVerification::check(
+            &owner,
+            &self.audit_trail(owner).await?,
+        ))
+    }
+}
+
+// Finds the last segment of the trail of the client, and the last entry in it.
+async fn load_state(client: &Client) -> Result<TrailState> {
+    let owner = client.signer_pk();
+    let mut state = TrailState {
+        segment: 0,
+        register: None,
+        last: None,
+    };
+    while let Some(register) = get_segment(client, &owner, state.segment).await? {
+        let mut heads = vec![];
+        for (_, entry) in register.read() {
+            heads.push(deserialize::<TrailEntry>(&entry)?);
+        }
+        if let Some(last) = heads.into_iter().max_by_key(|entry| entry.seq) {
+            state.last = Some((last.seq, last.hash()?));
+        }
+        state.register = Some(register);
+        state.segment += 1;
+    }
+    // The last segment found, if any, is the one appended to.
+    state.segment = state.segment.saturating_sub(1);
+    Ok(state)
+}
+
+async fn get_segment(client: &Client, owner: &PublicKey, segment: u64) -> Result<Option<Register>> {
+    match client
+        .get_register(segment_name(owner, segment), AUDIT_TRAIL_TAG)
+        .await
+    {
+        Ok(register) => Ok(Some(register)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn segment_name(owner: &PublicKey, segment: u64) -> XorName {
+    let mut content = b"audit-trail".to_vec();
+    content.extend_from_slice(&owner.to_bytes());
+    content.extend_from_slice(&segment.to_le_bytes());
+    XorName::from_content(&content)
+}
+
+// Whether the cmd writes to a trail, which is not itself recorded in the trail.
+fn is_trail_cmd(cmd: &Cmd) -> bool {
+    matches!(cmd, Cmd::Register(cmd) if cmd.dst().tag() == AUDIT_TRAIL_TAG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::address::ChunkAddress;
+
+    fn trail(owner: &PublicKey, length: u64) -> Vec<TrailEntry> {
+        let mut entries: Vec<TrailEntry> = vec![];
+        for seq in 0..length {
+            let previous = entries.last().map(|entry| entry.hash().expect("hash"));
+            entries.push(TrailEntry {
+                seq,
+                operation: "StoreChunk".to_string(),
+                target: DataAddress::Chunk(ChunkAddress::new(XorName::random(
+                    &mut rand::thread_rng(),
+                ))),
+                version: None,
+                hash: XorName::random(&mut rand::thread_rng()),
+                timestamp: seq,
+                signer: *owner,
+                previous,
+            });
+        }
+        entries
+    }
+
+    #[test]
+    fn missing_and_altered_entries_break_the_trail() {
+        let owner = bls::SecretKey::random().public_key();
+        let entries = trail(&owner, 5);
+        assert!(TrailVerification::check(&owner, &entries).is_continuous());
+
+        let mut missing = entries.clone();
+        let _ = missing.remove(2);
+        let verification = TrailVerification::check(&owner, &missing);
+        assert_eq!(verification.entries, 4);
+        assert_eq!(
+            verification
+                .breaks
+                .iter()
+                .map(|(seq, _)| *seq)
+                .collect::<Vec<_>>(),
+            vec![3, 3]
+        );
+
+        let mut altered = entries.clone();
+        altered[1].timestamp += 1;
+        let verification = TrailVerification::check(&owner, &altered);
+        assert_eq!(verification.breaks.len(), 1);
+        assert_eq!(verification.breaks[0].0, 2);
+
+        let other = bls::SecretKey::random().public_key();
+        assert!(!TrailVerification::check(&other, &entries).is_continuous());
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    file_apis::calculate_address,
+    Files,
+};
+
+use crate::protocol::address::ChunkAddress;
+
+use bincode::{deserialize, serialize};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use walkdir::WalkDir;
+
+/// A file as it was when a snapshot was taken.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// Where the content is stored, or None for an empty file.
+    pub address: Option<ChunkAddress>,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// What it took to create a snapshot.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotStats {
+    /// The number of files in the snapshot.
+    pub files: usize,
+    /// The number of files that had to be uploaded.
+    pub uploaded_files: usize,
+    /// The number of bytes that had to be uploaded.
+    pub uploaded_bytes: u64,
+    /// The number of files that were unchanged since the parent snapshot.
+    pub reused_files: usize,
+    /// The number of bytes of changed files that did not need to be uploaded again.
+    pub reused_bytes: u64,
+}
+
+/// The state of a directory at a point in time, as stored by [`Files::create_snapshot`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the snapshot was taken, in seconds since the unix epoch.
+    pub created: u64,
+    /// The snapshot this one was based on, if any.
+    pub parent: Option<ChunkAddress>,
+    /// The files of the directory, by their path relative to it.
+    pub files: BTreeMap<String, SnapshotFile>,
+    /// What it took to create the snapshot.
+    pub stats: SnapshotStats,
+}
+
+impl Files {
+    /// Snapshots the given directory, and returns the address of the snapshot.
+    ///
+    /// When a parent snapshot is given, files that are unchanged since then are not
+    /// uploaded again, and of files that were modified only the changed chunks are.
+    pub async fn create_snapshot(
+        &self,
+        dir: &Path,
+        parent: Option<ChunkAddress>,
+    ) -> Result<(ChunkAddress, Snapshot)> {
+        let parent_files = match parent {
+            Some(address) => self.get_snapshot(address).await?.files,
+            None => BTreeMap::new(),
+        };
+
+        let mut files = BTreeMap::new();
+        let mut stats = SnapshotStats::default();
+        for entry in WalkDir::new(dir).into_iter().flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = match entry.path().strip_prefix(dir) {
+                Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                Err(_) => continue,
+            };
+            let bytes = Bytes::from(tokio::fs::read(entry.path()).await?);
+            let size = bytes.len() as u64;
+
+            let address = if bytes.is_empty() {
+                None
+            } else {
+                let address = ChunkAddress::new(calculate_address(bytes.clone())?);
+                match parent_files.get(&path).and_then(|file| file.address) {
+                    Some(previous) if previous == address => stats.reused_files += 1,
+                    Some(previous) => {
+                        let (_, delta) = self.upload_delta(bytes, previous).await?;
+                        stats.uploaded_files += 1;
+                        stats.uploaded_bytes += delta.uploaded_bytes;
+                        stats.reused_bytes += delta.reused_bytes;
+                    }
+                    None => {
+                        let _ = self.upload(bytes).await?;
+                        stats.uploaded_files += 1;
+                        stats.uploaded_bytes += size;
+                    }
+                }
+                Some(address)
+            };
+
+            let _ = files.insert(path, SnapshotFile { address, size });
+        }
+        stats.files = files.len();
+
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let snapshot = Snapshot {
+            created,
+            parent,
+            files,
+            stats,
+        };
+        let address = self.upload(Bytes::from(serialize(&snapshot)?)).await?;
+        Ok((address, snapshot))
+    }
+
+    /// Retrieves the snapshot at the given address.
+    pub async fn get_snapshot(&self, address: ChunkAddress) -> Result<Snapshot> {
+        let bytes = self.read_bytes(address).await?;
+        Ok(deserialize(&bytes)?)
+    }
+
+    /// Retrieves the snapshot at the given address, followed by all of its ancestors.
+    pub async fn snapshot_history(
+        &self,
+        address: ChunkAddress,
+    ) -> Result<Vec<(ChunkAddress, Snapshot)>> {
+        let mut history = Vec::new();
+        let mut next = Some(address);
+        while let Some(address) = next {
+            let snapshot = self.get_snapshot(address).await?;
+            next = snapshot.parent;
+            history.push((address, snapshot));
+        }
+        Ok(history)
+    }
+
+    /// Writes the files of the snapshot at the given address to the target directory.
+    ///
+    /// Nothing is written if any path of the snapshot leads out of the target directory.
+    pub async fn restore_snapshot(&self, address: ChunkAddress, target: &Path) -> Result<()> {
+        let snapshot = self.get_snapshot(address).await?;
+        let files = snapshot
+            .files
+            .into_iter()
+            .map(|(path, file)| Ok((restore_path(target, &path)?, file)))
+            .collect::<Result<Vec<_>>>()?;
+        for (file_path, file) in files {
+            let bytes = match file.address {
+                Some(address) => self.read_bytes(address).await?,
+                None => Bytes::new(),
+            };
+            if let Some(parent_dir) = file_path.parent() {
+                tokio::fs::create_dir_all(parent_dir).await?;
+            }
+            tokio::fs::write(file_path, bytes).await?;
+        }
+        Ok(())
+    }
+}
+
+// Where to restore the file at the given path of a snapshot to. The snapshot comes from
+// the network, so a path that is absolute, or that has any `..`, is refused, as it
+// could lead out of the target dir.
+fn restore_path(target: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let is_normal = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !is_normal || relative.as_os_str().is_empty() {
+        return Err(Error::InvalidPath(format!(
+            "{path:?} of the snapshot is not within the target dir"
+        )));
+    }
+    Ok(target.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+
+    #[test]
+    fn paths_leading_out_of_the_target_are_refused() -> Result<()> {
+        let target = Path::new("/tmp/restore");
+        assert_eq!(
+            restore_path(target, "docs/notes.txt")?,
+            target.join("docs").join("notes.txt")
+        );
+        for path in ["../x", "docs/../../x", "/etc/x", ""] {
+            assert!(
+                matches!(restore_path(target, path), Err(Error::InvalidPath(_))),
+                "{path}"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Files;
+
+use crate::protocol::address::ChunkAddress;
+
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// What to run in a benchmark with [`Files::bench`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BenchConfig {
+    /// The size in bytes of each payload put and got.
+    pub payload_size: usize,
+    /// The number of payloads to put, and then get.
+    pub operations: usize,
+    /// The number of operations in flight at any time.
+    pub concurrency: usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            payload_size: 1024 * 1024,
+            operations: 20,
+            concurrency: 4,
+        }
+    }
+}
+
+/// The latency percentiles of the successful operations of a benchmark, in milliseconds.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    /// The median latency.
+    pub p50: u64,
+    /// The latency that 90% of operations did not exceed.
+    pub p90: u64,
+    /// The latency that 99% of operations did not exceed.
+    pub p99: u64,
+    /// The highest latency.
+    pub max: u64,
+}
+
+/// How one kind of operation performed in a benchmark.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    /// The operation, i.e. "put" or "get".
+    pub operation: String,
+    /// The size in bytes of each payload.
+    pub payload_size: usize,
+    /// The number of operations in flight at any time.
+    pub concurrency: usize,
+    /// The number of operations that succeeded.
+    pub succeeded: usize,
+    /// The number of operations that failed.
+    pub failed: usize,
+    /// How long it took to run all operations, in milliseconds.
+    pub elapsed_ms: u64,
+    /// The number of payload bytes per second of the successful operations.
+    pub bytes_per_sec: f64,
+    /// The latencies of the successful operations.
+    pub latency: LatencyPercentiles,
+}
+
+/// The results of a benchmark, as returned by [`Files::bench`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// One result per kind of operation, puts first.
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    /// Formats the results as csv, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "operation,payload_size,concurrency,succeeded,failed,elapsed_ms,\
+            bytes_per_sec,p50_ms,p90_ms,p99_ms,max_ms\n"
+            .to_string();
+        for result in &self.results {
+            let latency = result.latency;
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{:.0},{},{},{},{}",
+                result.operation,
+                result.payload_size,
+                result.concurrency,
+                result.succeeded,
+                result.failed,
+                result.elapsed_ms,
+                result.bytes_per_sec,
+                latency.p50,
+                latency.p90,
+                latency.p99,
+                latency.max
+            );
+        }
+        csv
+    }
+}
+
+impl Files {
+    /// Measures the throughput and latency of putting random payloads to the network,
+    /// and of then getting them back.
+    pub async fn bench(&self, config: BenchConfig) -> BenchReport {
+        let concurrency = config.concurrency.max(1);
+        let payloads: Vec<_> = (0..config.operations)
+            .map(|_| {
+                let mut payload = vec![0; config.payload_size];
+                rand::thread_rng().fill_bytes(&mut payload);
+                Bytes::from(payload)
+            })
+            .collect();
+
+        let start = Instant::now();
+        let puts: Vec<_> = stream::iter(payloads)
+            .map(|payload| async move {
+                let start = Instant::now();
+                let result = self.upload(payload).await;
+                (result, start.elapsed())
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        let put_elapsed = start.elapsed();
+
+        let mut addresses = Vec::new();
+        let mut put_latencies = Vec::new();
+        for (result, latency) in puts {
+            match result {
+                Ok(address) => {
+                    addresses.push(address);
+                    put_latencies.push(latency);
+                }
+                Err(error) => warn!("Bench put failed: {error}"),
+            }
+        }
+        let put_failures = config.operations - addresses.len();
+
+        let start = Instant::now();
+        let gets: Vec<_> = stream::iter(addresses)
+            .map(|address: ChunkAddress| async move {
+                let start = Instant::now();
+                let result = self.read_bytes(address).await;
+                (result, start.elapsed())
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        let get_elapsed = start.elapsed();
+
+        let get_attempts = gets.len();
+        let mut get_latencies = Vec::new();
+        for (result, latency) in gets {
+            match result {
+                Ok(_) => get_latencies.push(latency),
+                Err(error) => warn!("Bench get failed: {error}"),
+            }
+        }
+        let get_failures = get_attempts - get_latencies.len();
+
+        BenchReport {
+            results: vec![
+                bench_result("put", &config, put_elapsed, put_latencies, put_failures),
+                bench_result("get", &config, get_elapsed, get_latencies, get_failures),
+            ],
+        }
+    }
+}
+
+fn bench_result(
+    operation: &str,
+    config: &BenchConfig,
+    elapsed: Duration,
+    latencies: Vec<Duration>,
+    failed: usize,
+) -> BenchResult {
+    let succeeded = latencies.len();
+    let bytes = (succeeded * config.payload_size) as f64;
+    let bytes_per_sec = if elapsed.is_zero() {
+        0.0
+    } else {
+        bytes / elapsed.as_secs_f64()
+    };
+    BenchResult {
+        operation: operation.to_string(),
+        payload_size: config.payload_size,
+        concurrency: config.concurrency,
+        succeeded,
+        failed,
+        elapsed_ms: elapsed.as_millis() as u64,
+        bytes_per_sec,
+        latency: percentiles(latencies),
+    }
+}
+
+// The nearest-rank percentiles of the given latencies.
+pub(super) fn percentiles(mut latencies: Vec<Duration>) -> LatencyPercentiles {
+    if latencies.is_empty() {
+        return LatencyPercentiles::default();
+    }
+    latencies.sort();
+    let at = |percentile: usize| {
+        let rank = (percentile * latencies.len()).div_ceil(100);
+        latencies[rank.max(1) - 1].as_millis() as u64
+    };
+    LatencyPercentiles {
+        p50: at(50),
+        p90: at(90),
+        p99: at(99),
+        max: at(100),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_are_nearest_rank() {
+        let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+        assert_eq!(
+            percentiles(latencies),
+            LatencyPercentiles {
+                p50: 50,
+                p90: 90,
+                p99: 99,
+                max: 100,
+            }
+        );
+
+        let single = percentiles(vec![Duration::from_millis(7)]);
+        assert_eq!((single.p50, single.p99, single.max), (7, 7, 7));
+        assert_eq!(percentiles(vec![]), LatencyPercentiles::default());
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Error, Result};
+
+use crate::protocol::chunk::Chunk;
+
+use bytes::Bytes;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use self_encryption::EncryptedChunk;
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The Reed-Solomon parameters the content chunks of some data are erasure coded with.
+///
+/// The content chunks are split into groups of `data_shards` chunks, and `parity_shards`
+/// parity chunks are stored for each group. The content stays retrievable as long as
+/// no more than `parity_shards` chunks of any group are unavailable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ErasureCoding {
+    /// The number of content chunks per group.
+    pub data_shards: usize,
+    /// The number of parity chunks per group.
+    pub parity_shards: usize,
+}
+
+impl ErasureCoding {
+    /// Validates the parameters: both must be at least 1, and at most 256 in total.
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self> {
+        if data_shards == 0 || parity_shards == 0 || data_shards + parity_shards > 256 {
+            return Err(Error::ErasureCoding(format!(
+                "{data_shards} data and {parity_shards} parity shards are not supported, \
+                both must be at least 1, and at most 256 in total"
+            )));
+        }
+        Ok(Self {
+            data_shards,
+            parity_shards,
+        })
+    }
+}
+
+/// The parity of the content chunks of some data, recorded in its data map.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Parity {
+    pub(crate) coding: ErasureCoding,
+    pub(crate) groups: Vec<ParityGroup>,
+}
+
+/// A group of consecutive content chunks, and the parity chunks coded from them.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ParityGroup {
+    // The index, in the data map, of the first content chunk of the group.
+    pub(crate) first: usize,
+    // The sizes of the content chunks, as they are padded to the largest one to be coded.
+    pub(crate) sizes: Vec<usize>,
+    // The addresses of the parity chunks.
+    pub(crate) parity: Vec<XorName>,
+}
+
+impl ParityGroup {
+    /// Whether the content chunk with the given index in the data map is in this group.
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        (self.first..self.first + self.sizes.len()).contains(&index)
+    }
+
+    fn shard_len(&self) -> usize {
+        self.sizes.iter().copied().max().unwrap_or_default()
+    }
+}
+
+/// Codes the content chunks, returning their parity and the parity chunks to store.
+pub(crate) fn encode(
+    coding: ErasureCoding,
+    encrypted_chunks: &[EncryptedChunk],
+) -> Result<(Parity, Vec<Chunk>)> {
+    let mut contents: Vec<_> = encrypted_chunks.iter().collect();
+    contents.sort_by_key(|chunk| chunk.index);
+
+    let mut groups = vec![];
+    let mut parity_chunks = vec![];
+    for (group_index, group) in contents.chunks(coding.data_shards).enumerate() {
+        let sizes: Vec<_> = group.iter().map(|chunk| chunk.content.len()).collect();
+        let shard_len = sizes.iter().copied().max().unwrap_or_default();
+        let mut shards: Vec<_> = group
+            .iter()
+            .map(|chunk| padded(&chunk.content, shard_len))
+            .collect();
+        shards.extend((0..coding.parity_shards).map(|_| vec![0; shard_len]));
+
+        ReedSolomon::new(group.len(), coding.parity_shards)
+            .and_then(|coder| coder.encode(&mut shards))
+            .map_err(|err| Error::ErasureCoding(err.to_string()))?;
+
+        let chunks: Vec<_> = shards
+            .split_off(group.len())
+            .into_iter()
+            .map(|shard| Chunk::new(Bytes::from(shard)))
+            .collect();
+        groups.push(ParityGroup {
+            first: group_index * coding.data_shards,
+            sizes,
+            parity: chunks.iter().map(|chunk| *chunk.name()).collect(),
+        });
+        parity_chunks.extend(chunks);
+    }
+
+    Ok((Parity { coding, groups }, parity_chunks))
+}
+
+/// Recovers the content chunks of the group from the chunks of it that could be fetched.
+///
+/// `shards` holds the content chunks of the group followed by its parity chunks,
+/// with `None` for those that could not be fetched.
+pub(crate) fn reconstruct(group: &ParityGroup, shards: Vec<Option<Bytes>>) -> Result<Vec<Bytes>> {
+    let shard_len = group.shard_len();
+    let mut shards: Vec<_> = shards
+        .into_iter()
+        .map(|shard| shard.map(|shard| padded(&shard, shard_len)))
+        .collect();
+
+    ReedSolomon::new(group.sizes.len(), group.parity.len())
+        .and_then(|coder| coder.reconstruct_data(&mut shards))
+        .map_err(|err| Error::ErasureCoding(err.to_string()))?;
+
+    shards
+        .into_iter()
+        .zip(&group.sizes)
+        .map(|(shard, size)| {
+            let mut shard = shard.ok_or_else(|| {
+                Error::ErasureCoding("a content chunk was not reconstructed".to_string())
+            })?;
+            shard.truncate(*size);
+            Ok(Bytes::from(shard))
+        })
+        .collect()
+}
+
+fn padded(content: &[u8], len: usize) -> Vec<u8> {
+    let mut shard = content.to_vec();
+    shard.resize(len, 0);
+    shard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypted_chunks(sizes: &[usize]) -> Vec<EncryptedChunk> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(index, size)| EncryptedChunk {
+                index,
+                content: Bytes::from(
+                    (0..*size)
+                        .map(|byte| (byte + index) as u8)
+                        .collect::<Vec<_>>(),
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn content_is_recovered_while_enough_chunks_of_a_group_remain() -> Result<()> {
+        let coding = ErasureCoding::new(3, 2)?;
+        let chunks = encrypted_chunks(&[100, 100, 80, 100, 60]);
+        let (parity, parity_chunks) = encode(coding, &chunks)?;
+
+        assert_eq!(parity.groups.len(), 2);
+        assert_eq!(parity_chunks.len(), 4);
+        let last = &parity.groups[1];
+        assert_eq!((last.first, last.sizes.clone()), (3, vec![100, 60]));
+        assert!(last.contains(4) && !last.contains(2));
+
+        // Two chunks of the first group are lost, one content chunk and one parity chunk.
+        let group = &parity.groups[0];
+        let shards = vec![
+            None,
+            Some(chunks[1].content.clone()),
+            Some(chunks[2].content.clone()),
+            None,
+            Some(parity_chunks[1].value().clone()),
+        ];
+        let recovered = reconstruct(group, shards)?;
+        let expected: Vec<_> = chunks[..3]
+            .iter()
+            .map(|chunk| chunk.content.clone())
+            .collect();
+        assert_eq!(recovered, expected);
+
+        // Three lost chunks are too many.
+        let shards = vec![None, None, Some(chunks[2].content.clone()), None, None];
+        assert!(reconstruct(group, shards).is_err());
+
+        assert!(ErasureCoding::new(0, 2).is_err());
+        assert!(ErasureCoding::new(200, 100).is_err());
+        Ok(())
+    }
+}
@@ -63,6 +63,9 @@ pub enum Error {
         retrieved: usize,
     },
 
+    #[error("Erasure coding error: {0}")]
+    ErasureCoding(String),
+
     #[error("Not all data was chunked, expected {expected}, but we have {chunked}.)")]
     NotAllDataWasChunked {
         /// Number of Chunks expected to be generated
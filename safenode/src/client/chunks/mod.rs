@@ -6,11 +6,17 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod erasure;
 mod error;
 mod pac_man;
 
-pub(crate) use self::error::{Error, Result};
-pub(crate) use pac_man::{encrypt_large, to_chunk, DataMapLevel};
+pub use self::erasure::ErasureCoding;
+
+pub(crate) use self::{
+    erasure::{reconstruct, Parity},
+    error::{Error, Result},
+};
+pub(crate) use pac_man::{encrypt_large, encrypt_large_erasure_coded, to_chunk, DataMapLevel};
 
 use bytes::Bytes;
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
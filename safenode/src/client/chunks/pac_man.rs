@@ -6,7 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{Error, Result};
+use super::{
+    erasure::{self, ErasureCoding, Parity},
+    Error, Result,
+};
 
 use crate::protocol::chunk::Chunk;
 
@@ -27,17 +30,34 @@ pub(crate) enum DataMapLevel {
     // resulting from chunking up a previous level data map.
     // This happens when that previous level data map was too big to fit in a chunk itself.
     Additional(DataMap),
+    // Holds the data map to the source data, whose chunks are erasure coded with the given parity.
+    ErasureCoded(DataMap, Parity),
 }
 
 #[allow(unused)]
 pub(crate) fn encrypt_from_path(path: &Path) -> Result<(XorName, Vec<Chunk>)> {
     let (data_map, encrypted_chunks) = encrypt_file(path)?;
-    pack(data_map, encrypted_chunks)
+    pack(DataMapLevel::First(data_map), encrypted_chunks)
 }
 
 pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     let (data_map, encrypted_chunks) = encrypt_data(data)?;
-    pack(data_map, encrypted_chunks)
+    pack(DataMapLevel::First(data_map), encrypted_chunks)
+}
+
+/// Like [`encrypt_large`], also returning the parity chunks of the content chunks.
+pub(crate) fn encrypt_large_erasure_coded(
+    data: Bytes,
+    coding: ErasureCoding,
+) -> Result<(XorName, Vec<Chunk>)> {
+    let (data_map, encrypted_chunks) = encrypt_data(data)?;
+    let (parity, parity_chunks) = erasure::encode(coding, &encrypted_chunks)?;
+    let (address, mut chunks) = pack(
+        DataMapLevel::ErasureCoded(data_map, parity),
+        encrypted_chunks,
+    )?;
+    chunks.extend(parity_chunks);
+    Ok((address, chunks))
 }
 
 /// Returns the top-most chunk address through which the entire
@@ -45,17 +65,17 @@ pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
 /// If encryption is provided, the additional `DataMapLevel` chunks are encrypted with it.
 /// This is necessary if the data is meant to be private, since a `DataMap` is used to find and decrypt the original file.
 pub(crate) fn pack(
-    data_map: DataMap,
+    first_level: DataMapLevel,
     encrypted_chunks: Vec<EncryptedChunk>,
 ) -> Result<(XorName, Vec<Chunk>)> {
-    // Produces a chunk out of the first `DataMap`, which is validated for its size.
+    // Produces a chunk out of the first level `DataMap`, which is validated for its size.
     // If the chunk is too big, it is self-encrypted and the resulting (additional level) `DataMap` is put into a chunk.
     // The above step is repeated as many times as required until the chunk size is valid.
     // In other words: If the chunk content is too big, it will be
     // self encrypted into additional chunks, and now we have a new `DataMap`
     // which points to all of those additional chunks.. and so on.
     let mut chunks = vec![];
-    let mut chunk_content = pack_data_map(DataMapLevel::First(data_map))?;
+    let mut chunk_content = pack_data_map(first_level)?;
 
     let (address, additional_chunks) = loop {
         let chunk = to_chunk(chunk_content);
@@ -0,0 +1,100 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::network::close_group_majority;
+
+use itertools::Itertools;
+use std::hash::Hash;
+
+/// How the answers of the peers a query was sent to must agree for the client to accept one.
+///
+/// Answers are compared by what they sign or address, not by their serialised bytes.
+/// Only answers that verified are considered, so invalid ones never count towards agreement.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConsistencyPolicy {
+    /// Every peer asked must return the same valid answer.
+    AllEqual,
+    /// At least the given number of peers must return the same valid answer.
+    Quorum(usize),
+    /// The first valid answer is accepted.
+    FirstValid,
+}
+
+impl Default for ConsistencyPolicy {
+    fn default() -> Self {
+        Self::Quorum(close_group_majority())
+    }
+}
+
+impl ConsistencyPolicy {
+    /// Picks the answer to accept out of the answers of `asked` peers that verified,
+    /// in the order they arrived. Answers are compared by `key`.
+    pub(super) fn resolve<T, K: Eq + Hash>(
+        &self,
+        asked: usize,
+        valid: Vec<T>,
+        key: impl Fn(&T) -> K,
+    ) -> Option<T> {
+        match *self {
+            Self::FirstValid => valid.into_iter().next(),
+            Self::AllEqual => {
+                if valid.len() < asked.max(1) || !valid.iter().map(&key).all_equal() {
+                    return None;
+                }
+                valid.into_iter().next()
+            }
+            Self::Quorum(quorum) => valid
+                .into_iter()
+                .map(|answer| (key(&answer), answer))
+                .into_group_map()
+                .into_values()
+                .filter(|votes| votes.len() >= quorum.max(1))
+                .max_by_key(|votes| votes.len())
+                .and_then(|votes| votes.into_iter().next()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers are (value, valid) pairs, compared by their value.
+    fn resolve(policy: ConsistencyPolicy, asked: usize, answers: &[(u8, bool)]) -> Option<u8> {
+        let valid = answers
+            .iter()
+            .filter(|(_, valid)| *valid)
+            .copied()
+            .collect();
+        policy
+            .resolve(asked, valid, |(value, _)| *value)
+            .map(|(value, _)| value)
+    }
+
+    #[test]
+    fn answers_are_accepted_as_per_the_policy() {
+        let agreeing = [(1, true), (1, true), (1, true)];
+        let one_off = [(2, true), (1, true), (1, true)];
+        let one_invalid = [(2, false), (1, true), (1, true)];
+
+        assert_eq!(resolve(ConsistencyPolicy::AllEqual, 3, &agreeing), Some(1));
+        assert_eq!(resolve(ConsistencyPolicy::AllEqual, 3, &one_off), None);
+        assert_eq!(resolve(ConsistencyPolicy::AllEqual, 3, &one_invalid), None);
+        assert_eq!(resolve(ConsistencyPolicy::AllEqual, 4, &agreeing), None);
+
+        assert_eq!(resolve(ConsistencyPolicy::Quorum(2), 3, &one_off), Some(1));
+        assert_eq!(resolve(ConsistencyPolicy::Quorum(3), 3, &one_off), None);
+
+        assert_eq!(resolve(ConsistencyPolicy::FirstValid, 3, &one_off), Some(2));
+        assert_eq!(
+            resolve(ConsistencyPolicy::FirstValid, 3, &one_invalid),
+            Some(1)
+        );
+        assert_eq!(resolve(ConsistencyPolicy::FirstValid, 3, &[]), None);
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{chunks::ErasureCoding, error::Error, Files};
+
+use crate::protocol::address::ChunkAddress;
+
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::task;
+use walkdir::WalkDir;
+
+/// The outcome of uploading one file of a directory with [`Files::upload_dir_with`].
+#[derive(Debug)]
+pub struct FileUpload {
+    /// The path of the file, relative to the directory.
+    pub path: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// Where the file was stored, or why it was not.
+    pub result: Result<ChunkAddress, Error>,
+    /// How long it took to read and upload the file.
+    pub elapsed: Duration,
+}
+
+impl Files {
+    /// Uploads every file under the given directory, `jobs` files at a time, and hands the
+    /// outcome of each file to `on_done` as soon as it is uploaded, in the order they complete.
+    ///
+    /// Whenever a file is done, the next one takes its place, so that small files are not
+    /// held up behind a large one. The chunks of all the files share the budget of chunk
+    /// uploads of this [`Files`] and its clones, so uploading more files at once does not
+    /// put more chunks in flight than uploading a few.
+    pub async fn upload_dir_with(
+        &self,
+        dir: &Path,
+        jobs: usize,
+        coding: Option<ErasureCoding>,
+        on_done: impl FnMut(FileUpload),
+    ) {
+        let files = dir_files(dir);
+        info!(
+            "Uploading {} files of {dir:?}, {jobs} at a time",
+            files.len()
+        );
+        self.upload_files_with(files, jobs, coding, on_done).await
+    }
+
+    // Uploads the given files, by their relative path and their path on disk, as per
+    // `upload_dir_with`.
+    pub(super) async fn upload_files_with(
+        &self,
+        files: Vec<(String, PathBuf)>,
+        jobs: usize,
+        coding: Option<ErasureCoding>,
+        mut on_done: impl FnMut(FileUpload),
+    ) {
+        let mut uploads = stream::iter(files)
+            .map(|(path, file)| {
+                let files = self.clone();
+                async move {
+                    let start = Instant::now();
+                    let upload =
+                        task::spawn(async move { files.upload_file(&file, coding).await }).await;
+                    let (size, result) = match upload {
+                        Ok(upload) => upload,
+                        Err(error) => (0, Err(Error::Io(error.into()))),
+                    };
+                    FileUpload {
+                        path,
+                        size,
+                        result,
+                        elapsed: start.elapsed(),
+                    }
+                }
+            })
+            .buffer_unordered(jobs.max(1));
+
+        while let Some(upload) = uploads.next().await {
+            on_done(upload);
+        }
+    }
+
+    // Reads and uploads a single file, returning its size along with where it was stored.
+    async fn upload_file(
+        &self,
+        file: &Path,
+        coding: Option<ErasureCoding>,
+    ) -> (u64, Result<ChunkAddress, Error>) {
+        let bytes = match tokio::fs::read(file).await {
+            Ok(content) => Bytes::from(content),
+            Err(error) => return (0, Err(error.into())),
+        };
+        let size = bytes.len() as u64;
+        let result = match coding {
+            Some(coding) => self.upload_erasure_coded(bytes, coding).await,
+            None => self.upload(bytes).await,
+        };
+        (size, result)
+    }
+}
+
+// The files under the directory, by their path relative to it, with their path on disk.
+pub(super) fn dir_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let path = entry
+                .path()
+                .strip_prefix(dir)
+                .ok()?
+                .to_string_lossy()
+                .replace('\\', "/");
+            Some((path, entry.into_path()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+    use std::fs;
+
+    #[test]
+    fn files_are_listed_by_their_relative_path() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir_all(dir.path().join("docs/img"))?;
+        fs::write(dir.path().join("index.html"), b"<html>")?;
+        fs::write(dir.path().join("docs/img/logo.png"), b"png")?;
+
+        let mut files = dir_files(dir.path());
+        files.sort();
+        let paths: Vec<_> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["docs/img/logo.png", "index.html"]);
+        assert_eq!(files[1].1, dir.path().join("index.html"));
+        Ok(())
+    }
+}
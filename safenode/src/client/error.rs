@@ -8,7 +8,14 @@
 
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
 
-use crate::protocol::register::{Entry, EntryHash};
+use crate::{
+    network::Error as NetworkError,
+    network_transfers::Error as TransferError,
+    protocol::{
+        error::Error as ProtocolError,
+        register::{Entry, EntryHash},
+    },
+};
 
 use std::collections::BTreeSet;
 use thiserror::Error;
@@ -35,9 +42,170 @@ pub enum Error {
     #[error("Serialisation error: {0}")]
     BincodeError(#[from] bincode::Error),
 
+    #[error("Signer error: {0}")]
+    Signer(String),
+
+    #[error("There is no file at {0:?} in the archive.")]
+    ArchiveEntryNotFound(String),
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("There is nothing at {0} in the json document.")]
+    JsonPointerNotFound(String),
+
+    #[error("Invalid json: {0}")]
+    InvalidJson(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Journal error: {0}")]
+    Journal(String),
+
+    #[error("Telemetry error: {0}")]
+    Telemetry(String),
+
+    #[error("Recovery error: {0}")]
+    Recovery(String),
+
+    #[error("There is no interrupted operation {0:016x}.")]
+    PendingOperationNotFound(u64),
+
+    #[error("Profile error: {0}")]
+    Profile(String),
+
+    #[error("The gateway address {0} does not end with the peer id of the gateway.")]
+    InvalidGateway(String),
+
+    #[error("The answer of the network did not verify: {0}")]
+    VerificationFailed(String),
+
+    #[error("The data is not a typed payload, it has no schema header.")]
+    NotTyped,
+
+    #[error("The schema {0:?} is not registered.")]
+    UnknownSchema(String),
+
+    #[error("Expected data of the schema {expected:?}, but it is of the schema {found:?}.")]
+    SchemaMismatch { expected: String, found: String },
+
+    #[error(
+        "The data is of version {found} of the schema {schema:?}, \
+        but version {expected} is registered."
+    )]
+    SchemaVersionMismatch {
+        schema: String,
+        expected: u16,
+        found: u16,
+    },
+
+    #[error("Invalid typed payload of {0}")]
+    InvalidTypedPayload(String),
+
+    #[error("Site publish error: {0}")]
+    SitePublish(String),
+
+    #[error("Invalid Register policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] crate::protocol::wallet::Error),
+
+    #[error("Too many claims to the faucet, try again in {retry_after:?}.")]
+    FaucetRateLimited { retry_after: std::time::Duration },
+
+    #[error("The answer to the captcha was not valid.")]
+    CaptchaRejected,
+
     #[error(
         "Content branches detected in the Register which need to be merged/resolved by user. \
         Entries hashes of branches are: {0:?}"
     )]
     ContentBranchDetected(BTreeSet<(EntryHash, Entry)>),
 }
+
+/// The broad causes of client errors, for callers that act on why an operation failed,
+/// e.g. to tell data missing from the network apart from a network that cannot be reached.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The data asked for does not exist on the network.
+    NotFound,
+    /// The network could not be reached, or did not answer in time.
+    NetworkUnavailable,
+    /// The operation was given invalid input.
+    InvalidInput,
+    /// The data returned by the network did not verify.
+    VerificationFailed,
+    /// The network refused the operation, e.g. for lack of permissions, fees or space.
+    Refused,
+    /// Any other failure.
+    Other,
+}
+
+impl Error {
+    /// The broad cause of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Network(
+                NetworkError::InvalidNetworkId(_)
+                | NetworkError::InvalidContacts(_)
+                | NetworkError::NetworkMismatch { .. },
+            ) => ErrorKind::InvalidInput,
+            Self::Network(_) | Self::ResponseTimeout(_) => ErrorKind::NetworkUnavailable,
+            Self::Protocol(error) => protocol_error_kind(error),
+            Self::ArchiveEntryNotFound(_)
+            | Self::JsonPointerNotFound(_)
+            | Self::PendingOperationNotFound(_) => ErrorKind::NotFound,
+            Self::InvalidGateway(_)
+            | Self::Profile(_)
+            | Self::UnknownSchema(_)
+            | Self::SitePublish(_)
+            | Self::InvalidPolicy(_)
+            | Self::InvalidPath(_) => ErrorKind::InvalidInput,
+            Self::VerificationFailed(_) => ErrorKind::VerificationFailed,
+            Self::FaucetRateLimited { .. } | Self::CaptchaRejected => ErrorKind::Refused,
+            Self::Chunks(super::chunks::Error::EmptyFileProvided) => ErrorKind::InvalidInput,
+            Self::EventsReceiver(_)
+            | Self::Chunks(_)
+            | Self::BincodeError(_)
+            | Self::Signer(_)
+            | Self::InvalidJson(_)
+            | Self::NotTyped
+            | Self::SchemaMismatch { .. }
+            | Self::SchemaVersionMismatch { .. }
+            | Self::InvalidTypedPayload(_)
+            | Self::Io(_)
+            | Self::Journal(_)
+            | Self::Telemetry(_)
+            | Self::Recovery(_)
+            | Self::Wallet(_)
+            | Self::ContentBranchDetected(_) => ErrorKind::Other,
+        }
+    }
+}
+
+fn protocol_error_kind(error: &ProtocolError) -> ErrorKind {
+    match error {
+        ProtocolError::ChunkNotFound(_)
+        | ProtocolError::RegisterNotFound(_)
+        | ProtocolError::NoSuchEntry(_)
+        | ProtocolError::NoSuchUser(_)
+        | ProtocolError::Transfers(TransferError::SpendNotFound(_)) => ErrorKind::NotFound,
+        ProtocolError::InvalidSignature(_)
+        | ProtocolError::Transfers(TransferError::DoubleSpendAttempt { .. }) => {
+            ErrorKind::VerificationFailed
+        }
+        ProtocolError::NotEnoughSpace
+        | ProtocolError::AccessDenied(_)
+        | ProtocolError::EntryTooBig { .. }
+        | ProtocolError::TooManyEntries(_)
+        | ProtocolError::RateLimited { .. }
+        | ProtocolError::Overloaded { .. }
+        | ProtocolError::Banned { .. }
+        | ProtocolError::Transfers(_) => ErrorKind::Refused,
+        ProtocolError::UnexpectedResponses => ErrorKind::NetworkUnavailable,
+        ProtocolError::InvalidAddress(_) => ErrorKind::InvalidInput,
+        _ => ErrorKind::Other,
+    }
+}
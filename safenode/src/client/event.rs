@@ -8,6 +8,8 @@
 
 use super::error::Result;
 
+use libp2p::PeerId;
+use std::fmt;
 use tokio::sync::broadcast;
 
 // Channel where events will be broadcasted by the client.
@@ -40,6 +42,49 @@ impl ClientEventsChannel {
 pub enum ClientEvent {
     /// The client has been connected to the network
     ConnectedToNetwork,
+    /// The client made progress connecting to peers, which is mostly of interest
+    /// while it is connecting to the network.
+    Bootstrap(BootstrapProgress),
+}
+
+/// A step of the client connecting to peers, see [`ClientEvent::Bootstrap`].
+#[derive(Clone, Debug)]
+pub enum BootstrapProgress {
+    /// A peer is being dialled.
+    Dialing(PeerId),
+    /// A connection with the peer was established, after a successful handshake.
+    Connected(PeerId),
+    /// A connection could not be established.
+    ConnectionFailed {
+        /// The peer dialled, if known.
+        peer: Option<PeerId>,
+        /// Why the connection failed.
+        error: String,
+    },
+    /// Peers were added to the routing table of the client.
+    PeersKnown {
+        /// The number of peers in the routing table.
+        known: usize,
+        /// The number of peers needed to reach the close group of any address.
+        needed: usize,
+    },
+}
+
+impl fmt::Display for BootstrapProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dialing(peer) => write!(f, "dialing {peer}"),
+            Self::Connected(peer) => write!(f, "connected to {peer}"),
+            Self::ConnectionFailed {
+                peer: Some(peer),
+                error,
+            } => write!(f, "could not connect to {peer}: {error}"),
+            Self::ConnectionFailed { peer: None, error } => {
+                write!(f, "could not connect: {error}")
+            }
+            Self::PeersKnown { known, needed } => write!(f, "peers known: {known}/{needed}"),
+        }
+    }
 }
 
 /// Receiver Channel where users of the public API can listen to events broadcasted by the client.
@@ -0,0 +1,316 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Client, WalletClient,
+};
+
+use crate::protocol::wallet::SendWallet;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sn_dbc::{PublicAddress, Token};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::OpenOptions,
+    io::Write,
+    net::IpAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+
+/// The amount a faucet dispenses, and how often it does so to the same claimant.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FaucetConfig {
+    /// The amount sent for every claim.
+    pub amount: Token,
+    /// How long an address has to wait between two claims.
+    pub address_cooldown: Duration,
+    /// The number of claims an IP address can make within `ip_window`.
+    pub ip_claims: u32,
+    /// The window over which the claims of an IP address are counted.
+    pub ip_window: Duration,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            amount: Token::from_nano(1_000_000_000),
+            address_cooldown: Duration::from_secs(24 * 60 * 60),
+            ip_claims: 5,
+            ip_window: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Checks the answer to a captcha given with a claim, before the faucet sends anything.
+///
+/// Faucets exposed to the internet plug the captcha provider of their choice in here,
+/// see [`Faucet::with_captcha`]. By default, no captcha is asked for.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    /// Whether the answer, if any, is a valid one for a claim from the given IP address.
+    async fn verify(&self, answer: Option<&str>, ip: IpAddr) -> bool;
+}
+
+/// A captcha verifier accepting every claim.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoCaptcha;
+
+#[async_trait]
+impl CaptchaVerifier for NoCaptcha {
+    async fn verify(&self, _answer: Option<&str>, _ip: IpAddr) -> bool {
+        true
+    }
+}
+
+/// A request for tokens to a faucet.
+#[derive(Clone, Debug)]
+pub struct FaucetClaim {
+    /// The address to send the tokens to.
+    pub to: PublicAddress,
+    /// The IP address the claim was made from.
+    pub ip: IpAddr,
+    /// The answer to the captcha, if one was asked for.
+    pub captcha: Option<String>,
+}
+
+/// An entry of the audit log of a faucet, one per claim, dispensed or not.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FaucetAuditEntry {
+    /// When the claim was made, in seconds since the unix epoch.
+    pub at: u64,
+    /// The IP address the claim was made from.
+    pub ip: IpAddr,
+    /// The hex encoded address the tokens were claimed for.
+    pub address: String,
+    /// The amount sent, in nanos, or zero if the claim was refused.
+    pub amount_nano: u64,
+    /// Why the claim was refused, if it was.
+    pub error: Option<String>,
+}
+
+/// A faucet, dispensing small amounts of test tokens from a funded wallet.
+///
+/// Claims are limited per address and per IP address, can be required to come with a
+/// captcha answer, and are recorded to an audit log, if one is given. Claims are sent
+/// one at a time, the wallet being spent from for each of them.
+pub struct Faucet<W: SendWallet> {
+    config: FaucetConfig,
+    captcha: Arc<dyn CaptchaVerifier>,
+    audit_log: Option<PathBuf>,
+    state: Mutex<FaucetState<W>>,
+}
+
+struct FaucetState<W: SendWallet> {
+    wallet_client: WalletClient<W>,
+    limits: ClaimLimits,
+}
+
+impl<W: SendWallet> Faucet<W> {
+    /// Creates a faucet dispensing from the given wallet.
+    pub fn new(client: Client, wallet: W, config: FaucetConfig) -> Self {
+        Self {
+            config,
+            captcha: Arc::new(NoCaptcha),
+            audit_log: None,
+            state: Mutex::new(FaucetState {
+                wallet_client: WalletClient::new(client, wallet),
+                limits: ClaimLimits::default(),
+            }),
+        }
+    }
+
+    /// Requires claims to come with an answer to a captcha, checked by the given verifier.
+    pub fn with_captcha(mut self, verifier: Arc<dyn CaptchaVerifier>) -> Self {
+        self.captcha = verifier;
+        self
+    }
+
+    /// Appends an entry for every claim to the json lines file at the given path.
+    pub fn with_audit_log(mut self, path: PathBuf) -> Self {
+        self.audit_log = Some(path);
+        self
+    }
+
+    /// The amount sent for every claim.
+    pub fn amount(&self) -> Token {
+        self.config.amount
+    }
+
+    /// The balance left in the wallet of the faucet.
+    pub async fn balance(&self) -> Token {
+        self.state.lock().await.wallet_client.wallet().balance()
+    }
+
+    /// Sends the amount of the faucet to the address of the claim, if the claimant is
+    /// not over its limits, and answered the captcha.
+    pub async fn claim(&self, claim: FaucetClaim) -> Result<Token> {
+        let result = self.dispense(&claim).await;
+        let entry = FaucetAuditEntry {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            ip: claim.ip,
+            address: hex::encode(claim.to.to_bytes()),
+            amount_nano: result.as_ref().map(Token::as_nano).unwrap_or_default(),
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        info!("Faucet claim: {entry:?}");
+        if let Err(error) = self.audit(&entry) {
+            error!("Could not write the faucet claim to the audit log: {error}");
+        }
+        result
+    }
+
+    async fn dispense(&self, claim: &FaucetClaim) -> Result<Token> {
+        if !self
+            .captcha
+            .verify(claim.captcha.as_deref(), claim.ip)
+            .await
+        {
+            return Err(Error::CaptchaRejected);
+        }
+
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+        state
+            .limits
+            .check(&self.config, &claim.to, claim.ip, now)
+            .map_err(|retry_after| Error::FaucetRateLimited { retry_after })?;
+        state
+            .wallet_client
+            .send(self.config.amount, claim.to)
+            .await?;
+        state.limits.record(claim.to, claim.ip, now);
+        Ok(self.config.amount)
+    }
+
+    fn audit(&self, entry: &FaucetAuditEntry) -> Result<()> {
+        let path = match &self.audit_log {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut line =
+            serde_json::to_string(entry).map_err(|err| Error::InvalidJson(err.to_string()))?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+// The last claim of every address, and the claims of every IP address within the window.
+#[derive(Debug, Default)]
+struct ClaimLimits {
+    addresses: HashMap<PublicAddress, Instant>,
+    ips: HashMap<IpAddr, VecDeque<Instant>>,
+}
+
+impl ClaimLimits {
+    // Returns how long to wait before claiming again, if the claimant is over its limits.
+    fn check(
+        &mut self,
+        config: &FaucetConfig,
+        to: &PublicAddress,
+        ip: IpAddr,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.prune(config, now);
+
+        let address_wait = self
+            .addresses
+            .get(to)
+            .map(|last| config.address_cooldown.saturating_sub(now - *last))
+            .unwrap_or_default();
+        let ip_wait = match self.ips.get(&ip) {
+            Some(claims) if claims.len() >= config.ip_claims as usize => claims
+                .front()
+                .map(|oldest| config.ip_window.saturating_sub(now - *oldest))
+                .unwrap_or_default(),
+            _ => Duration::ZERO,
+        };
+        let wait = address_wait.max(ip_wait);
+        if wait.is_zero() {
+            Ok(())
+        } else {
+            Err(wait)
+        }
+    }
+
+    fn record(&mut self, to: PublicAddress, ip: IpAddr, now: Instant) {
+        let _ = self.addresses.insert(to, now);
+        self.ips.entry(ip).or_default().push_back(now);
+    }
+
+    // Forgets the claims that no longer count towards any limit.
+    fn prune(&mut self, config: &FaucetConfig, now: Instant) {
+        self.addresses
+            .retain(|_, last| now - *last < config.address_cooldown);
+        self.ips.retain(|_, claims| {
+            while claims
+                .front()
+                .is_some_and(|oldest| now - *oldest >= config.ip_window)
+            {
+                let _ = claims.pop_front();
+            }
+            !claims.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_are_limited_per_address_and_per_ip() {
+        let config = FaucetConfig {
+            address_cooldown: Duration::from_secs(60),
+            ip_claims: 2,
+            ip_window: Duration::from_secs(100),
+            ..Default::default()
+        };
+        let mut limits = ClaimLimits::default();
+        let ip: IpAddr = [10, 0, 0, 1].into();
+        let first = PublicAddress::new(bls::SecretKey::random().public_key());
+        let second = PublicAddress::new(bls::SecretKey::random().public_key());
+        let third = PublicAddress::new(bls::SecretKey::random().public_key());
+        let start = Instant::now();
+
+        assert_eq!(limits.check(&config, &first, ip, start), Ok(()));
+        limits.record(first, ip, start);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(
+            limits.check(&config, &first, ip, later),
+            Err(Duration::from_secs(50))
+        );
+        assert_eq!(limits.check(&config, &second, ip, later), Ok(()));
+        limits.record(second, ip, later);
+
+        // The ip address has used up its claims, until the first one leaves the window.
+        let after_cooldown = start + Duration::from_secs(70);
+        assert_eq!(
+            limits.check(&config, &first, ip, after_cooldown),
+            Err(Duration::from_secs(30))
+        );
+        assert_eq!(
+            limits.check(&config, &third, [10, 0, 0, 2].into(), after_cooldown),
+            Ok(())
+        );
+        let after_window = start + Duration::from_secs(100);
+        assert_eq!(limits.check(&config, &third, ip, after_window), Ok(()));
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{node::NodeId, protocol::fees::RequiredFee};
+
+use sn_dbc::DbcId;
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+// The fee quoted by each node of the close group of a dbc.
+type QuotedFees = BTreeMap<NodeId, RequiredFee>;
+
+/// How long the fees quoted for spending a dbc are reused, by default.
+pub const DEFAULT_FEE_QUOTE_TTL: Duration = Duration::from_secs(30);
+
+// The fees quoted by the close group of each dbc, kept for a while so that
+// estimating and then spending, or retrying a spend, does not quote them again.
+// The fees are encrypted to the dbc being spent, so they cannot be shared between dbcs.
+#[derive(Debug)]
+pub(crate) struct FeeCache {
+    ttl: Duration,
+    quotes: RwLock<BTreeMap<DbcId, (Instant, QuotedFees)>>,
+}
+
+impl FeeCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            quotes: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    // The fees quoted for spending the dbc, if they are still valid.
+    pub(crate) async fn get(&self, dbc_id: &DbcId) -> Option<QuotedFees> {
+        let quotes = self.quotes.read().await;
+        let (quoted_at, fees) = quotes.get(dbc_id)?;
+        (quoted_at.elapsed() < self.ttl).then(|| fees.clone())
+    }
+
+    pub(crate) async fn insert(&self, dbc_id: DbcId, fees: QuotedFees) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut quotes = self.quotes.write().await;
+        quotes.retain(|_, (quoted_at, _)| quoted_at.elapsed() < self.ttl);
+        let _ = quotes.insert(dbc_id, (Instant::now(), fees));
+    }
+
+    // Discards the fees quoted for spending the dbc, e.g. when they were rejected.
+    pub(crate) async fn remove(&self, dbc_id: &DbcId) {
+        let _ = self.quotes.write().await.remove(dbc_id);
+    }
+}
+
+impl Default for FeeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FEE_QUOTE_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sn_dbc::MainKey;
+    use std::sync::Arc;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_tasks_share_the_cache() {
+        let cache = Arc::new(FeeCache::default());
+        let mut rng = rand::thread_rng();
+        let dbc_ids: Vec<_> = (0..20)
+            .map(|_| MainKey::random().random_dbc_id_src(&mut rng).dbc_id())
+            .collect();
+
+        let tasks = (0..500).map(|i| {
+            let cache = cache.clone();
+            let dbc_id = dbc_ids[i % dbc_ids.len()];
+            tokio::spawn(async move {
+                if i % 3 == 0 {
+                    cache.remove(&dbc_id).await;
+                }
+                cache.insert(dbc_id, QuotedFees::new()).await;
+                cache.get(&dbc_id).await
+            })
+        });
+        for result in futures::future::join_all(tasks).await {
+            // Another task may have removed the fees in between, but none may panic.
+            assert!(result.is_ok());
+        }
+
+        for dbc_id in &dbc_ids {
+            cache.insert(*dbc_id, QuotedFees::new()).await;
+            assert!(cache.get(dbc_id).await.is_some());
+        }
+    }
+}
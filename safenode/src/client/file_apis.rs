@@ -7,7 +7,10 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    chunks::{to_chunk, DataMapLevel, Error, LargeFile, SmallFile},
+    chunks::{
+        encrypt_large_erasure_coded, reconstruct, to_chunk, DataMapLevel, ErasureCoding, Error,
+        LargeFile, Parity, SmallFile,
+    },
     error::Result,
     Client,
 };
@@ -20,22 +23,70 @@ use bincode::deserialize;
 use bytes::Bytes;
 use futures::future::join_all;
 use itertools::Itertools;
-use tokio::task;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+use tokio::{sync::Semaphore, task};
 use tracing::trace;
 use xor_name::XorName;
 
 // Maximum number of concurrent chunks to be uploaded/retrieved for a file
 const CHUNKS_BATCH_MAX_SIZE: usize = 5;
+// Maximum number of concurrent chunks to be uploaded across all the files being uploaded
+const CHUNK_UPLOADS_MAX: usize = 10;
+
+/// What it took to upload a new version of some data with [`Files::upload_delta`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DeltaReport {
+    /// The number of chunks that had to be uploaded.
+    pub uploaded_chunks: usize,
+    /// The number of chunks that were already stored for the previous version.
+    pub reused_chunks: usize,
+    /// The number of bytes that had to be uploaded.
+    pub uploaded_bytes: u64,
+    /// The number of bytes that were already stored for the previous version.
+    pub reused_bytes: u64,
+}
+
+/// How some data is laid out in chunks on the network, as returned by [`Files::data_info`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataInfo {
+    /// The address of the data.
+    pub address: ChunkAddress,
+    /// The size of the data, in bytes.
+    pub size: usize,
+    /// The number of data maps leading to the content, 0 for data stored in a single chunk.
+    pub data_map_levels: usize,
+    /// The chunks the data maps past the first one are stored in.
+    pub data_map_chunks: Vec<ChunkAddress>,
+    /// The chunks of the content, in order, with the number of bytes of content in each.
+    pub content_chunks: Vec<(ChunkAddress, usize)>,
+    /// The parity chunks of the content chunks, if they are erasure coded.
+    pub parity_chunks: Vec<ChunkAddress>,
+}
 
 /// File APIs.
+#[derive(Clone)]
 pub struct Files {
     client: Client,
+    // Shared by the clones, so that concurrent uploads share the budget of chunk uploads.
+    chunk_uploads: Arc<Semaphore>,
 }
 
 impl Files {
     /// Create file apis instance.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            chunk_uploads: Arc::new(Semaphore::new(CHUNK_UPLOADS_MAX)),
+        }
+    }
+
+    /// The client the files are read and written with.
+    pub fn client(&self) -> &Client {
+        &self.client
     }
 
     #[instrument(skip(self), level = "debug")]
@@ -44,8 +95,8 @@ impl Files {
         let chunk = self.client.get_chunk(address).await?;
 
         // first try to deserialize a LargeFile, if it works, we go and seek it
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            self.read_all(data_map).await
+        if let Ok((data_map, parity)) = self.unpack_chunk(chunk.clone()).await {
+            self.read_all(data_map, parity.as_ref()).await
         } else {
             // if an error occurs, we assume it's a SmallFile
             Ok(chunk.value().clone())
@@ -75,8 +126,8 @@ impl Files {
 
         // First try to deserialize a LargeFile, if it works, we go and seek it.
         // If an error occurs, we consider it to be a SmallFile.
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            return self.seek(data_map, position, length).await;
+        if let Ok((data_map, parity)) = self.unpack_chunk(chunk.clone()).await {
+            return self.seek(data_map, parity.as_ref(), position, length).await;
         }
 
         // The error above is ignored to avoid leaking the storage format detail of SmallFiles and LargeFiles.
@@ -106,6 +157,128 @@ impl Files {
         self.upload_bytes(bytes, true).await
     }
 
+    /// Like [`Files::upload`], also storing parity chunks for the content chunks, so that
+    /// the data can be read while some of its chunks are unavailable.
+    ///
+    /// The parity is recorded in the data map, and used when reading the data whenever
+    /// content chunks cannot be fetched. Data that fits in a single chunk is stored as is.
+    #[instrument(skip(self, bytes), level = "debug")]
+    pub async fn upload_erasure_coded(
+        &self,
+        bytes: Bytes,
+        coding: ErasureCoding,
+    ) -> Result<ChunkAddress> {
+        match LargeFile::new(bytes.clone()) {
+            Ok(file) => {
+                let (head_address, all_chunks) = encrypt_large_erasure_coded(file.bytes(), coding)?;
+                self.store_chunks(all_chunks, false).await?;
+                Ok(ChunkAddress::new(head_address))
+            }
+            Err(Error::TooSmallForSelfEncryption { .. }) => {
+                self.upload_small(SmallFile::new(bytes)?, false).await
+            }
+            Err(error) => Err(error)?,
+        }
+    }
+
+    /// Writes a new version of the data at `previous` to the network,
+    /// only uploading the chunks that the previous version does not already have.
+    ///
+    /// Self-encryption splits data into chunks by position, and each chunk is encrypted
+    /// using its neighbours, so a change only affects the chunks around it as long as the
+    /// size of the data stays the same. Any other change will mostly need a full upload.
+    #[instrument(skip(self, bytes), level = "debug")]
+    pub async fn upload_delta(
+        &self,
+        bytes: Bytes,
+        previous: ChunkAddress,
+    ) -> Result<(ChunkAddress, DeltaReport)> {
+        let chunk = self.client.get_chunk(previous).await?;
+        // A previous SmallFile has no chunks that could be reused.
+        let previous_chunks = match self.unpack_chunk(chunk).await {
+            Ok((data_map, _)) => data_map.infos().iter().map(|info| info.dst_hash).collect(),
+            Err(_) => BTreeSet::new(),
+        };
+
+        let (head_address, chunks) = chunk_bytes(bytes)?;
+        let (new_chunks, report) = split_known_chunks(chunks, &previous_chunks);
+        debug!("Uploading new version of {previous:?}: {report:?}");
+
+        self.store_chunks(new_chunks, false).await?;
+        Ok((ChunkAddress::new(head_address), report))
+    }
+
+    /// Returns the addresses of all the chunks the data at the given address is stored in,
+    /// starting with the given address itself.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn chunk_addresses(&self, address: ChunkAddress) -> Result<Vec<ChunkAddress>> {
+        let mut addresses = vec![address];
+        let mut chunk = self.client.get_chunk(address).await?;
+        loop {
+            let data_map = match deserialize(chunk.value()) {
+                Ok(DataMapLevel::First(data_map)) => {
+                    addresses.extend(chunk_infos_addresses(&data_map));
+                    return Ok(addresses);
+                }
+                Ok(DataMapLevel::ErasureCoded(data_map, parity)) => {
+                    addresses.extend(chunk_infos_addresses(&data_map));
+                    addresses.extend(parity_addresses(&parity));
+                    return Ok(addresses);
+                }
+                Ok(DataMapLevel::Additional(data_map)) => data_map,
+                // A SmallFile is stored in that one chunk.
+                Err(_) => return Ok(addresses),
+            };
+            addresses.extend(chunk_infos_addresses(&data_map));
+            let serialized_chunk = self.read_all(data_map, None).await?;
+            chunk = deserialize(&serialized_chunk).map_err(Error::Serialisation)?;
+        }
+    }
+
+    /// Returns how the data at the given address is laid out in chunks, without fetching
+    /// the chunks of its content.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn data_info(&self, address: ChunkAddress) -> Result<DataInfo> {
+        let mut info = DataInfo {
+            address,
+            size: 0,
+            data_map_levels: 0,
+            data_map_chunks: vec![],
+            content_chunks: vec![],
+            parity_chunks: vec![],
+        };
+        let mut chunk = self.client.get_chunk(address).await?;
+        loop {
+            let data_map = match deserialize(chunk.value()) {
+                Ok(DataMapLevel::First(data_map)) => {
+                    info.data_map_levels += 1;
+                    info.size = data_map.file_size();
+                    info.content_chunks = content_chunks(&data_map);
+                    return Ok(info);
+                }
+                Ok(DataMapLevel::ErasureCoded(data_map, parity)) => {
+                    info.data_map_levels += 1;
+                    info.size = data_map.file_size();
+                    info.content_chunks = content_chunks(&data_map);
+                    info.parity_chunks = parity_addresses(&parity);
+                    return Ok(info);
+                }
+                Ok(DataMapLevel::Additional(data_map)) => data_map,
+                // A SmallFile is stored in that one chunk.
+                Err(_) => {
+                    info.size = chunk.value().len();
+                    info.content_chunks = vec![(address, info.size)];
+                    return Ok(info);
+                }
+            };
+            info.data_map_levels += 1;
+            info.data_map_chunks
+                .extend(chunk_infos_addresses(&data_map));
+            let serialized_chunk = self.read_all(data_map, None).await?;
+            chunk = deserialize(&serialized_chunk).map_err(Error::Serialisation)?;
+        }
+    }
+
     // --------------------------------------------
     // ---------- Private helpers -----------------
     // --------------------------------------------
@@ -147,11 +320,20 @@ impl Files {
     #[instrument(skip_all, level = "trace")]
     async fn upload_large(&self, large: LargeFile, verify: bool) -> Result<ChunkAddress> {
         let (head_address, all_chunks) = encrypt_large(large)?;
-        for next_batch in all_chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
+        self.store_chunks(all_chunks, verify).await?;
+        Ok(ChunkAddress::new(head_address))
+    }
+
+    // Stores the chunks in batches of `CHUNKS_BATCH_MAX_SIZE`.
+    async fn store_chunks(&self, chunks: Vec<Chunk>, verify: bool) -> Result<()> {
+        for next_batch in chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
             let tasks = next_batch.iter().cloned().map(|chunk| {
                 let client = self.client.clone();
+                let chunk_uploads = self.chunk_uploads.clone();
 
                 task::spawn(async move {
+                    // The semaphore is never closed.
+                    let _permit = chunk_uploads.acquire_owned().await.ok();
                     let chunk_addr = *chunk.address();
                     client.store_chunk(chunk).await?;
                     if verify {
@@ -173,7 +355,7 @@ impl Files {
             }
         }
 
-        Ok(ChunkAddress::new(head_address))
+        Ok(())
     }
 
     // Verify a chunk is stored at provided address
@@ -183,26 +365,31 @@ impl Files {
     }
 
     // Gets and decrypts chunks from the network using nothing else but the data map,
-    // then returns the raw data.
-    async fn read_all(&self, data_map: DataMap) -> Result<Bytes> {
-        let encrypted_chunks = self.try_get_chunks(data_map.infos()).await?;
+    // and its parity if any, then returns the raw data.
+    async fn read_all(&self, data_map: DataMap, parity: Option<&Parity>) -> Result<Bytes> {
+        let encrypted_chunks = self
+            .try_get_chunks(data_map.infos(), parity.map(|parity| (&data_map, parity)))
+            .await?;
         let bytes = self_encryption::decrypt_full_set(&data_map, &encrypted_chunks)
             .map_err(Error::SelfEncryption)?;
         Ok(bytes)
     }
 
-    /// Extracts a file DataMapLevel from a chunk.
+    /// Extracts a file DataMapLevel from a chunk, with the parity of its chunks if any.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
     #[instrument(skip_all, level = "trace")]
-    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<DataMap> {
+    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<(DataMap, Option<Parity>)> {
         loop {
             match deserialize(chunk.value()).map_err(Error::Serialisation)? {
                 DataMapLevel::First(data_map) => {
-                    return Ok(data_map);
+                    return Ok((data_map, None));
+                }
+                DataMapLevel::ErasureCoded(data_map, parity) => {
+                    return Ok((data_map, Some(parity)));
                 }
                 DataMapLevel::Additional(data_map) => {
-                    let serialized_chunk = self.read_all(data_map).await?;
+                    let serialized_chunk = self.read_all(data_map, None).await?;
                     chunk = deserialize(&serialized_chunk).map_err(Error::Serialisation)?;
                 }
             }
@@ -211,7 +398,13 @@ impl Files {
     // Gets a subset of chunks from the network, decrypts and
     // reads `len` bytes of the data starting at given `pos` of original file.
     #[instrument(skip_all, level = "trace")]
-    async fn seek(&self, data_map: DataMap, pos: usize, len: usize) -> Result<Bytes> {
+    async fn seek(
+        &self,
+        data_map: DataMap,
+        parity: Option<&Parity>,
+        pos: usize,
+        len: usize,
+    ) -> Result<Bytes> {
         let info = self_encryption::seek_info(data_map.file_size(), pos, len);
         let range = &info.index_range;
         let all_infos = data_map.infos();
@@ -222,6 +415,7 @@ impl Files {
                     .clone()
                     .map(|i| all_infos[i].clone())
                     .collect_vec(),
+                parity.map(|parity| (&data_map, parity)),
             )
             .await?;
 
@@ -232,8 +426,13 @@ impl Files {
         Ok(bytes)
     }
 
+    // Gets the chunks, recovering those that cannot be fetched from the parity, if any.
     #[instrument(skip_all, level = "trace")]
-    async fn try_get_chunks(&self, chunks_info: Vec<ChunkInfo>) -> Result<Vec<EncryptedChunk>> {
+    async fn try_get_chunks(
+        &self,
+        chunks_info: Vec<ChunkInfo>,
+        parity: Option<(&DataMap, &Parity)>,
+    ) -> Result<Vec<EncryptedChunk>> {
         let expected_count = chunks_info.len();
         let mut retrieved_chunks = vec![];
         for next_batch in chunks_info.chunks(CHUNKS_BATCH_MAX_SIZE) {
@@ -264,6 +463,20 @@ impl Files {
             retrieved_chunks.extend(join_all(tasks).await.into_iter().flatten().flatten());
         }
 
+        if let (true, Some((data_map, parity))) = (expected_count > retrieved_chunks.len(), parity)
+        {
+            let retrieved: BTreeSet<_> = retrieved_chunks.iter().map(|chunk| chunk.index).collect();
+            let missing = chunks_info
+                .iter()
+                .map(|info| info.index)
+                .filter(|index| !retrieved.contains(index))
+                .collect_vec();
+            let recovered = self
+                .recover_chunks(data_map, parity, &missing, &retrieved_chunks)
+                .await?;
+            retrieved_chunks.extend(recovered);
+        }
+
         if expected_count > retrieved_chunks.len() {
             Err(Error::NotEnoughChunksRetrieved {
                 expected: expected_count,
@@ -275,6 +488,98 @@ impl Files {
     }
 }
 
+impl Files {
+    // Reconstructs the missing content chunks from the other chunks of their parity groups.
+    async fn recover_chunks(
+        &self,
+        data_map: &DataMap,
+        parity: &Parity,
+        missing: &[usize],
+        retrieved: &[EncryptedChunk],
+    ) -> Result<Vec<EncryptedChunk>> {
+        let infos = data_map.infos();
+        let retrieved: BTreeMap<_, _> = retrieved
+            .iter()
+            .map(|chunk| (chunk.index, chunk.content.clone()))
+            .collect();
+
+        let mut recovered = vec![];
+        for group in &parity.groups {
+            if !missing.iter().any(|index| group.contains(*index)) {
+                continue;
+            }
+            let members = infos
+                .get(group.first..group.first + group.sizes.len())
+                .ok_or_else(|| {
+                    Error::ErasureCoding("the parity does not match the data map".to_string())
+                })?;
+            let fetches = members
+                .iter()
+                .map(|info| (retrieved.get(&info.index).cloned(), info.dst_hash))
+                .chain(group.parity.iter().map(|name| (None, *name)))
+                .map(|(known, name)| async move {
+                    match known {
+                        Some(content) => Some(content),
+                        None => self
+                            .client
+                            .get_chunk(ChunkAddress::new(name))
+                            .await
+                            .ok()
+                            .map(|chunk| chunk.value().clone()),
+                    }
+                });
+            let shards = join_all(fetches).await;
+            debug!(
+                "Recovering chunks {missing:?} from {} of the {} chunks of their parity group",
+                shards.iter().flatten().count(),
+                shards.len()
+            );
+
+            for (info, content) in members.iter().zip(reconstruct(group, shards)?) {
+                if !missing.contains(&info.index) {
+                    continue;
+                }
+                if XorName::from_content(&content) != info.dst_hash {
+                    return Err(Error::ErasureCoding(format!(
+                        "the chunk {} recovered from parity does not match its address",
+                        info.dst_hash
+                    )))?;
+                }
+                recovered.push(EncryptedChunk {
+                    index: info.index,
+                    content,
+                });
+            }
+        }
+        Ok(recovered)
+    }
+}
+
+fn content_chunks(data_map: &DataMap) -> Vec<(ChunkAddress, usize)> {
+    data_map
+        .infos()
+        .into_iter()
+        .map(|info| (ChunkAddress::new(info.dst_hash), info.src_size))
+        .collect()
+}
+
+fn parity_addresses(parity: &Parity) -> Vec<ChunkAddress> {
+    parity
+        .groups
+        .iter()
+        .flat_map(|group| group.parity.iter())
+        .map(|name| ChunkAddress::new(*name))
+        .collect()
+}
+
+fn chunk_infos_addresses(data_map: &DataMap) -> Vec<ChunkAddress> {
+    data_map
+        .infos()
+        .into_iter()
+        .map(|info| ChunkAddress::new(info.dst_hash))
+        .collect()
+}
+
 /// Calculates a LargeFile's/SmallFile's address from self encrypted chunks,
 /// without storing them onto the network.
 #[instrument(skip(bytes), level = "debug")]
@@ -296,6 +601,28 @@ pub fn chunk_bytes(bytes: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     }
 }
 
+// Separates out the chunks that are not among the known ones,
+// and reports how much of the data they make up.
+fn split_known_chunks(chunks: Vec<Chunk>, known: &BTreeSet<XorName>) -> (Vec<Chunk>, DeltaReport) {
+    let mut report = DeltaReport::default();
+    let new_chunks = chunks
+        .into_iter()
+        .filter(|chunk| {
+            let size = chunk.value().len() as u64;
+            if known.contains(chunk.name()) {
+                report.reused_chunks += 1;
+                report.reused_bytes += size;
+                false
+            } else {
+                report.uploaded_chunks += 1;
+                report.uploaded_bytes += size;
+                true
+            }
+        })
+        .collect();
+    (new_chunks, report)
+}
+
 /// Encrypts a [`LargeFile`] and returns the resulting address and all chunks.
 /// Does not store anything to the network.
 #[instrument(skip(file), level = "trace")]
@@ -312,3 +639,28 @@ fn package_small(file: SmallFile) -> Result<Chunk> {
     }
     Ok(chunk)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unknown_chunks_are_uploaded() {
+        let kept = Chunk::new(Bytes::from_static(b"unchanged"));
+        let changed = Chunk::new(Bytes::from_static(b"changed"));
+        let known = BTreeSet::from([*kept.name()]);
+
+        let (new_chunks, report) = split_known_chunks(vec![kept, changed.clone()], &known);
+
+        assert_eq!(new_chunks, vec![changed]);
+        assert_eq!(
+            report,
+            DeltaReport {
+                uploaded_chunks: 1,
+                reused_chunks: 1,
+                uploaded_bytes: 7,
+                reused_bytes: 9,
+            }
+        );
+    }
+}
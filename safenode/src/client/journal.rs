@@ -0,0 +1,260 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use crate::protocol::messages::{
+    Cmd, Query, RegisterCmd, RegisterQuery, Request, Response, SpendQuery,
+};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Filename of the journal, in the client dir.
+const JOURNAL_FILENAME: &str = "journal.jsonl";
+
+/// The number of entries a journal keeps, by default.
+pub const DEFAULT_JOURNAL_SIZE: usize = 1000;
+
+/// An operation sent to the network by a client, as recorded in its [`Journal`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// A random id of the operation, to refer to it in bug reports.
+    pub id: u64,
+    /// When the operation was started, in milliseconds since the unix epoch.
+    pub started_at: u64,
+    /// The kind of operation, e.g. `StoreChunk`.
+    pub operation: String,
+    /// The address the operation was sent to.
+    pub target: String,
+    /// The number of times the operation was sent.
+    pub attempts: u32,
+    /// How long the operation took, including retries, in milliseconds.
+    pub duration_ms: u64,
+    /// The number of peers the operation was last sent to.
+    pub peers: usize,
+    /// The number of peers that answered with a successful response.
+    pub succeeded: usize,
+    /// The first error, if no peer answered with a successful response.
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    pub(super) fn new(
+        request: &Request,
+        started_at: SystemTime,
+        attempts: u32,
+        duration: Duration,
+        result: &Result<Vec<Result<Response>>>,
+    ) -> Self {
+        let (peers, succeeded, error) = match result {
+            Ok(responses) => {
+                let succeeded = responses
+                    .iter()
+                    .filter(|resp| matches!(resp, Ok(resp) if is_success(resp)))
+                    .count();
+                let error = if succeeded > 0 {
+                    None
+                } else {
+                    responses.first().map(|resp| match resp {
+                        Ok(resp) => format!("{resp:?}"),
+                        Err(error) => error.to_string(),
+                    })
+                };
+                (responses.len(), succeeded, error)
+            }
+            Err(error) => (0, 0, Some(error.to_string())),
+        };
+        Self {
+            id: rand::random(),
+            started_at: started_at
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_millis() as u64)
+                .unwrap_or_default(),
+            operation: operation_name(request).to_string(),
+            target: format!("{:?}", request.dst()),
+            attempts,
+            duration_ms: duration.as_millis() as u64,
+            peers,
+            succeeded,
+            error,
+        }
+    }
+}
+
+/// A local record of the operations a client sent to the network, to attach to bug reports.
+///
+/// The entries are appended to a json lines file in the client dir, which is kept to
+/// the given number of most recent entries.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    max_entries: usize,
+    // The number of entries in the file.
+    entries: Mutex<usize>,
+}
+
+impl Journal {
+    /// Opens the journal in the given dir, keeping at most `max_entries` entries.
+    pub fn open(root_dir: &Path, max_entries: usize) -> Result<Self> {
+        let path = root_dir.join(JOURNAL_FILENAME);
+        let entries = if path.is_file() {
+            fs::read_to_string(&path)?.lines().count()
+        } else {
+            0
+        };
+        Ok(Self {
+            path,
+            max_entries: max_entries.max(1),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Reads the last `count` entries of the journal in the given dir, oldest first.
+    pub fn read_last(root_dir: &Path, count: usize) -> Result<Vec<JournalEntry>> {
+        let path = root_dir.join(JOURNAL_FILENAME);
+        if !path.is_file() {
+            return Ok(vec![]);
+        }
+        let content = fs::read_to_string(path)?;
+        let lines: Vec<_> = content.lines().collect();
+        lines[lines.len().saturating_sub(count)..]
+            .iter()
+            .map(|line| serde_json::from_str(line).map_err(|err| Error::Journal(err.to_string())))
+            .collect()
+    }
+
+    /// Appends the entry to the journal, dropping the oldest entries once it is full.
+    pub(super) fn record(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line =
+            serde_json::to_string(entry).map_err(|err| Error::Journal(err.to_string()))?;
+        line.push('\n');
+
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| Error::Journal("the journal lock is poisoned".to_string()))?;
+        append_capped(&self.path, &mut entries, self.max_entries, &line)?;
+        Ok(())
+    }
+}
+
+// Appends the line to the json lines file holding `entries` lines, keeping only the
+// last `max_entries` lines. Trimming rewrites the file, so it is only done once it has
+// grown well past its size.
+pub(super) fn append_capped(
+    path: &Path,
+    entries: &mut usize,
+    max_entries: usize,
+    line: &str,
+) -> std::io::Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    *entries += 1;
+
+    if *entries >= 2 * max_entries {
+        let content = fs::read_to_string(path)?;
+        let lines: Vec<_> = content.lines().collect();
+        let kept = &lines[lines.len().saturating_sub(max_entries)..];
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, kept.join("\n") + "\n")?;
+        fs::rename(tmp_path, path)?;
+        *entries = kept.len();
+    }
+    Ok(())
+}
+
+pub(super) fn is_success(response: &Response) -> bool {
+    match response {
+        Response::Cmd(resp) => resp.is_success(),
+        Response::Query(resp) => resp.is_success(),
+    }
+}
+
+pub(super) fn operation_name(request: &Request) -> &'static str {
+    match request {
+        Request::Cmd(Cmd::StoreChunk(_)) => "StoreChunk",
+        Request::Cmd(Cmd::Register(RegisterCmd::Create(_))) => "CreateRegister",
+        Request::Cmd(Cmd::Register(RegisterCmd::Edit(_))) => "EditRegister",
+        Request::Cmd(Cmd::SpendDbc { .. }) => "SpendDbc",
+        Request::Query(Query::GetChunk(_)) => "GetChunk",
+        Request::Query(Query::Register(query)) => match query {
+            RegisterQuery::Get(_) => "GetRegister",
+            RegisterQuery::Read(_) => "ReadRegister",
+            RegisterQuery::GetEntry { .. } => "GetRegisterEntry",
+            RegisterQuery::GetPolicy(_) => "GetRegisterPolicy",
+            RegisterQuery::GetUserPermissions { .. } => "GetRegisterUserPermissions",
+            RegisterQuery::GetOwner(_) => "GetRegisterOwner",
+        },
+        Request::Query(Query::Spend(SpendQuery::GetFees { .. })) => "GetFees",
+        Request::Query(Query::Spend(SpendQuery::GetDbcSpend(_))) => "GetDbcSpend",
+        Request::Query(Query::GetRecordDistribution { .. }) => "GetRecordDistribution",
+        Request::Query(Query::GetTime { .. }) => "GetTime",
+        Request::Event(_) => "Event",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        address::ChunkAddress, error::Error as ProtocolError, messages::QueryResponse,
+    };
+
+    use xor_name::XorName;
+
+    #[test]
+    fn journal_keeps_the_most_recent_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journal = Journal::open(dir.path(), 3)?;
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let request = Request::Query(Query::GetChunk(address));
+        let not_found = Ok(vec![Ok(Response::Query(QueryResponse::GetChunk(Err(
+            ProtocolError::ChunkNotFound(address),
+        ))))]);
+
+        for attempts in 1..=7 {
+            let entry = JournalEntry::new(
+                &request,
+                SystemTime::now(),
+                attempts,
+                Duration::from_millis(5),
+                &not_found,
+            );
+            journal.record(&entry)?;
+        }
+
+        let last = Journal::read_last(dir.path(), 50)?;
+        assert!(last.len() >= 3 && last.len() < 6);
+        let attempts: Vec<_> = Journal::read_last(dir.path(), 2)?
+            .iter()
+            .map(|entry| entry.attempts)
+            .collect();
+        assert_eq!(attempts, vec![6, 7]);
+
+        let entry = &last[last.len() - 1];
+        assert_eq!(entry.operation, "GetChunk");
+        assert_eq!((entry.peers, entry.succeeded), (1, 0));
+        assert!(entry.error.is_some());
+
+        // Reopening picks up the entries already in the file.
+        let journal = Journal::open(dir.path(), 3)?;
+        assert_eq!(*journal.entries.lock().expect("lock"), last.len());
+        Ok(())
+    }
+}
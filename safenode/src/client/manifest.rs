@@ -0,0 +1,156 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    AuditReport, Client, Files, Signer,
+};
+
+use crate::protocol::{address::ChunkAddress, wallet::verify_detached};
+
+use bincode::serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A file of an upload, as recorded in a [`Manifest`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The address the file was uploaded to.
+    pub address: ChunkAddress,
+    /// All the chunks the file is stored in, starting with its address.
+    pub chunks: Vec<ChunkAddress>,
+}
+
+/// A signed record of the files of an upload and the chunks they are stored in,
+/// for anyone to check later that what they fetch is what was uploaded,
+/// e.g. when distributing software over the network.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The files, by their path relative to the uploaded directory.
+    pub files: BTreeMap<String, ManifestEntry>,
+    /// The hex encoded public key of the signer.
+    pub signer: String,
+    /// The hex encoded signature of the files by the signer.
+    pub signature: String,
+}
+
+impl Manifest {
+    /// Signs the files with the given signer.
+    pub fn sign(files: BTreeMap<String, ManifestEntry>, signer: &dyn Signer) -> Result<Self> {
+        let signature = signer.sign(&serialize(&files)?)?;
+        Ok(Self {
+            files,
+            signer: hex::encode(signer.public_key().to_bytes()),
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    /// Checks that the files were signed by the signer of the manifest.
+    pub fn verify_signature(&self) -> Result<()> {
+        verify_detached(&serialize(&self.files)?, &self.signature, &self.signer)
+            .map_err(|err| Error::VerificationFailed(format!("the manifest signature: {err}")))
+    }
+}
+
+/// The outcome of checking a [`Manifest`] against the network, with [`Client::verify_manifest`].
+#[derive(Clone, Debug, Default)]
+pub struct ManifestReport {
+    /// The files whose chunks on the network are not the ones in the manifest,
+    /// or could not be listed.
+    pub mismatched: Vec<String>,
+    /// The audit of every chunk of the manifest.
+    pub audit: AuditReport,
+}
+
+impl ManifestReport {
+    /// Returns true if every file is stored in the chunks of the manifest, and all are intact.
+    pub fn passed(&self) -> bool {
+        self.mismatched.is_empty() && self.audit.passed()
+    }
+}
+
+impl Files {
+    /// Records the chunks the file at the given address is stored in, for a [`Manifest`].
+    pub async fn manifest_entry(&self, address: ChunkAddress) -> Result<ManifestEntry> {
+        Ok(ManifestEntry {
+            address,
+            chunks: self.chunk_addresses(address).await?,
+        })
+    }
+}
+
+impl Client {
+    /// Checks the signature of the manifest, then that each file is still stored in
+    /// the chunks of the manifest, and that every one of those chunks is intact.
+    pub async fn verify_manifest(&self, manifest: &Manifest) -> Result<ManifestReport> {
+        manifest.verify_signature()?;
+
+        let files = Files::new(self.clone());
+        let mut mismatched = vec![];
+        for (path, entry) in &manifest.files {
+            match files.chunk_addresses(entry.address).await {
+                Ok(chunks) if chunks == entry.chunks => {}
+                Ok(chunks) => {
+                    warn!(
+                        "{path} is stored in {} chunks, the manifest has {}",
+                        chunks.len(),
+                        entry.chunks.len()
+                    );
+                    mismatched.push(path.clone());
+                }
+                Err(error) => {
+                    warn!("Could not list the chunks of {path}: {error}");
+                    mismatched.push(path.clone());
+                }
+            }
+        }
+
+        let chunks: BTreeSet<_> = manifest
+            .files
+            .values()
+            .flat_map(|entry| entry.chunks.iter().copied())
+            .collect();
+        let audit = self.audit_chunks(chunks.into_iter().collect()).await;
+        Ok(ManifestReport { mismatched, audit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use xor_name::XorName;
+
+    #[test]
+    fn manifests_verify_only_as_signed() -> Result<()> {
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let files = BTreeMap::from([(
+            "bin/app".to_string(),
+            ManifestEntry {
+                address,
+                chunks: vec![address],
+            },
+        )]);
+        let mut manifest = Manifest::sign(files, &bls::SecretKey::random())?;
+        assert!(manifest.verify_signature().is_ok());
+
+        let other = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let _ = manifest.files.insert(
+            "bin/app".to_string(),
+            ManifestEntry {
+                address: other,
+                chunks: vec![other],
+            },
+        );
+        assert!(matches!(
+            manifest.verify_signature(),
+            Err(Error::VerificationFailed(_))
+        ));
+        Ok(())
+    }
+}
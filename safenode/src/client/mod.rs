@@ -7,29 +7,113 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod api;
+mod archive;
+mod audit;
+mod audit_trail;
+mod backup;
+mod bench;
 mod chunks;
+mod consistency;
+mod dir_upload;
 mod error;
 mod event;
+mod faucet;
+mod fee_cache;
 mod file_apis;
+mod journal;
+mod manifest;
+mod network_check;
+mod network_map;
+mod pins;
+mod profile;
+mod recovery;
 mod register;
+mod retry;
+mod schema;
+mod signer;
+mod site;
+mod site_cache;
+mod telemetry;
+mod verification;
+mod virtual_dir;
 mod wallet;
 
 pub use self::{
-    error::Error,
-    event::{ClientEvent, ClientEventsReceiver},
-    file_apis::Files,
-    register::{Register, RegisterOffline},
-    wallet::WalletClient,
+    archive::{Archive, ArchiveEntry},
+    audit::{AuditOutcome, AuditReport, ChunkAudit},
+    audit_trail::{AuditTrail, TrailEntry, TrailVerification, AUDIT_TRAIL_TAG},
+    backup::{Snapshot, SnapshotFile, SnapshotStats},
+    bench::{BenchConfig, BenchReport, BenchResult, LatencyPercentiles},
+    chunks::ErasureCoding,
+    consistency::ConsistencyPolicy,
+    dir_upload::FileUpload,
+    error::{Error, ErrorKind},
+    event::{BootstrapProgress, ClientEvent, ClientEventsReceiver},
+    faucet::{CaptchaVerifier, Faucet, FaucetAuditEntry, FaucetClaim, FaucetConfig, NoCaptcha},
+    fee_cache::DEFAULT_FEE_QUOTE_TTL,
+    file_apis::{DataInfo, DeltaReport, Files},
+    journal::{Journal, JournalEntry, DEFAULT_JOURNAL_SIZE},
+    manifest::{Manifest, ManifestEntry, ManifestReport},
+    network_check::{NetworkCheckReport, ProbeResult},
+    network_map::{NetworkMap, NodeRecords},
+    pins::{Pin, PinList, PinStatus},
+    profile::{Profile, Profiles},
+    recovery::{InFlight, OperationState, PendingOperation, RecoveryStore},
+    register::{PolicyFile, PolicyTemplate, Register, RegisterOffline},
+    retry::{RetryConfig, RetryOperation, RetryPolicy, RetryStats},
+    schema::{Schema, SchemaHeader, SchemaRegistry},
+    signer::{CommandSigner, Signer},
+    site::{SiteChanges, SiteMap, SitePublish, SitePublishReport, SITE_REGISTER_TAG},
+    site_cache::{SiteCacheStats, DEFAULT_SITE_CACHE_TTL},
+    telemetry::{
+        OperationTelemetry, Telemetry, TelemetryRecord, TelemetryReport, DEFAULT_TELEMETRY_SIZE,
+    },
+    verification::VerificationStats,
+    virtual_dir::{DirEntry, EntryKind, EntryMetadata, VirtualDir},
+    wallet::{SpendStatus, WalletClient},
 };
 
-use self::event::ClientEventsChannel;
+pub(crate) use self::{fee_cache::FeeCache, site_cache::SiteCache};
+
+use self::{event::ClientEventsChannel, retry::RetryCounters, verification::VerificationCounters};
 
 use crate::network::Network;
 
+use std::sync::Arc;
+use tokio::sync::watch;
+
 /// Client API implementation to store and get data.
+///
+/// Cloning a client is cheap, and all clones share the same connection to the network,
+/// fee quote and site caches, retry counters and bootstrap state. Any number of tasks can use their
+/// own clone concurrently: requests are queued to the single driver of the connection, and
+/// each response is routed back to the task that sent the request. The order in which the
+/// requests of different tasks reach the network is not defined.
 #[derive(Clone)]
 pub struct Client {
     network: Network,
     events_channel: ClientEventsChannel,
-    signer: bls::SecretKey,
+    connected: Arc<watch::Sender<bool>>,
+    signer: Arc<dyn Signer>,
+    retry_config: Arc<RetryConfig>,
+    retries: Arc<RetryCounters>,
+    verifications: Arc<VerificationCounters>,
+    fee_cache: Arc<FeeCache>,
+    site_cache: Arc<SiteCache>,
+    journal: Option<Arc<Journal>>,
+    telemetry: Option<Arc<Telemetry>>,
+    audit_trail: Option<Arc<AuditTrail>>,
+    consistency: ConsistencyPolicy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_handle_can_be_shared_across_tasks() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<Client>();
+        assert_shareable::<Files>();
+    }
 }
@@ -0,0 +1,160 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+
+use crate::{
+    network::{close_group_majority, CLOSE_GROUP_SIZE},
+    protocol::{
+        address::ChunkAddress,
+        chunk::Chunk,
+        messages::{Query, Request},
+    },
+};
+
+use bytes::Bytes;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use xor_name::XorName;
+
+/// Size of the random chunk stored and then retrieved by the round trip probe.
+const PROBE_CHUNK_SIZE: usize = 1024;
+
+/// The outcome of a single probe run by [`Client::check_network`].
+#[derive(Clone, Debug)]
+pub struct ProbeResult {
+    /// The name of the probe.
+    pub name: &'static str,
+    /// Whether the probe passed.
+    pub passed: bool,
+    /// How long the probe took to complete.
+    pub duration: Duration,
+    /// What was observed, or why the probe failed.
+    pub details: String,
+}
+
+/// The health of the network as seen from a client,
+/// as established by the probes of [`Client::check_network`].
+#[derive(Clone, Debug, Default)]
+pub struct NetworkCheckReport {
+    /// The result of each probe, in the order they were run.
+    pub probes: Vec<ProbeResult>,
+}
+
+impl NetworkCheckReport {
+    /// Returns true if every probe passed.
+    pub fn passed(&self) -> bool {
+        self.probes.iter().all(|probe| probe.passed)
+    }
+}
+
+impl fmt::Display for NetworkCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for probe in &self.probes {
+            let status = if probe.passed { "PASS" } else { "FAIL" };
+            writeln!(
+                f,
+                "[{status}] {:<24} {:>6}ms  {}",
+                probe.name,
+                probe.duration.as_millis(),
+                probe.details
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Runs a suite of probes against the network and reports on each of them,
+    /// so that a misconfigured client can be told apart from a network that is down.
+    ///
+    /// The probes are run in order, and all of them are run even if an earlier one fails:
+    ///  - the closest peers to a random address can be found,
+    ///  - a majority of the close group of that address answers a query,
+    ///  - a small random chunk can be stored and then retrieved intact.
+    ///
+    /// Note that the last probe leaves a small chunk behind on the network.
+    pub async fn check_network(&self) -> NetworkCheckReport {
+        let target = XorName::random(&mut rand::thread_rng());
+        NetworkCheckReport {
+            probes: vec![
+                self.probe_closest_peers(target).await,
+                self.probe_close_group(target).await,
+                self.probe_chunk_round_trip().await,
+            ],
+        }
+    }
+
+    async fn probe_closest_peers(&self, target: XorName) -> ProbeResult {
+        let start = Instant::now();
+        let (passed, details) = match self.network.client_get_closest_peers(target).await {
+            Ok(peers) => (
+                peers.len() >= CLOSE_GROUP_SIZE,
+                format!("found {} of {CLOSE_GROUP_SIZE} peers", peers.len()),
+            ),
+            Err(error) => (false, error.to_string()),
+        };
+
+        ProbeResult {
+            name: "closest peers",
+            passed,
+            duration: start.elapsed(),
+            details,
+        }
+    }
+
+    async fn probe_close_group(&self, target: XorName) -> ProbeResult {
+        let start = Instant::now();
+        // Any response, including `ChunkNotFound`, means the peer is reachable.
+        let request = Request::Query(Query::GetChunk(ChunkAddress::new(target)));
+        let (passed, details) = match self.send_to_closest(request).await {
+            Ok(responses) => {
+                let responded = responses.iter().filter(|resp| resp.is_ok()).count();
+                (
+                    responded >= close_group_majority(),
+                    format!("{responded} of {} peers responded", responses.len()),
+                )
+            }
+            Err(error) => (false, error.to_string()),
+        };
+
+        ProbeResult {
+            name: "close group connectivity",
+            passed,
+            duration: start.elapsed(),
+            details,
+        }
+    }
+
+    async fn probe_chunk_round_trip(&self) -> ProbeResult {
+        let start = Instant::now();
+        let value: Vec<u8> = (0..PROBE_CHUNK_SIZE).map(|_| rand::random()).collect();
+        let chunk = Chunk::new(Bytes::from(value));
+        let address = *chunk.address();
+
+        let (passed, details) = match self.store_chunk(chunk.clone()).await {
+            Ok(()) => match self.get_chunk(address).await {
+                Ok(retrieved) if retrieved == chunk => {
+                    (true, format!("stored and retrieved {address:?}"))
+                }
+                Ok(_) => (false, format!("retrieved content of {address:?} differs")),
+                Err(error) => (false, format!("could not retrieve {address:?}: {error}")),
+            },
+            Err(error) => (false, format!("could not store {address:?}: {error}")),
+        };
+
+        ProbeResult {
+            name: "chunk round trip",
+            passed,
+            duration: start.elapsed(),
+            details,
+        }
+    }
+}
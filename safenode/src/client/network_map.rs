@@ -0,0 +1,174 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+
+use crate::{
+    node::to_node_id,
+    protocol::messages::{Query, QueryResponse, RecordDistribution, Request, Response},
+};
+
+use futures::{stream, StreamExt};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt, time::Duration};
+
+// Maximum number of lookups or queries in flight while mapping the network.
+const MAP_CONCURRENCY: usize = 8;
+// How long to wait for any single node to report its records.
+const MAP_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The records held by a single node, as found by [`Client::network_map`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeRecords {
+    /// The peer id of the node.
+    pub peer: String,
+    /// The records the node holds.
+    pub distribution: RecordDistribution,
+}
+
+/// The distribution of the records held across the xor namespace,
+/// as assembled by [`Client::network_map`] from the reports of the reachable nodes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NetworkMap {
+    /// The number of leading bits the records are bucketed by.
+    pub prefix_bits: u8,
+    /// The records held by each node that reported them.
+    pub nodes: Vec<NodeRecords>,
+    /// The peer ids of the nodes that were found, but did not report their records.
+    pub unreachable: Vec<String>,
+    /// The records held by all reporting nodes, so with every replica counted.
+    pub total: RecordDistribution,
+}
+
+impl fmt::Display for NetworkMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} nodes reported their records, {} did not respond",
+            self.nodes.len(),
+            self.unreachable.len()
+        )?;
+        let width = usize::from(self.prefix_bits).max(1);
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>10}",
+            "prefix", "chunks", "registers"
+        )?;
+        for (bucket, (chunks, registers)) in self
+            .total
+            .chunks
+            .iter()
+            .zip(&self.total.registers)
+            .enumerate()
+        {
+            let prefix = if self.prefix_bits == 0 {
+                "*".to_string()
+            } else {
+                format!("{bucket:0width$b}")
+            };
+            writeln!(f, "{prefix:<width$}  {chunks:>10}  {registers:>10}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Maps how the records stored on the network are distributed across the xor namespace,
+    /// bucketed by the given number of leading bits of their names.
+    ///
+    /// The nodes are found by looking up the closest peers to each bucket, and every node
+    /// found is asked for the records it holds. The map is only as complete as the set of
+    /// nodes that could be found and reached.
+    pub async fn network_map(&self, prefix_bits: u8) -> NetworkMap {
+        let mut total = RecordDistribution::new(prefix_bits);
+        let targets: Vec<_> = (0..total.chunks.len())
+            .map(|bucket| total.bucket_name(bucket))
+            .collect();
+
+        let lookups: Vec<_> = stream::iter(targets)
+            .map(|target| self.network.client_get_closest_peers(target))
+            .buffer_unordered(MAP_CONCURRENCY)
+            .collect()
+            .await;
+        let mut peers = BTreeSet::new();
+        for lookup in lookups {
+            match lookup {
+                Ok(found) => peers.extend(found),
+                Err(error) => warn!("Could not look up peers while mapping the network: {error}"),
+            }
+        }
+        debug!("Asking {} nodes for the records they hold", peers.len());
+
+        let prefix_bits = total.prefix_bits;
+        let reports: Vec<_> = stream::iter(peers)
+            .map(
+                |peer| async move { (peer, self.get_record_distribution(peer, prefix_bits).await) },
+            )
+            .buffer_unordered(MAP_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut nodes = Vec::new();
+        let mut unreachable = Vec::new();
+        for (peer, distribution) in reports {
+            match distribution {
+                Some(distribution) => {
+                    total.merge(&distribution);
+                    nodes.push(NodeRecords {
+                        peer: peer.to_string(),
+                        distribution,
+                    });
+                }
+                None => unreachable.push(peer.to_string()),
+            }
+        }
+        nodes.sort_by(|a, b| a.peer.cmp(&b.peer));
+        unreachable.sort();
+
+        NetworkMap {
+            prefix_bits,
+            nodes,
+            unreachable,
+            total,
+        }
+    }
+
+    async fn get_record_distribution(
+        &self,
+        peer: PeerId,
+        prefix_bits: u8,
+    ) -> Option<RecordDistribution> {
+        let request = Request::Query(Query::GetRecordDistribution {
+            node: *to_node_id(peer).name(),
+            prefix_bits,
+        });
+        let response = tokio::time::timeout(
+            MAP_RESPONSE_TIMEOUT,
+            self.network.send_request(request, peer),
+        )
+        .await;
+        match response {
+            Ok(Ok(Response::Query(QueryResponse::GetRecordDistribution(Ok(distribution))))) => {
+                Some(distribution)
+            }
+            Ok(Ok(other)) => {
+                warn!("Unexpected response from {peer:?} while mapping the network: {other:?}");
+                None
+            }
+            Ok(Err(error)) => {
+                warn!("Could not get the records of {peer:?}: {error}");
+                None
+            }
+            Err(_elapsed) => {
+                warn!("Timed out getting the records of {peer:?}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, AuditOutcome, AuditReport, Client, Files};
+
+use crate::protocol::address::ChunkAddress;
+
+use bincode::{deserialize, serialize};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::fs;
+
+// Filename for storing the pin list.
+const PINS_FILENAME: &str = "pins";
+
+/// Whether pinned content could be retrieved when it was last verified.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PinStatus {
+    /// The content has not been verified since it was pinned.
+    Unverified,
+    /// All the chunks of the content were intact.
+    Retrievable,
+    /// The given chunks of the content were missing or corrupted.
+    Degraded(Vec<ChunkAddress>),
+}
+
+/// Content that the user relies on being retrievable.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Pin {
+    /// The chunks the content is stored in.
+    pub chunks: Vec<ChunkAddress>,
+    /// When the content was pinned, in seconds since the unix epoch.
+    pub pinned_at: u64,
+    /// When the content was last verified, in seconds since the unix epoch.
+    pub verified_at: Option<u64>,
+    /// What the last verification found.
+    pub status: PinStatus,
+}
+
+/// The content pinned by a client, by its address.
+///
+/// The list is kept in the client dir, and can be stored on the network
+/// with [`Files::upload_pins`] to be shared, or restored elsewhere.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PinList {
+    pins: BTreeMap<ChunkAddress, Pin>,
+}
+
+impl PinList {
+    /// Loads the pin list from the given dir, or returns an empty list if there is none.
+    pub async fn load(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(PINS_FILENAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let bytes = fs::read(&path).await?;
+        Ok(deserialize(&bytes)?)
+    }
+
+    /// Writes the pin list to the given dir.
+    pub async fn store(&self, root_dir: &Path) -> Result<()> {
+        fs::write(root_dir.join(PINS_FILENAME), serialize(self)?).await?;
+        Ok(())
+    }
+
+    /// The pin of the content at the given address, if it is pinned.
+    pub fn get(&self, address: &ChunkAddress) -> Option<&Pin> {
+        self.pins.get(address)
+    }
+
+    /// All pinned content, by address.
+    pub fn iter(&self) -> impl Iterator<Item = (&ChunkAddress, &Pin)> {
+        self.pins.iter()
+    }
+
+    /// Unpins the content at the given address.
+    pub fn remove(&mut self, address: &ChunkAddress) -> Option<Pin> {
+        self.pins.remove(address)
+    }
+
+    /// Adds the pins of the other list that are not in this one.
+    pub fn merge(&mut self, other: PinList) {
+        for (address, pin) in other.pins {
+            let _ = self.pins.entry(address).or_insert(pin);
+        }
+    }
+
+    fn insert(&mut self, address: ChunkAddress, chunks: Vec<ChunkAddress>) {
+        let pin = Pin {
+            chunks,
+            pinned_at: now(),
+            verified_at: None,
+            status: PinStatus::Unverified,
+        };
+        let _ = self.pins.insert(address, pin);
+    }
+
+    // Updates the status of every pin whose chunks were all audited.
+    fn record(&mut self, report: &AuditReport) {
+        let outcomes: BTreeMap<_, _> = report
+            .audits
+            .iter()
+            .map(|audit| (audit.address, &audit.outcome))
+            .collect();
+        let verified_at = now();
+        for pin in self.pins.values_mut() {
+            if !pin
+                .chunks
+                .iter()
+                .all(|address| outcomes.contains_key(address))
+            {
+                continue;
+            }
+            let lost: Vec<_> = pin
+                .chunks
+                .iter()
+                .filter(|address| outcomes.get(address) != Some(&&AuditOutcome::Intact))
+                .copied()
+                .collect();
+            pin.verified_at = Some(verified_at);
+            pin.status = if lost.is_empty() {
+                PinStatus::Retrievable
+            } else {
+                PinStatus::Degraded(lost)
+            };
+        }
+    }
+}
+
+impl Files {
+    /// Pins the content at the given address, recording the chunks it is stored in.
+    pub async fn pin(&self, pins: &mut PinList, address: ChunkAddress) -> Result<()> {
+        let chunks = self.chunk_addresses(address).await?;
+        debug!("Pinning {address:?}, stored in {} chunks", chunks.len());
+        pins.insert(address, chunks);
+        Ok(())
+    }
+
+    /// Stores the pin list on the network, and returns its address.
+    pub async fn upload_pins(&self, pins: &PinList) -> Result<ChunkAddress> {
+        self.upload(Bytes::from(serialize(pins)?)).await
+    }
+
+    /// Retrieves the pin list at the given address.
+    pub async fn get_pins(&self, address: ChunkAddress) -> Result<PinList> {
+        let bytes = self.read_bytes(address).await?;
+        Ok(deserialize(&bytes)?)
+    }
+}
+
+impl Client {
+    /// Checks that all pinned content can still be retrieved, and updates the status of each pin.
+    pub async fn verify_pins(&self, pins: &mut PinList) -> AuditReport {
+        let chunks: BTreeSet<_> = pins
+            .pins
+            .values()
+            .flat_map(|pin| pin.chunks.iter().copied())
+            .collect();
+        let report = self.audit_chunks(chunks.into_iter().collect()).await;
+        pins.record(&report);
+        report
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::ChunkAudit;
+
+    use std::time::Duration;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn verification_updates_the_status_of_pins() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let a = ChunkAddress::new(XorName::random(&mut rng));
+        let b = ChunkAddress::new(XorName::random(&mut rng));
+        let c = ChunkAddress::new(XorName::random(&mut rng));
+        let mut pins = PinList::default();
+        pins.insert(a, vec![a, b]);
+        pins.insert(c, vec![c]);
+        assert_eq!(
+            pins.get(&a).map(|pin| &pin.status),
+            Some(&PinStatus::Unverified)
+        );
+
+        let audit = |address, outcome| ChunkAudit {
+            address,
+            outcome,
+            latency: Duration::ZERO,
+        };
+        pins.record(&AuditReport {
+            audits: vec![
+                audit(a, AuditOutcome::Intact),
+                audit(b, AuditOutcome::Missing("timed out".to_string())),
+            ],
+        });
+        assert_eq!(
+            pins.get(&a).map(|pin| &pin.status),
+            Some(&PinStatus::Degraded(vec![b]))
+        );
+        // Not all of its chunks were audited.
+        assert_eq!(
+            pins.get(&c).map(|pin| &pin.status),
+            Some(&PinStatus::Unverified)
+        );
+
+        let dir = tempfile::tempdir()?;
+        pins.store(dir.path()).await?;
+        assert_eq!(PinList::load(dir.path()).await?, pins);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Filename of the name of the current profile, in the profiles dir.
+const CURRENT_FILENAME: &str = "current";
+// Extension of the profile files, in the profiles dir.
+const PROFILE_EXTENSION: &str = "toml";
+
+/// A named set of client settings, e.g. to keep the wallet of a work identity apart
+/// from a personal one.
+///
+/// Every setting is optional, and those that are set on the command line take
+/// precedence over the ones of the profile.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// The dir the wallet, and so the credentials, of the profile are kept in.
+    pub client_dir: Option<PathBuf>,
+    /// The place the secret key of the wallet is kept, e.g. `keyring`.
+    pub key_store: Option<String>,
+    /// The gateway to reach the network through, instead of bootstrapping locally.
+    pub gateway: Option<String>,
+    /// The contacts file of the private network to join.
+    pub network_contacts: Option<PathBuf>,
+    /// The base to write addresses in, e.g. `base32z`.
+    pub address_base: Option<String>,
+    /// Whether to print details as json.
+    pub json: Option<bool>,
+}
+
+/// The profiles of a client, one toml file per profile, kept in a single dir.
+#[derive(Clone, Debug)]
+pub struct Profiles {
+    dir: PathBuf,
+}
+
+impl Profiles {
+    /// Opens the profiles in the given dir, creating it if it does not exist.
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// The dir the profiles are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Stores a new profile, failing if one with the same name already exists.
+    pub fn create(&self, name: &str, profile: &Profile) -> Result<()> {
+        let path = self.path(name)?;
+        if path.exists() {
+            return Err(Error::Profile(format!("the profile {name} already exists")));
+        }
+        let content = toml::to_string(profile).map_err(|err| Error::Profile(err.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Reads the profile with the given name.
+    pub fn load(&self, name: &str) -> Result<Profile> {
+        let path = self.path(name)?;
+        if !path.is_file() {
+            return Err(Error::Profile(format!("there is no profile named {name}")));
+        }
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| Error::Profile(format!("the profile {name}: {err}")))
+    }
+
+    /// The names of the profiles, in alphabetical order.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(PROFILE_EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Makes the profile with the given name the one used when none is named.
+    pub fn switch(&self, name: &str) -> Result<()> {
+        let _ = self.load(name)?;
+        fs::write(self.dir.join(CURRENT_FILENAME), name)?;
+        Ok(())
+    }
+
+    /// The name of the profile used when none is named, if one was switched to.
+    pub fn current(&self) -> Result<Option<String>> {
+        let path = self.dir.join(CURRENT_FILENAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let name = fs::read_to_string(path)?.trim().to_string();
+        Ok((!name.is_empty()).then_some(name))
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::Profile(format!(
+                "{name:?} is not a valid profile name, only letters, digits, '-' and '_' are"
+            )));
+        }
+        Ok(self.dir.join(name).with_extension(PROFILE_EXTENSION))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_can_be_created_listed_and_switched_to() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let profiles = Profiles::open(dir.path())?;
+        assert_eq!(profiles.current()?, None);
+
+        let work = Profile {
+            client_dir: Some(dir.path().join("work")),
+            key_store: Some("keyring".to_string()),
+            json: Some(true),
+            ..Default::default()
+        };
+        profiles.create("work", &work)?;
+        profiles.create("personal", &Profile::default())?;
+        assert!(profiles.create("work", &Profile::default()).is_err());
+        assert!(profiles.create("../work", &Profile::default()).is_err());
+
+        assert_eq!(profiles.list()?, vec!["personal", "work"]);
+        assert_eq!(profiles.load("work")?, work);
+
+        assert!(profiles.switch("unknown").is_err());
+        profiles.switch("work")?;
+        assert_eq!(profiles.current()?, Some("work".to_string()));
+        Ok(())
+    }
+}
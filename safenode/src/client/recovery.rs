@@ -0,0 +1,334 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    chunks::ErasureCoding,
+    dir_upload::{dir_files, FileUpload},
+    error::{Error, Result},
+    Files,
+};
+
+use crate::protocol::address::ChunkAddress;
+
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Dir of the records of the operations in flight, in the client dir.
+const RECOVERY_DIR: &str = "recovery";
+
+/// The state of an operation that can be resumed if it is interrupted.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OperationState {
+    /// The upload of the files of a directory, with [`Files::upload_dir_recoverable`].
+    UploadDir {
+        /// The directory.
+        dir: PathBuf,
+        /// The erasure coding the files are uploaded with, if any.
+        erasure_coding: Option<ErasureCoding>,
+        /// The files uploaded so far, by their path relative to the directory.
+        uploaded: BTreeMap<String, ChunkAddress>,
+    },
+}
+
+/// An operation recorded in a [`RecoveryStore`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PendingOperation {
+    /// A random id of the operation.
+    pub id: u64,
+    /// When the operation was started, in milliseconds since the unix epoch.
+    pub started_at: u64,
+    /// How far the operation got.
+    pub state: OperationState,
+}
+
+/// The records of the operations of the client that are in flight, in the client dir,
+/// so that those interrupted by a crash or a kill can be resumed or cleaned up later.
+///
+/// Each operation holds a lock on its record for as long as it runs. The OS releases it
+/// when the process exits, however it exits, so a record that is not locked is that of an
+/// interrupted operation.
+#[derive(Clone, Debug)]
+pub struct RecoveryStore {
+    dir: PathBuf,
+}
+
+impl RecoveryStore {
+    /// Opens the store in the given client dir.
+    pub fn open(client_dir: &Path) -> Result<Self> {
+        let dir = client_dir.join(RECOVERY_DIR);
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Records the start of an operation.
+    pub fn begin(&self, state: OperationState) -> Result<InFlight> {
+        let operation = PendingOperation {
+            id: rand::random(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_millis() as u64)
+                .unwrap_or_default(),
+            state,
+        };
+        let lock = self.lock(operation.id)?.ok_or_else(|| {
+            Error::Recovery(format!(
+                "operation {:016x} is already running",
+                operation.id
+            ))
+        })?;
+        let in_flight = InFlight {
+            dir: self.dir.clone(),
+            operation,
+            _lock: lock,
+        };
+        in_flight.store()?;
+        Ok(in_flight)
+    }
+
+    /// Returns the operations that were interrupted, i.e. whose process is gone.
+    pub fn interrupted(&self) -> Result<Vec<PendingOperation>> {
+        let mut operations = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let operation: PendingOperation = match fs::read(&path)
+                .ok()
+                .and_then(|record| serde_json::from_slice(&record).ok())
+            {
+                Some(operation) => operation,
+                None => {
+                    warn!("Skipping unreadable recovery record {path:?}");
+                    continue;
+                }
+            };
+            // Releasing the lock right away, the operation is only listed.
+            if self.lock(operation.id)?.is_some() {
+                operations.push(operation);
+            }
+        }
+        operations.sort_by_key(|operation| operation.started_at);
+        Ok(operations)
+    }
+
+    /// Takes over the interrupted operation with the given id, to resume it.
+    pub fn claim(&self, id: u64) -> Result<InFlight> {
+        let path = record_path(&self.dir, id);
+        if !path.is_file() {
+            return Err(Error::PendingOperationNotFound(id));
+        }
+        let lock = self.lock(id)?.ok_or_else(|| {
+            Error::Recovery(format!(
+                "operation {id:016x} is still running in another process"
+            ))
+        })?;
+        let operation = serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|err| Error::Recovery(format!("unreadable record {path:?}: {err}")))?;
+        Ok(InFlight {
+            dir: self.dir.clone(),
+            operation,
+            _lock: lock,
+        })
+    }
+
+    /// Drops the record of the interrupted operation with the given id, without resuming it.
+    ///
+    /// The chunks it uploaded stay on the network, they are just no longer tracked.
+    pub fn discard(&self, id: u64) -> Result<()> {
+        self.claim(id)?.finish()
+    }
+
+    // Takes the lock of the operation, or returns None if another operation holds it.
+    fn lock(&self, id: u64) -> Result<Option<File>> {
+        let lock = File::create(self.dir.join(format!("{id:016x}.lock")))?;
+        match lock.try_lock_exclusive() {
+            Ok(()) => Ok(Some(lock)),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// An operation that is running, holding the lock on its record.
+///
+/// Dropping it leaves the record behind, to be found by [`RecoveryStore::interrupted`],
+/// as if the process had crashed. Call [`InFlight::finish`] once the operation is done.
+#[derive(Debug)]
+pub struct InFlight {
+    dir: PathBuf,
+    operation: PendingOperation,
+    _lock: File,
+}
+
+impl InFlight {
+    /// The operation.
+    pub fn operation(&self) -> &PendingOperation {
+        &self.operation
+    }
+
+    /// Records the progress of the operation.
+    pub fn update(&mut self, update: impl FnOnce(&mut OperationState)) -> Result<()> {
+        update(&mut self.operation.state);
+        self.store()
+    }
+
+    /// Removes the record of the operation, which is done.
+    pub fn finish(self) -> Result<()> {
+        let id = self.operation.id;
+        fs::remove_file(record_path(&self.dir, id))?;
+        // Another process could only take the lock now, and then find no record.
+        let _ = fs::remove_file(self.dir.join(format!("{id:016x}.lock")));
+        Ok(())
+    }
+
+    // Writes the record to a temporary file first, so that a crash while writing it
+    // does not leave a truncated record.
+    fn store(&self) -> Result<()> {
+        let path = record_path(&self.dir, self.operation.id);
+        let temp_path = path.with_extension("tmp");
+        let record =
+            serde_json::to_vec(&self.operation).map_err(|err| Error::Recovery(err.to_string()))?;
+        fs::write(&temp_path, record)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+}
+
+impl Files {
+    /// Like [`Files::upload_dir_with`], recording which files are uploaded in the store,
+    /// so that an interrupted upload can be resumed with [`Files::resume_operation`].
+    ///
+    /// The record is removed once every file is uploaded. If any file fails, it is kept,
+    /// for the upload of the files left to be resumed.
+    pub async fn upload_dir_recoverable(
+        &self,
+        store: &RecoveryStore,
+        dir: &Path,
+        jobs: usize,
+        coding: Option<ErasureCoding>,
+        on_done: impl FnMut(FileUpload),
+    ) -> Result<()> {
+        let in_flight = store.begin(OperationState::UploadDir {
+            dir: dir.to_path_buf(),
+            erasure_coding: coding,
+            uploaded: BTreeMap::new(),
+        })?;
+        self.run_operation(in_flight, jobs, on_done).await
+    }
+
+    /// Resumes the interrupted operation with the given id, only doing what is left of it.
+    pub async fn resume_operation(
+        &self,
+        store: &RecoveryStore,
+        id: u64,
+        jobs: usize,
+        on_done: impl FnMut(FileUpload),
+    ) -> Result<()> {
+        let in_flight = store.claim(id)?;
+        self.run_operation(in_flight, jobs, on_done).await
+    }
+
+    async fn run_operation(
+        &self,
+        mut in_flight: InFlight,
+        jobs: usize,
+        mut on_done: impl FnMut(FileUpload),
+    ) -> Result<()> {
+        let OperationState::UploadDir {
+            dir,
+            erasure_coding,
+            uploaded,
+        } = in_flight.operation().state.clone();
+
+        let files: Vec<_> = dir_files(&dir)
+            .into_iter()
+            .filter(|(path, _)| !uploaded.contains_key(path))
+            .collect();
+        info!(
+            "Uploading {} files of {dir:?} left of operation {:016x}",
+            files.len(),
+            in_flight.operation().id
+        );
+
+        let mut failed = 0;
+        self.upload_files_with(files, jobs, erasure_coding, |upload| {
+            match upload.result {
+                Ok(address) => {
+                    let recorded = in_flight.update(|state| {
+                        let OperationState::UploadDir { uploaded, .. } = state;
+                        let _ = uploaded.insert(upload.path.clone(), address);
+                    });
+                    if let Err(error) = recorded {
+                        warn!("Could not record the upload of {}: {error}", upload.path);
+                    }
+                }
+                Err(_) => failed += 1,
+            }
+            on_done(upload);
+        })
+        .await;
+
+        if failed == 0 {
+            in_flight.finish()?;
+        }
+        Ok(())
+    }
+}
+
+fn record_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:016x}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+    use xor_name::XorName;
+
+    #[test]
+    fn only_operations_of_gone_processes_are_interrupted() -> Result<()> {
+        let client_dir = tempfile::tempdir()?;
+        let store = RecoveryStore::open(client_dir.path())?;
+        let mut in_flight = store.begin(OperationState::UploadDir {
+            dir: PathBuf::from("site"),
+            erasure_coding: None,
+            uploaded: BTreeMap::new(),
+        })?;
+        let id = in_flight.operation().id;
+        assert!(store.interrupted()?.is_empty());
+        assert!(store.claim(id).is_err());
+
+        let address = ChunkAddress::new(XorName::from_content(b"index"));
+        in_flight.update(|state| {
+            let OperationState::UploadDir { uploaded, .. } = state;
+            let _ = uploaded.insert("index.html".to_string(), address);
+        })?;
+        // As if the process was killed.
+        drop(in_flight);
+
+        let interrupted = store.interrupted()?;
+        assert_eq!(interrupted.len(), 1);
+        let OperationState::UploadDir { uploaded, .. } = &interrupted[0].state;
+        assert_eq!(uploaded.get("index.html"), Some(&address));
+
+        store.discard(id)?;
+        assert!(store.interrupted()?.is_empty());
+        assert!(matches!(
+            store.claim(id),
+            Err(Error::PendingOperationNotFound(_))
+        ));
+        Ok(())
+    }
+}
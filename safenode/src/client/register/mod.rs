@@ -7,14 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod offline_replica;
+mod policy_file;
 
-pub use offline_replica::RegisterOffline;
+pub use self::{
+    offline_replica::RegisterOffline,
+    policy_file::{PolicyFile, PolicyTemplate},
+};
 
 use super::{error::Result, Client};
 
-use crate::protocol::register::{Entry, EntryHash, Policy};
+use crate::protocol::register::{Entry, EntryHash, Permissions, Policy, RegisterInfo, User};
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use xor_name::XorName;
 
 /// Operations made to a Register instance are applied not only locally,
@@ -32,6 +36,20 @@ impl Register {
         Ok(Self { offline_reg })
     }
 
+    /// Create a new Register, with the given permissions for users other than its owner,
+    /// e.g. as compiled from a [`PolicyFile`].
+    pub async fn create_with_permissions(
+        client: Client,
+        name: XorName,
+        tag: u64,
+        permissions: BTreeMap<User, Permissions>,
+    ) -> Result<Self> {
+        let mut offline_reg =
+            RegisterOffline::create_with_permissions(client, name, tag, permissions)?;
+        offline_reg.push().await?;
+        Ok(Self { offline_reg })
+    }
+
     /// Retrieve a Register from the network.
     pub async fn retrieve(client: Client, name: XorName, tag: u64) -> Result<Self> {
         let offline_reg = RegisterOffline::retrieve(client, name, tag).await?;
@@ -58,6 +76,11 @@ impl Register {
         self.offline_reg.tag()
     }
 
+    /// Return a summary of the state of the Register, with the metadata of all its entries.
+    pub fn info(&self) -> RegisterInfo {
+        self.offline_reg.info()
+    }
+
     /// Return the number of items held in the register
     pub fn size(&self) -> u64 {
         self.offline_reg.size()
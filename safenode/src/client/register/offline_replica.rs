@@ -19,11 +19,14 @@ use crate::protocol::{
         Cmd, CmdResponse, CreateRegister, EditRegister, Query, QueryResponse, RegisterCmd,
         RegisterQuery, Request, Response, SignedRegisterCreate, SignedRegisterEdit,
     },
-    register::{Action, Entry, EntryHash, Permissions, Policy, Register as RegisterReplica, User},
+    register::{
+        Action, Entry, EntryHash, Permissions, Policy, Register as RegisterReplica, RegisterInfo,
+        User,
+    },
 };
 
 use bincode::serialize;
-use std::collections::{BTreeSet, LinkedList};
+use std::collections::{BTreeMap, BTreeSet, LinkedList};
 use xor_name::XorName;
 
 /// Ops made to an offline Register instance are applied locally only,
@@ -38,7 +41,20 @@ pub struct RegisterOffline {
 impl RegisterOffline {
     /// Create a new Register offline.
     pub fn create(client: Client, name: XorName, tag: u64) -> Result<Self> {
-        Self::new(client, name, tag)
+        let permissions = [(User::Anyone, Permissions::new(true))]
+            .into_iter()
+            .collect();
+        Self::new(client, name, tag, permissions)
+    }
+
+    /// Create a new Register offline, with the given permissions for users other than its owner.
+    pub fn create_with_permissions(
+        client: Client,
+        name: XorName,
+        tag: u64,
+        permissions: BTreeMap<User, Permissions>,
+    ) -> Result<Self> {
+        Self::new(client, name, tag, permissions)
     }
 
     /// Retrieve a Register from the network to work on it offline.
@@ -82,6 +98,11 @@ impl RegisterOffline {
         self.register.tag()
     }
 
+    /// Return a summary of the state of the Register, with the metadata of all its entries.
+    pub fn info(&self) -> RegisterInfo {
+        self.register.info()
+    }
+
     /// Return the number of items held in the register
     pub fn size(&self) -> u64 {
         self.register.size()
@@ -143,7 +164,7 @@ impl RegisterOffline {
         };
         let auth = DataAuthority {
             public_key,
-            signature: self.client.sign(&serialize(&op)?),
+            signature: self.client.sign(&serialize(&op)?)?,
         };
         let cmd = RegisterCmd::Edit(SignedRegisterEdit { op, auth });
 
@@ -192,16 +213,16 @@ impl RegisterOffline {
 
     // ********* Private helpers  *********
 
-    // Create a new RegisterOffline instance with the given name and tag.
-    fn new(client: Client, name: XorName, tag: u64) -> Result<Self> {
+    // Create a new RegisterOffline instance with the given name, tag and permissions.
+    fn new(
+        client: Client,
+        name: XorName,
+        tag: u64,
+        permissions: BTreeMap<User, Permissions>,
+    ) -> Result<Self> {
         let public_key = client.signer_pk();
         let owner = User::Key(public_key);
-        let policy = Policy {
-            owner,
-            permissions: [(User::Anyone, Permissions::new(true))]
-                .into_iter()
-                .collect(),
-        };
+        let policy = Policy { owner, permissions };
 
         let op = CreateRegister {
             name,
@@ -210,7 +231,7 @@ impl RegisterOffline {
         };
         let auth = DataAuthority {
             public_key,
-            signature: client.sign(&serialize(&op)?),
+            signature: client.sign(&serialize(&op)?)?,
         };
         let create_cmd = RegisterCmd::Create(SignedRegisterCreate { op, auth });
 
@@ -291,22 +312,28 @@ impl RegisterOffline {
         let request = Request::Query(Query::Register(RegisterQuery::Get(address)));
         let responses = client.send_to_closest(request).await?;
 
-        // We will return the first register we get.
+        // We will return the first register we get that is at the address asked for.
+        let mut rejection = None;
         for resp in responses.iter().flatten() {
             if let Response::Query(QueryResponse::GetRegister(Ok(register))) = resp {
-                return Ok(register.clone());
+                match client.verifications().register(&address, register) {
+                    Ok(()) => return Ok(register.clone()),
+                    Err(err) => rejection = Some(err),
+                }
             };
         }
 
         // If no register was gotten, we will return the first error sent to us.
         for resp in responses.iter().flatten() {
-            if let Response::Query(QueryResponse::GetChunk(result)) = resp {
-                let _ = result.clone()?;
+            if let Response::Query(QueryResponse::GetRegister(Err(err))) = resp {
+                return Err(err.clone().into());
             };
         }
 
-        // If there were no success or fail to the expected query,
-        // we check if there were any send errors.
+        // Otherwise, all registers returned were rejected, or we check if there were any send errors.
+        if let Some(err) = rejection {
+            return Err(err);
+        }
         for resp in responses {
             let _ = resp?;
         }
@@ -0,0 +1,186 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::super::error::{Error, Result};
+
+use crate::protocol::{
+    authority::PublicKey,
+    register::{Permissions, User},
+};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// The permissions of the most common kinds of Registers.
+///
+/// Whatever the template, the owner can always write, and anyone can read:
+/// the content of a Register is public.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyTemplate {
+    /// Only the owner can write.
+    #[default]
+    Private,
+    /// Anyone can write, i.e. append entries.
+    PublicAppend,
+    /// Only the owner and the writers listed in the policy file can write.
+    TeamWrite,
+}
+
+/// The permissions of a Register, as written in a toml policy file, e.g.
+///
+/// ```toml
+/// template = "team-write"
+/// writers = ["<hex encoded public key>", "<hex encoded public key>"]
+/// denied = ["<hex encoded public key>"]
+/// ```
+///
+/// The policy file is compiled into the permissions of the Register when it is created,
+/// see [`PolicyFile::compile`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    /// The template the permissions start from.
+    #[serde(default)]
+    pub template: PolicyTemplate,
+    /// The hex encoded public keys of the users who can write, besides the owner.
+    #[serde(default)]
+    pub writers: Vec<String>,
+    /// The hex encoded public keys of the users who cannot write,
+    /// even if the template lets anyone write.
+    #[serde(default)]
+    pub denied: Vec<String>,
+}
+
+impl From<PolicyTemplate> for PolicyFile {
+    fn from(template: PolicyTemplate) -> Self {
+        Self {
+            template,
+            ..Default::default()
+        }
+    }
+}
+
+impl PolicyFile {
+    /// Reads the policy file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|err| Error::InvalidPolicy(format!("{path:?}: {err}")))
+    }
+
+    /// Compiles the policy into the permissions of a Register owned by the given key,
+    /// checking up front that they can be applied as written.
+    pub fn compile(&self, owner: PublicKey) -> Result<BTreeMap<User, Permissions>> {
+        let writers = parse_keys(&self.writers, "writer")?;
+        let denied = parse_keys(&self.denied, "denied user")?;
+        if let Some(key) = writers.intersection(&denied).next() {
+            return Err(Error::InvalidPolicy(format!(
+                "{} is both a writer and denied",
+                hex::encode(key.to_bytes())
+            )));
+        }
+        if denied.contains(&owner) {
+            return Err(Error::InvalidPolicy(
+                "the owner cannot be denied, it can always write".to_string(),
+            ));
+        }
+        if self.template == PolicyTemplate::TeamWrite && writers.is_empty() {
+            return Err(Error::InvalidPolicy(
+                "a team-write Register needs at least one writer".to_string(),
+            ));
+        }
+
+        let anyone_can_write = self.template == PolicyTemplate::PublicAppend;
+        let mut permissions = BTreeMap::from([(User::Anyone, Permissions::new(anyone_can_write))]);
+        for key in writers {
+            let _ = permissions.insert(User::Key(key), Permissions::new(true));
+        }
+        for key in denied {
+            let _ = permissions.insert(User::Key(key), Permissions::new(false));
+        }
+        Ok(permissions)
+    }
+}
+
+fn parse_keys(keys: &[String], role: &str) -> Result<BTreeSet<PublicKey>> {
+    keys.iter()
+        .map(|key| {
+            hex::decode(key.trim())
+                .ok()
+                .and_then(|bytes| <[u8; bls::PK_SIZE]>::try_from(bytes.as_slice()).ok())
+                .and_then(|bytes| PublicKey::from_bytes(bytes).ok())
+                .ok_or_else(|| {
+                    Error::InvalidPolicy(format!(
+                        "the {role} {key:?} is not a hex encoded public key"
+                    ))
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+
+    fn hex_key(key: &bls::SecretKey) -> String {
+        hex::encode(key.public_key().to_bytes())
+    }
+
+    #[test]
+    fn policy_files_compile_to_permissions() -> Result<()> {
+        let owner = bls::SecretKey::random();
+        let writer = bls::SecretKey::random();
+        let troll = bls::SecretKey::random();
+
+        let policy: PolicyFile = toml::from_str(&format!(
+            "template = \"public-append\"\ndenied = [\"{}\"]",
+            hex_key(&troll)
+        ))?;
+        let permissions = policy.compile(owner.public_key())?;
+        assert_eq!(permissions[&User::Anyone], Permissions::new(true));
+        assert_eq!(
+            permissions[&User::Key(troll.public_key())],
+            Permissions::new(false)
+        );
+
+        let team = PolicyFile {
+            template: PolicyTemplate::TeamWrite,
+            writers: vec![hex_key(&writer)],
+            ..Default::default()
+        };
+        let permissions = team.compile(owner.public_key())?;
+        assert_eq!(permissions[&User::Anyone], Permissions::new(false));
+        assert_eq!(
+            permissions[&User::Key(writer.public_key())],
+            Permissions::new(true)
+        );
+
+        // Policies that cannot be applied as written are rejected up front.
+        let no_team = PolicyFile::from(PolicyTemplate::TeamWrite);
+        assert!(no_team.compile(owner.public_key()).is_err());
+        let owner_denied = PolicyFile {
+            denied: vec![hex_key(&owner)],
+            ..Default::default()
+        };
+        assert!(owner_denied.compile(owner.public_key()).is_err());
+        let both = PolicyFile {
+            writers: vec![hex_key(&writer)],
+            denied: vec![hex_key(&writer)],
+            ..Default::default()
+        };
+        assert!(both.compile(owner.public_key()).is_err());
+        assert!(toml::from_str::<PolicyFile>("template = \"everyone\"").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::messages::{Cmd, Query, Request};
+
+use rand::Rng;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// The kinds of requests that can be retried with different policies.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RetryOperation {
+    /// Read-only requests, other than those for spends.
+    Query,
+    /// Writes, other than spends.
+    Cmd,
+    /// Spending dbcs and querying spends.
+    Spend,
+}
+
+impl RetryOperation {
+    pub(super) fn of(request: &Request) -> Self {
+        match request {
+            Request::Cmd(Cmd::SpendDbc { .. }) | Request::Query(Query::Spend(_)) => Self::Spend,
+            Request::Query(_) => Self::Query,
+            Request::Cmd(_) | Request::Event(_) => Self::Cmd,
+        }
+    }
+}
+
+/// How often, and how patiently, a request is retried.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is sent, including the first time.
+    pub max_attempts: u32,
+    /// The time waited before the first retry. It is doubled for every retry after that.
+    pub base_backoff: Duration,
+    /// The longest time waited before any retry.
+    pub max_backoff: Duration,
+    /// Whether to wait a random time between half of and the full backoff,
+    /// so that clients that failed at the same time do not all retry at the same time.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The time to wait before the given retry, where the first retry is 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31);
+        let backoff = self
+            .base_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        if self.jitter && !backoff.is_zero() {
+            rand::thread_rng().gen_range(backoff / 2..=backoff)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// The retry policies of a client.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RetryConfig {
+    /// The policy used for any operation without an override.
+    pub default: RetryPolicy,
+    /// Policies for specific operations.
+    pub overrides: BTreeMap<RetryOperation, RetryPolicy>,
+}
+
+impl RetryConfig {
+    /// Uses the given policy for the operation, instead of the default one.
+    pub fn with_override(mut self, operation: RetryOperation, policy: RetryPolicy) -> Self {
+        let _ = self.overrides.insert(operation, policy);
+        self
+    }
+
+    /// The policy used for the operation.
+    pub fn policy(&self, operation: RetryOperation) -> &RetryPolicy {
+        self.overrides.get(&operation).unwrap_or(&self.default)
+    }
+}
+
+/// The number of retries a client has made, per operation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RetryStats {
+    /// Retries of queries.
+    pub queries: u64,
+    /// Retries of cmds.
+    pub cmds: u64,
+    /// Retries of spends.
+    pub spends: u64,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct RetryCounters {
+    queries: AtomicU64,
+    cmds: AtomicU64,
+    spends: AtomicU64,
+}
+
+impl RetryCounters {
+    pub(super) fn increment(&self, operation: RetryOperation) {
+        let counter = match operation {
+            RetryOperation::Query => &self.queries,
+            RetryOperation::Cmd => &self.cmds,
+            RetryOperation::Spend => &self.spends,
+        };
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn stats(&self) -> RetryStats {
+        RetryStats {
+            queries: self.queries.load(Ordering::Relaxed),
+            cmds: self.cmds.load(Ordering::Relaxed),
+            spends: self.spends.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: false,
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_the_backoff() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= policy.base_backoff / 2 && backoff <= policy.base_backoff);
+        }
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let spend_policy = RetryPolicy {
+            max_attempts: 10,
+            ..Default::default()
+        };
+        let config = RetryConfig::default().with_override(RetryOperation::Spend, spend_policy);
+
+        assert_eq!(config.policy(RetryOperation::Spend), &spend_policy);
+        assert_eq!(
+            config.policy(RetryOperation::Query),
+            &RetryPolicy::default()
+        );
+    }
+}
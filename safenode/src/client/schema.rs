@@ -0,0 +1,251 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Files,
+};
+
+use crate::protocol::address::ChunkAddress;
+
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The bytes typed payloads start with, to tell them apart from any other data.
+const TYPED_PAYLOAD_MAGIC: &[u8] = b"SAFETYPE";
+
+/// A type of app data, stored as a typed payload, see [`SchemaRegistry`].
+///
+/// The version is to be bumped whenever the serialised form of the type changes,
+/// so that data written with another version is refused instead of misread.
+pub trait Schema: Serialize + DeserializeOwned {
+    /// The id of the schema, unique among the schemas of an app, e.g. `myapp/profile`.
+    const ID: &'static str;
+    /// The version of the schema.
+    const VERSION: u16;
+}
+
+/// The header written before the serialised value of a typed payload.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SchemaHeader {
+    /// The id of the schema of the value.
+    pub id: String,
+    /// The version of the schema the value was written with.
+    pub version: u16,
+}
+
+// Checks that a payload deserialises as the type of a schema.
+type Validator = fn(&[u8]) -> bincode::Result<()>;
+
+/// The schemas of the typed payloads an app reads and writes.
+///
+/// A typed payload is a value serialised after a header naming its schema and version,
+/// so that apps do not each have to frame their data inside raw bytes, and data of
+/// another type or version is refused with a clear error when it is read.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<&'static str, (u16, Validator)>,
+}
+
+impl SchemaRegistry {
+    /// Registers the schema, failing if another version of it is registered already.
+    pub fn register<T: Schema>(&mut self) -> Result<()> {
+        if let Some((version, _)) = self.schemas.get(T::ID) {
+            if *version != T::VERSION {
+                return Err(Error::SchemaVersionMismatch {
+                    schema: T::ID.to_string(),
+                    expected: *version,
+                    found: T::VERSION,
+                });
+            }
+        }
+        let validator: Validator = |payload| bincode::deserialize::<T>(payload).map(|_| ());
+        let _ = self.schemas.insert(T::ID, (T::VERSION, validator));
+        Ok(())
+    }
+
+    /// Serialises the value as a typed payload.
+    pub fn encode<T: Schema>(&self, value: &T) -> Result<Bytes> {
+        let _ = self.registered(T::ID)?;
+        let header = SchemaHeader {
+            id: T::ID.to_string(),
+            version: T::VERSION,
+        };
+        let mut bytes = TYPED_PAYLOAD_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(&header)?);
+        bytes.extend(bincode::serialize(value)?);
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Deserialises a typed payload, checking that it is of the schema and version of `T`.
+    pub fn decode<T: Schema>(&self, bytes: &[u8]) -> Result<T> {
+        let _ = self.registered(T::ID)?;
+        let (header, payload) = unframe(bytes)?;
+        if header.id != T::ID {
+            return Err(Error::SchemaMismatch {
+                expected: T::ID.to_string(),
+                found: header.id,
+            });
+        }
+        check_version(&header, T::VERSION)?;
+        bincode::deserialize(payload).map_err(|err| invalid_payload(&header, &err))
+    }
+
+    /// Checks that the bytes are a valid typed payload of any registered schema,
+    /// returning its header.
+    pub fn validate(&self, bytes: &[u8]) -> Result<SchemaHeader> {
+        let (header, payload) = unframe(bytes)?;
+        let (version, validator) = self.registered(&header.id)?;
+        check_version(&header, version)?;
+        validator(payload).map_err(|err| invalid_payload(&header, &err))?;
+        Ok(header)
+    }
+
+    fn registered(&self, id: &str) -> Result<(u16, Validator)> {
+        self.schemas
+            .get(id)
+            .copied()
+            .ok_or_else(|| Error::UnknownSchema(id.to_string()))
+    }
+}
+
+impl Files {
+    /// Uploads the value as a typed payload of its schema.
+    pub async fn upload_typed<T: Schema>(
+        &self,
+        schemas: &SchemaRegistry,
+        value: &T,
+    ) -> Result<ChunkAddress> {
+        self.upload(schemas.encode(value)?).await
+    }
+
+    /// Reads the typed payload at the address, checking that it is of the schema
+    /// and version of `T`.
+    pub async fn read_typed<T: Schema>(
+        &self,
+        schemas: &SchemaRegistry,
+        address: ChunkAddress,
+    ) -> Result<T> {
+        schemas.decode(&self.read_bytes(address).await?)
+    }
+}
+
+// Splits a typed payload into its header and the serialised value.
+fn unframe(bytes: &[u8]) -> Result<(SchemaHeader, &[u8])> {
+    let mut payload = bytes
+        .strip_prefix(TYPED_PAYLOAD_MAGIC)
+        .ok_or(Error::NotTyped)?;
+    let header = bincode::deserialize_from(&mut payload).map_err(|_| Error::NotTyped)?;
+    Ok((header, payload))
+}
+
+fn check_version(header: &SchemaHeader, expected: u16) -> Result<()> {
+    if header.version != expected {
+        return Err(Error::SchemaVersionMismatch {
+            schema: header.id.clone(),
+            expected,
+            found: header.version,
+        });
+    }
+    Ok(())
+}
+
+fn invalid_payload(header: &SchemaHeader, err: &bincode::Error) -> Error {
+    Error::InvalidTypedPayload(format!(
+        "version {} of the schema {:?}: {err}",
+        header.version, header.id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Profile {
+        name: String,
+    }
+
+    impl Schema for Profile {
+        const ID: &'static str = "test/profile";
+        const VERSION: u16 = 2;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct OldProfile {
+        name: String,
+        age: u8,
+    }
+
+    impl Schema for OldProfile {
+        const ID: &'static str = "test/profile";
+        const VERSION: u16 = 1;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Note(String);
+
+    impl Schema for Note {
+        const ID: &'static str = "test/note";
+        const VERSION: u16 = 1;
+    }
+
+    #[test]
+    fn typed_payloads_are_only_read_as_their_schema_and_version() -> Result<()> {
+        let mut schemas = SchemaRegistry::default();
+        schemas.register::<Profile>()?;
+        schemas.register::<Note>()?;
+        assert!(matches!(
+            schemas.register::<OldProfile>(),
+            Err(Error::SchemaVersionMismatch { .. })
+        ));
+
+        let profile = Profile {
+            name: "alice".to_string(),
+        };
+        let bytes = schemas.encode(&profile)?;
+        assert_eq!(schemas.decode::<Profile>(&bytes)?, profile);
+        assert_eq!(schemas.validate(&bytes)?.version, Profile::VERSION);
+        assert!(matches!(
+            schemas.decode::<Note>(&bytes),
+            Err(Error::SchemaMismatch { .. })
+        ));
+
+        // Data written by an app still on the old version of the schema.
+        let mut old_schemas = SchemaRegistry::default();
+        old_schemas.register::<OldProfile>()?;
+        let old = old_schemas.encode(&OldProfile {
+            name: "bob".to_string(),
+            age: 30,
+        })?;
+        assert!(matches!(
+            schemas.decode::<Profile>(&old),
+            Err(Error::SchemaVersionMismatch {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            old_schemas.decode::<Note>(&old),
+            Err(Error::UnknownSchema(_))
+        ));
+
+        assert!(matches!(
+            schemas.decode::<Profile>(b"raw bytes"),
+            Err(Error::NotTyped)
+        ));
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(
+            schemas.validate(truncated),
+            Err(Error::InvalidTypedPayload(_))
+        ));
+        Ok(())
+    }
+}
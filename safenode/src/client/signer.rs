@@ -0,0 +1,134 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use crate::protocol::wallet::{LocalWallet, Wallet};
+
+use bls::{PublicKey, SecretKey, Signature, PK_SIZE, SIG_SIZE};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Produces the signatures with which a client proves ownership of its data.
+///
+/// This lets the secret key be kept outside of the client,
+/// for example in a separate process or on a hardware device.
+pub trait Signer: Send + Sync {
+    /// The public key that the signatures can be verified with.
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs the given data.
+    fn sign(&self, data: &[u8]) -> Result<Signature>;
+}
+
+impl Signer for SecretKey {
+    fn public_key(&self) -> PublicKey {
+        SecretKey::public_key(self)
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Signature> {
+        Ok(SecretKey::sign(self, data))
+    }
+}
+
+impl Signer for LocalWallet {
+    fn public_key(&self) -> PublicKey {
+        self.address().public_key()
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Signature> {
+        Ok(LocalWallet::sign(self, data))
+    }
+}
+
+/// A [`Signer`] that delegates to an external program.
+///
+/// The program is run as `<program> public-key`, and should then print
+/// the hex encoded public key. To sign, it is run as `<program> sign`,
+/// gets the hex encoded data on stdin, and should print the hex encoded signature.
+#[derive(Clone, Debug)]
+pub struct CommandSigner {
+    program: PathBuf,
+    public_key: PublicKey,
+}
+
+impl CommandSigner {
+    /// Asks the program for its public key, and returns a signer using it.
+    pub fn new(program: &Path) -> Result<Self> {
+        let output = run(program, "public-key", None)?;
+        let bytes: [u8; PK_SIZE] = decode_output(&output)?;
+        let public_key = PublicKey::from_bytes(bytes)
+            .map_err(|err| Error::Signer(format!("invalid public key: {err}")))?;
+
+        Ok(Self {
+            program: program.to_path_buf(),
+            public_key,
+        })
+    }
+}
+
+impl Signer for CommandSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Signature> {
+        let output = run(&self.program, "sign", Some(hex::encode(data)))?;
+        let bytes: [u8; SIG_SIZE] = decode_output(&output)?;
+        let signature = Signature::from_bytes(bytes)
+            .map_err(|err| Error::Signer(format!("invalid signature: {err}")))?;
+
+        if !self.public_key.verify(&signature, data) {
+            return Err(Error::Signer(
+                "signature does not match the public key".to_string(),
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+// Runs the program with the given argument, feeding it the input if any, and returns its stdout.
+fn run(program: &Path, arg: &str, input: Option<String>) -> Result<String> {
+    let signer_error = |err: std::io::Error| Error::Signer(format!("{program:?} {arg}: {err}"));
+
+    let mut child = Command::new(program)
+        .arg(arg)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(signer_error)?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Some(input) = input {
+            stdin.write_all(input.as_bytes()).map_err(signer_error)?;
+        }
+        // Dropping stdin closes it, so that the program knows the input is complete.
+    }
+
+    let output = child.wait_with_output().map_err(signer_error)?;
+    if !output.status.success() {
+        return Err(Error::Signer(format!(
+            "{program:?} {arg} exited with {}",
+            output.status
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|err| Error::Signer(format!("{program:?} {arg}: {err}")))
+}
+
+fn decode_output<const N: usize>(output: &str) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(output.trim(), &mut bytes)
+        .map_err(|err| Error::Signer(format!("could not decode {output:?}: {err}")))?;
+    Ok(bytes)
+}
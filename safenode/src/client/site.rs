@@ -0,0 +1,472 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Client, ErrorKind, Files, PolicyFile, Register,
+};
+
+use crate::protocol::address::{decode_name, ChunkAddress};
+
+use bincode::{deserialize, serialize};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use xor_name::XorName;
+
+/// The tag of the Registers pointing at the current site map of each site.
+pub const SITE_REGISTER_TAG: u64 = 3007;
+
+/// The names of a site, and the container each of them points to.
+///
+/// A site is published as a whole: its Register points at its current site map,
+/// so that all its names change at once.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SiteMap {
+    /// The address of the archive each name points to.
+    pub names: BTreeMap<String, ChunkAddress>,
+}
+
+/// What a [`SitePublish`] changed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SitePublishReport {
+    /// The site map the site pointed to before, if it was published before.
+    pub previous: Option<ChunkAddress>,
+    /// The site map the site points to now.
+    pub current: ChunkAddress,
+    /// The address each staged container was uploaded to.
+    pub containers: BTreeMap<String, ChunkAddress>,
+    /// The names that point somewhere else, or nowhere, now.
+    pub changed_names: Vec<String>,
+}
+
+/// Changes of the names of a site, as written in a json changes file, e.g.
+///
+/// ```json
+/// {
+///   "add": { "manual": "<address of an archive>" },
+///   "update": { "docs": "<address of an archive>" },
+///   "remove": ["beta"]
+/// }
+/// ```
+///
+/// Names are pointed at archives uploaded before, their addresses written in any base.
+/// Added names must be new to the site, and updated or removed names must be in it,
+/// else none of the changes are published, see [`SitePublish::apply_changes`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteChanges {
+    /// The names to add, and the addresses of the archives they point to.
+    #[serde(default)]
+    pub add: BTreeMap<String, String>,
+    /// The names to point at other archives, and the addresses of those.
+    #[serde(default)]
+    pub update: BTreeMap<String, String>,
+    /// The names to remove.
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+impl SiteChanges {
+    /// Reads the changes file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|err| Error::SitePublish(format!("{path:?} is not a changes file: {err}")))
+    }
+}
+
+/// Updates of several containers and names of a site, published all at once.
+///
+/// The containers are uploaded first, then the new site map. The site is only pointed
+/// at the new site map once all uploads succeeded, so that it never serves a
+/// half updated state. A publish that fails leaves the site as it was.
+#[derive(Clone, Debug)]
+pub struct SitePublish {
+    site: String,
+    containers: BTreeMap<String, PathBuf>,
+    links: BTreeMap<String, String>,
+    address_links: BTreeMap<String, ChunkAddress>,
+    unlinks: BTreeSet<String>,
+    // Whether each name must, or must not, be in the site before the publish.
+    expected: BTreeMap<String, bool>,
+    policy: PolicyFile,
+}
+
+impl SitePublish {
+    /// Starts staging the updates of the site with the given name.
+    pub fn new(site: impl Into<String>) -> Self {
+        Self {
+            site: site.into(),
+            containers: BTreeMap::new(),
+            links: BTreeMap::new(),
+            address_links: BTreeMap::new(),
+            unlinks: BTreeSet::new(),
+            expected: BTreeMap::new(),
+            policy: PolicyFile::default(),
+        }
+    }
+
+    /// Sets who can write to the Register of the site, if this publish creates it.
+    /// Only the owner can unless set otherwise, as anyone can derive the address of the
+    /// Register from the name of the site: with
+    /// [`PolicyTemplate::PublicAppend`](super::PolicyTemplate::PublicAppend), anyone can
+    /// point the site elsewhere.
+    pub fn with_policy(mut self, policy: PolicyFile) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Stages the upload of the dir as a container with the given name.
+    pub fn stage_container(mut self, container: impl Into<String>, dir: PathBuf) -> Self {
+        let _ = self.containers.insert(container.into(), dir);
+        self
+    }
+
+    /// Stages pointing the name at a staged container.
+    pub fn link(mut self, name: impl Into<String>, container: impl Into<String>) -> Self {
+        let name = name.into();
+        let _ = self.unlinks.remove(&name);
+        let _ = self.address_links.remove(&name);
+        let _ = self.links.insert(name, container.into());
+        self
+    }
+
+    /// Stages pointing the name at an archive uploaded before.
+    pub fn link_address(mut self, name: impl Into<String>, address: ChunkAddress) -> Self {
+        let name = name.into();
+        let _ = self.unlinks.remove(&name);
+        let _ = self.links.remove(&name);
+        let _ = self.address_links.insert(name, address);
+        self
+    }
+
+    /// Stages removing the name from the site.
+    pub fn unlink(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        let _ = self.links.remove(&name);
+        let _ = self.address_links.remove(&name);
+        let _ = self.unlinks.insert(name);
+        self
+    }
+
+    /// Stages the changes of a changes file, checking that each name is changed only once.
+    ///
+    /// When committed, the publish fails without changing anything if an added name
+    /// is already in the site, or an updated or removed name is not.
+    pub fn apply_changes(mut self, changes: &SiteChanges) -> Result<Self> {
+        let mut seen = BTreeSet::new();
+        let names = changes
+            .add
+            .keys()
+            .chain(changes.update.keys())
+            .chain(changes.remove.iter());
+        for name in names {
+            if !seen.insert(name) {
+                return Err(Error::SitePublish(format!(
+                    "{name} is changed more than once"
+                )));
+            }
+        }
+
+        for (exists, links) in [(false, &changes.add), (true, &changes.update)] {
+            for (name, address) in links {
+                let address = decode_name(address).map_err(|err| {
+                    Error::SitePublish(format!("{name} does not point at an address: {err}"))
+                })?;
+                self = self.link_address(name.clone(), ChunkAddress::new(address));
+                let _ = self.expected.insert(name.clone(), exists);
+            }
+        }
+        for name in &changes.remove {
+            self = self.unlink(name.clone());
+            let _ = self.expected.insert(name.clone(), true);
+        }
+        Ok(self)
+    }
+
+    /// Uploads the staged containers and the new site map, and then points the site at it.
+    pub async fn commit(self, files: &Files) -> Result<SitePublishReport> {
+        if let Some((name, container)) = self
+            .links
+            .iter()
+            .find(|(_, container)| !self.containers.contains_key(*container))
+        {
+            return Err(Error::SitePublish(format!(
+                "{name} is linked to {container}, which is not a staged container"
+            )));
+        }
+
+        let client = files.client();
+        let permissions = self.policy.compile(client.signer_pk())?;
+        let register = site_register(client, &self.site).await?;
+        let previous = match &register {
+            Some(register) => current_site_map(register)?,
+            None => None,
+        };
+        let mut site_map = match previous {
+            Some(address) => files.get_site_map(address).await?,
+            None => SiteMap::default(),
+        };
+        self.check_expected(&site_map)?;
+
+        let mut containers = BTreeMap::new();
+        for (container, dir) in &self.containers {
+            info!(
+                "Uploading the container {container} of the site {}",
+                self.site
+            );
+            let _ = containers.insert(container.clone(), files.upload_archive(dir).await?);
+        }
+
+        let changed_names = self.apply(&mut site_map, &containers);
+        let current = files.upload(Bytes::from(serialize(&site_map)?)).await?;
+
+        // Only now that everything is uploaded does the site point at the new site map.
+        let mut register = match register {
+            Some(register) => register,
+            None => {
+                client
+                    .create_register_with_permissions(
+                        site_name(&self.site),
+                        SITE_REGISTER_TAG,
+                        permissions,
+                    )
+                    .await?
+            }
+        };
+        register
+            .write_merging_branches(&serialize(&current)?)
+            .await?;
+        client.site_cache().invalidate(&self.site).await;
+        info!(
+            "Published the site {}, with {} names changed",
+            self.site,
+            changed_names.len()
+        );
+
+        Ok(SitePublishReport {
+            previous,
+            current,
+            containers,
+            changed_names,
+        })
+    }
+
+    // Checks, before uploading anything, that the names expected in the site are in it,
+    // and that those expected not to be are not.
+    fn check_expected(&self, site_map: &SiteMap) -> Result<()> {
+        for (name, exists) in &self.expected {
+            match (exists, site_map.names.contains_key(name)) {
+                (true, false) => {
+                    return Err(Error::SitePublish(format!(
+                        "{name} is not in the site, so it cannot be updated or removed"
+                    )))
+                }
+                (false, true) => {
+                    return Err(Error::SitePublish(format!(
+                        "{name} is already in the site, so it cannot be added"
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Applies the staged links and unlinks to the site map, given the addresses the
+    // containers were uploaded to, returning the names that changed.
+    fn apply(
+        &self,
+        site_map: &mut SiteMap,
+        containers: &BTreeMap<String, ChunkAddress>,
+    ) -> Vec<String> {
+        let mut changed_names = BTreeSet::new();
+        for (name, container) in &self.links {
+            let address = match containers.get(container) {
+                Some(address) => *address,
+                None => continue,
+            };
+            if site_map.names.insert(name.clone(), address) != Some(address) {
+                let _ = changed_names.insert(name.clone());
+            }
+        }
+        for (name, address) in &self.address_links {
+            if site_map.names.insert(name.clone(), *address) != Some(*address) {
+                let _ = changed_names.insert(name.clone());
+            }
+        }
+        for name in &self.unlinks {
+            if site_map.names.remove(name).is_some() {
+                let _ = changed_names.insert(name.clone());
+            }
+        }
+        changed_names.into_iter().collect()
+    }
+}
+
+impl Files {
+    /// Retrieves the site map at the given address.
+    pub async fn get_site_map(&self, address: ChunkAddress) -> Result<SiteMap> {
+        Ok(deserialize(&self.read_bytes(address).await?)?)
+    }
+
+    /// Retrieves the site map the site currently points to.
+    ///
+    /// The site map is reused for a while, so that resolving the same site over and over
+    /// does not go to the network each time, see [`Client::with_site_cache_ttl`].
+    /// Publishing the site with this client discards the site map it resolved to before.
+    pub async fn current_site(&self, site: &str) -> Result<SiteMap> {
+        let cache = self.client().site_cache();
+        if let Some(site_map) = cache.get(site).await {
+            return Ok(site_map);
+        }
+        let register = site_register(self.client(), site).await?;
+        let address = register
+            .as_ref()
+            .map(current_site_map)
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| Error::SitePublish(format!("the site {site} was never published")))?;
+        let site_map = self.get_site_map(address).await?;
+        let versions = register.as_ref().map_or(0, Register::size);
+        cache.insert(site, versions, site_map.clone()).await;
+        Ok(site_map)
+    }
+}
+
+fn site_name(site: &str) -> XorName {
+    XorName::from_content(site.as_bytes())
+}
+
+// The Register of the site, if it was published before.
+async fn site_register(client: &Client, site: &str) -> Result<Option<Register>> {
+    match client
+        .get_register(site_name(site), SITE_REGISTER_TAG)
+        .await
+    {
+        Ok(register) => Ok(Some(register)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// The site map the Register of a site points to, failing if concurrent
+// publishes left it pointing at several.
+fn current_site_map(register: &Register) -> Result<Option<ChunkAddress>> {
+    let entries = register.read();
+    if entries.len() > 1 {
+        return Err(Error::ContentBranchDetected(entries));
+    }
+    entries
+        .iter()
+        .next()
+        .map(|(_, entry)| Ok(deserialize(entry)?))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::PolicyTemplate;
+
+    use eyre::Result;
+
+    #[test]
+    fn only_the_owner_can_write_to_a_site_by_default() {
+        let publish = SitePublish::new("example");
+        assert_eq!(publish.policy.template, PolicyTemplate::Private);
+
+        let public = publish.with_policy(PolicyFile::from(PolicyTemplate::PublicAppend));
+        assert_eq!(public.policy.template, PolicyTemplate::PublicAppend);
+    }
+
+    #[test]
+    fn staged_links_and_unlinks_are_applied_to_the_site_map() {
+        let address = || ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let (old_docs, blog) = (address(), address());
+        let mut site_map = SiteMap {
+            names: BTreeMap::from([
+                ("docs".to_string(), old_docs),
+                ("blog".to_string(), blog),
+                ("beta".to_string(), address()),
+            ]),
+        };
+
+        let publish = SitePublish::new("example")
+            .stage_container("docs-v2", PathBuf::from("docs"))
+            .link("docs", "docs-v2")
+            .link("manual", "docs-v2")
+            .link("beta", "docs-v2")
+            .unlink("beta");
+        let new_docs = address();
+        let containers = BTreeMap::from([("docs-v2".to_string(), new_docs)]);
+        let changed = publish.apply(&mut site_map, &containers);
+
+        assert_eq!(changed, vec!["beta", "docs", "manual"]);
+        assert_eq!(
+            site_map.names,
+            BTreeMap::from([
+                ("blog".to_string(), blog),
+                ("docs".to_string(), new_docs),
+                ("manual".to_string(), new_docs),
+            ])
+        );
+
+        // Publishing the same again changes nothing.
+        assert!(publish.apply(&mut site_map, &containers).is_empty());
+    }
+
+    #[test]
+    fn changes_files_are_applied_all_or_nothing() -> Result<()> {
+        let (docs, manual) = (
+            XorName::random(&mut rand::thread_rng()),
+            XorName::random(&mut rand::thread_rng()),
+        );
+        let site_map = SiteMap {
+            names: BTreeMap::from([
+                ("docs".to_string(), ChunkAddress::new(docs)),
+                ("beta".to_string(), ChunkAddress::new(docs)),
+            ]),
+        };
+        let changes: SiteChanges = serde_json::from_str(&format!(
+            r#"{{"add": {{"manual": "{}"}}, "update": {{"docs": "{}"}}, "remove": ["beta"]}}"#,
+            hex::encode(manual),
+            hex::encode(manual)
+        ))?;
+
+        let publish = SitePublish::new("example").apply_changes(&changes)?;
+        publish.check_expected(&site_map)?;
+        let mut updated = site_map.clone();
+        let changed = publish.apply(&mut updated, &BTreeMap::new());
+        assert_eq!(changed, vec!["beta", "docs", "manual"]);
+        assert_eq!(
+            updated.names,
+            BTreeMap::from([
+                ("docs".to_string(), ChunkAddress::new(manual)),
+                ("manual".to_string(), ChunkAddress::new(manual)),
+            ])
+        );
+
+        // Once applied, the same changes cannot be published again.
+        assert!(publish.check_expected(&updated).is_err());
+
+        let twice = SiteChanges {
+            update: BTreeMap::from([("docs".to_string(), hex::encode(manual))]),
+            remove: vec!["docs".to_string()],
+            ..Default::default()
+        };
+        assert!(SitePublish::new("example").apply_changes(&twice).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::site::SiteMap;
+
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+/// How long the site map a site resolves to is reused at most, by default.
+pub const DEFAULT_SITE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// How long the site map of a site is reused at least, however often the site changed.
+const MIN_SITE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// How often the resolution of sites was answered from the cache.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SiteCacheStats {
+    /// The resolutions answered from the cache.
+    pub hits: u64,
+    /// The resolutions that went to the network.
+    pub misses: u64,
+}
+
+impl SiteCacheStats {
+    /// The share of resolutions answered from the cache, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+// The site map each site resolved to, kept for a while so that resolving the same
+// site over and over does not read its Register and site map from the network each time.
+//
+// A site that was published many times is likely to be published again soon, so the
+// longer the history of its Register, the sooner its site map is resolved again.
+#[derive(Debug)]
+pub(crate) struct SiteCache {
+    max_ttl: Duration,
+    sites: RwLock<BTreeMap<String, (Instant, Duration, SiteMap)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SiteCache {
+    pub(crate) fn new(max_ttl: Duration) -> Self {
+        Self {
+            max_ttl,
+            sites: RwLock::new(BTreeMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // The site map the site resolved to, if it is still fresh, counting the hit or miss.
+    pub(crate) async fn get(&self, site: &str) -> Option<SiteMap> {
+        let sites = self.sites.read().await;
+        let site_map = sites
+            .get(site)
+            .filter(|(resolved_at, ttl, _)| resolved_at.elapsed() < *ttl)
+            .map(|(_, _, site_map)| site_map.clone());
+        let counter = if site_map.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+        site_map
+    }
+
+    // Keeps the site map the site resolved to, given the number of versions of the site.
+    pub(crate) async fn insert(&self, site: &str, versions: u64, site_map: SiteMap) {
+        let ttl = self.ttl(versions);
+        if ttl.is_zero() {
+            return;
+        }
+        let mut sites = self.sites.write().await;
+        sites.retain(|_, (resolved_at, ttl, _)| resolved_at.elapsed() < *ttl);
+        let _ = sites.insert(site.to_string(), (Instant::now(), ttl, site_map));
+    }
+
+    // Discards the site map of the site, e.g. when it was just published.
+    pub(crate) async fn invalidate(&self, site: &str) {
+        let _ = self.sites.write().await.remove(site);
+    }
+
+    pub(crate) fn stats(&self) -> SiteCacheStats {
+        SiteCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn ttl(&self, versions: u64) -> Duration {
+        let versions = u32::try_from(versions.max(1)).unwrap_or(u32::MAX);
+        (self.max_ttl / versions).max(MIN_SITE_CACHE_TTL.min(self.max_ttl))
+    }
+}
+
+impl Default for SiteCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SITE_CACHE_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sites_are_resolved_from_the_cache_until_invalidated() {
+        let cache = SiteCache::default();
+        assert_eq!(cache.get("docs").await, None);
+
+        cache.insert("docs", 1, SiteMap::default()).await;
+        assert_eq!(cache.get("docs").await, Some(SiteMap::default()));
+        cache.invalidate("docs").await;
+        assert_eq!(cache.get("docs").await, None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+
+        // Sites that changed often are resolved again sooner.
+        assert_eq!(cache.ttl(1), DEFAULT_SITE_CACHE_TTL);
+        assert_eq!(cache.ttl(4), DEFAULT_SITE_CACHE_TTL / 4);
+        assert_eq!(cache.ttl(1000), MIN_SITE_CACHE_TTL);
+        assert!(SiteCache::new(Duration::ZERO).ttl(3).is_zero());
+    }
+}
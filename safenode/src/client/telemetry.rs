@@ -0,0 +1,255 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    bench::percentiles,
+    error::{Error, Result},
+    journal::{append_capped, is_success, operation_name},
+    LatencyPercentiles,
+};
+
+use crate::protocol::messages::{Request, Response};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+// Filename of the telemetry records, in the client dir.
+const TELEMETRY_FILENAME: &str = "telemetry.jsonl";
+
+/// The number of most recent operations the telemetry keeps, by default.
+pub const DEFAULT_TELEMETRY_SIZE: usize = 10_000;
+
+/// An operation sent to the network, as recorded by [`Telemetry`].
+///
+/// Unlike a [`JournalEntry`](super::JournalEntry), it holds nothing that could tell who
+/// sent the operation, when, or about what data: no address, no id and no time.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryRecord {
+    /// The kind of operation, e.g. `StoreChunk`.
+    pub operation: String,
+    /// The number of times the operation was sent.
+    pub attempts: u32,
+    /// How long the operation took, including retries, in milliseconds.
+    pub duration_ms: u64,
+    /// Why the operation failed, if it did, e.g. `NetworkUnavailable`, or `Rejected`
+    /// if the peers answered with an error.
+    pub error_class: Option<String>,
+}
+
+impl TelemetryRecord {
+    pub(super) fn new(
+        request: &Request,
+        attempts: u32,
+        duration: Duration,
+        result: &Result<Vec<Result<Response>>>,
+    ) -> Self {
+        let error_class = match result {
+            Ok(responses)
+                if responses
+                    .iter()
+                    .any(|resp| matches!(resp, Ok(resp) if is_success(resp))) =>
+            {
+                None
+            }
+            Ok(responses) => Some(
+                match responses.iter().find_map(|resp| resp.as_ref().err()) {
+                    Some(error) if responses.iter().all(|resp| resp.is_err()) => {
+                        format!("{:?}", error.kind())
+                    }
+                    _ => "Rejected".to_string(),
+                },
+            ),
+            Err(error) => Some(format!("{:?}", error.kind())),
+        };
+        Self {
+            operation: operation_name(request).to_string(),
+            attempts,
+            duration_ms: duration.as_millis() as u64,
+            error_class,
+        }
+    }
+}
+
+/// How one kind of operation performed, as reported by [`Telemetry::report`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationTelemetry {
+    /// The number of operations recorded.
+    pub count: u64,
+    /// The number of operations that failed.
+    pub failures: u64,
+    /// The number of operations that had to be sent more than once.
+    pub retried: u64,
+    /// The number of failures, by error class.
+    pub errors: BTreeMap<String, u64>,
+    /// The latency percentiles of the operations that succeeded.
+    pub latency: LatencyPercentiles,
+}
+
+/// A summary of the telemetry of a client, to attach to reports of performance issues.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    /// The version of the client.
+    pub client_version: String,
+    /// The operating system the client runs on, e.g. `linux`.
+    pub os: String,
+    /// The architecture the client runs on, e.g. `x86_64`.
+    pub arch: String,
+    /// The number of operations summarised.
+    pub records: u64,
+    /// The summary of each kind of operation.
+    pub operations: BTreeMap<String, OperationTelemetry>,
+}
+
+/// An opt-in local record of how the operations of a client performed, without anything
+/// identifying the user or their data, so that it can be shared with the maintainers.
+///
+/// The records are appended to a json lines file in the client dir, which is kept to
+/// the given number of most recent records. Nothing is ever sent anywhere: the user
+/// decides whether to share the [`TelemetryReport`].
+#[derive(Debug)]
+pub struct Telemetry {
+    path: PathBuf,
+    max_records: usize,
+    // The number of records in the file.
+    records: Mutex<usize>,
+}
+
+impl Telemetry {
+    /// Opens the telemetry in the given dir, keeping at most `max_records` records.
+    pub fn open(root_dir: &Path, max_records: usize) -> Result<Self> {
+        let path = root_dir.join(TELEMETRY_FILENAME);
+        let records = if path.is_file() {
+            fs::read_to_string(&path)?.lines().count()
+        } else {
+            0
+        };
+        Ok(Self {
+            path,
+            max_records: max_records.max(1),
+            records: Mutex::new(records),
+        })
+    }
+
+    /// Summarises the telemetry recorded in the given dir, per kind of operation.
+    pub fn report(root_dir: &Path) -> Result<TelemetryReport> {
+        let path = root_dir.join(TELEMETRY_FILENAME);
+        let content = if path.is_file() {
+            fs::read_to_string(path)?
+        } else {
+            String::new()
+        };
+        let records = content
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(|err| Error::Telemetry(err.to_string())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(summarise(&records))
+    }
+
+    /// Appends the record, dropping the oldest records once the telemetry is full.
+    pub(super) fn record(&self, record: &TelemetryRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).map_err(|err| Error::Telemetry(err.to_string()))?;
+        line.push('\n');
+
+        let mut records = self
+            .records
+            .lock()
+            .map_err(|_| Error::Telemetry("the telemetry lock is poisoned".to_string()))?;
+        append_capped(&self.path, &mut records, self.max_records, &line)?;
+        Ok(())
+    }
+}
+
+fn summarise(records: &[TelemetryRecord]) -> TelemetryReport {
+    let mut operations: BTreeMap<String, OperationTelemetry> = BTreeMap::new();
+    let mut latencies: BTreeMap<&str, Vec<Duration>> = BTreeMap::new();
+    for record in records {
+        let summary = operations.entry(record.operation.clone()).or_default();
+        summary.count += 1;
+        if record.attempts > 1 {
+            summary.retried += 1;
+        }
+        match &record.error_class {
+            Some(class) => {
+                summary.failures += 1;
+                *summary.errors.entry(class.clone()).or_default() += 1;
+            }
+            None => latencies
+                .entry(&record.operation)
+                .or_default()
+                .push(Duration::from_millis(record.duration_ms)),
+        }
+    }
+    for (operation, latencies) in latencies {
+        if let Some(summary) = operations.get_mut(operation) {
+            summary.latency = percentiles(latencies);
+        }
+    }
+
+    TelemetryReport {
+        client_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        records: records.len() as u64,
+        operations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        address::ChunkAddress,
+        error::Error as ProtocolError,
+        messages::{Query, QueryResponse},
+    };
+
+    use xor_name::XorName;
+
+    #[test]
+    fn reports_summarise_records_per_operation() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let telemetry = Telemetry::open(dir.path(), 100)?;
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let request = Request::Query(Query::GetChunk(address));
+        let not_found = Ok(vec![Ok(Response::Query(QueryResponse::GetChunk(Err(
+            ProtocolError::ChunkNotFound(address),
+        ))))]);
+
+        for millis in 1..=10 {
+            let record = TelemetryRecord {
+                operation: "GetChunk".to_string(),
+                attempts: 1,
+                duration_ms: millis * 10,
+                error_class: None,
+            };
+            telemetry.record(&record)?;
+        }
+        let failed = TelemetryRecord::new(&request, 3, Duration::from_secs(1), &not_found);
+        assert_eq!(failed.error_class.as_deref(), Some("Rejected"));
+        telemetry.record(&failed)?;
+
+        let line = fs::read_to_string(dir.path().join(TELEMETRY_FILENAME))?;
+        assert!(!line.contains(&hex::encode(address.name().0)));
+
+        let report = Telemetry::report(dir.path())?;
+        assert_eq!(report.records, 11);
+        let chunks = &report.operations["GetChunk"];
+        assert_eq!((chunks.count, chunks.failures, chunks.retried), (11, 1, 1));
+        assert_eq!(chunks.errors["Rejected"], 1);
+        assert_eq!((chunks.latency.p50, chunks.latency.max), (50, 100));
+        Ok(())
+    }
+}
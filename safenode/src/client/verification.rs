@@ -0,0 +1,138 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use crate::protocol::{
+    address::{dbc_address, ChunkAddress, DbcAddress, RegisterAddress},
+    chunk::Chunk,
+    register::Register,
+};
+
+use sn_dbc::SignedSpend;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of answers the client rejected as they did not verify, per kind of data.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct VerificationStats {
+    /// Chunks whose content is not that of the address asked for.
+    pub chunks: u64,
+    /// Registers at another address than the one asked for.
+    pub registers: u64,
+    /// Spends of another dbc, or not signed by its owner.
+    pub spends: u64,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct VerificationCounters {
+    chunks: AtomicU64,
+    registers: AtomicU64,
+    spends: AtomicU64,
+}
+
+impl VerificationCounters {
+    /// Verifies a chunk returned for the given address, counting it if rejected.
+    pub(super) fn chunk(&self, address: &ChunkAddress, chunk: &Chunk) -> Result<()> {
+        // The address of a chunk is derived from its content when it is deserialised.
+        let result = if chunk.address() == address {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed(format!(
+                "the chunk returned for {address:?} is {:?}",
+                chunk.address()
+            )))
+        };
+        count_rejection(&self.chunks, result)
+    }
+
+    /// Verifies a register returned for the given address, counting it if rejected.
+    pub(super) fn register(&self, address: &RegisterAddress, register: &Register) -> Result<()> {
+        let result = if register.address() == address {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed(format!(
+                "the register returned for {address:?} is {:?}",
+                register.address()
+            )))
+        };
+        count_rejection(&self.registers, result)
+    }
+
+    /// Verifies a spend returned for the dbc at the given address, counting it if rejected.
+    pub(super) fn spend(&self, address: DbcAddress, spend: &SignedSpend) -> Result<()> {
+        count_rejection(&self.spends, verify_spend(address, spend))
+    }
+
+    pub(super) fn stats(&self) -> VerificationStats {
+        VerificationStats {
+            chunks: self.chunks.load(Ordering::Relaxed),
+            registers: self.registers.load(Ordering::Relaxed),
+            spends: self.spends.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Verifies that the spend is of the dbc at the given address, and signed by its owner.
+pub(super) fn verify_spend(address: DbcAddress, spend: &SignedSpend) -> Result<()> {
+    if dbc_address(spend.dbc_id()) != address {
+        return Err(Error::VerificationFailed(format!(
+            "the spend returned for {address:?} is of {:?}",
+            dbc_address(spend.dbc_id())
+        )));
+    }
+    spend
+        .verify(spend.dst_tx_hash())
+        .map_err(|err| Error::VerificationFailed(format!("the spend of {address:?}: {err}")))
+}
+
+fn count_rejection(counter: &AtomicU64, result: Result<()>) -> Result<()> {
+    if let Err(err) = &result {
+        warn!("Rejected an answer of the network: {err}");
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::register::User;
+
+    use bytes::Bytes;
+    use xor_name::XorName;
+
+    #[test]
+    fn answers_for_other_addresses_are_rejected_and_counted() {
+        let counters = VerificationCounters::default();
+        let chunk = Chunk::new(Bytes::from_static(b"content"));
+        let other = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        assert!(counters.chunk(chunk.address(), &chunk).is_ok());
+        assert!(matches!(
+            counters.chunk(&other, &chunk),
+            Err(Error::VerificationFailed(_))
+        ));
+
+        let owner = User::Key(bls::SecretKey::random().public_key());
+        let register = Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 1);
+        let mut address = *register.address();
+        assert!(counters.register(&address, &register).is_ok());
+        address.tag += 1;
+        assert!(counters.register(&address, &register).is_err());
+
+        assert_eq!(
+            counters.stats(),
+            VerificationStats {
+                chunks: 1,
+                registers: 1,
+                spends: 0,
+            }
+        );
+    }
+}
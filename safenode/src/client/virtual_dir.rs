@@ -0,0 +1,399 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    archive::{pack_files, MAX_PACK_SIZE},
+    error::{Error, Result},
+    Archive, ArchiveEntry, Files,
+};
+
+use crate::protocol::address::ChunkAddress;
+
+use bincode::serialize;
+use bytes::Bytes;
+use std::collections::BTreeMap;
+
+/// Whether a path of a [`VirtualDir`] is a file or a dir.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EntryKind {
+    /// A file, with content.
+    File,
+    /// A dir, holding files or other dirs.
+    Dir,
+}
+
+/// What is at a path of a [`VirtualDir`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EntryMetadata {
+    /// Whether it is a file or a dir.
+    pub kind: EntryKind,
+    /// The size of the content of a file, 0 for a dir.
+    pub len: usize,
+    /// Whether the file was written since the dir was opened, and is not committed yet.
+    pub staged: bool,
+}
+
+/// An entry of a dir, as listed by [`VirtualDir::read_dir`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirEntry {
+    /// The name of the entry within the dir.
+    pub name: String,
+    /// What the entry is.
+    pub metadata: EntryMetadata,
+}
+
+/// A filesystem view of an [`Archive`], so that tools share one implementation of
+/// path resolution instead of each matching path strings against the archive entries.
+///
+/// Paths are relative to the root of the archive, with `/` as separator. Leading,
+/// trailing and repeated separators, `.` and `..` are resolved, `..` not going above
+/// the root. Dirs are implied by the paths of the files under them.
+///
+/// Writes and removals are staged, and visible to reads right away. They are only
+/// uploaded by [`VirtualDir::commit`], as a new archive: archives are immutable, so the
+/// archive the dir was opened from is left as it was. The content of unchanged files
+/// is not uploaded again.
+pub struct VirtualDir {
+    files: Files,
+    packs: Vec<ChunkAddress>,
+    tree: Tree,
+}
+
+impl VirtualDir {
+    /// An empty dir, to be filled and committed as a new archive.
+    pub fn new(files: Files) -> Self {
+        Self {
+            files,
+            packs: vec![],
+            tree: Tree::default(),
+        }
+    }
+
+    /// Opens the archive at the given address.
+    pub async fn open_archive(files: Files, address: ChunkAddress) -> Result<Self> {
+        let archive = files.get_archive(address).await?;
+        let tree = Tree {
+            entries: archive
+                .entries
+                .into_iter()
+                .map(|(path, entry)| Ok((normalise(&path)?, Content::Archived(entry))))
+                .collect::<Result<_>>()?,
+            changed: false,
+        };
+        Ok(Self {
+            files,
+            packs: archive.packs,
+            tree,
+        })
+    }
+
+    /// Returns the content of the file at the given path.
+    pub async fn open(&self, path: &str) -> Result<Bytes> {
+        let path = normalise(path)?;
+        match self.tree.entries.get(&path) {
+            Some(Content::Staged(content)) => Ok(content.clone()),
+            Some(Content::Archived(entry)) => {
+                let address = self
+                    .packs
+                    .get(entry.pack)
+                    .ok_or_else(|| Error::ArchiveEntryNotFound(path.clone()))?;
+                self.files
+                    .read_from(*address, entry.offset, entry.len)
+                    .await
+            }
+            None if self.tree.is_dir(&path) => {
+                Err(Error::InvalidPath(format!("{path:?} is a dir, not a file")))
+            }
+            None => Err(Error::ArchiveEntryNotFound(path)),
+        }
+    }
+
+    /// Lists the files and dirs directly under the dir at the given path, by name.
+    pub fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+        self.tree.read_dir(&normalise(path)?)
+    }
+
+    /// Tells what is at the given path.
+    pub fn metadata(&self, path: &str) -> Result<EntryMetadata> {
+        self.tree.metadata(&normalise(path)?)
+    }
+
+    /// Stages writing the content to the file at the given path, creating the dirs
+    /// leading to it, and replacing the file if there is one.
+    pub fn write(&mut self, path: &str, content: Bytes) -> Result<()> {
+        self.tree.write(normalise(path)?, content)
+    }
+
+    /// Stages removing the file, or the dir and everything under it, at the given path.
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        self.tree.remove(&normalise(path)?)
+    }
+
+    /// Whether anything was written or removed since the dir was opened.
+    pub fn has_staged_changes(&self) -> bool {
+        self.tree.changed
+    }
+
+    /// Uploads the staged files, and the new archive, returning its address.
+    pub async fn commit(self) -> Result<ChunkAddress> {
+        let mut entries = BTreeMap::new();
+        let mut staged = vec![];
+        for (path, content) in self.tree.entries {
+            match content {
+                Content::Archived(entry) => {
+                    let _ = entries.insert(path, entry);
+                }
+                Content::Staged(content) => staged.push((path, content)),
+            }
+        }
+
+        let (staged_entries, new_packs) = pack_files(staged, MAX_PACK_SIZE);
+        let mut packs = self.packs;
+        let first_new_pack = packs.len();
+        for pack in new_packs {
+            packs.push(self.files.upload(pack).await?);
+        }
+        for (path, mut entry) in staged_entries {
+            entry.pack += first_new_pack;
+            let _ = entries.insert(path, entry);
+        }
+
+        let archive = Archive { packs, entries };
+        self.files.upload(Bytes::from(serialize(&archive)?)).await
+    }
+}
+
+// Where the content of a file is.
+#[derive(Clone, Debug)]
+enum Content {
+    Archived(ArchiveEntry),
+    Staged(Bytes),
+}
+
+// The files of a dir, by normalised path, with what was staged applied.
+#[derive(Debug, Default)]
+struct Tree {
+    entries: BTreeMap<String, Content>,
+    changed: bool,
+}
+
+impl Tree {
+    // Whether there is a dir at the normalised path, i.e. files under it.
+    fn is_dir(&self, path: &str) -> bool {
+        path.is_empty() || self.under(path).next().is_some()
+    }
+
+    // The files under the dir at the normalised path, with their path relative to it.
+    fn under<'a>(&'a self, dir: &str) -> impl Iterator<Item = (&'a str, &'a Content)> + 'a {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        let len = prefix.len();
+        self.entries
+            .range(prefix.clone()..)
+            .take_while(move |(path, _)| path.starts_with(&prefix))
+            .map(move |(path, content)| (&path[len..], content))
+    }
+
+    fn metadata(&self, path: &str) -> Result<EntryMetadata> {
+        match self.entries.get(path) {
+            Some(content) => Ok(file_metadata(content)),
+            None if self.is_dir(path) => Ok(EntryMetadata {
+                kind: EntryKind::Dir,
+                len: 0,
+                staged: false,
+            }),
+            None => Err(Error::ArchiveEntryNotFound(path.to_string())),
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+        if !self.is_dir(path) {
+            return match self.entries.contains_key(path) {
+                true => Err(Error::InvalidPath(format!("{path:?} is a file, not a dir"))),
+                false => Err(Error::ArchiveEntryNotFound(path.to_string())),
+            };
+        }
+        let mut children: BTreeMap<&str, EntryMetadata> = BTreeMap::new();
+        for (relative, content) in self.under(path) {
+            match relative.split_once('/') {
+                Some((dir, _)) => {
+                    let _ = children.insert(
+                        dir,
+                        EntryMetadata {
+                            kind: EntryKind::Dir,
+                            len: 0,
+                            staged: false,
+                        },
+                    );
+                }
+                None => {
+                    let _ = children.insert(relative, file_metadata(content));
+                }
+            }
+        }
+        Ok(children
+            .into_iter()
+            .map(|(name, metadata)| DirEntry {
+                name: name.to_string(),
+                metadata,
+            })
+            .collect())
+    }
+
+    fn write(&mut self, path: String, content: Bytes) -> Result<()> {
+        if path.is_empty() || self.is_dir(&path) {
+            return Err(Error::InvalidPath(format!(
+                "{path:?} is a dir, a file cannot be written there"
+            )));
+        }
+        // No dir on the way to the file can be a file.
+        let mut parent = path.as_str();
+        while let Some((dir, _)) = parent.rsplit_once('/') {
+            if self.entries.contains_key(dir) {
+                return Err(Error::InvalidPath(format!(
+                    "{dir:?} is a file, so {path:?} cannot be written under it"
+                )));
+            }
+            parent = dir;
+        }
+        let _ = self.entries.insert(path, Content::Staged(content));
+        self.changed = true;
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &str) -> Result<()> {
+        if path.is_empty() {
+            return Err(Error::InvalidPath("the root cannot be removed".to_string()));
+        }
+        let removed: Vec<String> = match self.entries.contains_key(path) {
+            true => vec![path.to_string()],
+            false => self
+                .under(path)
+                .map(|(relative, _)| format!("{path}/{relative}"))
+                .collect(),
+        };
+        if removed.is_empty() {
+            return Err(Error::ArchiveEntryNotFound(path.to_string()));
+        }
+        for path in removed {
+            let _ = self.entries.remove(&path);
+        }
+        self.changed = true;
+        Ok(())
+    }
+}
+
+fn file_metadata(content: &Content) -> EntryMetadata {
+    let (len, staged) = match content {
+        Content::Archived(entry) => (entry.len, false),
+        Content::Staged(content) => (content.len(), true),
+    };
+    EntryMetadata {
+        kind: EntryKind::File,
+        len,
+        staged,
+    }
+}
+
+// Resolves the path to its components joined by `/`, the root being the empty path.
+fn normalise(path: &str) -> Result<String> {
+    let mut components: Vec<&str> = vec![];
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.pop().is_none() {
+                    return Err(Error::InvalidPath(format!(
+                        "{path:?} goes above the root of the dir"
+                    )));
+                }
+            }
+            component => components.push(component),
+        }
+    }
+    Ok(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+
+    #[test]
+    fn paths_resolve_to_the_same_files_and_dirs() -> Result<()> {
+        assert_eq!(
+            normalise("/docs//./guide/../index.html")?,
+            "docs/index.html"
+        );
+        assert_eq!(normalise("/")?, "");
+        assert!(normalise("docs/../../etc/passwd").is_err());
+
+        let mut tree = Tree {
+            entries: BTreeMap::from([(
+                "docs/index.html".to_string(),
+                Content::Archived(ArchiveEntry {
+                    pack: 0,
+                    offset: 0,
+                    len: 10,
+                }),
+            )]),
+            changed: false,
+        };
+        tree.write(
+            normalise("docs/guide/intro.md")?,
+            Bytes::from_static(b"hello"),
+        )?;
+        tree.write(normalise("README")?, Bytes::from_static(b"readme"))?;
+
+        let names = |entries: Vec<DirEntry>| -> Vec<(String, EntryKind)> {
+            entries
+                .into_iter()
+                .map(|entry| (entry.name, entry.metadata.kind))
+                .collect()
+        };
+        assert_eq!(
+            names(tree.read_dir("")?),
+            vec![
+                ("README".to_string(), EntryKind::File),
+                ("docs".to_string(), EntryKind::Dir)
+            ]
+        );
+        assert_eq!(
+            names(tree.read_dir("docs")?),
+            vec![
+                ("guide".to_string(), EntryKind::Dir),
+                ("index.html".to_string(), EntryKind::File)
+            ]
+        );
+        let intro = tree.metadata("docs/guide/intro.md")?;
+        assert_eq!(
+            (intro.kind, intro.len, intro.staged),
+            (EntryKind::File, 5, true)
+        );
+
+        // Files and dirs cannot take each other's place.
+        assert!(tree.write("docs".to_string(), Bytes::new()).is_err());
+        assert!(tree
+            .write("README/notes".to_string(), Bytes::new())
+            .is_err());
+        assert!(tree.read_dir("README").is_err());
+
+        tree.remove("docs")?;
+        assert_eq!(
+            names(tree.read_dir("")?),
+            vec![("README".to_string(), EntryKind::File)]
+        );
+        assert!(tree.metadata("docs/index.html").is_err());
+        assert!(tree.remove("docs").is_err());
+        Ok(())
+    }
+}
@@ -6,15 +6,29 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_dbc::{Dbc, DbcIdSource, DerivedKey, PublicAddress, Token};
+use sn_dbc::{Dbc, DbcId, DbcIdSource, DerivedKey, PublicAddress, SignedSpend, Token};
 
 use crate::protocol::{
-    transfers::{create_online_transfer, Outputs as TransferDetails},
-    wallet::{Result, SendClient, SendWallet},
+    address::dbc_address,
+    fees::SpendFeeEstimate,
+    transfers::{create_online_transfer, estimate_spend_fees, Outputs as TransferDetails},
+    wallet::{Error, Result, SendClient, SendWallet},
 };
 
 use super::Client;
 
+/// The state of a dbc according to the network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpendStatus {
+    /// The network knows of no spend of the dbc.
+    Unspent,
+    /// The dbc was spent, with the given spend.
+    Spent(Box<SignedSpend>),
+    /// The dbc was spent twice, with different spends, and can no longer be spent.
+    /// Any dbc created by either of the spends must be considered invalid.
+    Conflicted(Box<SignedSpend>, Box<SignedSpend>),
+}
+
 /// A wallet client can be used to send and
 /// receive tokens to/from other wallets.
 pub struct WalletClient<W: SendWallet> {
@@ -28,11 +42,54 @@ impl<W: SendWallet> WalletClient<W> {
         Self { client, wallet }
     }
 
+    /// The wallet the client spends from.
+    pub fn wallet(&self) -> &W {
+        &self.wallet
+    }
+
     /// Send tokens to another wallet.
     pub async fn send(&mut self, amount: Token, to: PublicAddress) -> Result<()> {
         let _dbcs = self.wallet.send(vec![(amount, to)], &self.client).await?;
         Ok(())
     }
+
+    /// Estimate the fee for spending a single input dbc, by querying the nodes
+    /// responsible for the dbcs in this wallet for their current fees.
+    /// A send will pay this fee once for every input dbc it needs to spend.
+    pub async fn estimate_spend_fees(&self) -> Result<SpendFeeEstimate> {
+        let dbcs = self.wallet.spendable_dbcs();
+        Ok(estimate_spend_fees(dbcs, &self.client).await?)
+    }
+
+    /// Get the state of the dbc with the given id, according to the network.
+    pub async fn spend_status(&self, dbc_id: DbcId) -> Result<SpendStatus> {
+        self.client
+            .spend_status(dbc_address(&dbc_id))
+            .await
+            .map_err(|err| Error::CouldNotGetSpendStatus(err.to_string()))
+    }
+
+    /// Checks the dbcs in this wallet against the network, and returns those that are
+    /// implicated in a double spend: either they were created by a conflicted spend, or
+    /// they were themselves spent twice. The conflicting spends are returned along with them.
+    pub async fn implicated_dbcs(&self) -> Result<Vec<(DbcId, SpendStatus)>> {
+        let mut implicated = Vec::new();
+        for (dbc, _) in self.wallet.spendable_dbcs() {
+            let ids = dbc
+                .signed_spends
+                .iter()
+                .map(|spend| *spend.dbc_id())
+                .chain([dbc.id()]);
+            for id in ids {
+                let status = self.spend_status(id).await?;
+                if matches!(status, SpendStatus::Conflicted(..)) {
+                    implicated.push((dbc.id(), status));
+                    break;
+                }
+            }
+        }
+        Ok(implicated)
+    }
 }
 
 #[async_trait::async_trait]
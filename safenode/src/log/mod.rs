@@ -58,11 +58,36 @@ where
     }
 }
 
+/// Environment variable with the address of the OpenTelemetry collector to export traces to.
+/// Traces are only exported when it is set, and the crate is built with the `otlp` feature.
+/// The exporter can be further configured with the other standard `OTEL_*` variables.
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Keeps the logging alive, and flushes any pending logs and traces when dropped.
+/// This guard should be held for the life of the program.
+#[allow(missing_debug_implementations)]
+pub struct LogGuard {
+    _appender: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    otlp: bool,
+}
+
+#[cfg(feature = "otlp")]
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if self.otlp {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
 /// The different Subscribers composed into a list of layers
 #[derive(Default)]
 pub struct TracingLayers {
     layers: Vec<Box<dyn Layer<Registry> + Send + Sync>>,
     guard: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    otlp: bool,
 }
 
 impl TracingLayers {
@@ -107,19 +132,67 @@ impl TracingLayers {
             self.layers.push(layer);
         };
     }
+
+    #[cfg(feature = "otlp")]
+    fn otlp_layer(&mut self) -> Result<(), std::io::Error> {
+        use opentelemetry::{
+            sdk::{trace, Resource},
+            KeyValue,
+        };
+        use opentelemetry_otlp::WithExportConfig;
+
+        let endpoint = match std::env::var(OTLP_ENDPOINT_ENV) {
+            Ok(endpoint) => endpoint,
+            Err(_) => return Ok(()),
+        };
+        println!("Exporting traces to {endpoint}");
+
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", current_crate_str()),
+                ])))
+                .install_batch(opentelemetry::runtime::Tokio)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+        let target_filters: Box<dyn Filter<Registry> + Send + Sync> =
+            Box::new(Targets::new().with_target(current_crate_str(), tracing::Level::DEBUG));
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(target_filters)
+            .boxed();
+        self.layers.push(layer);
+        self.otlp = true;
+        Ok(())
+    }
 }
 
-/// Inits node logging, returning the global node guard if required.
+/// Inits node logging, returning the global node guard.
 /// This guard should be held for the life of the program.
 ///
+/// When built with the `otlp` feature, traces are also exported to the collector
+/// at [`OTLP_ENDPOINT_ENV`], if set. This must then be called within a tokio runtime.
+///
 /// Logging should be instantiated only once.
-pub fn init_node_logging(log_dir: &Option<PathBuf>) -> Result<Option<WorkerGuard>, std::io::Error> {
+pub fn init_node_logging(log_dir: &Option<PathBuf>) -> Result<LogGuard, std::io::Error> {
     let mut layers = TracingLayers::default();
     layers.fmt_layer(log_dir);
+    #[cfg(feature = "otlp")]
+    layers.otlp_layer()?;
 
     tracing_subscriber::registry().with(layers.layers).init();
 
-    Ok(layers.guard)
+    Ok(LogGuard {
+        _appender: layers.guard,
+        #[cfg(feature = "otlp")]
+        otlp: layers.otlp,
+    })
 }
 
 /// Get current root module name (e.g. "sn_node")
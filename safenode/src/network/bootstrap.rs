@@ -0,0 +1,346 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use sn_dbc::MainKey;
+
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path, str::FromStr};
+use xor_name::XorName;
+
+// Filename of the genesis key, in a genesis bundle dir.
+const GENESIS_KEY_FILENAME: &str = "genesis_key";
+// Filename of the contacts, in a genesis bundle dir.
+const CONTACTS_FILENAME: &str = "contacts.toml";
+// Filename of the network a node or client dir was first used on.
+const NETWORK_FILENAME: &str = "network";
+// The number of bytes of the id of a private network.
+const NETWORK_ID_LEN: usize = 8;
+
+/// The network that nodes and clients talk on.
+///
+/// The id is part of the name of the wire protocols, so that peers of different
+/// networks cannot talk to each other, even when they can reach each other.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum NetworkId {
+    /// The public Safe Network.
+    #[default]
+    Public,
+    /// A private network, e.g. of an organisation.
+    Private {
+        /// The name the network was created with.
+        name: String,
+        /// The hex encoded hash of the name and the genesis key of the network.
+        id: String,
+    },
+}
+
+impl NetworkId {
+    /// The id of the private network with the given name and genesis key.
+    pub fn private(name: &str, genesis_key: &MainKey) -> Result<Self> {
+        validate_name(name)?;
+        let hash = XorName::from_content_parts(&[
+            name.as_bytes(),
+            &genesis_key.public_address().to_bytes(),
+        ]);
+        Ok(Self::Private {
+            name: name.to_string(),
+            id: hex::encode(&hash.0[..NETWORK_ID_LEN]),
+        })
+    }
+
+    /// The name of the request/response protocol of the network.
+    pub(crate) fn msg_protocol(&self) -> String {
+        match self {
+            Self::Public => "/safe/1".to_string(),
+            Self::Private { .. } => format!("/safe/{self}/1"),
+        }
+    }
+
+    /// The name of the Kademlia protocol of the network, if not the default one.
+    pub(crate) fn kad_protocol(&self) -> Option<String> {
+        match self {
+            Self::Public => None,
+            Self::Private { .. } => Some(format!("/safe/{self}/kad/1.0.0")),
+        }
+    }
+}
+
+impl fmt::Display for NetworkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Public => write!(f, "public"),
+            Self::Private { name, id } => write!(f, "{name}-{id}"),
+        }
+    }
+}
+
+impl FromStr for NetworkId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "public" {
+            return Ok(Self::Public);
+        }
+        let invalid = || {
+            Error::InvalidNetworkId(format!(
+                "{s:?} is neither \"public\" nor the <name>-<id> of a private network"
+            ))
+        };
+        let (name, id) = s.rsplit_once('-').ok_or_else(invalid)?;
+        let valid_id = id.len() == 2 * NETWORK_ID_LEN
+            && id
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_uppercase());
+        if !valid_id || validate_name(name).is_err() {
+            return Err(invalid());
+        }
+        Ok(Self::Private {
+            name: name.to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for NetworkId {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<NetworkId> for String {
+    fn from(network_id: NetworkId) -> Self {
+        network_id.to_string()
+    }
+}
+
+/// The network to join, and the peers to first connect to on it, as handed out
+/// to the operators of the nodes and clients of a private network.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkContacts {
+    /// The network the contacts are on.
+    pub network_id: NetworkId,
+    /// The addresses of the contacts, each ending with the peer id, i.e. `/p2p/<peer id>`.
+    pub contacts: Vec<Multiaddr>,
+}
+
+impl NetworkContacts {
+    /// Reads the contacts from the toml file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contacts: Self = toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| Error::InvalidContacts(format!("{path:?}: {err}")))?;
+        let _ = contacts.peers()?;
+        Ok(contacts)
+    }
+
+    /// Writes the contacts to a toml file at the given path.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content =
+            toml::to_string(self).map_err(|err| Error::InvalidContacts(err.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Refuses contacts that are not on the expected network.
+    pub fn check(&self, expected: &NetworkId) -> Result<()> {
+        if &self.network_id != expected {
+            return Err(Error::NetworkMismatch {
+                expected: expected.clone(),
+                found: self.network_id.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The peer id and address of each contact.
+    pub(crate) fn peers(&self) -> Result<Vec<(PeerId, Multiaddr)>> {
+        self.contacts
+            .iter()
+            .map(|contact| {
+                let mut address = contact.clone();
+                let peer_id = match address.pop() {
+                    Some(Protocol::P2p(hash)) => PeerId::from_multihash(hash).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    Error::InvalidContacts(format!("{contact} does not end with a peer id"))
+                })?;
+                Ok((peer_id, address))
+            })
+            .collect()
+    }
+}
+
+/// Records the network the given node or client dir is used on, the first time it is,
+/// and refuses it on any other network afterwards, so that the data and keys kept
+/// in the dir never cross networks.
+pub fn check_network(dir: &Path, network_id: &NetworkId) -> Result<()> {
+    let path = dir.join(NETWORK_FILENAME);
+    if path.is_file() {
+        let found: NetworkId = fs::read_to_string(&path)?.trim().parse()?;
+        if &found != network_id {
+            return Err(Error::NetworkMismatch {
+                expected: found,
+                found: network_id.clone(),
+            });
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    fs::write(path, network_id.to_string())?;
+    Ok(())
+}
+
+/// Builds the [`GenesisBundle`] of a new private network.
+#[derive(Debug)]
+pub struct NetworkBootstrapBuilder {
+    name: String,
+    genesis_key: Option<MainKey>,
+    contacts: Vec<Multiaddr>,
+}
+
+impl NetworkBootstrapBuilder {
+    /// Starts building a private network with the given name, which can only hold
+    /// letters, digits, '-' and '_'.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            genesis_key: None,
+            contacts: vec![],
+        }
+    }
+
+    /// Uses the given genesis key, instead of a random one.
+    pub fn genesis_key(mut self, genesis_key: MainKey) -> Self {
+        self.genesis_key = Some(genesis_key);
+        self
+    }
+
+    /// Adds a peer for nodes and clients to first connect to,
+    /// its address ending with its peer id, i.e. `/p2p/<peer id>`.
+    pub fn contact(mut self, address: Multiaddr) -> Self {
+        self.contacts.push(address);
+        self
+    }
+
+    /// Derives the id of the network, and checks the contacts.
+    pub fn build(self) -> Result<GenesisBundle> {
+        let genesis_key = self.genesis_key.unwrap_or_else(MainKey::random);
+        let contacts = NetworkContacts {
+            network_id: NetworkId::private(&self.name, &genesis_key)?,
+            contacts: self.contacts,
+        };
+        let _ = contacts.peers()?;
+        Ok(GenesisBundle {
+            genesis_key,
+            contacts,
+        })
+    }
+}
+
+/// What a private network is started from: the genesis key, which the genesis DBC
+/// is created with and is to be kept secret, and the contacts of the network,
+/// which carry its id and are handed out.
+#[derive(Debug)]
+pub struct GenesisBundle {
+    /// The key the genesis DBC is created with.
+    pub genesis_key: MainKey,
+    /// The id of the network, and the peers to first connect to.
+    pub contacts: NetworkContacts,
+}
+
+impl GenesisBundle {
+    /// The id of the network.
+    pub fn network_id(&self) -> &NetworkId {
+        &self.contacts.network_id
+    }
+
+    /// Writes the hex encoded genesis key and the contacts file to the given dir,
+    /// refusing to overwrite the genesis key of another network.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let key_path = dir.join(GENESIS_KEY_FILENAME);
+        if key_path.exists() {
+            return Err(Error::InvalidContacts(format!(
+                "there is a genesis key at {key_path:?} already"
+            )));
+        }
+        fs::write(key_path, hex::encode(self.genesis_key.to_bytes()))?;
+        self.contacts.write(&dir.join(CONTACTS_FILENAME))
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(Error::InvalidNetworkId(format!(
+            "{name:?} is not a valid network name, only letters, digits, '-' and '_' are"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn networks_are_told_apart_by_their_id() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let contact: Multiaddr =
+            format!("/ip4/10.0.0.1/udp/12000/quic-v1/p2p/{}", PeerId::random())
+                .parse()
+                .map_err(|_| Error::InvalidContacts("the test contact".to_string()))?;
+        let bundle = NetworkBootstrapBuilder::new("acme")
+            .contact(contact.clone())
+            .build()?;
+        bundle.write(dir.path())?;
+        assert!(bundle.write(dir.path()).is_err());
+
+        let contacts = NetworkContacts::load(&dir.path().join(CONTACTS_FILENAME))?;
+        assert_eq!(&contacts, &bundle.contacts);
+        assert_eq!(contacts.peers()?.len(), 1);
+        let network_id = contacts.network_id.clone();
+        assert_eq!(network_id.to_string().parse::<NetworkId>()?, network_id);
+        assert!(network_id.msg_protocol().starts_with("/safe/acme-"));
+
+        // The same name with another genesis key is another network.
+        let other = NetworkBootstrapBuilder::new("acme").build()?;
+        assert_ne!(other.network_id(), &network_id);
+        assert!(contacts.check(other.network_id()).is_err());
+        assert!(contacts.check(&network_id).is_ok());
+
+        let node_dir = dir.path().join("node");
+        check_network(&node_dir, &network_id)?;
+        check_network(&node_dir, &network_id)?;
+        assert!(matches!(
+            check_network(&node_dir, &NetworkId::Public),
+            Err(Error::NetworkMismatch { .. })
+        ));
+
+        let unreachable = NetworkBootstrapBuilder::new("acme")
+            .contact(
+                "/ip4/10.0.0.1/udp/12000/quic-v1"
+                    .parse()
+                    .map_err(|_| Error::InvalidContacts("the test contact".to_string()))?,
+            )
+            .build();
+        assert!(unreachable.is_err());
+        assert!(NetworkBootstrapBuilder::new("acme net").build().is_err());
+        Ok(())
+    }
+}
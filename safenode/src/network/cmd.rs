@@ -11,9 +11,16 @@ use crate::{
     protocol::messages::{Request, Response},
 };
 
-use super::{error::Error, SwarmDriver};
-use libp2p::{multiaddr::Protocol, request_response::ResponseChannel, Multiaddr, PeerId};
-use std::collections::{hash_map, HashSet};
+use super::{error::Error, msg::message_size, ConnectionInfo, SwarmDriver};
+use libp2p::{
+    multiaddr::Protocol,
+    request_response::{RequestId, ResponseChannel},
+    Multiaddr, PeerId,
+};
+use std::{
+    collections::{hash_map, HashSet},
+    time::Instant,
+};
 use tokio::sync::oneshot;
 use tracing::warn;
 use xor_name::XorName;
@@ -41,7 +48,25 @@ pub enum SwarmCmd {
     },
     SendResponse {
         resp: Response,
+        peer: PeerId,
+        channel: ResponseChannel<Response>,
+    },
+    SendResponseWithAck {
+        resp: Response,
+        peer: PeerId,
+        request_id: RequestId,
         channel: ResponseChannel<Response>,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    GetConnections {
+        sender: oneshot::Sender<Vec<ConnectionInfo>>,
+    },
+    GetDeprioritised {
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<HashSet<PeerId>>,
+    },
+    RecordFault {
+        peer: PeerId,
     },
 }
 
@@ -88,6 +113,7 @@ impl SwarmDriver {
                     .insert(query_id, (sender, Default::default()));
             }
             SwarmCmd::SendRequest { req, peer, sender } => {
+                self.links.sent(peer, message_size(&req));
                 let request_id = self
                     .swarm
                     .behaviour_mut()
@@ -95,13 +121,61 @@ impl SwarmDriver {
                     .send_request(&peer, req);
                 let _ = self.pending_requests.insert(request_id, sender);
             }
-            SwarmCmd::SendResponse { resp, channel } => {
+            SwarmCmd::SendResponse {
+                resp,
+                peer,
+                channel,
+            } => {
+                self.links.sent(peer, message_size(&resp));
                 self.swarm
                     .behaviour_mut()
                     .request_response
                     .send_response(channel, resp)
                     .map_err(Error::OutgoingResponseDropped)?;
             }
+            SwarmCmd::SendResponseWithAck {
+                resp,
+                peer,
+                request_id,
+                channel,
+                sender,
+            } => {
+                self.links.sent(peer, message_size(&resp));
+                match self
+                    .swarm
+                    .behaviour_mut()
+                    .request_response
+                    .send_response(channel, resp)
+                {
+                    Ok(()) => {
+                        let _ = self.pending_response_acks.insert(request_id, sender);
+                    }
+                    Err(resp) => {
+                        let _ = sender.send(Err(Error::OutgoingResponseDropped(resp)));
+                    }
+                }
+            }
+            SwarmCmd::GetConnections { sender } => {
+                let known: Vec<_> = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .kbuckets()
+                    .flat_map(|bucket| {
+                        bucket
+                            .iter()
+                            .map(|entry| {
+                                (*entry.node.key.preimage(), entry.node.value.first().clone())
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                let _ = sender.send(self.links.list(known, Instant::now()));
+            }
+            SwarmCmd::GetDeprioritised { peers, sender } => {
+                let _ = sender.send(self.links.deprioritised(&peers, Instant::now()));
+            }
+            SwarmCmd::RecordFault { peer } => self.links.fault(peer, Instant::now()),
         }
         Ok(())
     }
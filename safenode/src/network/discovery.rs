@@ -0,0 +1,94 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, NetworkEvent, SwarmDriver};
+
+use crate::protocol::{
+    address::ChunkAddress,
+    messages::{Query, Request},
+};
+
+use libp2p::{
+    request_response::{OutboundFailure, RequestId},
+    Multiaddr, PeerId,
+};
+use xor_name::XorName;
+
+impl SwarmDriver {
+    /// Probes the peers discovered on the local network with mDNS, before using them.
+    ///
+    /// Only peers that answer a request on the protocol of our network, whose name is
+    /// derived from the genesis key of the network, are added to the routing table,
+    /// so that testnets sharing a LAN do not get mixed up.
+    pub(super) fn probe_discovered(
+        &mut self,
+        peers: impl IntoIterator<Item = (PeerId, Multiaddr)>,
+    ) {
+        for (peer, address) in peers {
+            let probing = self
+                .pending_discovery_probes
+                .values()
+                .any(|(pending, _)| *pending == peer);
+            if probing || self.rejected_discoveries.contains(&peer) || self.is_routing_peer(&peer) {
+                continue;
+            }
+            info!("Peer {peer:?} discovered at {address}, checking it is on our network");
+            let request_response = &mut self.swarm.behaviour_mut().request_response;
+            request_response.add_address(&peer, address.clone());
+            let request_id = request_response.send_request(&peer, probe());
+            let _ = self
+                .pending_discovery_probes
+                .insert(request_id, (peer, address));
+        }
+    }
+
+    /// Handles the outcome of a request, if it was the probe of a discovered peer,
+    /// returning whether it was.
+    pub(super) async fn probed(
+        &mut self,
+        request_id: RequestId,
+        failure: Option<&OutboundFailure>,
+    ) -> Result<bool> {
+        let (peer, address) = match self.pending_discovery_probes.remove(&request_id) {
+            Some(probed) => probed,
+            None => return Ok(false),
+        };
+        self.swarm
+            .behaviour_mut()
+            .request_response
+            .remove_address(&peer, &address);
+        match failure {
+            None => {
+                info!("Peer {peer:?} discovered at {address} is on our network");
+                let _routing_update = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .add_address(&peer, address);
+                let routing_table_size = self.routing_table_size();
+                self.event_sender
+                    .send(NetworkEvent::PeerAdded { routing_table_size })
+                    .await?;
+            }
+            Some(OutboundFailure::UnsupportedProtocols) => {
+                info!("Ignoring peer {peer:?} discovered at {address}, it is on another network");
+                let _ = self.rejected_discoveries.insert(peer);
+            }
+            Some(error) => {
+                debug!("Could not probe peer {peer:?} discovered at {address}: {error}");
+            }
+        }
+        Ok(true)
+    }
+}
+
+// A request that any node answers, and cheaply: the probability that the chunk exists is nil.
+fn probe() -> Request {
+    let name = XorName::random(&mut rand::thread_rng());
+    Request::Query(Query::GetChunk(ChunkAddress::new(name)))
+}
@@ -8,11 +8,11 @@
 
 use crate::protocol::messages::Response;
 
-use super::{cmd::SwarmCmd, NetworkEvent};
+use super::{cmd::SwarmCmd, NetworkEvent, NetworkId};
 
 use libp2p::{
     kad,
-    request_response::{OutboundFailure, RequestId},
+    request_response::{InboundFailure, OutboundFailure, RequestId},
     swarm::DialError,
     TransportError,
 };
@@ -47,6 +47,9 @@ pub enum Error {
     #[error("Outbound Error")]
     OutboundError(#[from] OutboundFailure),
 
+    #[error("Inbound Error")]
+    InboundError(#[from] InboundFailure),
+
     #[error("Kademlia Store error: {0}")]
     KademliaStoreError(#[from] kad::store::Error),
 
@@ -64,4 +67,16 @@ pub enum Error {
 
     #[error("Could not get CLOSE_GROUP_SIZE number of peers.")]
     NotEnoughPeers,
+
+    #[error("Invalid network id: {0}")]
+    InvalidNetworkId(String),
+
+    #[error("Invalid network contacts: {0}")]
+    InvalidContacts(String),
+
+    #[error("Expected to be on the {expected} network, but found {found}")]
+    NetworkMismatch {
+        expected: NetworkId,
+        found: NetworkId,
+    },
 }
@@ -17,11 +17,11 @@ use libp2p::{
     kad::{store::MemoryStore, Kademlia, KademliaEvent, QueryResult, K_VALUE},
     mdns,
     multiaddr::Protocol,
-    request_response::{self, ResponseChannel},
-    swarm::{NetworkBehaviour, SwarmEvent},
+    request_response::{self, RequestId, ResponseChannel},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent},
     PeerId,
 };
-use std::collections::HashSet;
+use std::{collections::HashSet, net::IpAddr, time::Instant};
 use tracing::{info, warn};
 
 #[derive(NetworkBehaviour)]
@@ -29,7 +29,7 @@ use tracing::{info, warn};
 pub(super) struct NodeBehaviour {
     pub(super) request_response: request_response::Behaviour<MsgCodec>,
     pub(super) kademlia: Kademlia<MemoryStore>,
-    pub(super) mdns: mdns::tokio::Behaviour,
+    pub(super) mdns: Toggle<mdns::tokio::Behaviour>,
 }
 
 #[derive(Debug)]
@@ -57,18 +57,41 @@ impl From<mdns::Event> for NodeEvent {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 /// Events forwarded by the underlying Network; to be used by the upper layers
 pub enum NetworkEvent {
     /// Incoming `Request` from a peer
     RequestReceived {
+        /// The peer that sent the request
+        peer: PeerId,
         /// Request
         req: Request,
         /// The channel to send the `Response` through
         channel: ResponseChannel<Response>,
+        /// The id of the request, to await the delivery of the `Response` with
+        request_id: RequestId,
+        /// Whether the peer is in our routing table, i.e. is a node rather than a client
+        from_node: bool,
+        /// The IP the peer is connected from, if known
+        ip: Option<IpAddr>,
     },
     /// Emitted when the DHT is updated
-    PeerAdded,
+    PeerAdded {
+        /// The number of peers in the routing table
+        routing_table_size: usize,
+    },
+    /// Emitted when a peer is being dialled
+    Dialing(PeerId),
+    /// Emitted when a connection with a peer is established, i.e. the handshake succeeded
+    PeerConnected(PeerId),
+    /// Emitted when a peer could not be connected to
+    ConnectionFailed {
+        /// The peer dialled, if known
+        peer: Option<PeerId>,
+        /// Why the connection failed
+        error: String,
+    },
 }
 
 impl SwarmDriver {
@@ -120,7 +143,10 @@ impl SwarmDriver {
                 }
                 KademliaEvent::RoutingUpdated { is_new_peer, .. } => {
                     if *is_new_peer {
-                        self.event_sender.send(NetworkEvent::PeerAdded).await?;
+                        let routing_table_size = self.routing_table_size();
+                        self.event_sender
+                            .send(NetworkEvent::PeerAdded { routing_table_size })
+                            .await?;
                     }
                 }
                 KademliaEvent::InboundRequest { request } => {
@@ -131,17 +157,7 @@ impl SwarmDriver {
                 }
             },
             SwarmEvent::Behaviour(NodeEvent::Mdns(mdns_event)) => match *mdns_event {
-                mdns::Event::Discovered(list) => {
-                    for (peer_id, multiaddr) in list {
-                        info!("Node discovered: {multiaddr:?}");
-                        let _routing_update = self
-                            .swarm
-                            .behaviour_mut()
-                            .kademlia
-                            .add_address(&peer_id, multiaddr);
-                    }
-                    self.event_sender.send(NetworkEvent::PeerAdded).await?;
-                }
+                mdns::Event::Discovered(list) => self.probe_discovered(list),
                 mdns::Event::Expired(peer) => {
                     info!("mdns peer {peer:?} expired");
                 }
@@ -157,25 +173,75 @@ impl SwarmDriver {
             SwarmEvent::ConnectionEstablished {
                 peer_id, endpoint, ..
             } => {
+                self.links.connected(
+                    peer_id,
+                    endpoint.get_remote_address().clone(),
+                    Instant::now(),
+                );
                 if endpoint.is_dialer() {
                     info!("Connected with {peer_id:?}");
                     if let Some(sender) = self.pending_dial.remove(&peer_id) {
                         let _ = sender.send(Ok(()));
                     }
+                    self.event_sender
+                        .send(NetworkEvent::PeerConnected(peer_id))
+                        .await?;
                 }
             }
-            SwarmEvent::ConnectionClosed { .. } => {}
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established,
+                cause,
+                ..
+            } => {
+                self.links.disconnected(
+                    peer_id,
+                    num_established,
+                    cause.map(|cause| cause.to_string()),
+                );
+            }
             SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                let event = NetworkEvent::ConnectionFailed {
+                    peer: peer_id,
+                    error: error.to_string(),
+                };
                 if let Some(peer_id) = peer_id {
+                    self.links.failed(peer_id, error.to_string());
+                    self.links.fault(peer_id, Instant::now());
                     if let Some(sender) = self.pending_dial.remove(&peer_id) {
                         let _ = sender.send(Err(error.into()));
                     }
                 }
+                self.event_sender.send(event).await?;
             }
             SwarmEvent::IncomingConnectionError { .. } => {}
-            SwarmEvent::Dialing(peer_id) => info!("Dialing {peer_id}"),
+            SwarmEvent::Dialing(peer_id) => {
+                info!("Dialing {peer_id}");
+                self.event_sender
+                    .send(NetworkEvent::Dialing(peer_id))
+                    .await?;
+            }
             todo => error!("SwarmEvent has not been implemented: {todo:?}"),
         }
         Ok(())
     }
+
+    /// Whether the peer is in our routing table.
+    pub(crate) fn is_routing_peer(&mut self, peer: &PeerId) -> bool {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .kbucket(*peer)
+            .map(|bucket| bucket.iter().any(|entry| entry.node.key.preimage() == peer))
+            .unwrap_or(false)
+    }
+
+    pub(super) fn routing_table_size(&mut self) -> usize {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .kbuckets()
+            .map(|bucket| bucket.num_entries())
+            .sum()
+    }
 }
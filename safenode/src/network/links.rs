@@ -0,0 +1,295 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+// The number of recent faults of a peer at which it is deprioritised as a target of requests.
+const FAULT_THRESHOLD: usize = 3;
+// How long a fault of a peer counts against it.
+const FAULT_WINDOW: Duration = Duration::from_secs(10 * 60);
+// How often a deprioritised peer is tried again anyway, to find out whether it recovered.
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What is known about the link with a peer, as listed by
+/// [`Network::connections`](super::Network::connections).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    /// The peer id of the peer.
+    pub peer: String,
+    /// The address the peer was last connected at, or is known at.
+    pub address: Option<String>,
+    /// How long, in seconds, the peer has been connected, if it is connected.
+    pub connected_secs: Option<u64>,
+    /// The size in bytes of the messages sent to the peer.
+    pub bytes_sent: u64,
+    /// The size in bytes of the messages received from the peer.
+    pub bytes_received: u64,
+    /// The last error of a connection with, or a request to, the peer.
+    pub last_error: Option<String>,
+    /// The number of recent faults of the peer, such as failed requests or misbehaviour.
+    pub faults: usize,
+    /// Whether the peer is deprioritised as a target of requests, for its faults.
+    pub deprioritised: bool,
+}
+
+// The link with a single peer.
+#[derive(Debug, Default)]
+struct Link {
+    address: Option<Multiaddr>,
+    connected_since: Option<Instant>,
+    bytes_sent: u64,
+    bytes_received: u64,
+    last_error: Option<String>,
+    faults: VecDeque<Instant>,
+    next_probe: Option<Instant>,
+}
+
+impl Link {
+    fn recent_faults(&self, now: Instant) -> usize {
+        self.faults
+            .iter()
+            .filter(|at| now.saturating_duration_since(**at) < FAULT_WINDOW)
+            .count()
+    }
+}
+
+/// The links with every peer that the node was connected to, or failed to connect to.
+#[derive(Debug, Default)]
+pub(super) struct Links {
+    links: HashMap<PeerId, Link>,
+}
+
+impl Links {
+    /// Records a connection with the peer at the given address.
+    pub(super) fn connected(&mut self, peer: PeerId, address: Multiaddr, now: Instant) {
+        let link = self.links.entry(peer).or_default();
+        link.address = Some(address);
+        if link.connected_since.is_none() {
+            link.connected_since = Some(now);
+        }
+    }
+
+    /// Records that a connection with the peer was closed, leaving `remaining` connections open.
+    pub(super) fn disconnected(&mut self, peer: PeerId, remaining: u32, error: Option<String>) {
+        let link = self.links.entry(peer).or_default();
+        if remaining == 0 {
+            link.connected_since = None;
+        }
+        if error.is_some() {
+            link.last_error = error;
+        }
+    }
+
+    /// Records an error of a connection with, or a request to, the peer.
+    pub(super) fn failed(&mut self, peer: PeerId, error: String) {
+        self.links.entry(peer).or_default().last_error = Some(error);
+    }
+
+    /// Records a fault of the peer, such as a failed request or misbehaviour.
+    /// Once it has too many recent faults, it is deprioritised, see [`Self::deprioritised`].
+    pub(super) fn fault(&mut self, peer: PeerId, now: Instant) {
+        let link = self.links.entry(peer).or_default();
+        while let Some(at) = link.faults.front() {
+            if now.saturating_duration_since(*at) < FAULT_WINDOW {
+                break;
+            }
+            let _ = link.faults.pop_front();
+        }
+        link.faults.push_back(now);
+        if link.faults.len() >= FAULT_THRESHOLD && link.next_probe.is_none() {
+            debug!("Deprioritising {peer:?} after {} faults", link.faults.len());
+            link.next_probe = Some(now + PROBE_INTERVAL);
+        }
+    }
+
+    /// The IP the peer was last connected from, if known.
+    pub(super) fn ip(&self, peer: &PeerId) -> Option<IpAddr> {
+        let address = self.links.get(peer)?.address.as_ref()?;
+        address.iter().find_map(|protocol| match protocol {
+            Protocol::Ip4(ip) => Some(IpAddr::from(ip)),
+            Protocol::Ip6(ip) => Some(IpAddr::from(ip)),
+            _ => None,
+        })
+    }
+
+    /// Records a response from the peer, which clears its faults.
+    pub(super) fn responded(&mut self, peer: PeerId) {
+        if let Some(link) = self.links.get_mut(&peer) {
+            if link.next_probe.take().is_some() {
+                debug!("{peer:?} responded, it is no longer deprioritised");
+            }
+            link.faults.clear();
+        }
+    }
+
+    /// The given peers that are to be deprioritised as targets of requests, for their
+    /// recent faults. Each of them is left out once every `PROBE_INTERVAL`, so that
+    /// it is tried again, and gets back in line once it responds.
+    pub(super) fn deprioritised(&mut self, peers: &[PeerId], now: Instant) -> HashSet<PeerId> {
+        peers
+            .iter()
+            .filter(|peer| {
+                let link = match self.links.get_mut(peer) {
+                    Some(link) => link,
+                    None => return false,
+                };
+                if link.recent_faults(now) < FAULT_THRESHOLD {
+                    link.next_probe = None;
+                    return false;
+                }
+                match link.next_probe {
+                    Some(at) if now < at => true,
+                    _ => {
+                        trace!("Probing the deprioritised {peer:?}");
+                        link.next_probe = Some(now + PROBE_INTERVAL);
+                        false
+                    }
+                }
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Records a message of the given size sent to the peer.
+    pub(super) fn sent(&mut self, peer: PeerId, bytes: u64) {
+        self.links.entry(peer).or_default().bytes_sent += bytes;
+    }
+
+    /// Records a message of the given size received from the peer.
+    pub(super) fn received(&mut self, peer: PeerId, bytes: u64) {
+        self.links.entry(peer).or_default().bytes_received += bytes;
+    }
+
+    /// The links with the given peers, and with every other peer that there is a link with.
+    /// Peers without a link are listed at the given address.
+    pub(super) fn list(
+        &self,
+        known: impl IntoIterator<Item = (PeerId, Multiaddr)>,
+        now: Instant,
+    ) -> Vec<ConnectionInfo> {
+        let mut known: HashMap<_, _> = known.into_iter().collect();
+        let mut list: Vec<_> = self
+            .links
+            .iter()
+            .map(|(peer, link)| {
+                let known_address = known.remove(peer);
+                let faults = link.recent_faults(now);
+                ConnectionInfo {
+                    peer: peer.to_string(),
+                    address: link
+                        .address
+                        .as_ref()
+                        .or(known_address.as_ref())
+                        .map(|address| address.to_string()),
+                    connected_secs: link
+                        .connected_since
+                        .map(|since| now.saturating_duration_since(since).as_secs()),
+                    bytes_sent: link.bytes_sent,
+                    bytes_received: link.bytes_received,
+                    last_error: link.last_error.clone(),
+                    faults,
+                    deprioritised: faults >= FAULT_THRESHOLD,
+                }
+            })
+            .collect();
+        list.extend(known.into_iter().map(|(peer, address)| ConnectionInfo {
+            peer: peer.to_string(),
+            address: Some(address.to_string()),
+            connected_secs: None,
+            bytes_sent: 0,
+            bytes_received: 0,
+            last_error: None,
+            faults: 0,
+            deprioritised: false,
+        }));
+        list.sort_by(|a, b| a.peer.cmp(&b.peer));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn links_track_connections_traffic_and_errors() {
+        let start = Instant::now();
+        let address: Multiaddr = "/ip4/10.0.0.1/udp/12000/quic-v1"
+            .parse()
+            .expect("address to parse");
+        let peer = PeerId::random();
+        let other = PeerId::random();
+
+        let mut links = Links::default();
+        links.connected(peer, address.clone(), start);
+        links.connected(peer, address.clone(), start + Duration::from_secs(5));
+        links.sent(peer, 100);
+        links.received(peer, 40);
+        links.failed(peer, "timed out".to_string());
+
+        let list = links.list([(other, address.clone())], start + Duration::from_secs(10));
+        assert_eq!(list.len(), 2);
+        let info = list
+            .iter()
+            .find(|info| info.peer == peer.to_string())
+            .expect("peer to be listed");
+        assert_eq!(info.connected_secs, Some(10));
+        assert_eq!((info.bytes_sent, info.bytes_received), (100, 40));
+        assert_eq!(info.last_error.as_deref(), Some("timed out"));
+        assert_eq!(info.address, Some(address.to_string()));
+        assert_eq!(links.ip(&peer), Some(IpAddr::from([10, 0, 0, 1])));
+        assert_eq!(links.ip(&PeerId::random()), None);
+
+        links.disconnected(peer, 1, None);
+        links.disconnected(peer, 0, Some("closed".to_string()));
+        let list = links.list([], start);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].connected_secs, None);
+        assert_eq!(list[0].last_error.as_deref(), Some("closed"));
+    }
+
+    #[test]
+    fn faulty_peers_are_deprioritised_and_probed() {
+        let start = Instant::now();
+        let faulty = PeerId::random();
+        let healthy = PeerId::random();
+        let peers = [faulty, healthy];
+
+        let mut links = Links::default();
+        for secs in 0..FAULT_THRESHOLD as u64 {
+            assert!(links.deprioritised(&peers, start).is_empty());
+            links.fault(faulty, start + Duration::from_secs(secs));
+        }
+        let now = start + Duration::from_secs(10);
+        assert_eq!(links.deprioritised(&peers, now), HashSet::from([faulty]));
+        let list = links.list([], now);
+        assert_eq!((list[0].faults, list[0].deprioritised), (3, true));
+
+        // Once every probe interval, it is tried again.
+        let probe = start + PROBE_INTERVAL + Duration::from_secs(10);
+        assert!(links.deprioritised(&peers, probe).is_empty());
+        assert_eq!(links.deprioritised(&peers, probe), HashSet::from([faulty]));
+
+        // Once it responds, or its faults are old enough, it is back in line.
+        links.responded(faulty);
+        assert!(links.deprioritised(&peers, probe).is_empty());
+        for _ in 0..FAULT_THRESHOLD {
+            links.fault(faulty, probe);
+        }
+        assert_eq!(links.deprioritised(&peers, probe), HashSet::from([faulty]));
+        assert!(links.deprioritised(&peers, probe + FAULT_WINDOW).is_empty());
+    }
+}
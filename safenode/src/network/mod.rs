@@ -6,19 +6,31 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod bootstrap;
 mod cmd;
+mod discovery;
 mod error;
 mod event;
+mod links;
 mod msg;
 
 use crate::protocol::messages::{Request, Response};
 
-pub use self::{error::Error, event::NetworkEvent};
+pub use self::{
+    bootstrap::{
+        check_network, GenesisBundle, NetworkBootstrapBuilder, NetworkContacts, NetworkId,
+    },
+    error::Error,
+    event::NetworkEvent,
+    links::ConnectionInfo,
+    msg::{decode_request, decode_response},
+};
 
 use self::{
     cmd::SwarmCmd,
     error::Result,
     event::NodeBehaviour,
+    links::Links,
     msg::{MsgCodec, MsgProtocol},
 };
 
@@ -26,7 +38,10 @@ use futures::StreamExt;
 use libp2p::{
     core::muxing::StreamMuxerBox,
     identity,
-    kad::{record::store::MemoryStore, KBucketKey, Kademlia, KademliaConfig, QueryId},
+    kad::{
+        record::store::MemoryStore, KBucketKey, Kademlia, KademliaBucketInserts, KademliaConfig,
+        QueryId,
+    },
     mdns,
     multiaddr::Protocol,
     request_response::{self, ProtocolSupport, RequestId, ResponseChannel},
@@ -69,6 +84,10 @@ pub struct SwarmDriver {
     pending_dial: HashMap<PeerId, oneshot::Sender<Result<()>>>,
     pending_get_closest_peers: PendingGetClosest,
     pending_requests: HashMap<RequestId, oneshot::Sender<Result<Response>>>,
+    pending_response_acks: HashMap<RequestId, oneshot::Sender<Result<()>>>,
+    pending_discovery_probes: HashMap<RequestId, (PeerId, Multiaddr)>,
+    rejected_discoveries: HashSet<PeerId>,
+    links: Links,
 }
 
 impl SwarmDriver {
@@ -76,6 +95,7 @@ impl SwarmDriver {
     /// for sending commands and an `mpsc::Receiver<NetworkEvent>` for receiving
     /// network events. It initializes the swarm, sets up the transport, and
     /// configures the Kademlia and mDNS behaviors for peer discovery.
+    /// mDNS is only used when built with the `local-discovery` feature.
     ///
     /// # Returns
     ///
@@ -85,18 +105,23 @@ impl SwarmDriver {
     /// # Errors
     ///
     /// Returns an error if there is a problem initializing the mDNS behavior.
-    pub fn new(addr: SocketAddr) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
-        let mut cfg = KademliaConfig::default();
+    pub fn new(
+        addr: SocketAddr,
+        keypair: identity::Keypair,
+        network_id: &NetworkId,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+        let mut cfg = kad_config(network_id);
         let _ = cfg.set_query_timeout(Duration::from_secs(5 * 60));
         let _ = cfg.set_connection_idle_timeout(Duration::from_secs(10 * 60));
 
         let request_response = request_response::Behaviour::new(
             MsgCodec(),
-            iter::once((MsgProtocol(), ProtocolSupport::Full)),
+            iter::once((MsgProtocol::of(network_id), ProtocolSupport::Full)),
             Default::default(),
         );
 
-        let (network, events_receiver, mut swarm_driver) = Self::with(cfg, request_response)?;
+        let (network, events_receiver, mut swarm_driver) =
+            Self::with(keypair, cfg, request_response, true)?;
 
         // Listen on the provided address
         let addr = Multiaddr::from(addr.ip())
@@ -111,26 +136,52 @@ impl SwarmDriver {
     }
 
     /// Same as `new` API but creates the network components in client mode
-    pub fn new_client() -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+    pub fn new_client(
+        network_id: &NetworkId,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
         // Create a Kademlia behaviour for client mode, i.e. set req/resp protocol
         // to outbound-only mode and don't listen on any address
-        let cfg = KademliaConfig::default(); // default query timeout is 60 secs
+        let cfg = kad_config(network_id); // default query timeout is 60 secs
         let request_response = request_response::Behaviour::new(
             MsgCodec(),
-            iter::once((MsgProtocol(), ProtocolSupport::Outbound)),
+            iter::once((MsgProtocol::of(network_id), ProtocolSupport::Outbound)),
             Default::default(),
         );
 
-        Self::with(cfg, request_response)
+        // Create a random key for ourself.
+        let keypair = identity::Keypair::generate_ed25519();
+        Self::with(keypair, cfg, request_response, true)
     }
 
-    // Private helper to create the network components with the provided config and req/res behaviour
+    /// Same as `new_client`, but for a light client, which keeps no routing table of its own.
+    ///
+    /// Peers are not discovered on the local network, nor added to the routing table when
+    /// connected to, so the routing table only holds the peers explicitly dialled, e.g. a
+    /// gateway. Lookups of the closest peers to a name then start from those peers.
+    pub fn new_light_client(
+        network_id: &NetworkId,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+        let mut cfg = kad_config(network_id);
+        let _ = cfg.set_kbucket_inserts(KademliaBucketInserts::Manual);
+        let request_response = request_response::Behaviour::new(
+            MsgCodec(),
+            iter::once((MsgProtocol::of(network_id), ProtocolSupport::Outbound)),
+            Default::default(),
+        );
+
+        let keypair = identity::Keypair::generate_ed25519();
+        Self::with(keypair, cfg, request_response, false)
+    }
+
+    // Private helper to create the network components with the provided config and req/res behaviour,
+    // discovering peers on the local network with mDNS if `local_discovery` is set,
+    // and the `local-discovery` feature is enabled.
     fn with(
+        keypair: identity::Keypair,
         cfg: KademliaConfig,
         request_response: request_response::Behaviour<MsgCodec>,
+        local_discovery: bool,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
-        // Create a random key for ourself.
-        let keypair = identity::Keypair::generate_ed25519();
         let peer_id = PeerId::from(keypair.public());
 
         info!("Peer id: {:?}", peer_id);
@@ -145,11 +196,18 @@ impl SwarmDriver {
         // Create a Kademlia behaviour for client mode, i.e. set req/resp protocol
         // to outbound-only mode and don't listen on any address
         let kademlia = Kademlia::with_config(peer_id, MemoryStore::new(peer_id), cfg);
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id)?;
+        let mdns = if local_discovery && cfg!(feature = "local-discovery") {
+            Some(mdns::tokio::Behaviour::new(
+                mdns::Config::default(),
+                peer_id,
+            )?)
+        } else {
+            None
+        };
         let behaviour = NodeBehaviour {
             request_response,
             kademlia,
-            mdns,
+            mdns: mdns.into(),
         };
 
         let swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build();
@@ -163,6 +221,10 @@ impl SwarmDriver {
             pending_dial: Default::default(),
             pending_get_closest_peers: Default::default(),
             pending_requests: Default::default(),
+            pending_response_acks: Default::default(),
+            pending_discovery_probes: Default::default(),
+            rejected_discoveries: Default::default(),
+            links: Default::default(),
         };
 
         Ok((
@@ -205,6 +267,16 @@ impl SwarmDriver {
     }
 }
 
+// The Kademlia config of the given network, with the protocol named after it,
+// so that the routing tables only hold peers of the same network.
+fn kad_config(network_id: &NetworkId) -> KademliaConfig {
+    let mut cfg = KademliaConfig::default();
+    if let Some(name) = network_id.kad_protocol() {
+        let _ = cfg.set_protocol_names(vec![name.into_bytes().into()]);
+    }
+    cfg
+}
+
 /// Restarts the whole program.
 /// It does this at random, one in X times called.
 ///
@@ -283,9 +355,56 @@ impl Network {
         self.get_closest_peers(xor_name, false).await
     }
 
+    /// Returns the peers for a client to send the request to: the closest peers to its
+    /// destination. For a query, those deprioritised for their recent faults are replaced
+    /// by the next closest ones, see [`ConnectionInfo::deprioritised`]. Cmds and events
+    /// always go to the close group, which is where the data must be stored.
+    pub async fn client_get_targets(&self, request: &Request) -> Result<Vec<PeerId>> {
+        self.get_targets(request, true).await
+    }
+
+    /// Returns the peers for our node to send the request to,
+    /// see [`Self::client_get_targets`].
+    pub async fn node_get_targets(&self, request: &Request) -> Result<Vec<PeerId>> {
+        self.get_targets(request, false).await
+    }
+
+    /// Records a fault of the peer, detected above the network layer, e.g. an invalid
+    /// response, so that it is deprioritised as a target too once it has too many.
+    pub async fn record_fault(&self, peer: PeerId) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::RecordFault { peer }).await
+    }
+
     /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name.
     /// If `client` is false, then include `self` among the `closest_peers`
     async fn get_closest_peers(&self, xor_name: XorName, client: bool) -> Result<Vec<PeerId>> {
+        let closest_peers = self.get_sorted_peers(xor_name, client).await?;
+        Self::close_group(xor_name, closest_peers)
+    }
+
+    // The closest peers to the destination of the request, after moving the deprioritised
+    // ones behind all the others if the request is a query.
+    async fn get_targets(&self, request: &Request, client: bool) -> Result<Vec<PeerId>> {
+        let xor_name = *request.dst().name();
+        let peers = self.get_sorted_peers(xor_name, client).await?;
+        if !matches!(request, Request::Query(_)) {
+            return Self::close_group(xor_name, peers);
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetDeprioritised {
+            peers: peers.clone(),
+            sender,
+        })
+        .await?;
+        let deprioritised = receiver.await?;
+        if !deprioritised.is_empty() {
+            debug!("Deprioritising {deprioritised:?} as targets for {xor_name}");
+        }
+        Self::close_group(xor_name, prioritise(request, peers, &deprioritised))
+    }
+
+    // All the peers found close to the given `XorName`, sorted by their distance to it.
+    async fn get_sorted_peers(&self, xor_name: XorName, client: bool) -> Result<Vec<PeerId>> {
         let (sender, receiver) = oneshot::channel();
         self.send_swarm_cmd(SwarmCmd::GetClosestPeers { xor_name, sender })
             .await?;
@@ -302,6 +421,11 @@ impl Network {
             let b = KBucketKey::new(b.to_bytes());
             target.distance(&a).cmp(&target.distance(&b))
         });
+        Ok(closest_peers)
+    }
+
+    // The first `CLOSE_GROUP_SIZE` of the peers.
+    fn close_group(xor_name: XorName, closest_peers: Vec<PeerId>) -> Result<Vec<PeerId>> {
         let closest_peers: Vec<PeerId> = closest_peers
             .iter()
             .take(CLOSE_GROUP_SIZE)
@@ -329,14 +453,53 @@ impl Network {
         receiver.await?
     }
 
-    /// Send a `Response` through the channel opened by the requester.
+    /// Send a `Response` to the given `PeerId`, through the channel opened by the requester.
     pub async fn send_response(
         &self,
         resp: Response,
+        peer: PeerId,
         channel: ResponseChannel<Response>,
     ) -> Result<()> {
-        self.send_swarm_cmd(SwarmCmd::SendResponse { resp, channel })
-            .await
+        self.send_swarm_cmd(SwarmCmd::SendResponse {
+            resp,
+            peer,
+            channel,
+        })
+        .await
+    }
+
+    /// Send a `Response` to the given `PeerId`, and wait until it has been written to the
+    /// connection, or has failed to be.
+    ///
+    /// Unlike [`send_response`](Self::send_response), which only queues the response,
+    /// this lets callers make sure a response was delivered before sending anything that
+    /// depends on it, without the peer having to acknowledge it with a request of its own.
+    pub async fn send_response_with_ack(
+        &self,
+        resp: Response,
+        peer: PeerId,
+        request_id: RequestId,
+        channel: ResponseChannel<Response>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::SendResponseWithAck {
+            resp,
+            peer,
+            request_id,
+            channel,
+            sender,
+        })
+        .await?;
+        receiver.await?
+    }
+
+    /// Lists every peer that we were connected to, failed to connect to, or have in
+    /// the routing table, with the state of the link with it.
+    pub async fn connections(&self) -> Result<Vec<ConnectionInfo>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetConnections { sender })
+            .await?;
+        Ok(receiver.await?)
     }
 
     // Helper to send SwarmCmd
@@ -347,10 +510,32 @@ impl Network {
     }
 }
 
+// Moves the deprioritised peers behind all the others, for a query only: a cmd or an event
+// must reach the close group, however faulty its members.
+fn prioritise(
+    request: &Request,
+    mut peers: Vec<PeerId>,
+    deprioritised: &HashSet<PeerId>,
+) -> Vec<PeerId> {
+    if matches!(request, Request::Query(_)) && !deprioritised.is_empty() {
+        // Stable, so that the peers stay sorted by distance otherwise.
+        peers.sort_by_key(|peer| deprioritised.contains(peer));
+    }
+    peers
+}
+
 #[cfg(test)]
 mod tests {
-    use super::SwarmDriver;
-    use crate::log::init_node_logging;
+    use super::{prioritise, NetworkId, SwarmDriver, CLOSE_GROUP_SIZE};
+    use crate::{
+        log::init_node_logging,
+        protocol::{
+            address::ChunkAddress,
+            chunk::Chunk,
+            messages::{Cmd, Query, Request},
+        },
+    };
+    use bytes::Bytes;
     use eyre::{eyre, Result};
     use libp2p::{
         kad::{
@@ -361,7 +546,7 @@ mod tests {
     };
     use rand::thread_rng;
     use std::{
-        collections::{BTreeMap, HashMap},
+        collections::{BTreeMap, HashMap, HashSet},
         fmt,
         net::SocketAddr,
         time::Duration,
@@ -378,6 +563,8 @@ mod tests {
                 "0.0.0.0:0"
                     .parse::<SocketAddr>()
                     .expect("0.0.0.0:0 should parse into a valid `SocketAddr`"),
+                libp2p::identity::Keypair::generate_ed25519(),
+                &NetworkId::Public,
             )?;
             let _handle = tokio::spawn(driver.run());
 
@@ -459,4 +646,21 @@ mod tests {
 
         assert_eq!(vec2.len(), 0);
     }
+
+    #[test]
+    fn cmds_go_to_the_close_group_however_faulty() {
+        let peers: Vec<PeerId> = (0..CLOSE_GROUP_SIZE + 4)
+            .map(|_| PeerId::random())
+            .collect();
+        let faulty = HashSet::from([peers[0]]);
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let query = Request::Query(Query::GetChunk(ChunkAddress::new(*chunk.name())));
+        let cmd = Request::Cmd(Cmd::StoreChunk(chunk));
+
+        assert_eq!(prioritise(&cmd, peers.clone(), &faulty), peers);
+
+        let targets = prioritise(&query, peers.clone(), &faulty);
+        assert_eq!(targets[..CLOSE_GROUP_SIZE], peers[1..=CLOSE_GROUP_SIZE]);
+        assert_eq!(targets.last(), Some(&peers[0]));
+    }
 }
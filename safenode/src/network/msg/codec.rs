@@ -6,7 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::protocol::messages::{Request, Response};
+use crate::{
+    network::NetworkId,
+    protocol::messages::{Request, Response},
+};
 use async_trait::async_trait;
 use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use libp2p::{
@@ -16,14 +19,27 @@ use libp2p::{
 use serde::{de::DeserializeOwned, Serialize};
 use std::io;
 
+// The largest request read from a peer. Requests carry at most a chunk, which
+// self_encryption caps well below this, so anything larger is dropped unread.
+const MAX_REQUEST_SIZE: usize = 8 * 1024 * 1024;
+// The largest response read from a peer.
+const MAX_RESPONSE_SIZE: usize = 500_000_000;
+
+// The protocol is named after the network, so that peers of other networks are not talked to.
 #[derive(Debug, Clone)]
-pub(crate) struct MsgProtocol();
+pub(crate) struct MsgProtocol(String);
 #[derive(Clone)]
 pub(crate) struct MsgCodec();
 
+impl MsgProtocol {
+    pub(crate) fn of(network_id: &NetworkId) -> Self {
+        Self(network_id.msg_protocol())
+    }
+}
+
 impl ProtocolName for MsgProtocol {
     fn protocol_name(&self) -> &[u8] {
-        "/safe/1".as_bytes()
+        self.0.as_bytes()
     }
 }
 
@@ -37,7 +53,7 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        read_and_decode(io, MAX_REQUEST_SIZE).await
     }
 
     async fn read_response<T>(
@@ -48,7 +64,7 @@ impl request_response::Codec for MsgCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        read_and_decode(io, MAX_RESPONSE_SIZE).await
     }
 
     async fn write_request<T>(
@@ -89,16 +105,106 @@ where
     Ok(())
 }
 
-// Decodes the Response/Response using rmp_serde
-async fn read_and_decode<IO, T>(io: &mut IO) -> io::Result<T>
+// Decodes the Response/Response using rmp_serde.
+// A message announcing more than `max_size` bytes is rejected before it is read.
+async fn read_and_decode<IO, T>(io: &mut IO, max_size: usize) -> io::Result<T>
 where
     IO: AsyncRead + Unpin,
     T: DeserializeOwned,
 {
-    let vec = read_length_prefixed(io, 500_000_000).await?; // update transfer maximum
-    if vec.is_empty() {
+    let vec = read_length_prefixed(io, max_size).await?;
+    decode(&vec)
+}
+
+/// Decodes a `Request` as sent by a peer, without the length prefix.
+///
+/// The bytes come straight from the network, so any bytes must either decode or
+/// be an error, and never panic. This is the entry point of the `decode_request` fuzz target.
+pub fn decode_request(bytes: &[u8]) -> io::Result<Request> {
+    decode(bytes)
+}
+
+/// Decodes a `Response` as sent by a peer, without the length prefix.
+///
+/// This is the entry point of the `decode_response` fuzz target.
+pub fn decode_response(bytes: &[u8]) -> io::Result<Response> {
+    decode(bytes)
+}
+
+/// The size in bytes of the message as sent on the wire, without the length prefix.
+pub(crate) fn message_size<T: Serialize>(msg: &T) -> u64 {
+    let mut counter = ByteCounter(0);
+    match rmp_serde::encode::write(&mut counter, msg) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+// Counts the bytes written to it, without keeping them.
+struct ByteCounter(u64);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
+    if bytes.is_empty() {
         return Err(io::ErrorKind::UnexpectedEof.into());
     }
-    rmp_serde::from_slice::<T>(vec.as_slice())
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    rmp_serde::from_slice::<T>(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        address::ChunkAddress,
+        error::Error as ProtocolError,
+        messages::{Query, QueryResponse},
+    };
+
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use xor_name::XorName;
+
+    #[test]
+    fn decoding_arbitrary_bytes_does_not_panic() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let request = Request::Query(Query::GetChunk(ChunkAddress::new(XorName::random(
+            &mut rng,
+        ))));
+        let encoded = rmp_serde::to_vec(&request).expect("a request to encode");
+        assert!(matches!(
+            decode_request(&encoded),
+            Ok(Request::Query(Query::GetChunk(_)))
+        ));
+
+        for _ in 0..10_000 {
+            let len = rng.gen_range(0..256);
+            let mut bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let _ = decode_request(&bytes);
+            let _ = decode_response(&bytes);
+
+            // Also mutate valid messages, which get much further into decoding.
+            bytes = encoded.clone();
+            let at = rng.gen_range(0..bytes.len());
+            bytes[at] = rng.gen();
+            bytes.truncate(rng.gen_range(at..=bytes.len()));
+            let _ = decode_request(&bytes);
+        }
+
+        let missing = ChunkAddress::new(XorName::random(&mut rng));
+        let response = Response::Query(QueryResponse::GetChunk(Err(ProtocolError::ChunkNotFound(
+            missing,
+        ))));
+        let encoded = rmp_serde::to_vec(&response).expect("a response to encode");
+        assert!(decode_response(&encoded).is_ok());
+    }
 }
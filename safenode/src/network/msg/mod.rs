@@ -7,11 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod codec;
-pub(crate) use codec::{MsgCodec, MsgProtocol};
+pub use codec::{decode_request, decode_response};
+pub(crate) use codec::{message_size, MsgCodec, MsgProtocol};
 
 use crate::network::{error::Error, NetworkEvent, SwarmDriver};
 use crate::protocol::messages::{Request, Response};
 use libp2p::request_response::{self, Message};
+use std::time::Instant;
 use tracing::{trace, warn};
 
 impl SwarmDriver {
@@ -21,7 +23,7 @@ impl SwarmDriver {
         event: request_response::Event<Request, Response>,
     ) -> Result<(), Error> {
         match event {
-            request_response::Event::Message { message, .. } => match message {
+            request_response::Event::Message { peer, message } => match message {
                 Message::Request {
                     request,
                     channel,
@@ -29,10 +31,17 @@ impl SwarmDriver {
                     ..
                 } => {
                     trace!("Received request with id: {request_id:?}, req: {request:?}");
+                    self.links.received(peer, message_size(&request));
+                    let from_node = self.is_routing_peer(&peer);
+                    let ip = self.links.ip(&peer);
                     self.event_sender
                         .send(NetworkEvent::RequestReceived {
+                            peer,
                             req: request,
                             channel,
+                            request_id,
+                            from_node,
+                            ip,
                         })
                         .await?
                 }
@@ -41,6 +50,11 @@ impl SwarmDriver {
                     response,
                 } => {
                     trace!("Got response for id: {request_id:?}, res: {response:?} ");
+                    self.links.received(peer, message_size(&response));
+                    self.links.responded(peer);
+                    if self.probed(request_id, None).await? {
+                        return Ok(());
+                    }
                     self.pending_requests
                         .remove(&request_id)
                         .ok_or(Error::ReceivedResponseDropped(request_id))?
@@ -49,8 +63,15 @@ impl SwarmDriver {
                 }
             },
             request_response::Event::OutboundFailure {
-                request_id, error, ..
+                peer,
+                request_id,
+                error,
             } => {
+                if self.probed(request_id, Some(&error)).await? {
+                    return Ok(());
+                }
+                self.links.failed(peer, error.to_string());
+                self.links.fault(peer, Instant::now());
                 self.pending_requests
                     .remove(&request_id)
                     .ok_or(Error::ReceivedResponseDropped(request_id))?
@@ -63,9 +84,16 @@ impl SwarmDriver {
                 error,
             } => {
                 warn!("RequestResponse: InboundFailure for request_id: {request_id:?} and peer: {peer:?}, with error: {error:?}");
+                self.links.failed(peer, error.to_string());
+                if let Some(sender) = self.pending_response_acks.remove(&request_id) {
+                    let _ = sender.send(Err(error.into()));
+                }
             }
             request_response::Event::ResponseSent { peer, request_id } => {
                 trace!("ResponseSent for request_id: {request_id:?} and peer: {peer:?}");
+                if let Some(sender) = self.pending_response_acks.remove(&request_id) {
+                    let _ = sender.send(Ok(()));
+                }
             }
         }
         Ok(())
@@ -9,67 +9,182 @@
 use super::{
     error::{Error, Result},
     event::NodeEventsChannel,
-    Node, NodeEvent,
+    lanes::{Lane, Lanes},
+    BanConfig, MaintenanceSchedule, Misbehaviour, MisbehaviourTracker, Node, NodeCtl, NodeEvent,
+    NodeIdentity, NodeMetrics, Operation, QueryCache, RateLimitConfig, RateLimiter, Rejection,
+    RequestSource,
 };
 
 use crate::{
-    network::{close_group_majority, NetworkEvent, SwarmDriver},
+    network::{close_group_majority, NetworkContacts, NetworkEvent, SwarmDriver},
     network_transfers::{Error as TransferError, Transfers},
     protocol::{
-        address::{dbc_address, DbcAddress},
+        address::{dbc_address, DataAddress, DbcAddress},
         error::Error as ProtocolError,
         messages::{
-            Cmd, CmdResponse, Event, Query, QueryResponse, RegisterCmd, Request, Response,
+            Cmd, CmdResponse, Event, Query, QueryResponse, RecordDistribution, Request, Response,
             SpendQuery,
         },
-        register::User,
     },
-    storage::{ChunkStorage, RegisterStorage},
+    storage::{ChunkStorage, DataTypes, RegisterStorage},
 };
 
-use sn_dbc::{DbcTransaction, MainKey, SignedSpend};
+use sn_dbc::{DbcTransaction, SignedSpend};
 
 use futures::future::select_all;
 use libp2p::{request_response::ResponseChannel, PeerId};
-use std::{collections::BTreeSet, net::SocketAddr, time::Duration};
-use tokio::task::spawn;
+use std::{
+    collections::BTreeSet,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::watch, task::spawn};
 use xor_name::XorName;
 
+// How often the stored Registers are compacted and checked for integrity.
+const REGISTER_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// The number of stored Registers whose signatures are verified on each maintenance pass.
+const REGISTER_INTEGRITY_SAMPLE_SIZE: usize = 100;
+// The number of stored Registers whose signatures are verified in a maintenance window.
+const WINDOW_INTEGRITY_SAMPLE_SIZE: usize = 10 * REGISTER_INTEGRITY_SAMPLE_SIZE;
+// How long a peer whose request was shed is told to back off for.
+const SHED_RETRY_AFTER: Duration = Duration::from_secs(1);
+// How often the node checks whether it entered or left a maintenance window.
+const MAINTENANCE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 impl Node {
     /// Asynchronously runs a new node instance, setting up the swarm driver,
-    /// creating a data storage, and handling network events. Returns the
-    /// created node and a `NodeEventsChannel` for listening to node-related
-    /// events.
+    /// creating a data storage, and handling network events. Returns a
+    /// `NodeEventsChannel` for listening to node-related events, and a
+    /// `NodeCtl` for controlling the node.
+    ///
+    /// The node is known on the network by the given `identity`, and joins the
+    /// network of the given `contacts`, connecting to them first.
+    /// Requests from any single peer are limited according to `rate_limit`,
+    /// and the responses to their queries are cached for `query_cache_ttl`
+    /// so that retries are cheap. Within the windows of `maintenance`, the node
+    /// takes less load, and does its heavy upkeep.
     ///
     /// # Returns
     ///
-    /// A tuple containing a `Node` instance and a `NodeEventsChannel`.
+    /// A tuple containing a `NodeEventsChannel` and a `NodeCtl`.
     ///
     /// # Errors
     ///
     /// Returns an error if there is a problem initializing the `SwarmDriver`.
-    pub async fn run(addr: SocketAddr) -> Result<NodeEventsChannel> {
-        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new(addr)?;
+    pub async fn run(
+        identity: NodeIdentity,
+        addr: SocketAddr,
+        contacts: &NetworkContacts,
+        rate_limit: RateLimitConfig,
+        query_cache_ttl: Duration,
+        ban_config: BanConfig,
+        maintenance: MaintenanceSchedule,
+    ) -> Result<(NodeEventsChannel, NodeCtl)> {
+        let (network, mut network_event_receiver, swarm_driver) =
+            SwarmDriver::new(addr, identity.keypair().clone(), &contacts.network_id)?;
         let node_events_channel = NodeEventsChannel::default();
         let node_id = super::to_node_id(network.peer_id);
 
+        let registers = RegisterStorage::new();
+        let mut data = DataTypes::default();
+        data.register(Arc::new(ChunkStorage::new()));
+        data.register(Arc::new(registers.clone()));
+
         let mut node = Self {
             network,
-            chunks: ChunkStorage::new(),
-            registers: RegisterStorage::new(),
-            transfers: Transfers::new(node_id, MainKey::random()),
+            data,
+            transfers: Transfers::new(node_id, identity.main_key().clone()),
+            events_channel: node_events_channel.clone(),
+            rate_limiter: RateLimiter::new(rate_limit),
+            query_cache: QueryCache::new(query_cache_ttl),
+            misbehaviours: MisbehaviourTracker::new(ban_config),
+            metrics: NodeMetrics::default(),
+        };
+
+        let (runtime_config, mut runtime_config_rx) = watch::channel((rate_limit, ban_config));
+        let node_ctl = NodeCtl {
+            network: node.network.clone(),
+            data: node.data.clone(),
             events_channel: node_events_channel.clone(),
+            metrics: node.metrics.clone(),
+            runtime_config: Arc::new(runtime_config),
+            keypair: identity.keypair().clone(),
+            query_cache: node.query_cache.clone(),
         };
 
         let _handle = spawn(swarm_driver.run());
+        if contacts.contacts.is_empty() && !cfg!(feature = "local-discovery") {
+            warn!("There are no contacts, and local discovery is not enabled: only peers that know our address can join us");
+        }
+        for (peer_id, address) in contacts.peers()? {
+            let network = node.network.clone();
+            let _handle = spawn(async move {
+                if let Err(err) = network.dial(peer_id, address).await {
+                    warn!("Could not connect to the contact {peer_id:?}: {err}");
+                }
+            });
+        }
+        let (in_window, mut in_window_rx) = watch::channel(false);
+        if maintenance.windows.is_empty() {
+            let _handle = spawn(maintain_registers(
+                registers,
+                node.query_cache.clone(),
+                node_events_channel.clone(),
+            ));
+        } else {
+            let _handle = spawn(run_maintenance_windows(
+                maintenance.clone(),
+                registers,
+                node.query_cache.clone(),
+                node_events_channel.clone(),
+                in_window,
+            ));
+        }
         let _handle = spawn(async move {
+            let mut usual_rate_limit = rate_limit;
+            let mut lanes = Lanes::default();
             loop {
-                let event = match network_event_receiver.recv().await {
-                    Some(event) => event,
-                    None => {
-                        error!("The `NetworkEvent` channel has been closed");
-                        continue;
+                // Only wait for new events when there is nothing left to handle.
+                if lanes.is_empty() {
+                    match network_event_receiver.recv().await {
+                        Some(event) => node.queue(&mut lanes, event).await,
+                        None => {
+                            error!("The `NetworkEvent` channel has been closed");
+                            continue;
+                        }
+                    }
+                }
+                // Sort everything that has arrived meanwhile into its lane, so that the next
+                // event to handle is picked among all of them. Once the fast lane is full,
+                // the rest is left in the channel, holding the swarm driver back.
+                while lanes.has_room() {
+                    match network_event_receiver.try_recv() {
+                        Ok(event) => node.queue(&mut lanes, event).await,
+                        Err(_) => break,
                     }
+                }
+
+                let reconfigured = runtime_config_rx.has_changed().unwrap_or(false);
+                if reconfigured {
+                    let (rate_limit, ban_config) = *runtime_config_rx.borrow_and_update();
+                    info!("Reconfigured with {rate_limit:?} and {ban_config:?}");
+                    usual_rate_limit = rate_limit;
+                    node.misbehaviours.set_config(ban_config);
+                }
+                if reconfigured || in_window_rx.has_changed().unwrap_or(false) {
+                    let in_window = *in_window_rx.borrow_and_update();
+                    node.rate_limiter
+                        .set_config(maintenance.rate_limit(usual_rate_limit, in_window));
+                }
+
+                let (fast_depth, bulk_depth) = lanes.depths();
+                trace!("Queued events, fast lane: {fast_depth}, bulk lane: {bulk_depth}");
+                node.metrics.set_lane_depths((fast_depth, bulk_depth)).await;
+                let event = match lanes.pop() {
+                    Some(event) => event,
+                    None => continue,
                 };
                 if let Err(err) = node.handle_network_event(event).await {
                     warn!("Error handling network event: {err}");
@@ -77,15 +192,114 @@ impl Node {
             }
         });
 
-        Ok(node_events_channel)
+        Ok((node_events_channel, node_ctl))
+    }
+
+    // Queues the event in its lane, or sheds it if the lane is full, telling the peer
+    // that sent a shed request to back off. Requests that are not admitted are rejected
+    // before they are queued, so that they take no room in the lanes.
+    async fn queue(&mut self, lanes: &mut Lanes<NetworkEvent>, event: NetworkEvent) {
+        let event = match self.admit(event).await {
+            Some(event) => event,
+            None => return,
+        };
+        let lane = Lane::of(&event);
+        let event = match lanes.push(lane, event) {
+            Ok(()) => return,
+            Err(event) => event,
+        };
+        self.metrics.record_shed(lane).await;
+        match event {
+            NetworkEvent::RequestReceived {
+                peer, req, channel, ..
+            } => {
+                debug!("Shedding request from {peer:?}, as the {lane:?} lane is full");
+                let error = ProtocolError::Overloaded {
+                    retry_after: SHED_RETRY_AFTER,
+                };
+                self.reject_request(peer, req, channel, error).await;
+            }
+            event => warn!("Shedding {event:?}, as the {lane:?} lane is full"),
+        }
+    }
+
+    // Returns the event if it is not a request, or if it is a request of a peer that is
+    // neither banned nor sending more than its share. Any other request is rejected,
+    // or dropped without a response if its peer is muted.
+    async fn admit(&mut self, event: NetworkEvent) -> Option<NetworkEvent> {
+        let (peer, from_node, ip) = match &event {
+            NetworkEvent::RequestReceived {
+                peer,
+                from_node,
+                ip,
+                ..
+            } => (*peer, *from_node, *ip),
+            _ => return Some(event),
+        };
+
+        let now = Instant::now();
+        let error = if let Some(retry_after) = self.misbehaviours.banned(&peer, now) {
+            debug!("Rejecting request from banned peer {peer:?}, ban lifted in {retry_after:?}");
+            self.metrics.record_banned().await;
+            Some(ProtocolError::Banned { retry_after })
+        } else {
+            let rejection = match self.rate_limiter.check(peer, from_node, ip, now) {
+                Ok(()) => return Some(event),
+                Err(rejection) => rejection,
+            };
+            self.metrics.record_rejection(rejection).await;
+            let (allowed, throttled, muted) = self.rate_limiter.counters();
+            match rejection {
+                Rejection::Throttled(retry_after) | Rejection::IpThrottled(retry_after) => {
+                    debug!(
+                        "Throttling request from {peer:?} at {ip:?}: {rejection:?} \
+                        (total allowed: {allowed}, throttled: {throttled}, muted: {muted})"
+                    );
+                    Some(ProtocolError::RateLimited { retry_after })
+                }
+                // Muted peers kept sending while throttled, so they are not answered at all.
+                Rejection::Muted { remaining, newly } => {
+                    if newly {
+                        warn!("Muting {peer:?} for {remaining:?}, as it ignores throttling");
+                        self.track_misbehaviour(peer, Misbehaviour::Flooding);
+                    }
+                    None
+                }
+            }
+        };
+
+        if let NetworkEvent::RequestReceived {
+            peer, req, channel, ..
+        } = event
+        {
+            match error {
+                Some(error) => self.reject_request(peer, req, channel, error).await,
+                None => {
+                    trace!("Dropping request from muted peer {peer:?}: {req:?}");
+                    drop(channel);
+                }
+            }
+        }
+        None
     }
 
     async fn handle_network_event(&mut self, event: NetworkEvent) -> Result<()> {
         match event {
-            NetworkEvent::RequestReceived { req, channel } => {
-                self.handle_request(req, channel).await?
+            NetworkEvent::RequestReceived {
+                peer,
+                req,
+                channel,
+                from_node,
+                ..
+            } => {
+                let source = if from_node {
+                    RequestSource::Node
+                } else {
+                    RequestSource::Client
+                };
+                self.handle_request(peer, source, req, channel).await?
             }
-            NetworkEvent::PeerAdded => {
+            NetworkEvent::PeerAdded { .. } => {
                 self.events_channel.broadcast(NodeEvent::ConnectedToNetwork);
                 let target = {
                     let mut rng = rand::thread_rng();
@@ -99,6 +313,9 @@ impl Node {
                     trace!("For target {target:?}, get closest peers {result:?}");
                 });
             }
+            NetworkEvent::Dialing(_)
+            | NetworkEvent::PeerConnected(_)
+            | NetworkEvent::ConnectionFailed { .. } => {}
         }
 
         Ok(())
@@ -106,38 +323,108 @@ impl Node {
 
     async fn handle_request(
         &mut self,
+        peer: PeerId,
+        source: RequestSource,
         request: Request,
         response_channel: ResponseChannel<Response>,
     ) -> Result<()> {
         trace!("Handling request: {request:?}");
+        let started = Instant::now();
+        let operation = Operation::of(&request);
         let response = match request {
-            Request::Cmd(cmd) => Response::Cmd(self.handle_cmd(cmd).await),
-            Request::Query(query) => Response::Query(self.handle_query(query).await),
+            Request::Cmd(cmd) => {
+                self.query_cache.invalidate(&cmd.dst());
+                let response = self.handle_cmd(cmd).await;
+                if let Some(issue) = Misbehaviour::of(&response) {
+                    self.track_misbehaviour(peer, issue);
+                }
+                Response::Cmd(response)
+            }
+            Request::Query(query) => {
+                let now = Instant::now();
+                if let Some(response) = self.query_cache.get(peer, &query, now) {
+                    debug!(
+                        "Answering repeated query from {peer:?} from the cache (total hits: {})",
+                        self.query_cache.hits()
+                    );
+                    Response::Query(response)
+                } else {
+                    let response = self.handle_query(query.clone()).await;
+                    self.query_cache.insert(peer, &query, &response, now);
+                    Response::Query(response)
+                }
+            }
             Request::Event(event) => {
+                self.query_cache.invalidate(&event.dst());
                 match event {
                     Event::DoubleSpendAttempted(a_spend, b_spend) => {
                         self.transfers
                             .try_add_double(a_spend.as_ref(), b_spend.as_ref())
                             .await
                             .map_err(ProtocolError::Transfers)?;
+                        self.events_channel
+                            .broadcast(NodeEvent::DoubleSpendDetected {
+                                new: b_spend,
+                                existing: a_spend,
+                            });
                         return Ok(());
                     }
                 };
             }
         };
+        if let Some(operation) = operation {
+            self.metrics
+                .record(operation, source, started.elapsed())
+                .await;
+        }
 
-        self.send_response(response, response_channel).await;
+        self.send_response(response, peer, response_channel).await;
 
         Ok(())
     }
 
+    // Records the misbehaviour of the peer as a fault, banning the peer once its
+    // misbehaviours add up to the ban threshold.
+    fn track_misbehaviour(&mut self, peer: PeerId, issue: Misbehaviour) {
+        debug!("Peer {peer:?} misbehaved: {issue:?}");
+        let network = self.network.clone();
+        let _handle = spawn(async move {
+            if let Err(err) = network.record_fault(peer).await {
+                warn!("Could not record the misbehaviour of {peer:?} as a fault: {err}");
+            }
+        });
+        if let Some(duration) = self.misbehaviours.track(peer, issue, Instant::now()) {
+            warn!("Banning peer {peer:?} for {duration:?} after repeated misbehaviour");
+            self.events_channel.broadcast(NodeEvent::PeerBanned {
+                peer: peer.to_string(),
+                misbehaviour: issue,
+                duration,
+            });
+        }
+    }
+
+    // Responds to the request with the error, e.g. `RateLimited`, without handling it.
+    async fn reject_request(
+        &self,
+        peer: PeerId,
+        request: Request,
+        response_channel: ResponseChannel<Response>,
+        error: ProtocolError,
+    ) {
+        let response = match request {
+            Request::Cmd(cmd) => Response::Cmd(cmd.error(error)),
+            Request::Query(query) => Response::Query(query.error(error)),
+            // Events get no response, so they are just dropped.
+            Request::Event(_) => return,
+        };
+        self.send_response(response, peer, response_channel).await;
+    }
+
     async fn handle_query(&mut self, query: Query) -> QueryResponse {
+        if let Some(resp) = self.data.handle_query(&query).await {
+            return resp;
+        }
         match query {
-            Query::Register(query) => self.registers.read(&query, User::Anyone).await,
-            Query::GetChunk(address) => {
-                let resp = self.chunks.get(&address).await;
-                QueryResponse::GetChunk(resp)
-            }
             Query::Spend(query) => {
                 match query {
                     SpendQuery::GetFees { dbc_id, priority } => {
@@ -157,22 +444,47 @@ impl Node {
                     }
                 }
             }
+            Query::GetRecordDistribution { prefix_bits, .. } => {
+                let mut distribution = RecordDistribution::new(prefix_bits);
+                for address in self.data.addrs().await {
+                    match address {
+                        DataAddress::Chunk(address) => distribution.add_chunk(address.name()),
+                        DataAddress::Register(address) => distribution.add_register(address.name()),
+                        DataAddress::Spend(_) => {}
+                    }
+                }
+                QueryResponse::GetRecordDistribution(Ok(distribution))
+            }
+            Query::GetTime { .. } => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|since| since.as_millis() as u64)
+                    .unwrap_or_default();
+                QueryResponse::GetTime(Ok(now))
+            }
+            other => {
+                warn!("No data type handles {other:?}");
+                other.error(ProtocolError::UnexpectedResponses)
+            }
         }
     }
 
     async fn handle_cmd(&mut self, cmd: Cmd) -> CmdResponse {
-        match cmd {
-            Cmd::StoreChunk(chunk) => {
-                let resp = self.chunks.store(&chunk).await;
-                CmdResponse::StoreChunk(resp)
-            }
-            Cmd::Register(cmd) => {
-                let result = self.registers.write(&cmd).await;
-                match cmd {
-                    RegisterCmd::Create(_) => CmdResponse::CreateRegister(result),
-                    RegisterCmd::Edit(_) => CmdResponse::EditRegister(result),
+        if let Some(resp) = self.data.handle_cmd(&cmd).await {
+            if resp.is_success() {
+                match cmd.dst() {
+                    DataAddress::Chunk(address) => self
+                        .events_channel
+                        .broadcast(NodeEvent::ChunkStored(address)),
+                    DataAddress::Register(address) => self
+                        .events_channel
+                        .broadcast(NodeEvent::RegisterStored(address)),
+                    DataAddress::Spend(_) => {}
                 }
             }
+            return resp;
+        }
+        match cmd {
             Cmd::SpendDbc {
                 signed_spend,
                 source_tx,
@@ -200,6 +512,11 @@ impl Node {
                 {
                     Err(TransferError::DoubleSpendAttempt { new, existing }) => {
                         warn!("Double spend attempted! New: {new:?}. Existing:  {existing:?}");
+                        self.events_channel
+                            .broadcast(NodeEvent::DoubleSpendDetected {
+                                new: new.clone(),
+                                existing: existing.clone(),
+                            });
                         if let Ok(event) =
                             Event::double_spend_attempt(new.clone(), existing.clone())
                         {
@@ -220,6 +537,10 @@ impl Node {
 
                 CmdResponse::Spend(res)
             }
+            other => {
+                warn!("No data type handles {other:?}");
+                other.error(ProtocolError::UnexpectedResponses)
+            }
         }
     }
 
@@ -309,8 +630,17 @@ impl Node {
         Err(super::Error::Protocol(ProtocolError::UnexpectedResponses))
     }
 
-    async fn send_response(&self, resp: Response, response_channel: ResponseChannel<Response>) {
-        if let Err(err) = self.network.send_response(resp, response_channel).await {
+    async fn send_response(
+        &self,
+        resp: Response,
+        peer: PeerId,
+        response_channel: ResponseChannel<Response>,
+    ) {
+        if let Err(err) = self
+            .network
+            .send_response(resp, peer, response_channel)
+            .await
+        {
             warn!("Error while sending response: {err:?}");
         }
     }
@@ -318,10 +648,7 @@ impl Node {
     async fn send_to_closest(&self, request: &Request) -> Result<Vec<Result<Response>>> {
         info!("Sending {:?} to the closest peers.", request.dst());
         // todo: if `self` is present among the closest peers, the request should be routed to self?
-        let closest_peers = self
-            .network
-            .node_get_closest_peers(*request.dst().name())
-            .await?;
+        let closest_peers = self.network.node_get_targets(request).await?;
 
         Ok(self
             .send_and_get_responses(closest_peers, request, true)
@@ -370,3 +697,67 @@ impl Node {
         responses
     }
 }
+
+// Compacts and checks the stored Registers on entering each maintenance window, and tells
+// the event loop whether the node is in a window, to scale its rate limits accordingly.
+async fn run_maintenance_windows(
+    schedule: MaintenanceSchedule,
+    registers: RegisterStorage,
+    query_cache: QueryCache,
+    events_channel: NodeEventsChannel,
+    in_window: watch::Sender<bool>,
+) {
+    let mut interval = tokio::time::interval(MAINTENANCE_CHECK_INTERVAL);
+    loop {
+        let _ = interval.tick().await;
+        let remaining = schedule.remaining(SystemTime::now());
+        let was_in_window = *in_window.borrow();
+        match (remaining, was_in_window) {
+            (Some(remaining), false) => {
+                info!("Entering a maintenance window, for {remaining:?}");
+                let _ = in_window.send(true);
+                events_channel.broadcast(NodeEvent::MaintenanceStarted { remaining });
+                let report = registers
+                    .maintain(WINDOW_INTEGRITY_SAMPLE_SIZE, |addr| {
+                        query_cache.invalidate(&DataAddress::Register(addr))
+                    })
+                    .await;
+                if report.repaired > 0 || report.quarantined > 0 {
+                    warn!("Register maintenance found invalid data: {report:?}");
+                } else {
+                    info!("Register maintenance done: {report:?}");
+                }
+                events_channel.broadcast(NodeEvent::RegistersMaintained(report));
+            }
+            (None, true) => {
+                info!("Leaving the maintenance window");
+                let _ = in_window.send(false);
+                events_channel.broadcast(NodeEvent::MaintenanceEnded);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Periodically compacts and checks the stored Registers, starting right away on startup.
+async fn maintain_registers(
+    registers: RegisterStorage,
+    query_cache: QueryCache,
+    events_channel: NodeEventsChannel,
+) {
+    let mut interval = tokio::time::interval(REGISTER_MAINTENANCE_INTERVAL);
+    loop {
+        let _ = interval.tick().await;
+        let report = registers
+            .maintain(REGISTER_INTEGRITY_SAMPLE_SIZE, |addr| {
+                query_cache.invalidate(&DataAddress::Register(addr))
+            })
+            .await;
+        if report.repaired > 0 || report.quarantined > 0 {
+            warn!("Register maintenance found invalid data: {report:?}");
+        } else {
+            info!("Register maintenance done: {report:?}");
+        }
+        events_channel.broadcast(NodeEvent::RegistersMaintained(report));
+    }
+}
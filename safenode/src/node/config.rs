@@ -0,0 +1,278 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, BanConfig, MaintenanceSchedule, MaintenanceWindow, RateLimitConfig};
+
+use crate::network::{NetworkContacts, NetworkId};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// How long the responses to queries are kept, by default.
+const DEFAULT_QUERY_CACHE_TTL_MS: u64 = 2000;
+// The number of records per second pushed when republishing, by default.
+const DEFAULT_REPUBLISH_RATE: u32 = 50;
+// The share of the usual rate limits taken in maintenance windows, by default.
+const DEFAULT_MAINTENANCE_LOAD: u8 = 50;
+// How often, in seconds, the records the node is no longer responsible for are pruned, by default.
+const DEFAULT_PRUNE_INTERVAL: u64 = 60 * 60;
+
+/// The configuration of a node, e.g. as read from a `safenode.toml` file.
+///
+/// Every setting is optional, so that configs from several sources can be layered
+/// with [`NodeConfig::or`], and settings that are set nowhere take their default.
+/// Only the rate limits and the ban settings can be changed while the node is running,
+/// see [`NodeCtl::reconfigure`](super::NodeCtl::reconfigure).
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    /// The dir the identity of the node is kept in. Defaults to `~/.safe/node`.
+    pub root_dir: Option<PathBuf>,
+    /// The IP to listen on. Defaults to 0.0.0.0, i.e. all network interfaces.
+    pub ip: Option<IpAddr>,
+    /// The port to listen on. Defaults to 0, i.e. any available port.
+    pub port: Option<u16>,
+    /// The dir to write logs to. Defaults to logging to stdout.
+    pub log_dir: Option<PathBuf>,
+    /// The maximum number of requests any single client can send in a burst.
+    pub max_request_burst: Option<u32>,
+    /// The number of requests per second any single client can sustain.
+    pub max_requests_per_sec: Option<u32>,
+    /// The maximum number of requests any single node in the routing table can send in a burst.
+    pub max_node_request_burst: Option<u32>,
+    /// The number of requests per second any single node in the routing table can sustain.
+    pub max_node_requests_per_sec: Option<u32>,
+    /// The maximum number of requests the clients at any single IP can send in a burst.
+    pub max_ip_request_burst: Option<u32>,
+    /// The number of requests per second the clients at any single IP can sustain.
+    pub max_ip_requests_per_sec: Option<u32>,
+    /// How long, in milliseconds, the responses to queries are kept to answer retries.
+    pub query_cache_ttl: Option<u64>,
+    /// The total weight of misbehaviours at which a peer is banned.
+    pub ban_threshold: Option<u32>,
+    /// How long, in seconds, a misbehaving peer is banned for.
+    pub ban_duration: Option<u64>,
+    /// The number of records per second to push when republishing.
+    pub republish_rate: Option<u32>,
+    /// The unix socket to serve the events of the node on.
+    pub events_socket: Option<PathBuf>,
+    /// The contacts file of the private network to join. Defaults to the public network.
+    pub network_contacts: Option<PathBuf>,
+    /// The network the node must be on, the node refusing to start on any other one.
+    pub network_id: Option<NetworkId>,
+    /// The windows in which the node does its heavy upkeep and takes less load,
+    /// e.g. `["sun 02:00-04:00"]`, in UTC. See [`MaintenanceWindow`] for the format.
+    pub maintenance_windows: Option<Vec<MaintenanceWindow>>,
+    /// The share of the usual rate limits the node takes in maintenance windows, in percent.
+    pub maintenance_load: Option<u8>,
+    /// How often, in seconds, the node deletes the records it is no longer responsible
+    /// for, once enough closer peers hold them. 0 disables pruning.
+    pub prune_interval: Option<u64>,
+}
+
+impl NodeConfig {
+    /// Reads the config from the toml file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Takes every setting that is not set in this config from the other one.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            root_dir: self.root_dir.or(other.root_dir),
+            ip: self.ip.or(other.ip),
+            port: self.port.or(other.port),
+            log_dir: self.log_dir.or(other.log_dir),
+            max_request_burst: self.max_request_burst.or(other.max_request_burst),
+            max_requests_per_sec: self.max_requests_per_sec.or(other.max_requests_per_sec),
+            max_node_request_burst: self.max_node_request_burst.or(other.max_node_request_burst),
+            max_node_requests_per_sec: self
+                .max_node_requests_per_sec
+                .or(other.max_node_requests_per_sec),
+            max_ip_request_burst: self.max_ip_request_burst.or(other.max_ip_request_burst),
+            max_ip_requests_per_sec: self
+                .max_ip_requests_per_sec
+                .or(other.max_ip_requests_per_sec),
+            query_cache_ttl: self.query_cache_ttl.or(other.query_cache_ttl),
+            ban_threshold: self.ban_threshold.or(other.ban_threshold),
+            ban_duration: self.ban_duration.or(other.ban_duration),
+            republish_rate: self.republish_rate.or(other.republish_rate),
+            events_socket: self.events_socket.or(other.events_socket),
+            network_contacts: self.network_contacts.or(other.network_contacts),
+            network_id: self.network_id.or(other.network_id),
+            maintenance_windows: self.maintenance_windows.or(other.maintenance_windows),
+            maintenance_load: self.maintenance_load.or(other.maintenance_load),
+            prune_interval: self.prune_interval.or(other.prune_interval),
+        }
+    }
+
+    /// The config with every setting that is not set taking its default.
+    pub fn effective(self) -> Self {
+        self.or(Self::defaults())
+    }
+
+    /// The config as toml.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// The address to listen on.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(
+            self.ip.unwrap_or(Ipv4Addr::UNSPECIFIED.into()),
+            self.port.unwrap_or_default(),
+        )
+    }
+
+    /// The limits applied to the requests of each peer, and of each IP.
+    pub fn rate_limit(&self) -> RateLimitConfig {
+        let defaults = RateLimitConfig::default();
+        RateLimitConfig {
+            burst: self.max_request_burst.unwrap_or(defaults.burst),
+            requests_per_sec: self
+                .max_requests_per_sec
+                .unwrap_or(defaults.requests_per_sec),
+            node_burst: self.max_node_request_burst.unwrap_or(defaults.node_burst),
+            node_requests_per_sec: self
+                .max_node_requests_per_sec
+                .unwrap_or(defaults.node_requests_per_sec),
+            ip_burst: self.max_ip_request_burst.unwrap_or(defaults.ip_burst),
+            ip_requests_per_sec: self
+                .max_ip_requests_per_sec
+                .unwrap_or(defaults.ip_requests_per_sec),
+            ..defaults
+        }
+    }
+
+    /// How long the responses to queries are kept to answer retries.
+    pub fn query_cache_ttl(&self) -> Duration {
+        Duration::from_millis(self.query_cache_ttl.unwrap_or(DEFAULT_QUERY_CACHE_TTL_MS))
+    }
+
+    /// When peers are banned for misbehaving.
+    pub fn ban_config(&self) -> BanConfig {
+        let defaults = BanConfig::default();
+        BanConfig {
+            ban_threshold: self.ban_threshold.unwrap_or(defaults.ban_threshold),
+            ban_duration: self
+                .ban_duration
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.ban_duration),
+            ..defaults
+        }
+    }
+
+    /// The number of records per second to push when republishing.
+    pub fn republish_rate(&self) -> u32 {
+        self.republish_rate.unwrap_or(DEFAULT_REPUBLISH_RATE)
+    }
+
+    /// When the node is in maintenance, and how much load it takes meanwhile.
+    pub fn maintenance(&self) -> MaintenanceSchedule {
+        MaintenanceSchedule {
+            windows: self.maintenance_windows.clone().unwrap_or_default(),
+            load_percent: self
+                .maintenance_load
+                .unwrap_or(DEFAULT_MAINTENANCE_LOAD)
+                .min(100),
+        }
+    }
+
+    /// How often the node prunes the records it is no longer responsible for,
+    /// or `None` if it does not.
+    pub fn prune_interval(&self) -> Option<Duration> {
+        match self.prune_interval.unwrap_or(DEFAULT_PRUNE_INTERVAL) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// The network to join and the contacts to first connect to on it,
+    /// checked to be on the expected network, if one is set.
+    pub fn network_contacts(&self) -> Result<NetworkContacts> {
+        let contacts = match &self.network_contacts {
+            Some(path) => NetworkContacts::load(path)?,
+            None => NetworkContacts::default(),
+        };
+        if let Some(network_id) = &self.network_id {
+            contacts.check(network_id)?;
+        }
+        Ok(contacts)
+    }
+
+    fn defaults() -> Self {
+        let rate_limit = RateLimitConfig::default();
+        let ban_config = BanConfig::default();
+        Self {
+            root_dir: dirs_next::home_dir().map(|home| home.join(".safe").join("node")),
+            ip: Some(Ipv4Addr::UNSPECIFIED.into()),
+            port: Some(0),
+            log_dir: None,
+            max_request_burst: Some(rate_limit.burst),
+            max_requests_per_sec: Some(rate_limit.requests_per_sec),
+            max_node_request_burst: Some(rate_limit.node_burst),
+            max_node_requests_per_sec: Some(rate_limit.node_requests_per_sec),
+            max_ip_request_burst: Some(rate_limit.ip_burst),
+            max_ip_requests_per_sec: Some(rate_limit.ip_requests_per_sec),
+            query_cache_ttl: Some(DEFAULT_QUERY_CACHE_TTL_MS),
+            ban_threshold: Some(ban_config.ban_threshold),
+            ban_duration: Some(ban_config.ban_duration.as_secs()),
+            republish_rate: Some(DEFAULT_REPUBLISH_RATE),
+            events_socket: None,
+            network_contacts: None,
+            network_id: None,
+            maintenance_windows: Some(vec![]),
+            maintenance_load: Some(DEFAULT_MAINTENANCE_LOAD),
+            prune_interval: Some(DEFAULT_PRUNE_INTERVAL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_take_precedence_over_lower_ones() -> Result<()> {
+        let file: NodeConfig = toml::from_str("port = 12000\nmax_request_burst = 10\n")?;
+        let flags = NodeConfig {
+            port: Some(13000),
+            ..Default::default()
+        };
+
+        let config = flags.or(file);
+        assert_eq!(config.socket_addr().port(), 13000);
+        assert_eq!(config.rate_limit().burst, 10);
+        assert_eq!(
+            config.rate_limit().requests_per_sec,
+            RateLimitConfig::default().requests_per_sec
+        );
+
+        let effective = config.effective();
+        assert_eq!(
+            effective.ban_threshold,
+            Some(BanConfig::default().ban_threshold)
+        );
+        assert_eq!(
+            toml::from_str::<NodeConfig>(&effective.to_toml()?)?,
+            effective
+        );
+
+        let windows: NodeConfig = toml::from_str("maintenance_windows = [\"sun 02:00-04:00\"]")?;
+        assert_eq!(windows.maintenance().windows.len(), 1);
+        assert!(toml::from_str::<NodeConfig>("maintenance_windows = [\"sunday\"]").is_err());
+
+        assert!(toml::from_str::<NodeConfig>("no_such_setting = 1").is_err());
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use std::path::PathBuf;
 use thiserror::Error;
 
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -22,4 +23,31 @@ pub enum Error {
 
     #[error("ResponseTimeout")]
     ResponseTimeout(#[from] tokio::time::error::Elapsed),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid config file: {0}")]
+    InvalidConfig(#[from] toml::de::Error),
+
+    #[error("Could not serialise the config: {0}")]
+    ConfigSerialisation(#[from] toml::ser::Error),
+
+    #[error("Invalid maintenance window {0}")]
+    InvalidMaintenanceWindow(String),
+
+    #[error("The identity is in use by a running node, per the lock at {0:?}")]
+    IdentityInUse(PathBuf),
+
+    #[error("Invalid node identity: {0}")]
+    InvalidIdentity(String),
+
+    #[error("Failed to encrypt the node identity")]
+    FailedToEncryptIdentity,
+
+    #[error("Failed to decrypt the node identity, the passphrase may be wrong")]
+    FailedToDecryptIdentity,
+
+    #[error("Deletion record error: {0}")]
+    DeletionRecord(String),
 }
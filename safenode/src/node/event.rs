@@ -6,6 +6,17 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::Misbehaviour;
+
+use crate::{
+    protocol::address::{ChunkAddress, RegisterAddress},
+    storage::RegisterMaintenanceReport,
+};
+
+use sn_dbc::SignedSpend;
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tokio::sync::broadcast;
 
 /// Channel where users of the public API can listen to events broadcasted by the node.
@@ -34,8 +45,44 @@ impl NodeEventsChannel {
 }
 
 /// Type of events broadcasted by the node to the public API.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum NodeEvent {
     /// The node has been connected to the network
     ConnectedToNetwork,
+    /// The node stored a chunk.
+    ChunkStored(ChunkAddress),
+    /// The node created or edited a Register.
+    RegisterStored(RegisterAddress),
+    /// The node has compacted and checked the integrity of its stored Registers.
+    RegistersMaintained(RegisterMaintenanceReport),
+    /// The node detected, or was notified of, two different spends of the same dbc.
+    DoubleSpendDetected {
+        /// The spend that was received last.
+        new: Box<SignedSpend>,
+        /// The spend that was already known.
+        existing: Box<SignedSpend>,
+    },
+    /// The node banned a peer that misbehaved too often.
+    PeerBanned {
+        /// The id of the banned peer.
+        peer: String,
+        /// The misbehaviour that got the peer banned.
+        misbehaviour: Misbehaviour,
+        /// How long the peer is banned for.
+        duration: Duration,
+    },
+    /// The node entered a maintenance window, taking less load until it ends.
+    MaintenanceStarted {
+        /// How long until the window ends.
+        remaining: Duration,
+    },
+    /// The node left its maintenance window, taking its usual load again.
+    MaintenanceEnded,
+    /// The node is republishing the records it holds, and is done with `done` out of `total`.
+    RepublishProgress {
+        /// The number of records handled so far.
+        done: usize,
+        /// The number of records to republish.
+        total: usize,
+    },
 }
@@ -0,0 +1,248 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use crate::protocol::wallet::{decrypt_with_passphrase, encrypt_with_passphrase};
+
+use sn_dbc::MainKey;
+
+use libp2p::{identity::Keypair, PeerId};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::{
+    fs::Permissions,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+};
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+// Filename for storing the identity of the node.
+const IDENTITY_FILENAME: &str = "identity";
+// Filename of the lock held while a node runs with the identity.
+const LOCK_FILENAME: &str = "identity.lock";
+
+/// The keys a node is known by on the network: the keypair that its peer id, and so its
+/// xorname, is derived from, and the key that its rewards are paid to.
+#[derive(Clone, Debug)]
+pub struct NodeIdentity {
+    keypair: Keypair,
+    main_key: MainKey,
+}
+
+// How an identity is written to a file.
+#[derive(Serialize, Deserialize)]
+struct IdentityBytes {
+    keypair: Vec<u8>,
+    main_key: [u8; 32],
+}
+
+impl NodeIdentity {
+    /// Creates a new random identity.
+    pub fn random() -> Self {
+        Self {
+            keypair: Keypair::generate_ed25519(),
+            main_key: MainKey::random(),
+        }
+    }
+
+    /// Loads the identity stored in the given dir, or creates and stores a new one there.
+    pub fn load_or_create(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(IDENTITY_FILENAME);
+        if path.is_file() {
+            return Self::from_bytes(&fs::read(path)?);
+        }
+        let identity = Self::random();
+        identity.store(root_dir)?;
+        info!(
+            "Created a new node identity, with peer id {}",
+            identity.peer_id()
+        );
+        Ok(identity)
+    }
+
+    /// Stores the identity in the given dir, replacing any identity stored there.
+    /// It is stored in the clear, so on unix only the user running the node can read it.
+    pub fn store(&self, root_dir: &Path) -> Result<()> {
+        fs::create_dir_all(root_dir)?;
+        let mut options = OpenOptions::new();
+        let _ = options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        let _ = options.mode(0o600);
+        let mut file = options.open(root_dir.join(IDENTITY_FILENAME))?;
+        // The mode only applies to a new file, not to one stored before.
+        #[cfg(unix)]
+        file.set_permissions(Permissions::from_mode(0o600))?;
+        file.write_all(&self.to_bytes()?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Writes the identity to the given file, encrypted with the passphrase.
+    pub fn export(&self, path: &Path, passphrase: &str) -> Result<()> {
+        let bytes = encrypt_with_passphrase(passphrase, &self.to_bytes()?)
+            .map_err(|_| Error::FailedToEncryptIdentity)?;
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Reads an identity written by [`NodeIdentity::export`] with the same passphrase.
+    pub fn import(path: &Path, passphrase: &str) -> Result<Self> {
+        let bytes = decrypt_with_passphrase(passphrase, &fs::read(path)?)
+            .map_err(|_| Error::FailedToDecryptIdentity)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// The peer id of the node.
+    pub fn peer_id(&self) -> PeerId {
+        PeerId::from(self.keypair.public())
+    }
+
+    pub(crate) fn keypair(&self) -> &Keypair {
+        &self.keypair
+    }
+
+    pub(crate) fn main_key(&self) -> &MainKey {
+        &self.main_key
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let keypair = self
+            .keypair
+            .to_protobuf_encoding()
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let bytes = IdentityBytes {
+            keypair,
+            main_key: self.main_key.to_bytes(),
+        };
+        bincode::serialize(&bytes).map_err(|err| Error::InvalidIdentity(err.to_string()))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: IdentityBytes =
+            bincode::deserialize(bytes).map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let keypair = Keypair::from_protobuf_encoding(&bytes.keypair)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let secret = bls::SecretKey::from_bytes(bytes.main_key)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        Ok(Self {
+            keypair,
+            main_key: MainKey::new(secret),
+        })
+    }
+}
+
+/// Held while a node runs with the identity stored in a dir, so that a second node
+/// is not started with the same identity. The lock is released when dropped.
+#[derive(Debug)]
+pub struct IdentityLock {
+    path: PathBuf,
+}
+
+impl IdentityLock {
+    /// Takes the lock on the identity stored in the given dir.
+    ///
+    /// Fails if another live process holds it. A lock left behind by a node that did not
+    /// exit cleanly is taken over where it can be told that the process is gone (on Linux),
+    /// and elsewhere has to be removed by hand.
+    pub fn acquire(root_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(root_dir)?;
+        let path = root_dir.join(LOCK_FILENAME);
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    return Ok(Self { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if !is_stale(&path) {
+                        return Err(Error::IdentityInUse(path));
+                    }
+                    warn!("Taking over the identity lock {path:?} left behind by a dead node");
+                    fs::remove_file(&path)?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for IdentityLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Could not release the identity lock {:?}: {err}", self.path);
+        }
+    }
+}
+
+// Whether the process that took the lock is known to be gone.
+fn is_stale(lock_path: &Path) -> bool {
+    let pid = match fs::read_to_string(lock_path) {
+        Ok(pid) => pid,
+        Err(_) => return false,
+    };
+    if cfg!(target_os = "linux") {
+        !Path::new("/proc").join(pid.trim()).exists()
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_identity_imports_with_the_passphrase_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let identity = NodeIdentity::load_or_create(dir.path())?;
+        let reloaded = NodeIdentity::load_or_create(dir.path())?;
+        assert_eq!(reloaded.peer_id(), identity.peer_id());
+
+        let backup = dir.path().join("backup");
+        identity.export(&backup, "passphrase")?;
+        let imported = NodeIdentity::import(&backup, "passphrase")?;
+        assert_eq!(imported.peer_id(), identity.peer_id());
+        assert_eq!(imported.main_key(), identity.main_key());
+        assert!(matches!(
+            NodeIdentity::import(&backup, "wrong"),
+            Err(Error::FailedToDecryptIdentity)
+        ));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_the_owner_can_read_the_stored_identity() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(IDENTITY_FILENAME);
+        fs::write(&path, b"")?;
+        fs::set_permissions(&path, Permissions::from_mode(0o644))?;
+
+        NodeIdentity::random().store(dir.path())?;
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        Ok(())
+    }
+
+    #[test]
+    fn identity_cannot_be_locked_twice() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let lock = IdentityLock::acquire(dir.path())?;
+        assert!(matches!(
+            IdentityLock::acquire(dir.path()),
+            Err(Error::IdentityInUse(_))
+        ));
+
+        drop(lock);
+        let _lock = IdentityLock::acquire(dir.path())?;
+        Ok(())
+    }
+}
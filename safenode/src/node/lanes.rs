@@ -0,0 +1,165 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    network::NetworkEvent,
+    protocol::messages::{Cmd, Request},
+};
+
+use std::collections::VecDeque;
+
+// The number of fast lane items handled for every bulk lane item, while both have items queued.
+const FAST_ITEMS_PER_BULK_ITEM: usize = 4;
+// The number of items the fast lane holds at most.
+const FAST_LANE_CAPACITY: usize = 1024;
+// The number of items the bulk lane holds at most, chunks being large.
+const BULK_LANE_CAPACITY: usize = 128;
+
+/// The lane an incoming event is queued in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Lane {
+    /// Queries, spends and everything else that is cheap to handle.
+    Fast,
+    /// Chunk uploads, which are large and can arrive in great numbers from a single client.
+    Bulk,
+}
+
+impl Lane {
+    pub(crate) fn of(event: &NetworkEvent) -> Self {
+        match event {
+            NetworkEvent::RequestReceived {
+                req: Request::Cmd(Cmd::StoreChunk(_)),
+                ..
+            } => Lane::Bulk,
+            _ => Lane::Fast,
+        }
+    }
+}
+
+/// Queues of incoming events, so that a bulk upload does not hold up everyone else's queries.
+///
+/// The fast lane is served first, but the bulk lane gets one item in every
+/// `FAST_ITEMS_PER_BULK_ITEM + 1`, so that it is throttled rather than starved.
+///
+/// Each lane holds a bounded number of items. Once the fast lane is full, no more
+/// items should be taken in, so that whatever feeds the lanes is held back. An item
+/// pushed to a full lane is handed back, to be shed.
+#[derive(Debug)]
+pub(crate) struct Lanes<T> {
+    fast: VecDeque<T>,
+    bulk: VecDeque<T>,
+    fast_capacity: usize,
+    bulk_capacity: usize,
+    fast_in_a_row: usize,
+}
+
+impl<T> Default for Lanes<T> {
+    fn default() -> Self {
+        Self::new(FAST_LANE_CAPACITY, BULK_LANE_CAPACITY)
+    }
+}
+
+impl<T> Lanes<T> {
+    pub(crate) fn new(fast_capacity: usize, bulk_capacity: usize) -> Self {
+        Self {
+            fast: VecDeque::new(),
+            bulk: VecDeque::new(),
+            fast_capacity: fast_capacity.max(1),
+            bulk_capacity: bulk_capacity.max(1),
+            fast_in_a_row: 0,
+        }
+    }
+
+    /// Queues the item in the lane, or hands it back if the lane is full.
+    pub(crate) fn push(&mut self, lane: Lane, item: T) -> Result<(), T> {
+        let (queue, capacity) = match lane {
+            Lane::Fast => (&mut self.fast, self.fast_capacity),
+            Lane::Bulk => (&mut self.bulk, self.bulk_capacity),
+        };
+        if queue.len() >= capacity {
+            return Err(item);
+        }
+        queue.push_back(item);
+        Ok(())
+    }
+
+    /// Whether more items can be taken in, i.e. the fast lane is not full.
+    pub(crate) fn has_room(&self) -> bool {
+        self.fast.len() < self.fast_capacity
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let bulk_turn = self.fast_in_a_row >= FAST_ITEMS_PER_BULK_ITEM || self.fast.is_empty();
+        if bulk_turn {
+            if let Some(item) = self.bulk.pop_front() {
+                self.fast_in_a_row = 0;
+                return Some(item);
+            }
+        }
+
+        let item = self.fast.pop_front()?;
+        self.fast_in_a_row += 1;
+        Some(item)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.fast.is_empty() && self.bulk.is_empty()
+    }
+
+    /// The number of items queued in the fast and the bulk lane.
+    pub(crate) fn depths(&self) -> (usize, usize) {
+        (self.fast.len(), self.bulk.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_lane_is_served_first() {
+        let mut lanes = Lanes::default();
+        assert!(lanes.push(Lane::Bulk, "bulk").is_ok());
+        assert!(lanes.push(Lane::Fast, "fast").is_ok());
+
+        assert_eq!(lanes.pop(), Some("fast"));
+        assert_eq!(lanes.pop(), Some("bulk"));
+        assert_eq!(lanes.pop(), None);
+        assert!(lanes.is_empty());
+    }
+
+    #[test]
+    fn bulk_lane_is_not_starved() {
+        let mut lanes = Lanes::default();
+        for i in 0..10 {
+            assert!(lanes.push(Lane::Fast, i).is_ok());
+        }
+        assert!(lanes.push(Lane::Bulk, 100).is_ok());
+        assert!(lanes.push(Lane::Bulk, 101).is_ok());
+        assert_eq!(lanes.depths(), (10, 2));
+
+        let order: Vec<_> = std::iter::from_fn(|| lanes.pop()).collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 100, 4, 5, 6, 7, 101, 8, 9]);
+    }
+
+    #[test]
+    fn full_lanes_hand_items_back() {
+        let mut lanes = Lanes::new(2, 1);
+        assert_eq!(lanes.push(Lane::Bulk, 100), Ok(()));
+        assert_eq!(lanes.push(Lane::Bulk, 101), Err(101));
+
+        assert_eq!(lanes.push(Lane::Fast, 0), Ok(()));
+        assert!(lanes.has_room());
+        assert_eq!(lanes.push(Lane::Fast, 1), Ok(()));
+        assert!(!lanes.has_room());
+        assert_eq!(lanes.push(Lane::Fast, 2), Err(2));
+
+        assert_eq!(lanes.pop(), Some(0));
+        assert!(lanes.has_room());
+    }
+}
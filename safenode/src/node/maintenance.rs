@@ -0,0 +1,221 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Error, RateLimitConfig};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+const SECS_PER_WEEK: u64 = 7 * SECS_PER_DAY;
+// The unix epoch fell on a thursday.
+const EPOCH_WEEKDAY: u64 = 3;
+
+/// A recurring period, in UTC, in which the node does its heavy upkeep and takes less load,
+/// written as `HH:MM-HH:MM` for every day, or prefixed with a day, e.g. `sun 02:00-04:00`,
+/// for once a week. A window ending before it starts ends on the next day.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MaintenanceWindow {
+    // The day of the week, from monday, or every day if not set.
+    day: Option<u64>,
+    // When the window starts, in seconds since midnight.
+    start: u64,
+    // How long the window lasts, in seconds.
+    duration: u64,
+}
+
+impl MaintenanceWindow {
+    /// How long the window lasts.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration)
+    }
+
+    /// If the time is within the window, how long until the window ends.
+    pub fn remaining(&self, now: SystemTime) -> Option<Duration> {
+        let secs = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let since_week_start = (secs + EPOCH_WEEKDAY * SECS_PER_DAY) % SECS_PER_WEEK;
+        let starts: Vec<u64> = match self.day {
+            Some(day) => vec![day * SECS_PER_DAY + self.start],
+            None => (0..7).map(|day| day * SECS_PER_DAY + self.start).collect(),
+        };
+        starts
+            .into_iter()
+            .map(|start| (since_week_start + SECS_PER_WEEK - start) % SECS_PER_WEEK)
+            .filter(|elapsed| *elapsed < self.duration)
+            .map(|elapsed| Duration::from_secs(self.duration - elapsed))
+            .max()
+    }
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = Error;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::InvalidMaintenanceWindow(format!(
+                "{window:?}, expected e.g. \"02:00-04:00\" or \"sun 02:00-04:00\""
+            ))
+        };
+        let (day, times) = match window.trim().split_once(' ') {
+            Some((day, times)) => {
+                let day = day.to_lowercase();
+                let index = DAYS
+                    .iter()
+                    .position(|name| day.starts_with(name))
+                    .ok_or_else(invalid)?;
+                (Some(index as u64), times.trim())
+            }
+            None => (None, window.trim()),
+        };
+        let (start, end) = times.split_once('-').ok_or_else(invalid)?;
+        let start = parse_time(start).ok_or_else(invalid)?;
+        let end = parse_time(end).ok_or_else(invalid)?;
+        if start == end {
+            return Err(Error::InvalidMaintenanceWindow(format!(
+                "{window:?} lasts no time"
+            )));
+        }
+        Ok(Self {
+            day,
+            start,
+            duration: (end + SECS_PER_DAY - start) % SECS_PER_DAY,
+        })
+    }
+}
+
+impl TryFrom<String> for MaintenanceWindow {
+    type Error = Error;
+
+    fn try_from(window: String) -> Result<Self, Self::Error> {
+        window.parse()
+    }
+}
+
+impl From<MaintenanceWindow> for String {
+    fn from(window: MaintenanceWindow) -> Self {
+        window.to_string()
+    }
+}
+
+impl fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "{} ", DAYS[day as usize % 7])?;
+        }
+        let end = (self.start + self.duration) % SECS_PER_DAY;
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 3600,
+            self.start % 3600 / 60,
+            end / 3600,
+            end % 3600 / 60
+        )
+    }
+}
+
+/// When the node is in maintenance, and how much load it takes meanwhile.
+///
+/// Within a window, the rate limits of the node are scaled down to `load_percent`,
+/// so that peers sending more are told to retry later and turn to the rest of the
+/// close group, and the stored Registers are compacted and checked more thoroughly
+/// than on the usual passes, which are skipped when any window is set.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MaintenanceSchedule {
+    /// The windows, which may overlap.
+    pub windows: Vec<MaintenanceWindow>,
+    /// The share of the usual rate limits the node takes within a window, in percent.
+    pub load_percent: u8,
+}
+
+impl MaintenanceSchedule {
+    /// If the time is within a window, how long until the node is out of maintenance.
+    pub fn remaining(&self, now: SystemTime) -> Option<Duration> {
+        self.windows
+            .iter()
+            .filter_map(|window| window.remaining(now))
+            .max()
+    }
+
+    /// The rate limits to apply, given the usual ones.
+    pub(crate) fn rate_limit(&self, usual: RateLimitConfig, in_window: bool) -> RateLimitConfig {
+        if !in_window {
+            return usual;
+        }
+        let scale = |limit: u32| (u64::from(limit) * u64::from(self.load_percent) / 100).max(1);
+        RateLimitConfig {
+            burst: scale(usual.burst) as u32,
+            requests_per_sec: scale(usual.requests_per_sec) as u32,
+            node_burst: scale(usual.node_burst) as u32,
+            node_requests_per_sec: scale(usual.node_requests_per_sec) as u32,
+            ip_burst: scale(usual.ip_burst) as u32,
+            ip_requests_per_sec: scale(usual.ip_requests_per_sec) as u32,
+            ..usual
+        }
+    }
+}
+
+// Reads `HH:MM` as seconds since midnight.
+fn parse_time(time: &str) -> Option<u64> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 3600 + minutes * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+
+    // 2023-06-04 was a sunday.
+    fn at(day: u64, hours: u64, minutes: u64) -> SystemTime {
+        UNIX_EPOCH
+            + Duration::from_secs(1_685_836_800 + day * SECS_PER_DAY + hours * 3600 + minutes * 60)
+    }
+
+    #[test]
+    fn windows_recur_at_the_given_times() -> Result<()> {
+        let weekly: MaintenanceWindow = "Sun 02:00-04:00".parse()?;
+        assert_eq!(weekly.to_string(), "sun 02:00-04:00");
+        assert_eq!(
+            weekly.remaining(at(0, 3, 30)),
+            Some(Duration::from_secs(30 * 60))
+        );
+        assert_eq!(weekly.remaining(at(0, 4, 0)), None);
+        assert_eq!(weekly.remaining(at(1, 3, 0)), None);
+        assert!(weekly.remaining(at(7, 2, 0)).is_some());
+
+        // A daily window across midnight.
+        let nightly: MaintenanceWindow = "23:30-00:30".parse()?;
+        assert_eq!(nightly.duration(), Duration::from_secs(3600));
+        assert!(nightly.remaining(at(2, 0, 15)).is_some());
+        assert!(nightly.remaining(at(5, 23, 45)).is_some());
+        assert!(nightly.remaining(at(5, 12, 0)).is_none());
+
+        for invalid in ["02:00", "24:00-01:00", "someday 02:00-03:00", "02:00-02:00"] {
+            assert!(invalid.parse::<MaintenanceWindow>().is_err(), "{invalid}");
+        }
+
+        let schedule = MaintenanceSchedule {
+            windows: vec![weekly, nightly],
+            load_percent: 25,
+        };
+        let usual = RateLimitConfig::default();
+        let reduced = schedule.rate_limit(usual, schedule.remaining(at(0, 2, 0)).is_some());
+        assert_eq!(reduced.requests_per_sec, usual.requests_per_sec / 4);
+        assert_eq!(schedule.rate_limit(usual, false), usual);
+        Ok(())
+    }
+}
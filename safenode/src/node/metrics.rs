@@ -0,0 +1,305 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{lanes::Lane, Rejection};
+
+use crate::protocol::messages::{Cmd, Query, Request};
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+
+// The upper bound, in microseconds, of the first bucket of a latency histogram.
+const FIRST_BUCKET_MICROS: u64 = 100;
+// The number of buckets of a latency histogram, each twice as wide as the one before,
+// so that the last bounded one ends at about 100s. Slower requests go in an extra bucket.
+const BUCKETS: usize = 21;
+
+/// The operations whose latency the node measures.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Operation {
+    /// Storing a chunk.
+    ChunkPut,
+    /// Getting a chunk.
+    ChunkGet,
+    /// Creating or editing a Register.
+    RegisterCmd,
+    /// Reading a Register, or its policy or permissions.
+    RegisterQuery,
+    /// Spending a DBC, or querying a spend or the fees.
+    Spend,
+}
+
+impl Operation {
+    /// The operation of the request, if it is one that is measured.
+    pub(crate) fn of(request: &Request) -> Option<Self> {
+        match request {
+            Request::Cmd(Cmd::StoreChunk(_)) => Some(Self::ChunkPut),
+            Request::Cmd(Cmd::Register(_)) => Some(Self::RegisterCmd),
+            Request::Cmd(Cmd::SpendDbc { .. }) => Some(Self::Spend),
+            Request::Query(Query::GetChunk(_)) => Some(Self::ChunkGet),
+            Request::Query(Query::Register(_)) => Some(Self::RegisterQuery),
+            Request::Query(Query::Spend(_)) => Some(Self::Spend),
+            Request::Query(Query::GetRecordDistribution { .. } | Query::GetTime { .. })
+            | Request::Event(_) => None,
+        }
+    }
+}
+
+/// Who a request came from: peers in the routing table of the node are other nodes,
+/// any other peer is taken to be a client.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum RequestSource {
+    /// A client, e.g. uploading or reading data.
+    Client,
+    /// Another node, e.g. replicating data or checking a spend.
+    Node,
+}
+
+/// The latencies of an operation, as listed by [`NodeCtl::metrics`](super::NodeCtl::metrics).
+///
+/// The percentiles are the upper bounds of the histogram buckets they fall in, so they
+/// are at most twice the actual latencies.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    /// The operation measured.
+    pub operation: Operation,
+    /// Who the requests came from.
+    pub source: RequestSource,
+    /// The number of requests handled.
+    pub count: u64,
+    /// The mean latency, in microseconds.
+    pub mean_micros: u64,
+    /// The median latency, in microseconds.
+    pub p50_micros: u64,
+    /// The 95th percentile of the latencies, in microseconds.
+    pub p95_micros: u64,
+    /// The 99th percentile of the latencies, in microseconds.
+    pub p99_micros: u64,
+}
+
+/// The queues of incoming events of the node, as listed by
+/// [`NodeCtl::lanes`](super::NodeCtl::lanes).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LaneStats {
+    /// The number of events queued in the fast lane.
+    pub fast_depth: usize,
+    /// The number of events queued in the bulk lane, i.e. chunk uploads.
+    pub bulk_depth: usize,
+    /// The number of events shed since the node started, as the fast lane was full.
+    pub fast_shed: u64,
+    /// The number of events shed since the node started, as the bulk lane was full.
+    pub bulk_shed: u64,
+}
+
+/// The requests the node rejected before queueing them, as listed by
+/// [`NodeCtl::rejections`](super::NodeCtl::rejections).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RejectionStats {
+    /// The number of requests rejected since the node started, as their peer was banned.
+    pub banned: u64,
+    /// The number of requests rejected since the node started, as their peer sent
+    /// more than its share.
+    pub throttled: u64,
+    /// The number of requests rejected since the node started, as the clients at
+    /// their IP sent more than their share.
+    pub ip_throttled: u64,
+    /// The number of requests dropped since the node started, as their peer was muted.
+    pub muted: u64,
+}
+
+// The number of requests whose latency fell in each bucket.
+#[derive(Clone, Debug)]
+struct LatencyHistogram {
+    buckets: [u64; BUCKETS + 1],
+    count: u64,
+    total_micros: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS + 1],
+            count: 0,
+            total_micros: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        let bucket = (0..BUCKETS)
+            .find(|bucket| micros <= upper_bound(*bucket))
+            .unwrap_or(BUCKETS);
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total_micros = self.total_micros.saturating_add(micros);
+    }
+
+    // The upper bound of the bucket the given fraction of the requests fell in, or under.
+    fn percentile(&self, fraction: f64) -> u64 {
+        let rank = (self.count as f64 * fraction).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return upper_bound(bucket);
+            }
+        }
+        upper_bound(BUCKETS)
+    }
+}
+
+// The upper bound, in microseconds, of the given bucket.
+fn upper_bound(bucket: usize) -> u64 {
+    match bucket {
+        BUCKETS.. => u64::MAX,
+        _ => FIRST_BUCKET_MICROS << bucket,
+    }
+}
+
+/// The latency histograms of the operations the node handles, per source,
+/// the state of its lanes, and the requests it rejected.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodeMetrics {
+    latencies: Arc<RwLock<BTreeMap<(Operation, RequestSource), LatencyHistogram>>>,
+    lanes: Arc<RwLock<LaneStats>>,
+    rejections: Arc<RwLock<RejectionStats>>,
+}
+
+impl NodeMetrics {
+    /// Records the time it took to handle a request.
+    pub(crate) async fn record(
+        &self,
+        operation: Operation,
+        source: RequestSource,
+        latency: Duration,
+    ) {
+        self.latencies
+            .write()
+            .await
+            .entry((operation, source))
+            .or_default()
+            .record(latency);
+    }
+
+    /// Records the number of events queued in the fast and the bulk lane.
+    pub(crate) async fn set_lane_depths(&self, (fast_depth, bulk_depth): (usize, usize)) {
+        let mut lanes = self.lanes.write().await;
+        lanes.fast_depth = fast_depth;
+        lanes.bulk_depth = bulk_depth;
+    }
+
+    /// Records that an event was shed, as its lane was full.
+    pub(crate) async fn record_shed(&self, lane: Lane) {
+        let mut lanes = self.lanes.write().await;
+        match lane {
+            Lane::Fast => lanes.fast_shed += 1,
+            Lane::Bulk => lanes.bulk_shed += 1,
+        }
+    }
+
+    /// The state of the lanes.
+    pub(crate) async fn lanes(&self) -> LaneStats {
+        *self.lanes.read().await
+    }
+
+    /// Records that a request was rejected, as its peer was banned.
+    pub(crate) async fn record_banned(&self) {
+        self.rejections.write().await.banned += 1;
+    }
+
+    /// Records that a request was rejected, or dropped, by the rate limiter.
+    pub(crate) async fn record_rejection(&self, rejection: Rejection) {
+        let mut rejections = self.rejections.write().await;
+        match rejection {
+            Rejection::Throttled(_) => rejections.throttled += 1,
+            Rejection::IpThrottled(_) => rejections.ip_throttled += 1,
+            Rejection::Muted { .. } => rejections.muted += 1,
+        }
+    }
+
+    /// The requests rejected so far.
+    pub(crate) async fn rejections(&self) -> RejectionStats {
+        *self.rejections.read().await
+    }
+
+    /// The latencies of each operation and source that requests were handled for.
+    pub(crate) async fn summaries(&self) -> Vec<LatencySummary> {
+        self.latencies
+            .read()
+            .await
+            .iter()
+            .map(|((operation, source), histogram)| LatencySummary {
+                operation: *operation,
+                source: *source,
+                count: histogram.count,
+                mean_micros: histogram.total_micros / histogram.count.max(1),
+                p50_micros: histogram.percentile(0.5),
+                p95_micros: histogram.percentile(0.95),
+                p99_micros: histogram.percentile(0.99),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn percentiles_are_the_bounds_of_their_buckets() {
+        let metrics = NodeMetrics::default();
+        for _ in 0..90 {
+            metrics
+                .record(
+                    Operation::ChunkGet,
+                    RequestSource::Client,
+                    Duration::from_micros(150),
+                )
+                .await;
+        }
+        for _ in 0..9 {
+            metrics
+                .record(
+                    Operation::ChunkGet,
+                    RequestSource::Client,
+                    Duration::from_millis(3),
+                )
+                .await;
+        }
+        metrics
+            .record(
+                Operation::ChunkGet,
+                RequestSource::Client,
+                Duration::from_secs(1000),
+            )
+            .await;
+        metrics
+            .record(
+                Operation::Spend,
+                RequestSource::Node,
+                Duration::from_micros(50),
+            )
+            .await;
+
+        let summaries = metrics.summaries().await;
+        assert_eq!(summaries.len(), 2);
+        let chunk_gets = &summaries[0];
+        assert_eq!(chunk_gets.operation, Operation::ChunkGet);
+        assert_eq!(chunk_gets.source, RequestSource::Client);
+        assert_eq!(chunk_gets.count, 100);
+        assert_eq!(chunk_gets.p50_micros, 200);
+        assert_eq!(chunk_gets.p95_micros, 3_200);
+        assert_eq!(chunk_gets.p99_micros, 3_200);
+
+        let spends = &summaries[1];
+        assert_eq!((spends.count, spends.p99_micros), (1, 100));
+    }
+}
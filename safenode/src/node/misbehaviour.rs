@@ -0,0 +1,244 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    network_transfers::Error as TransferError,
+    protocol::{error::Error as ProtocolError, messages::CmdResponse},
+};
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+// How often the peers whose misbehaviours no longer count, and whose bans are over,
+// are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The ways in which a peer sending us requests can misbehave.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Misbehaviour {
+    /// A cmd was signed with an invalid signature.
+    InvalidSignature,
+    /// A spend did not check out against its transaction or its parents.
+    InvalidSpend,
+    /// An entry was larger than is allowed.
+    OversizedPayload,
+    /// A cmd was inconsistent with itself, e.g. an op targeting another address than its cmd.
+    MalformedRequest,
+    /// Requests kept coming while the peer was told to retry later, and so it was muted.
+    Flooding,
+}
+
+impl Misbehaviour {
+    /// The misbehaviour, if any, that the response to a cmd of a peer shows.
+    pub(crate) fn of(response: &CmdResponse) -> Option<Self> {
+        let error = match response {
+            CmdResponse::StoreChunk(Err(error))
+            | CmdResponse::CreateRegister(Err(error))
+            | CmdResponse::EditRegister(Err(error))
+            | CmdResponse::Spend(Err(error)) => error,
+            _ => return None,
+        };
+        match error {
+            ProtocolError::InvalidSignature(_) => Some(Self::InvalidSignature),
+            ProtocolError::EntryTooBig { .. } => Some(Self::OversizedPayload),
+            ProtocolError::RegisterAddrMismatch { .. } | ProtocolError::CrdtWrongAddress(_) => {
+                Some(Self::MalformedRequest)
+            }
+            ProtocolError::Transfers(
+                TransferError::Dbcs(_)
+                | TransferError::TxSourceMismatch { .. }
+                | TransferError::TxTrailMismatch { .. }
+                | TransferError::InvalidSourceTxProvided { .. },
+            ) => Some(Self::InvalidSpend),
+            _ => None,
+        }
+    }
+
+    // How much the misbehaviour counts towards a ban.
+    fn weight(&self) -> u32 {
+        match self {
+            Self::InvalidSignature | Self::InvalidSpend | Self::Flooding => 3,
+            Self::OversizedPayload | Self::MalformedRequest => 1,
+        }
+    }
+}
+
+/// When peers are banned for misbehaving.
+///
+/// Every misbehaviour of a peer has a weight. A peer whose misbehaviours within
+/// `window` weigh `ban_threshold` or more is banned for `ban_duration`, during
+/// which all its requests are rejected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BanConfig {
+    /// The total weight of misbehaviours at which a peer is banned.
+    pub ban_threshold: u32,
+    /// How long misbehaviours count towards a ban.
+    pub window: Duration,
+    /// How long a peer stays banned.
+    pub ban_duration: Duration,
+}
+
+impl Default for BanConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: 10,
+            window: Duration::from_secs(5 * 60),
+            ban_duration: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Tracks the misbehaviours of peers, and bans those that misbehave too often.
+#[derive(Debug)]
+pub(crate) struct MisbehaviourTracker {
+    config: BanConfig,
+    issues: HashMap<PeerId, VecDeque<(Instant, Misbehaviour)>>,
+    bans: HashMap<PeerId, Instant>,
+    last_prune: Instant,
+}
+
+impl MisbehaviourTracker {
+    pub(crate) fn new(config: BanConfig) -> Self {
+        Self {
+            config,
+            issues: HashMap::new(),
+            bans: HashMap::new(),
+            last_prune: Instant::now(),
+        }
+    }
+
+    /// Applies the new config to misbehaviours tracked from now on, and bans made from now on.
+    pub(crate) fn set_config(&mut self, config: BanConfig) {
+        self.config = config;
+    }
+
+    /// Records a misbehaviour of the peer.
+    /// Returns how long the peer is banned for, if this got it banned.
+    pub(crate) fn track(
+        &mut self,
+        peer: PeerId,
+        issue: Misbehaviour,
+        now: Instant,
+    ) -> Option<Duration> {
+        if now.duration_since(self.last_prune) >= PRUNE_INTERVAL {
+            self.prune(now);
+        }
+
+        let window = self.config.window;
+        let issues = self.issues.entry(peer).or_default();
+        issues.push_back((now, issue));
+        while let Some((at, _)) = issues.front() {
+            if now.duration_since(*at) < window {
+                break;
+            }
+            let _ = issues.pop_front();
+        }
+
+        let weight: u32 = issues.iter().map(|(_, issue)| issue.weight()).sum();
+        if weight < self.config.ban_threshold {
+            return None;
+        }
+
+        let _ = self.issues.remove(&peer);
+        let _ = self.bans.insert(peer, now + self.config.ban_duration);
+        Some(self.config.ban_duration)
+    }
+
+    /// If the peer is banned, returns how long until the ban is lifted.
+    pub(crate) fn banned(&mut self, peer: &PeerId, now: Instant) -> Option<Duration> {
+        let until = *self.bans.get(peer)?;
+        if until <= now {
+            let _ = self.bans.remove(peer);
+            return None;
+        }
+        Some(until - now)
+    }
+
+    // A peer whose last misbehaviour is out of the window is no different from one that
+    // never misbehaved, so there is no need to keep track of it any longer.
+    fn prune(&mut self, now: Instant) {
+        let window = self.config.window;
+        self.issues.retain(|_, issues| {
+            issues
+                .back()
+                .is_some_and(|(at, _)| now.duration_since(*at) < window)
+        });
+        self.bans.retain(|_, until| *until > now);
+        self.last_prune = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_misbehaviour_gets_a_peer_banned_for_a_while() {
+        let config = BanConfig::default();
+        let mut tracker = MisbehaviourTracker::new(config);
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(tracker
+                .track(peer, Misbehaviour::InvalidSignature, now)
+                .is_none());
+        }
+        assert!(tracker.banned(&peer, now).is_none());
+        assert_eq!(
+            tracker.track(peer, Misbehaviour::OversizedPayload, now),
+            Some(config.ban_duration)
+        );
+
+        assert_eq!(tracker.banned(&peer, now), Some(config.ban_duration));
+        assert!(tracker.banned(&other, now).is_none());
+        assert!(tracker.banned(&peer, now + config.ban_duration).is_none());
+    }
+
+    #[test]
+    fn misbehaviour_outside_the_window_is_forgotten() {
+        let config = BanConfig::default();
+        let mut tracker = MisbehaviourTracker::new(config);
+        let peer = PeerId::random();
+        let mut now = Instant::now();
+
+        for _ in 0..10 {
+            assert!(tracker
+                .track(peer, Misbehaviour::InvalidSpend, now)
+                .is_none());
+            now += config.window;
+        }
+    }
+
+    #[test]
+    fn peers_that_stopped_misbehaving_are_pruned() {
+        let config = BanConfig::default();
+        let mut tracker = MisbehaviourTracker::new(config);
+        let (reformed, banned, recent) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert!(tracker
+            .track(reformed, Misbehaviour::MalformedRequest, now)
+            .is_none());
+        for _ in 0..4 {
+            let _ = tracker.track(banned, Misbehaviour::InvalidSignature, now);
+        }
+        let later = now + config.window.max(config.ban_duration) + PRUNE_INTERVAL;
+        assert!(tracker
+            .track(recent, Misbehaviour::MalformedRequest, later)
+            .is_none());
+
+        assert!(!tracker.issues.contains_key(&reformed));
+        assert!(!tracker.bans.contains_key(&banned));
+        assert!(tracker.issues.contains_key(&recent));
+    }
+}
@@ -7,19 +7,53 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod api;
+mod config;
 mod error;
 mod event;
+mod identity;
+mod lanes;
+mod maintenance;
+mod metrics;
+mod misbehaviour;
+mod preflight;
+mod pruning;
+mod query_cache;
+mod rate_limit;
+mod replication;
+mod republish;
+mod subscription;
 
-pub use self::event::NodeEvent;
+pub use self::{
+    config::NodeConfig,
+    event::NodeEvent,
+    identity::{IdentityLock, NodeIdentity},
+    maintenance::{MaintenanceSchedule, MaintenanceWindow},
+    metrics::{LaneStats, LatencySummary, Operation, RejectionStats, RequestSource},
+    misbehaviour::{BanConfig, Misbehaviour},
+    preflight::{preflight, CheckStatus, PreflightCheck, PreflightReport, MIN_FREE_SPACE},
+    pruning::{DeletionLog, DeletionRecord, PruneReport},
+    rate_limit::RateLimitConfig,
+    replication::{PeerHolding, ReplicationReport},
+    republish::{NodeCtl, RepublishManifest},
+    subscription::{EventClass, EventFilter, NodeRpc},
+};
 
-use self::{error::Error, event::NodeEventsChannel};
+#[cfg(unix)]
+pub use self::subscription::{
+    check_replication, list_connections, node_lanes, node_metrics, node_rejections, serve_events,
+};
 
-use crate::{
-    network::Network,
-    network_transfers::Transfers,
-    storage::{ChunkStorage, RegisterStorage},
+use self::{
+    error::Error,
+    event::NodeEventsChannel,
+    metrics::NodeMetrics,
+    misbehaviour::MisbehaviourTracker,
+    query_cache::QueryCache,
+    rate_limit::{RateLimiter, Rejection},
 };
 
+use crate::{network::Network, network_transfers::Transfers, storage::DataTypes};
+
 use libp2p::PeerId;
 use serde::{Deserialize, Serialize};
 use xor_name::{XorName, XOR_NAME_LEN};
@@ -29,10 +63,13 @@ use xor_name::{XorName, XOR_NAME_LEN};
 /// storage, and broadcasts node-related events.
 pub struct Node {
     network: Network,
-    chunks: ChunkStorage,
-    registers: RegisterStorage,
+    data: DataTypes,
     transfers: Transfers,
     events_channel: NodeEventsChannel,
+    rate_limiter: RateLimiter,
+    query_cache: QueryCache,
+    misbehaviours: MisbehaviourTracker,
+    metrics: NodeMetrics,
 }
 
 /// A unique identifier for a node in the network,
@@ -42,6 +79,13 @@ pub struct Node {
 )]
 pub struct NodeId(XorName);
 
+impl NodeId {
+    /// The location of the node in the xor space.
+    pub fn name(&self) -> &XorName {
+        &self.0
+    }
+}
+
 /// Returns a `NodeId` representation of the `PeerId`.
 pub fn to_node_id(peer_id: PeerId) -> NodeId {
     let mut xorname_bytes = [0u8; XOR_NAME_LEN];
@@ -0,0 +1,357 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{to_node_id, NodeCtl};
+
+use crate::protocol::messages::{Query, QueryResponse, Request, Response};
+
+use futures::future::join_all;
+use std::{
+    fmt, fs,
+    net::{SocketAddr, UdpSocket},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use xor_name::XorName;
+
+/// The free space the node needs in its root dir to start.
+pub const MIN_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+// How far our clock may be from that of our peers. Fee quotes and receipts carry times.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+// Any earlier time on the clock of the node is certainly wrong, e.g. the clock was never set.
+const EARLIEST_PLAUSIBLE_TIME: Duration = Duration::from_secs(1_672_531_200); // 2023-01-01
+                                                                              // How long to wait for a peer to tell its time.
+const TIME_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+// The number of peers asked for their time.
+const TIME_SAMPLES: usize = 8;
+
+/// The outcome of a preflight check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheckStatus {
+    /// Nothing is wrong.
+    Pass,
+    /// The node can run, but not as well as it could.
+    Warn,
+    /// The node would fail, or misbehave, if it ran.
+    Fail,
+}
+
+/// A single check of the environment of the node.
+#[derive(Clone, Debug)]
+pub struct PreflightCheck {
+    /// What was checked.
+    pub name: &'static str,
+    /// The outcome of the check.
+    pub status: CheckStatus,
+    /// What was observed, and when the check did not pass, what to do about it.
+    pub details: String,
+}
+
+impl PreflightCheck {
+    fn new(name: &'static str, status: CheckStatus, details: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            details: details.into(),
+        }
+    }
+}
+
+/// The checks of the environment of the node, run at startup and by `safenode --check`,
+/// to fail fast on problems that would otherwise surface as obscure runtime errors.
+#[derive(Clone, Debug, Default)]
+pub struct PreflightReport {
+    /// The checks, in the order they were run.
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Returns true if no check failed.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status != CheckStatus::Fail)
+    }
+
+    /// Adds the checks of the other report to this one.
+    pub fn extend(&mut self, other: PreflightReport) {
+        self.checks.extend(other.checks);
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Pass => "PASS",
+                CheckStatus::Warn => "WARN",
+                CheckStatus::Fail => "FAIL",
+            };
+            writeln!(f, "[{status}] {:<12} {}", check.name, check.details)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks what can be checked before the node joins the network: that it can write to
+/// its root dir, which has enough free space, that it can listen on its address,
+/// that the system clock is plausible, and that the CPU has the features used by
+/// the signature checks.
+pub fn preflight(root_dir: &Path, addr: SocketAddr) -> PreflightReport {
+    PreflightReport {
+        checks: vec![
+            check_root_dir(root_dir),
+            check_free_space(root_dir),
+            check_port(addr),
+            check_local_clock(SystemTime::now()),
+            check_cpu(),
+        ],
+    }
+}
+
+impl NodeCtl {
+    /// Checks what can only be checked once the node joined the network: that peers
+    /// answer it, and that its clock agrees with theirs.
+    pub async fn preflight_network(&self) -> PreflightReport {
+        let random_name = XorName::random(&mut rand::thread_rng());
+        let peers = match self.network.node_get_closest_peers(random_name).await {
+            Ok(peers) => peers,
+            Err(error) => {
+                return PreflightReport {
+                    checks: vec![PreflightCheck::new(
+                        "peers",
+                        CheckStatus::Fail,
+                        format!("Could not look up any peer: {error}"),
+                    )],
+                }
+            }
+        };
+
+        let queries = peers.into_iter().take(TIME_SAMPLES).map(|peer| {
+            let network = self.network.clone();
+            async move {
+                let request = Request::Query(Query::GetTime {
+                    node: *to_node_id(peer).name(),
+                });
+                let sent = Instant::now();
+                let response =
+                    tokio::time::timeout(TIME_QUERY_TIMEOUT, network.send_request(request, peer))
+                        .await;
+                match response {
+                    Ok(Ok(Response::Query(QueryResponse::GetTime(Ok(millis))))) => {
+                        Some(clock_offset(millis, sent.elapsed(), SystemTime::now()))
+                    }
+                    _ => None,
+                }
+            }
+        });
+        let answers = join_all(queries).await;
+        let asked = answers.len();
+        let offsets: Vec<i64> = answers.into_iter().flatten().collect();
+
+        let peers = if offsets.is_empty() {
+            PreflightCheck::new(
+                "peers",
+                CheckStatus::Fail,
+                format!(
+                    "None of the {asked} peers asked answered. Check that the UDP port of the \
+                    node is open in the firewall, and forwarded by the router if behind NAT."
+                ),
+            )
+        } else {
+            PreflightCheck::new(
+                "peers",
+                CheckStatus::Pass,
+                format!("{} of the {asked} peers asked answered", offsets.len()),
+            )
+        };
+        PreflightReport {
+            checks: vec![peers, check_clock_skew(offsets)],
+        }
+    }
+}
+
+fn check_root_dir(root_dir: &Path) -> PreflightCheck {
+    let probe = root_dir.join(".preflight");
+    let result = fs::create_dir_all(root_dir)
+        .and_then(|()| fs::write(&probe, b"preflight"))
+        .and_then(|()| fs::remove_file(&probe));
+    match result {
+        Ok(()) => PreflightCheck::new("root dir", CheckStatus::Pass, format!("{root_dir:?}")),
+        Err(error) => PreflightCheck::new(
+            "root dir",
+            CheckStatus::Fail,
+            format!(
+                "Cannot write to {root_dir:?}: {error}. Give the user running the node write \
+                access to it, or set --root-dir to a dir it can write to."
+            ),
+        ),
+    }
+}
+
+fn check_free_space(root_dir: &Path) -> PreflightCheck {
+    match fs2::available_space(root_dir) {
+        Ok(free) if free >= MIN_FREE_SPACE => PreflightCheck::new(
+            "disk space",
+            CheckStatus::Pass,
+            format!("{} MiB free", free / (1024 * 1024)),
+        ),
+        Ok(free) => PreflightCheck::new(
+            "disk space",
+            CheckStatus::Fail,
+            format!(
+                "Only {} MiB free in {root_dir:?}, at least {} MiB are needed. Free some space, \
+                or set --root-dir to a dir on a larger disk.",
+                free / (1024 * 1024),
+                MIN_FREE_SPACE / (1024 * 1024)
+            ),
+        ),
+        Err(error) => PreflightCheck::new(
+            "disk space",
+            CheckStatus::Warn,
+            format!("Could not tell the free space in {root_dir:?}: {error}"),
+        ),
+    }
+}
+
+fn check_port(addr: SocketAddr) -> PreflightCheck {
+    match UdpSocket::bind(addr) {
+        Ok(_socket) => PreflightCheck::new("port", CheckStatus::Pass, format!("udp {addr}")),
+        Err(error) => PreflightCheck::new(
+            "port",
+            CheckStatus::Fail,
+            format!(
+                "Cannot listen on udp {addr}: {error}. Another node may be running on it, set \
+                --port to a free one. Ports under 1024 need elevated privileges."
+            ),
+        ),
+    }
+}
+
+fn check_local_clock(now: SystemTime) -> PreflightCheck {
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    if since_epoch < EARLIEST_PLAUSIBLE_TIME {
+        PreflightCheck::new(
+            "clock",
+            CheckStatus::Fail,
+            format!(
+                "The system clock is {}s after the unix epoch, which is in the past. Set the \
+                clock right, e.g. by enabling NTP.",
+                since_epoch.as_secs()
+            ),
+        )
+    } else {
+        PreflightCheck::new(
+            "clock",
+            CheckStatus::Pass,
+            "plausible, to be checked against peers",
+        )
+    }
+}
+
+fn check_cpu() -> PreflightCheck {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("adx") && is_x86_feature_detected!("bmi2") {
+            PreflightCheck::new("cpu", CheckStatus::Pass, "adx and bmi2 available")
+        } else {
+            PreflightCheck::new(
+                "cpu",
+                CheckStatus::Warn,
+                "The CPU lacks adx or bmi2, so signatures will be checked more slowly. \
+                A node on this machine may lag behind its peers under load.",
+            )
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        PreflightCheck::new(
+            "cpu",
+            CheckStatus::Pass,
+            format!("no features to check on {}", std::env::consts::ARCH),
+        )
+    }
+}
+
+// The offset of the clock of a peer from ours, in milliseconds, given the time it told,
+// how long it took to answer, and our time when it answered. The peer is assumed
+// to have read its clock halfway through the round trip.
+fn clock_offset(peer_millis: u64, round_trip: Duration, now: SystemTime) -> i64 {
+    let now_millis = now
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as i64)
+        .unwrap_or_default();
+    let sent_at_peer = now_millis - round_trip.as_millis() as i64 / 2;
+    peer_millis as i64 - sent_at_peer
+}
+
+// Checks the median offset of the clocks of the peers, so that a few wrong clocks
+// among them do not fail the check.
+fn check_clock_skew(mut offsets: Vec<i64>) -> PreflightCheck {
+    if offsets.is_empty() {
+        return PreflightCheck::new(
+            "clock skew",
+            CheckStatus::Warn,
+            "No peer told its time, the clock could not be checked",
+        );
+    }
+    offsets.sort_unstable();
+    let median = offsets[offsets.len() / 2];
+    let skew = Duration::from_millis(median.unsigned_abs());
+    let direction = if median > 0 { "behind" } else { "ahead of" };
+    if skew > MAX_CLOCK_SKEW {
+        PreflightCheck::new(
+            "clock skew",
+            CheckStatus::Fail,
+            format!(
+                "The system clock is {}s {direction} those of its peers, more than the {}s \
+                allowed. Synchronise the clock, e.g. by enabling NTP.",
+                skew.as_secs(),
+                MAX_CLOCK_SKEW.as_secs()
+            ),
+        )
+    } else {
+        PreflightCheck::new(
+            "clock skew",
+            CheckStatus::Pass,
+            format!(
+                "{}ms {direction} the median of {} peers",
+                skew.as_millis(),
+                offsets.len()
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_checks_tell_skewed_clocks_apart() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(check_local_clock(now).status, CheckStatus::Pass);
+        assert_eq!(check_local_clock(UNIX_EPOCH).status, CheckStatus::Fail);
+
+        // A peer answering 100ms into a 200ms round trip, with the same clock as ours.
+        let peer_millis = 1_700_000_000_000 - 100;
+        assert_eq!(
+            clock_offset(peer_millis, Duration::from_millis(200), now),
+            0
+        );
+
+        // A single wrong clock among the peers is outvoted.
+        let check = check_clock_skew(vec![120_000, 50, -20, 10, 0]);
+        assert_eq!(check.status, CheckStatus::Pass);
+        let check = check_clock_skew(vec![-45_000, -44_000, -46_000]);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.details.contains("45s ahead of"));
+        assert_eq!(check_clock_skew(vec![]).status, CheckStatus::Warn);
+    }
+}
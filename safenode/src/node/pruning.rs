@@ -0,0 +1,234 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    NodeCtl,
+};
+
+use crate::{network::close_group_majority, protocol::address::DataAddress};
+
+use libp2p::{
+    identity::{Keypair, PublicKey},
+    PeerId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Filename of the deletion records, in the root dir of the node.
+const DELETIONS_FILENAME: &str = "deletions.jsonl";
+
+/// The proof, signed by the node, that it deleted a record it was no longer responsible
+/// for, once enough peers of the close group of the record were found to hold it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeletionRecord {
+    /// The address of the deleted record.
+    pub address: DataAddress,
+    /// When the record was deleted, in milliseconds since the unix epoch.
+    pub pruned_at: u64,
+    /// The peers of the close group of the record that held it.
+    pub holders: Vec<String>,
+    /// The peer id of the node that deleted the record.
+    pub node: String,
+    /// The public key of the node, protobuf encoded.
+    pub public_key: Vec<u8>,
+    /// The signature of the node over the address, time and holders.
+    pub signature: Vec<u8>,
+}
+
+impl DeletionRecord {
+    fn new(keypair: &Keypair, address: DataAddress, holders: Vec<String>) -> Result<Self> {
+        let pruned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or_default();
+        let signature = keypair
+            .sign(&signed_bytes(&address, pruned_at, &holders)?)
+            .map_err(|err| Error::DeletionRecord(err.to_string()))?;
+        Ok(Self {
+            address,
+            pruned_at,
+            holders,
+            node: PeerId::from(keypair.public()).to_string(),
+            public_key: keypair.public().encode_protobuf(),
+            signature,
+        })
+    }
+
+    /// Whether the record was signed by the node it names, and was not altered since.
+    pub fn verify(&self) -> bool {
+        let public_key = match PublicKey::try_decode_protobuf(&self.public_key) {
+            Ok(public_key) => public_key,
+            Err(_) => return false,
+        };
+        let signed = match signed_bytes(&self.address, self.pruned_at, &self.holders) {
+            Ok(signed) => signed,
+            Err(_) => return false,
+        };
+        PeerId::from(public_key.clone()).to_string() == self.node
+            && public_key.verify(&signed, &self.signature)
+    }
+}
+
+/// The deletion records of a node, appended to a json lines file in its root dir,
+/// so that an audit can tell the records it pruned from those it lost.
+#[derive(Clone, Debug)]
+pub struct DeletionLog {
+    path: PathBuf,
+}
+
+impl DeletionLog {
+    /// Opens the deletion log in the given root dir of a node.
+    pub fn open(root_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(root_dir)?;
+        Ok(Self {
+            path: root_dir.join(DELETIONS_FILENAME),
+        })
+    }
+
+    /// All the deletion records, oldest first.
+    pub fn records(&self) -> Result<Vec<DeletionRecord>> {
+        if !self.path.is_file() {
+            return Ok(vec![]);
+        }
+        fs::read_to_string(&self.path)?
+            .lines()
+            .map(|line| {
+                serde_json::from_str(line).map_err(|err| Error::DeletionRecord(err.to_string()))
+            })
+            .collect()
+    }
+
+    fn append(&self, record: &DeletionRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).map_err(|err| Error::DeletionRecord(err.to_string()))?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// The outcome of a pass of [`NodeCtl::prune`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// The records deleted, each with a deletion record.
+    pub pruned: Vec<DataAddress>,
+    /// The number of records the node is no longer responsible for, but kept as too
+    /// few peers of their close group were found to hold them.
+    pub kept: usize,
+}
+
+impl NodeCtl {
+    /// Deletes the records the node is no longer among the closest peers of, once a
+    /// majority of their close group is found to hold them, recording each deletion
+    /// in the log before deleting the record.
+    ///
+    /// The records that too few peers hold are replicated to them, and pruned on a later
+    /// pass once enough of them stored the records.
+    pub async fn prune(&self, log: &DeletionLog) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+        for address in self.data.addrs().await {
+            let closest_peers = match self.network.node_get_closest_peers(*address.name()).await {
+                Ok(peers) => peers,
+                Err(err) => {
+                    warn!("Could not get the closest peers of {address:?}: {err}");
+                    continue;
+                }
+            };
+            // Still responsible for the record.
+            if closest_peers.contains(&self.network.peer_id) {
+                continue;
+            }
+            let mut replication = self.check_replication_among(address, closest_peers).await;
+            // A peer may hold the register, but not every op of it, which would be lost.
+            if let DataAddress::Register(_) = address {
+                self.confirm_register_holders(&mut replication).await;
+            }
+            if replication.holders() < close_group_majority() {
+                debug!(
+                    "Keeping {address:?}, only {} peers of its close group hold it",
+                    replication.holders()
+                );
+                report.kept += 1;
+                continue;
+            }
+
+            let holders = replication
+                .peers
+                .into_iter()
+                .filter(|peer| peer.holds == Some(true))
+                .map(|peer| peer.peer)
+                .chain(replication.replicated_to)
+                .collect();
+            log.append(&DeletionRecord::new(&self.keypair, address, holders)?)?;
+            let removed = self.data.remove(&address).await;
+            self.query_cache.invalidate(&address);
+            match removed {
+                Some(Ok(())) => report.pruned.push(address),
+                Some(Err(err)) => warn!("Could not delete {address:?}: {err}"),
+                None => warn!("No data type handles {address:?}, not deleting it"),
+            }
+        }
+
+        info!(
+            "Pruned {} records, kept {} that are not replicated enough",
+            report.pruned.len(),
+            report.kept
+        );
+        Ok(report)
+    }
+}
+
+fn signed_bytes(address: &DataAddress, pruned_at: u64, holders: &[String]) -> Result<Vec<u8>> {
+    bincode::serialize(&(address, pruned_at, holders))
+        .map_err(|err| Error::DeletionRecord(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+    use xor_name::XorName;
+
+    #[test]
+    fn deletion_records_are_verified_against_their_node() -> Result<()> {
+        let root_dir = tempfile::tempdir()?;
+        let log = DeletionLog::open(root_dir.path())?;
+        assert!(log.records()?.is_empty());
+
+        let keypair = Keypair::generate_ed25519();
+        let address = DataAddress::chunk(XorName::random(&mut rand::thread_rng()));
+        let holders = vec![PeerId::random().to_string(), PeerId::random().to_string()];
+        let record = DeletionRecord::new(&keypair, address, holders)?;
+        log.append(&record)?;
+
+        let records = log.records()?;
+        assert_eq!(records, std::slice::from_ref(&record));
+        assert!(records[0].verify());
+
+        let mut altered = record.clone();
+        let _ = altered.holders.pop();
+        assert!(!altered.verify());
+
+        let mut impersonated = record;
+        impersonated.node = PeerId::random().to_string();
+        assert!(!impersonated.verify());
+        Ok(())
+    }
+}
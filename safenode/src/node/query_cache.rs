@@ -0,0 +1,234 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::{
+    address::DataAddress,
+    messages::{Query, QueryResponse},
+};
+
+use clru::CLruCache;
+use libp2p::PeerId;
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+// The number of responses kept, the least recently used being dropped first,
+// so that a peer querying many addresses cannot grow the cache without limit.
+const QUERY_CACHE_SIZE: usize = 10_000;
+
+#[derive(Debug)]
+struct CachedResponse {
+    dst: DataAddress,
+    response: QueryResponse,
+    expires: Instant,
+}
+
+/// A short-lived cache of the responses to the queries of each peer,
+/// so that a peer retrying a query does not make us redo the work.
+///
+/// Only successful responses are cached, and any write to an address
+/// drops the cached responses to queries of that address. Clones share the
+/// same entries, so that the tasks changing stored data can drop them too.
+#[derive(Clone, Debug)]
+pub(crate) struct QueryCache {
+    ttl: Duration,
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    entries: CLruCache<(PeerId, Vec<u8>), CachedResponse>,
+    hits: u64,
+}
+
+impl QueryCache {
+    /// A cache keeping responses for `ttl`. A zero `ttl` disables the cache.
+    pub(crate) fn new(ttl: Duration) -> Self {
+        let capacity =
+            NonZeroUsize::new(QUERY_CACHE_SIZE).expect("Failed to create the query cache");
+        Self::with_capacity(ttl, capacity)
+    }
+
+    fn with_capacity(ttl: Duration, capacity: NonZeroUsize) -> Self {
+        Self {
+            ttl,
+            state: Arc::new(Mutex::new(CacheState {
+                entries: CLruCache::new(capacity),
+                hits: 0,
+            })),
+        }
+    }
+
+    /// Returns the cached response to the same query from the same peer, if any.
+    pub(crate) fn get(&self, peer: PeerId, query: &Query, now: Instant) -> Option<QueryResponse> {
+        let key = (peer, key_bytes(query)?);
+        let mut state = self.state();
+        let cached = state.entries.get(&key)?;
+        if cached.expires <= now {
+            let _ = state.entries.pop(&key);
+            return None;
+        }
+        let response = cached.response.clone();
+        state.hits += 1;
+        Some(response)
+    }
+
+    /// Caches the response to the query of the peer, if it is a successful one.
+    pub(crate) fn insert(
+        &self,
+        peer: PeerId,
+        query: &Query,
+        response: &QueryResponse,
+        now: Instant,
+    ) {
+        if self.ttl.is_zero() || !response.is_success() {
+            return;
+        }
+        let key_bytes = match key_bytes(query) {
+            Some(bytes) => bytes,
+            None => return,
+        };
+
+        let _ = self.state().entries.put(
+            (peer, key_bytes),
+            CachedResponse {
+                dst: query.dst(),
+                response: response.clone(),
+                expires: now + self.ttl,
+            },
+        );
+    }
+
+    /// Drops the cached responses to queries of the given address, as they may now be stale.
+    pub(crate) fn invalidate(&self, dst: &DataAddress) {
+        self.state().entries.retain(|_, cached| &cached.dst != dst);
+    }
+
+    /// The number of queries answered from the cache.
+    pub(crate) fn hits(&self) -> u64 {
+        self.state().hits
+    }
+
+    // The entries stay consistent whatever a panicking holder of the lock was doing,
+    // at worst missing the response it was inserting.
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn key_bytes(query: &Query) -> Option<Vec<u8>> {
+    // The time is different on every query.
+    if matches!(query, Query::GetTime { .. }) {
+        return None;
+    }
+    bincode::serialize(query).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{address::ChunkAddress, chunk::Chunk, error::Error};
+
+    use bytes::Bytes;
+    use eyre::{eyre, Result};
+
+    fn chunk_query() -> (Query, QueryResponse) {
+        let chunk = Chunk::new(Bytes::from_static(b"cached"));
+        let query = Query::GetChunk(*chunk.address());
+        (query, QueryResponse::GetChunk(Ok(chunk)))
+    }
+
+    #[test]
+    fn repeated_query_is_answered_from_cache() {
+        let cache = QueryCache::new(Duration::from_secs(5));
+        let (query, response) = chunk_query();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(cache.get(peer, &query, now), None);
+        cache.insert(peer, &query, &response, now);
+        assert_eq!(cache.get(peer, &query, now), Some(response));
+        assert_eq!(cache.hits(), 1);
+
+        // Another peer asking the same is not a retry.
+        assert_eq!(cache.get(PeerId::random(), &query, now), None);
+    }
+
+    #[test]
+    fn entries_expire() {
+        let ttl = Duration::from_secs(5);
+        let cache = QueryCache::new(ttl);
+        let (query, response) = chunk_query();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        cache.insert(peer, &query, &response, now);
+        assert_eq!(cache.get(peer, &query, now + ttl), None);
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let cache = QueryCache::new(Duration::from_secs(5));
+        let address = ChunkAddress::new(xor_name::XorName::from_content(b"missing"));
+        let query = Query::GetChunk(address);
+        let response = QueryResponse::GetChunk(Err(Error::ChunkNotFound(address)));
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        cache.insert(peer, &query, &response, now);
+        assert_eq!(cache.get(peer, &query, now), None);
+    }
+
+    #[test]
+    fn writes_invalidate_entries() {
+        let cache = QueryCache::new(Duration::from_secs(5));
+        let (query, response) = chunk_query();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        cache.insert(peer, &query, &response, now);
+        cache.invalidate(&query.dst());
+        assert_eq!(cache.get(peer, &query, now), None);
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_dropped_once_full() -> Result<()> {
+        let capacity = NonZeroUsize::new(2).ok_or_else(|| eyre!("zero capacity"))?;
+        let cache = QueryCache::with_capacity(Duration::from_secs(5), capacity);
+        let (query, response) = chunk_query();
+        let (first, second, third) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        cache.insert(first, &query, &response, now);
+        cache.insert(second, &query, &response, now);
+        assert!(cache.get(first, &query, now).is_some());
+        cache.insert(third, &query, &response, now);
+
+        assert!(cache.get(second, &query, now).is_none());
+        assert!(cache.get(first, &query, now).is_some());
+        assert!(cache.get(third, &query, now).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn maintenance_of_stored_data_invalidates_entries() {
+        let cache = QueryCache::new(Duration::from_secs(5));
+        let (query, response) = chunk_query();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        // As done by the maintenance and pruning tasks, with their own clone.
+        cache.insert(peer, &query, &response, now);
+        cache.clone().invalidate(&query.dst());
+        assert_eq!(cache.get(peer, &query, now), None);
+    }
+}
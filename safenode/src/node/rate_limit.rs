@@ -0,0 +1,462 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+// How often buckets that have refilled completely are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The limits applied to the requests of each peer, and of each IP.
+///
+/// Every peer gets a bucket holding up to `burst` requests, which is refilled
+/// at `requests_per_sec`. Nodes in the routing table get their own, larger budget
+/// instead, as they replicate data to us. The clients connected from a single IP
+/// share a bucket on top of their own, so that one host cannot get around the limits
+/// by using many peer ids. A request arriving at an empty bucket is rejected.
+/// A peer that keeps sending while its bucket is empty, i.e. that ignores when it
+/// was told to retry, is muted: its requests are dropped without any response.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimitConfig {
+    /// The maximum number of requests a client can send in a burst.
+    pub burst: u32,
+    /// The number of requests per second a client can sustain.
+    pub requests_per_sec: u32,
+    /// The maximum number of requests a node in the routing table can send in a burst.
+    pub node_burst: u32,
+    /// The number of requests per second a node in the routing table can sustain.
+    pub node_requests_per_sec: u32,
+    /// The maximum number of requests the clients at a single IP can send in a burst.
+    pub ip_burst: u32,
+    /// The number of requests per second the clients at a single IP can sustain.
+    pub ip_requests_per_sec: u32,
+    /// The number of requests rejected in a row after which a peer is muted.
+    pub mute_after: u32,
+    /// How long a peer stays muted.
+    pub mute_duration: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            burst: 200,
+            requests_per_sec: 100,
+            node_burst: 2000,
+            node_requests_per_sec: 1000,
+            ip_burst: 1000,
+            ip_requests_per_sec: 500,
+            mute_after: 100,
+            mute_duration: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    // The burst and rate of the bucket of a peer, which are larger for nodes.
+    fn peer_limits(&self, from_node: bool) -> (u32, u32) {
+        if from_node {
+            (self.node_burst, self.node_requests_per_sec)
+        } else {
+            (self.burst, self.requests_per_sec)
+        }
+    }
+}
+
+/// Why a request of a peer was not let through.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Rejection {
+    /// The bucket of the peer is empty, and will have a token again after the given time.
+    Throttled(Duration),
+    /// The bucket of the IP of the peer is empty, and will have a token again after
+    /// the given time.
+    IpThrottled(Duration),
+    /// The peer is muted for the given time.
+    /// `newly` is set for the request that got the peer muted.
+    Muted { remaining: Duration, newly: bool },
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(burst: u32, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(burst),
+            last_refill: now,
+        }
+    }
+
+    // Refills the bucket for the time elapsed since it was last refilled.
+    // If it is still empty, returns how long until it holds a token.
+    fn refill(&mut self, burst: u32, requests_per_sec: u32, now: Instant) -> Option<Duration> {
+        let rate = f64::from(requests_per_sec);
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(f64::from(burst));
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            None
+        } else if rate > 0.0 {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        } else {
+            Some(Duration::MAX)
+        }
+    }
+
+    // Whether the bucket is full once refilled, i.e. no different from a new one.
+    fn is_full(&self, burst: u32, requests_per_sec: u32, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens + elapsed * f64::from(requests_per_sec) >= f64::from(burst)
+    }
+}
+
+#[derive(Debug)]
+struct PeerBucket {
+    bucket: TokenBucket,
+    // Whether the bucket was last used with the budget of nodes.
+    from_node: bool,
+    rejected_in_row: u32,
+    muted_until: Option<Instant>,
+}
+
+/// Per-peer and per-IP token buckets, used to reject requests from peers sending
+/// more than their share.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<PeerId, PeerBucket>,
+    ip_buckets: HashMap<IpAddr, TokenBucket>,
+    last_prune: Instant,
+    allowed: u64,
+    throttled: u64,
+    muted: u64,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+            ip_buckets: HashMap::new(),
+            last_prune: Instant::now(),
+            allowed: 0,
+            throttled: 0,
+            muted: 0,
+        }
+    }
+
+    /// Applies the new limits, keeping the current buckets of the peers.
+    pub(crate) fn set_config(&mut self, config: RateLimitConfig) {
+        self.config = config;
+    }
+
+    /// Takes a token from the bucket of the peer, using the budget of nodes if it is
+    /// in the routing table, and from the bucket of its IP if it is a client.
+    /// If either bucket is empty, returns how long until a token is available,
+    /// or how long the peer stays muted.
+    pub(crate) fn check(
+        &mut self,
+        peer: PeerId,
+        from_node: bool,
+        ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if now.duration_since(self.last_prune) >= PRUNE_INTERVAL {
+            self.prune(now);
+        }
+
+        let config = self.config;
+        let (burst, rate) = config.peer_limits(from_node);
+        let peer_bucket = self.buckets.entry(peer).or_insert(PeerBucket {
+            bucket: TokenBucket::new(burst, now),
+            from_node,
+            rejected_in_row: 0,
+            muted_until: None,
+        });
+
+        if let Some(until) = peer_bucket.muted_until {
+            if until > now {
+                self.muted += 1;
+                return Err(Rejection::Muted {
+                    remaining: until - now,
+                    newly: false,
+                });
+            }
+            peer_bucket.muted_until = None;
+            peer_bucket.rejected_in_row = 0;
+        }
+
+        peer_bucket.from_node = from_node;
+        let peer_wait = peer_bucket.bucket.refill(burst, rate, now);
+        let mut ip_bucket = match ip {
+            Some(ip) if !from_node => Some(
+                self.ip_buckets
+                    .entry(ip)
+                    .or_insert_with(|| TokenBucket::new(config.ip_burst, now)),
+            ),
+            _ => None,
+        };
+        let ip_wait = ip_bucket
+            .as_mut()
+            .and_then(|bucket| bucket.refill(config.ip_burst, config.ip_requests_per_sec, now));
+
+        let retry_after = match (peer_wait, ip_wait) {
+            (None, None) => {
+                peer_bucket.bucket.tokens -= 1.0;
+                if let Some(bucket) = ip_bucket {
+                    bucket.tokens -= 1.0;
+                }
+                peer_bucket.rejected_in_row = 0;
+                self.allowed += 1;
+                return Ok(());
+            }
+            // Only the peer using up its own bucket gets it muted, not the other
+            // clients at its IP using up the bucket of the IP.
+            (None, Some(ip_wait)) => {
+                self.throttled += 1;
+                return Err(Rejection::IpThrottled(ip_wait));
+            }
+            (Some(peer_wait), ip_wait) => {
+                ip_wait.map_or(peer_wait, |ip_wait| ip_wait.max(peer_wait))
+            }
+        };
+
+        peer_bucket.rejected_in_row += 1;
+        if peer_bucket.rejected_in_row >= config.mute_after {
+            peer_bucket.muted_until = Some(now + config.mute_duration);
+            self.muted += 1;
+            return Err(Rejection::Muted {
+                remaining: config.mute_duration,
+                newly: true,
+            });
+        }
+
+        self.throttled += 1;
+        Err(Rejection::Throttled(retry_after))
+    }
+
+    /// The number of requests that were let through, that were rejected,
+    /// and that were dropped as their peer was muted.
+    pub(crate) fn counters(&self) -> (u64, u64, u64) {
+        (self.allowed, self.throttled, self.muted)
+    }
+
+    // A bucket that has refilled completely is no different from a new one,
+    // so there is no need to keep track of it any longer.
+    fn prune(&mut self, now: Instant) {
+        let config = self.config;
+        self.buckets.retain(|_, peer_bucket| {
+            let muted = peer_bucket.muted_until.is_some_and(|until| until > now);
+            let (burst, rate) = config.peer_limits(peer_bucket.from_node);
+            muted || !peer_bucket.bucket.is_full(burst, rate, now)
+        });
+        self.ip_buckets
+            .retain(|_, bucket| !bucket.is_full(config.ip_burst, config.ip_requests_per_sec, now));
+        self.last_prune = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(burst: u32, requests_per_sec: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            burst,
+            requests_per_sec,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn burst_is_allowed_then_throttled() {
+        let mut limiter = limiter(3, 1);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(peer, false, None, now).is_ok());
+        }
+        let retry_after = limiter
+            .check(peer, false, None, now)
+            .expect_err("bucket should be empty");
+        assert_eq!(retry_after, Rejection::Throttled(Duration::from_secs(1)));
+        assert_eq!(limiter.counters(), (3, 1, 0));
+    }
+
+    #[test]
+    fn peers_ignoring_throttling_are_muted() {
+        let config = RateLimitConfig {
+            burst: 1,
+            requests_per_sec: 1,
+            mute_after: 3,
+            mute_duration: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let mut limiter = RateLimiter::new(config);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(limiter.check(peer, false, None, now).is_ok());
+        for _ in 0..2 {
+            assert!(matches!(
+                limiter.check(peer, false, None, now),
+                Err(Rejection::Throttled(_))
+            ));
+        }
+        assert_eq!(
+            limiter.check(peer, false, None, now),
+            Err(Rejection::Muted {
+                remaining: config.mute_duration,
+                newly: true
+            })
+        );
+        // The bucket refills, but the peer stays muted until the mute is over.
+        let later = now + Duration::from_secs(10);
+        assert!(matches!(
+            limiter.check(peer, false, None, later),
+            Err(Rejection::Muted { newly: false, .. })
+        ));
+        assert!(limiter
+            .check(peer, false, None, now + config.mute_duration)
+            .is_ok());
+        assert_eq!(limiter.counters(), (2, 2, 2));
+    }
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let mut limiter = limiter(1, 10);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(limiter.check(peer, false, None, now).is_ok());
+        assert!(limiter.check(peer, false, None, now).is_err());
+        assert!(limiter
+            .check(peer, false, None, now + Duration::from_millis(100))
+            .is_ok());
+    }
+
+    #[test]
+    fn peers_have_separate_buckets() {
+        let mut limiter = limiter(1, 1);
+        let now = Instant::now();
+        let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+
+        assert!(limiter.check(peer_a, false, None, now).is_ok());
+        assert!(limiter.check(peer_a, false, None, now).is_err());
+        assert!(limiter.check(peer_b, false, None, now).is_ok());
+    }
+
+    #[test]
+    fn refilled_buckets_are_pruned() {
+        let mut limiter = limiter(1, 1);
+        let now = Instant::now();
+        let (idle, busy) = (PeerId::random(), PeerId::random());
+
+        assert!(limiter.check(idle, false, None, now).is_ok());
+        let later = now + PRUNE_INTERVAL;
+        assert!(limiter.check(busy, false, None, later).is_ok());
+
+        assert!(!limiter.buckets.contains_key(&idle));
+        assert!(limiter.buckets.contains_key(&busy));
+    }
+
+    #[test]
+    fn nodes_have_their_own_budget() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            burst: 1,
+            node_burst: 3,
+            ..Default::default()
+        });
+        let (node, client) = (PeerId::random(), PeerId::random());
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(node, true, ip, now).is_ok());
+        }
+        assert!(limiter.check(node, true, ip, now).is_err());
+        // Nodes do not use up the bucket of their IP.
+        assert!(limiter.check(client, false, ip, now).is_ok());
+    }
+
+    #[test]
+    fn clients_at_an_ip_share_its_bucket() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            ip_burst: 2,
+            ip_requests_per_sec: 1,
+            ..Default::default()
+        });
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+        let now = Instant::now();
+
+        assert!(limiter.check(PeerId::random(), false, ip, now).is_ok());
+        assert!(limiter.check(PeerId::random(), false, ip, now).is_ok());
+        assert_eq!(
+            limiter.check(PeerId::random(), false, ip, now),
+            Err(Rejection::IpThrottled(Duration::from_secs(1)))
+        );
+        let elsewhere = Some(IpAddr::from([10, 0, 0, 2]));
+        assert!(limiter
+            .check(PeerId::random(), false, elsewhere, now)
+            .is_ok());
+        assert_eq!(limiter.counters(), (3, 1, 0));
+    }
+
+    #[test]
+    fn clients_are_not_muted_for_others_at_their_ip() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            ip_burst: 1,
+            ip_requests_per_sec: 1,
+            mute_after: 2,
+            ..Default::default()
+        });
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+        let (noisy, quiet) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert!(limiter.check(noisy, false, ip, now).is_ok());
+        for _ in 0..5 {
+            assert!(matches!(
+                limiter.check(quiet, false, ip, now),
+                Err(Rejection::IpThrottled(_))
+            ));
+        }
+        assert!(limiter
+            .check(quiet, false, ip, now + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn buckets_of_nodes_are_pruned_with_their_budget() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            burst: 1,
+            requests_per_sec: 0,
+            node_burst: 10,
+            node_requests_per_sec: 0,
+            ..Default::default()
+        });
+        let node = PeerId::random();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            assert!(limiter.check(node, true, None, now).is_ok());
+        }
+        let later = now + PRUNE_INTERVAL;
+        assert!(limiter.check(PeerId::random(), false, None, later).is_ok());
+
+        // Holding more than the burst of a client does not make it a full bucket.
+        assert!(limiter.buckets.contains_key(&node));
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, NodeCtl};
+
+use crate::{
+    network::CLOSE_GROUP_SIZE,
+    protocol::{
+        address::DataAddress,
+        messages::{Cmd, Query, RegisterQuery, Request, Response, SpendQuery},
+    },
+};
+
+use futures::future::join_all;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::timeout;
+
+// How long to wait for a peer to answer whether it holds the record, or to store it.
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether a peer of the close group of a record holds it, see [`ReplicationReport`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerHolding {
+    /// The peer id.
+    pub peer: String,
+    /// Whether the peer holds the record, or None if it did not answer in time.
+    pub holds: Option<bool>,
+}
+
+/// How well a record is replicated across its close group, as found by
+/// [`NodeCtl::check_replication`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationReport {
+    /// The address of the record.
+    pub address: DataAddress,
+    /// Whether this node is in the close group of the record.
+    pub should_hold: bool,
+    /// Whether this node holds the record.
+    pub holds: bool,
+    /// The other peers of the close group, and whether they hold the record.
+    pub peers: Vec<PeerHolding>,
+    /// The peers this node pushed the record to, which stored it.
+    pub replicated_to: Vec<String>,
+}
+
+impl ReplicationReport {
+    /// The number of peers of the close group known to hold the record, once replicated.
+    pub fn holders(&self) -> usize {
+        let peers = self
+            .peers
+            .iter()
+            .filter(|peer| peer.holds == Some(true))
+            .count();
+        let this_node = usize::from(self.should_hold && self.holds);
+        peers + this_node + self.replicated_to.len()
+    }
+
+    /// Only counts as holders the given peers among those found to hold the record.
+    pub(super) fn confirm_holders(&mut self, confirmed: &[String]) {
+        for peer in &mut self.peers {
+            if peer.holds == Some(true) && !confirmed.contains(&peer.peer) {
+                peer.holds = Some(false);
+            }
+        }
+    }
+
+    /// Whether fewer peers than the whole close group hold the record.
+    pub fn is_under_replicated(&self) -> bool {
+        self.holders() < CLOSE_GROUP_SIZE
+    }
+}
+
+impl NodeCtl {
+    /// Checks which peers of the close group of the record hold it, and if any that
+    /// answered does not while this node does, pushes the record to them.
+    pub async fn check_replication(&self, address: DataAddress) -> Result<ReplicationReport> {
+        let closest_peers = self.network.node_get_closest_peers(*address.name()).await?;
+        Ok(self.check_replication_among(address, closest_peers).await)
+    }
+
+    // Checks the replication of the record across the given closest peers to it,
+    // as per `check_replication`.
+    pub(super) async fn check_replication_among(
+        &self,
+        address: DataAddress,
+        closest_peers: Vec<PeerId>,
+    ) -> ReplicationReport {
+        let this_node = self.network.peer_id;
+        let should_hold = closest_peers.contains(&this_node);
+        let peers: Vec<PeerId> = closest_peers
+            .into_iter()
+            .filter(|peer| *peer != this_node)
+            .collect();
+
+        let query = Request::Query(holding_query(address));
+        let answers = join_all(peers.iter().map(|peer| {
+            timeout(
+                PEER_TIMEOUT,
+                self.network.send_request(query.clone(), *peer),
+            )
+        }))
+        .await;
+        let holdings: Vec<(PeerId, Option<bool>)> = peers
+            .into_iter()
+            .zip(answers)
+            .map(|(peer, answer)| {
+                let holds = match answer {
+                    Ok(Ok(Response::Query(resp))) => Some(resp.is_success()),
+                    _ => None,
+                };
+                (peer, holds)
+            })
+            .collect();
+
+        let cmds = match self.data.replication_cmds(&address).await {
+            Some(Ok(cmds)) => Some(cmds),
+            Some(Err(err)) => {
+                debug!("Not holding {address:?}: {err}");
+                None
+            }
+            None => None,
+        };
+        let holds = cmds.is_some();
+
+        let mut report = ReplicationReport {
+            address,
+            should_hold,
+            holds,
+            peers: holdings
+                .iter()
+                .map(|(peer, holds)| PeerHolding {
+                    peer: peer.to_string(),
+                    holds: *holds,
+                })
+                .collect(),
+            replicated_to: vec![],
+        };
+        if let Some(cmds) = cmds {
+            if report.is_under_replicated() {
+                let missing = holdings
+                    .iter()
+                    .filter(|(_, holds)| *holds == Some(false))
+                    .map(|(peer, _)| *peer);
+                let pushes = join_all(missing.map(|peer| self.push_to(peer, &cmds))).await;
+                report.replicated_to = pushes.into_iter().flatten().collect();
+            }
+        }
+
+        debug!(
+            "Replication of {address:?}: {} of {} peers hold it, replicated to {}",
+            report.holders(),
+            report.peers.len() + usize::from(should_hold),
+            report.replicated_to.len()
+        );
+        report
+    }
+
+    // Holding a register does not mean holding all of its ops, so its cmds are pushed
+    // to the peers found to hold it, and only those that stored them all are counted.
+    pub(super) async fn confirm_register_holders(&self, report: &mut ReplicationReport) {
+        let cmds = match self.data.replication_cmds(&report.address).await {
+            Some(Ok(cmds)) => cmds,
+            _ => {
+                report.confirm_holders(&[]);
+                return;
+            }
+        };
+        let holders = report
+            .peers
+            .iter()
+            .filter(|peer| peer.holds == Some(true))
+            .filter_map(|peer| peer.peer.parse::<PeerId>().ok());
+        let confirmed: Vec<String> = join_all(holders.map(|peer| self.push_to(peer, &cmds)))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        report.confirm_holders(&confirmed);
+    }
+
+    // Sends the cmds to the peer in order, returning the peer if it stored them all.
+    async fn push_to(&self, peer: PeerId, cmds: &[Cmd]) -> Option<String> {
+        for cmd in cmds {
+            let stored = timeout(
+                PEER_TIMEOUT,
+                self.network.send_request(Request::Cmd(cmd.clone()), peer),
+            )
+            .await;
+            if !matches!(stored, Ok(Ok(Response::Cmd(resp))) if resp.is_success()) {
+                warn!("Peer {peer} did not store {:?}", cmd.dst());
+                return None;
+            }
+        }
+        Some(peer.to_string())
+    }
+}
+
+// The query that a peer only answers successfully if it holds the record.
+fn holding_query(address: DataAddress) -> Query {
+    match address {
+        DataAddress::Chunk(addr) => Query::GetChunk(addr),
+        DataAddress::Register(addr) => Query::Register(RegisterQuery::Get(addr)),
+        DataAddress::Spend(addr) => Query::Spend(SpendQuery::GetDbcSpend(addr)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use xor_name::XorName;
+
+    #[test]
+    fn holders_count_the_peers_that_hold_the_record() {
+        let holding = |holds| PeerHolding {
+            peer: PeerId::random().to_string(),
+            holds,
+        };
+        let mut report = ReplicationReport {
+            address: DataAddress::chunk(XorName::random(&mut rand::thread_rng())),
+            should_hold: true,
+            holds: true,
+            peers: vec![holding(Some(true)), holding(Some(false)), holding(None)],
+            replicated_to: vec![],
+        };
+        assert_eq!(report.holders(), 2);
+        assert!(report.is_under_replicated());
+
+        report.replicated_to.push(report.peers[1].peer.clone());
+        assert_eq!(report.holders(), 3);
+
+        // Holding a record this node is not close to does not count.
+        report.should_hold = false;
+        assert_eq!(report.holders(), 2);
+
+        report.peers = (0..CLOSE_GROUP_SIZE).map(|_| holding(Some(true))).collect();
+        assert!(!report.is_under_replicated());
+    }
+
+    #[test]
+    fn holders_missing_ops_of_a_register_do_not_count() {
+        let holding = || PeerHolding {
+            peer: PeerId::random().to_string(),
+            holds: Some(true),
+        };
+        let mut report = ReplicationReport {
+            address: DataAddress::register(XorName::random(&mut rand::thread_rng()), 3006),
+            should_hold: false,
+            holds: true,
+            peers: vec![holding(), holding(), holding()],
+            replicated_to: vec![],
+        };
+        assert_eq!(report.holders(), 3);
+
+        // The last holder lacks an op, and did not store it when it was pushed.
+        let confirmed = [report.peers[0].peer.clone(), report.peers[1].peer.clone()];
+        report.confirm_holders(&confirmed);
+        assert_eq!(report.holders(), 2);
+        assert_eq!(report.peers[2].holds, Some(false));
+    }
+}
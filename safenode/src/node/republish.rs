@@ -0,0 +1,181 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::Result, event::NodeEventsChannel, BanConfig, LaneStats, LatencySummary, NodeEvent,
+    NodeMetrics, QueryCache, RateLimitConfig, RejectionStats,
+};
+
+use crate::{
+    network::{close_group_majority, ConnectionInfo, Network},
+    protocol::{
+        address::{ChunkAddress, DataAddress, RegisterAddress},
+        messages::{Request, Response},
+    },
+    storage::DataTypes,
+};
+
+use futures::future::join_all;
+use libp2p::identity::Keypair;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::watch,
+    time::{interval, timeout, Interval, MissedTickBehavior},
+};
+
+/// The records a node pushed to the current closest peers of each record,
+/// when republishing everything it holds.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepublishManifest {
+    /// Chunks stored by a majority of their close group.
+    pub chunks: Vec<ChunkAddress>,
+    /// Registers with all their cmds stored by a majority of their close group.
+    pub registers: Vec<RegisterAddress>,
+    /// Chunks that could not be stored by a majority of their close group.
+    pub failed_chunks: Vec<ChunkAddress>,
+    /// Registers that could not be stored by a majority of their close group.
+    pub failed_registers: Vec<RegisterAddress>,
+}
+
+/// Controls a running node, see [`Node::run`](super::Node::run).
+#[derive(Clone)]
+pub struct NodeCtl {
+    pub(super) network: Network,
+    pub(super) data: DataTypes,
+    pub(super) events_channel: NodeEventsChannel,
+    pub(super) metrics: NodeMetrics,
+    pub(super) runtime_config: Arc<watch::Sender<(RateLimitConfig, BanConfig)>>,
+    pub(super) keypair: Keypair,
+    pub(super) query_cache: QueryCache,
+}
+
+impl NodeCtl {
+    /// Changes the rate limits and the ban settings of the running node.
+    pub fn reconfigure(&self, rate_limit: RateLimitConfig, ban_config: BanConfig) {
+        let _ = self.runtime_config.send_replace((rate_limit, ban_config));
+    }
+
+    /// Lists the peers the node talks to, with the state of the link with each of them.
+    pub async fn connections(&self) -> Result<Vec<ConnectionInfo>> {
+        Ok(self.network.connections().await?)
+    }
+
+    /// The latencies of the operations the node handled since it started,
+    /// per operation and source of the requests.
+    pub async fn metrics(&self) -> Vec<LatencySummary> {
+        self.metrics.summaries().await
+    }
+
+    /// How many events are queued in each lane of the node, and how many it shed
+    /// since it started as their lane was full.
+    pub async fn lanes(&self) -> LaneStats {
+        self.metrics.lanes().await
+    }
+
+    /// How many requests the node rejected since it started, as their peer was banned
+    /// or sent more than its share, or as the clients at their IP did.
+    pub async fn rejections(&self) -> RejectionStats {
+        self.metrics.rejections().await
+    }
+
+    /// Pushes every record the node holds to the current closest peers of the record,
+    /// so that it stays available once this node leaves the network.
+    ///
+    /// At most `requests_per_sec` records (or Register cmds) are sent per second, so that
+    /// the peers do not throttle us. Progress is broadcast as [`NodeEvent::RepublishProgress`].
+    pub async fn republish(&self, requests_per_sec: u32) -> RepublishManifest {
+        // In replication order, so the data types with a higher priority go first.
+        let addrs = self.data.addrs().await;
+        let total = addrs.len();
+        info!("Republishing {total} records");
+
+        let mut pace = interval(Duration::from_secs(1) / requests_per_sec.max(1));
+        pace.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut manifest = RepublishManifest::default();
+        for addr in addrs {
+            let stored = match self.data.replication_cmds(&addr).await {
+                Some(Ok(cmds)) => {
+                    let mut stored = true;
+                    for cmd in cmds {
+                        stored &= self.push(Request::Cmd(cmd), &mut pace).await;
+                    }
+                    stored
+                }
+                Some(Err(err)) => {
+                    warn!("Could not read {addr:?} to republish: {err}");
+                    false
+                }
+                None => {
+                    warn!("No data type handles {addr:?}, not republishing it");
+                    false
+                }
+            };
+            match (addr, stored) {
+                (DataAddress::Chunk(addr), true) => manifest.chunks.push(addr),
+                (DataAddress::Chunk(addr), false) => manifest.failed_chunks.push(addr),
+                (DataAddress::Register(addr), true) => manifest.registers.push(addr),
+                (DataAddress::Register(addr), false) => manifest.failed_registers.push(addr),
+                (DataAddress::Spend(_), _) => {}
+            }
+            self.progress(&manifest, total);
+        }
+
+        info!(
+            "Republished {} chunks and {} registers, failed to republish {} chunks and {} registers",
+            manifest.chunks.len(),
+            manifest.registers.len(),
+            manifest.failed_chunks.len(),
+            manifest.failed_registers.len()
+        );
+        manifest
+    }
+
+    // Sends the cmd to the closest peers of its destination, other than ourselves,
+    // and returns whether a majority of them stored it.
+    async fn push(&self, request: Request, pace: &mut Interval) -> bool {
+        let _ = pace.tick().await;
+
+        let closest_peers = match self.network.node_get_targets(&request).await {
+            Ok(peers) => peers,
+            Err(err) => {
+                warn!(
+                    "Could not get the closest peers of {:?}: {err}",
+                    request.dst()
+                );
+                return false;
+            }
+        };
+
+        let requests = closest_peers
+            .into_iter()
+            .filter(|peer| *peer != self.network.peer_id)
+            .map(|peer| {
+                timeout(
+                    Duration::from_secs(10),
+                    self.network.send_request(request.clone(), peer),
+                )
+            });
+        let stored = join_all(requests)
+            .await
+            .into_iter()
+            .filter(|result| matches!(result, Ok(Ok(Response::Cmd(resp))) if resp.is_success()))
+            .count();
+
+        stored >= close_group_majority()
+    }
+
+    fn progress(&self, manifest: &RepublishManifest, total: usize) {
+        let done = manifest.chunks.len()
+            + manifest.registers.len()
+            + manifest.failed_chunks.len()
+            + manifest.failed_registers.len();
+        self.events_channel
+            .broadcast(NodeEvent::RepublishProgress { done, total });
+    }
+}
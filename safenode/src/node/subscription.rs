@@ -0,0 +1,267 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{LaneStats, LatencySummary, NodeCtl, NodeEvent, RejectionStats, ReplicationReport};
+
+use crate::{network::ConnectionInfo, protocol::address::DataAddress};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+#[cfg(unix)]
+use std::{fs::Permissions, io, os::unix::fs::PermissionsExt, path::Path};
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::broadcast::{error::RecvError, Receiver},
+};
+
+/// The classes of node events that local apps can subscribe to.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum EventClass {
+    /// The node joined the network, or connected to more peers.
+    Membership,
+    /// The node stored a chunk, or created or edited a Register.
+    DataStored,
+    /// The node detected misbehaviour, such as a double spend, or banned a misbehaving peer.
+    Faults,
+    /// The node maintained or republished the data it holds.
+    Maintenance,
+}
+
+/// What a local app can ask of the node on its socket, instead of subscribing to events.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum NodeRpc {
+    /// List the peers the node talks to, see [`NodeCtl::connections`].
+    ListConnections,
+    /// Get the latencies of the operations the node handled, see [`NodeCtl::metrics`].
+    Metrics,
+    /// Get the depths of the lanes of the node, and the events it shed, see [`NodeCtl::lanes`].
+    Lanes,
+    /// Get the number of requests the node rejected, see [`NodeCtl::rejections`].
+    Rejections,
+    /// Check how well the record at the address is replicated, and replicate it if need be,
+    /// see [`NodeCtl::check_replication`].
+    CheckReplication(DataAddress),
+}
+
+/// Selects the events sent to a subscriber.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// The classes of events to receive, or all of them if empty.
+    #[serde(default)]
+    pub classes: BTreeSet<EventClass>,
+    /// If set, the hex encoded bytes that the name of stored data must start with,
+    /// for the data stored event to be received.
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+impl NodeEvent {
+    /// The class of the event.
+    pub fn class(&self) -> EventClass {
+        match self {
+            Self::ConnectedToNetwork => EventClass::Membership,
+            Self::ChunkStored(_) | Self::RegisterStored(_) => EventClass::DataStored,
+            Self::DoubleSpendDetected { .. } | Self::PeerBanned { .. } => EventClass::Faults,
+            Self::RegistersMaintained(_)
+            | Self::RepublishProgress { .. }
+            | Self::MaintenanceStarted { .. }
+            | Self::MaintenanceEnded => EventClass::Maintenance,
+        }
+    }
+
+    // The name of the data the event is about, if any.
+    fn data_name(&self) -> Option<&XorName> {
+        match self {
+            Self::ChunkStored(addr) => Some(addr.name()),
+            Self::RegisterStored(addr) => Some(addr.name()),
+            _ => None,
+        }
+    }
+}
+
+impl EventFilter {
+    /// Whether the event is selected by this filter.
+    pub fn matches(&self, event: &NodeEvent) -> bool {
+        if !self.classes.is_empty() && !self.classes.contains(&event.class()) {
+            return false;
+        }
+        match (&self.prefix, event.data_name()) {
+            (Some(prefix), Some(name)) => match hex::decode(prefix) {
+                Ok(prefix) => name.0.starts_with(&prefix),
+                Err(_) => false,
+            },
+            _ => true,
+        }
+    }
+}
+
+/// Serves the events of the node on a unix socket at the given path, until an error occurs.
+///
+/// A subscriber connects and sends an [`EventFilter`] as a single line of json. It then
+/// receives every selected event, as a line of json each. Only processes of the user
+/// running the node can connect, as the socket is only accessible to that user.
+///
+/// An app can instead send a [`NodeRpc`] as a single line of json, and then receives
+/// the answer as a single line of json, see [`list_connections`].
+#[cfg(unix)]
+pub async fn serve_events(path: &Path, node_ctl: NodeCtl) -> io::Result<()> {
+    // A socket left behind by an earlier run would prevent us from binding.
+    let _ = tokio::fs::remove_file(path).await;
+    let listener = UnixListener::bind(path)?;
+    tokio::fs::set_permissions(path, Permissions::from_mode(0o600)).await?;
+    info!("Serving node events at {path:?}");
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let events = node_ctl.events_channel.subscribe();
+        let node_ctl = node_ctl.clone();
+        let _handle = tokio::spawn(async move {
+            if let Err(err) = stream_events(stream, events, node_ctl).await {
+                debug!("Event subscriber disconnected: {err}");
+            }
+        });
+    }
+}
+
+/// Asks the node serving events at the given path for the peers it talks to.
+#[cfg(unix)]
+pub async fn list_connections(path: &Path) -> io::Result<Vec<ConnectionInfo>> {
+    let line = call(path, NodeRpc::ListConnections).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Asks the node serving events at the given path for the latencies of the operations
+/// it handled.
+#[cfg(unix)]
+pub async fn node_metrics(path: &Path) -> io::Result<Vec<LatencySummary>> {
+    let line = call(path, NodeRpc::Metrics).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Asks the node serving events at the given path for the depths of its lanes,
+/// and the number of events it shed.
+#[cfg(unix)]
+pub async fn node_lanes(path: &Path) -> io::Result<LaneStats> {
+    let line = call(path, NodeRpc::Lanes).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Asks the node serving events at the given path for the number of requests it rejected.
+#[cfg(unix)]
+pub async fn node_rejections(path: &Path) -> io::Result<RejectionStats> {
+    let line = call(path, NodeRpc::Rejections).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Asks the node serving events at the given path to check the replication of the record
+/// at the address.
+#[cfg(unix)]
+pub async fn check_replication(path: &Path, address: DataAddress) -> io::Result<ReplicationReport> {
+    let line = call(path, NodeRpc::CheckReplication(address)).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+// Sends the rpc to the node serving events at the given path, returning the answer.
+#[cfg(unix)]
+async fn call(path: &Path, rpc: NodeRpc) -> io::Result<String> {
+    let (reader, mut writer) = UnixStream::connect(path).await?.into_split();
+    let mut rpc = serde_json::to_vec(&rpc)?;
+    rpc.push(b'\n');
+    writer.write_all(&rpc).await?;
+    let mut line = String::new();
+    let _ = BufReader::new(reader).read_line(&mut line).await?;
+    Ok(line)
+}
+
+#[cfg(unix)]
+async fn stream_events(
+    stream: UnixStream,
+    mut events: Receiver<NodeEvent>,
+    node_ctl: NodeCtl,
+) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    let _ = BufReader::new(reader).read_line(&mut line).await?;
+    if let Ok(rpc) = serde_json::from_str::<NodeRpc>(&line) {
+        let mut json = match rpc {
+            NodeRpc::ListConnections => {
+                let connections = node_ctl
+                    .connections()
+                    .await
+                    .map_err(|err| io::Error::other(err.to_string()))?;
+                serde_json::to_vec(&connections)?
+            }
+            NodeRpc::Metrics => serde_json::to_vec(&node_ctl.metrics().await)?,
+            NodeRpc::Lanes => serde_json::to_vec(&node_ctl.lanes().await)?,
+            NodeRpc::Rejections => serde_json::to_vec(&node_ctl.rejections().await)?,
+            NodeRpc::CheckReplication(address) => {
+                let report = node_ctl
+                    .check_replication(address)
+                    .await
+                    .map_err(|err| io::Error::other(err.to_string()))?;
+                serde_json::to_vec(&report)?
+            }
+        };
+        json.push(b'\n');
+        return writer.write_all(&json).await;
+    }
+    let filter: EventFilter = serde_json::from_str(&line)?;
+    debug!("New event subscriber, with {filter:?}");
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Event subscriber is too slow, {skipped} events were skipped");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        if filter.matches(&event) {
+            let mut json = serde_json::to_vec(&event)?;
+            json.push(b'\n');
+            writer.write_all(&json).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::address::ChunkAddress;
+
+    #[test]
+    fn filter_selects_by_class_and_prefix() {
+        let mut name = XorName::default();
+        name.0[0] = 0xab;
+        let stored = NodeEvent::ChunkStored(ChunkAddress::new(name));
+
+        let everything = EventFilter::default();
+        assert!(everything.matches(&stored));
+        assert!(everything.matches(&NodeEvent::ConnectedToNetwork));
+
+        let data = EventFilter {
+            classes: BTreeSet::from([EventClass::DataStored]),
+            prefix: Some("ab".to_string()),
+        };
+        assert!(data.matches(&stored));
+        assert!(!data.matches(&NodeEvent::ConnectedToNetwork));
+
+        let other_prefix = EventFilter {
+            prefix: Some("ac".to_string()),
+            ..Default::default()
+        };
+        assert!(!other_prefix.matches(&stored));
+        assert!(other_prefix.matches(&NodeEvent::ConnectedToNetwork));
+    }
+}
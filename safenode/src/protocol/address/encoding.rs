@@ -0,0 +1,86 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::error::{Error, Result};
+
+use multibase::Base;
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The bases the names of addresses can be written in.
+///
+/// Hex is written without a prefix, as addresses always have been. The other bases
+/// are written as [multibase](https://github.com/multiformats/multibase) strings,
+/// whose first character tells the base, so that [`decode_name`] can read any of them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressBase {
+    /// Lowercase hex, without a prefix.
+    #[default]
+    Hex,
+    /// Human-oriented base32, prefixed with `h`.
+    Base32z,
+    /// Bitcoin's base58, prefixed with `z`.
+    Base58Btc,
+    /// Url-safe base64 without padding, prefixed with `u`.
+    Base64Url,
+}
+
+impl AddressBase {
+    /// Writes the name in this base.
+    pub fn encode(&self, name: &XorName) -> String {
+        match self {
+            Self::Hex => hex::encode(name.0),
+            Self::Base32z => multibase::encode(Base::Base32Z, name.0),
+            Self::Base58Btc => multibase::encode(Base::Base58Btc, name.0),
+            Self::Base64Url => multibase::encode(Base::Base64Url, name.0),
+        }
+    }
+}
+
+/// Reads a name written in hex, or as a multibase string in any base.
+pub fn decode_name(encoded: &str) -> Result<XorName> {
+    let mut name = XorName::default();
+    if encoded.len() == 2 * name.0.len() && hex::decode_to_slice(encoded, &mut name.0).is_ok() {
+        return Ok(name);
+    }
+    let (_, bytes) = multibase::decode(encoded)
+        .map_err(|err| Error::InvalidAddress(format!("{encoded:?}: {err}")))?;
+    if bytes.len() != name.0.len() {
+        return Err(Error::InvalidAddress(format!(
+            "{encoded:?} is {} bytes long, not {}",
+            bytes.len(),
+            name.0.len()
+        )));
+    }
+    name.0.copy_from_slice(&bytes);
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_read_back_from_any_base() -> Result<()> {
+        let name = XorName::random(&mut rand::thread_rng());
+        for base in [
+            AddressBase::Hex,
+            AddressBase::Base32z,
+            AddressBase::Base58Btc,
+            AddressBase::Base64Url,
+        ] {
+            assert_eq!(decode_name(&base.encode(&name))?, name);
+        }
+        assert_eq!(AddressBase::Hex.encode(&name), format!("{name:x}"));
+
+        assert!(decode_name("not an address").is_err());
+        assert!(decode_name(&multibase::encode(Base::Base58Btc, [1, 2, 3])).is_err());
+        Ok(())
+    }
+}
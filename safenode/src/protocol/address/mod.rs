@@ -8,12 +8,16 @@
 
 mod chunk;
 mod dbc;
+mod encoding;
 mod register;
+mod vectors;
 
 pub use self::{
     chunk::ChunkAddress,
     dbc::{dbc_address, dbc_name, DbcAddress},
+    encoding::{decode_name, AddressBase},
     register::RegisterAddress,
+    vectors::{encoding_vectors, verify_vectors, EncodingVector, VectorMismatch},
 };
 
 use sn_dbc::DbcId;
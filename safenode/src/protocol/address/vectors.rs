@@ -0,0 +1,151 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::encoding::{decode_name, AddressBase};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use xor_name::XorName;
+
+// The bases every name of the corpus is written in.
+const BASES: [AddressBase; 4] = [
+    AddressBase::Hex,
+    AddressBase::Base32z,
+    AddressBase::Base58Btc,
+    AddressBase::Base64Url,
+];
+
+// The number of names of the corpus derived from hashes, i.e. without any pattern.
+const HASHED_NAMES: usize = 4;
+
+/// A name, and how it is written in a base.
+///
+/// Other implementations, e.g. the clients in other languages, can check that they
+/// write and read addresses as this crate does against the corpus of [`encoding_vectors`],
+/// which is also kept as a json golden file in the repository.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EncodingVector {
+    /// What the name exercises, e.g. `leading-zeros`.
+    pub label: String,
+    /// The 32 bytes of the name, in hex.
+    pub name: String,
+    /// The base the name is written in.
+    pub base: AddressBase,
+    /// The name written in the base.
+    pub encoded: String,
+}
+
+/// A vector the encoding of this crate disagrees with, as found by [`verify_vectors`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VectorMismatch {
+    /// The vector.
+    pub vector: EncodingVector,
+    /// What the vector was found to be wrong about.
+    pub reason: String,
+}
+
+impl fmt::Display for VectorMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {:?}: {}",
+            self.vector.label, self.vector.base, self.reason
+        )
+    }
+}
+
+/// The corpus of test vectors: names exercising the edge cases of the bases, such as
+/// leading zero bytes, which base58 writes apart, written in every base.
+///
+/// The corpus is deterministic, so that it only changes if the encoding does.
+pub fn encoding_vectors() -> Vec<EncodingVector> {
+    let mut names = vec![
+        ("zeros".to_string(), XorName([0; 32])),
+        ("ones".to_string(), XorName([0xff; 32])),
+        (
+            "ascending".to_string(),
+            XorName(std::array::from_fn(|i| i as u8)),
+        ),
+        (
+            "leading-zeros".to_string(),
+            XorName(std::array::from_fn(|i| if i < 4 { 0 } else { i as u8 })),
+        ),
+    ];
+    for i in 0..HASHED_NAMES {
+        let content = format!("safe-address-vector-{i}");
+        names.push((
+            format!("hashed-{i}"),
+            XorName::from_content(content.as_bytes()),
+        ));
+    }
+
+    names
+        .into_iter()
+        .flat_map(|(label, name)| {
+            BASES.into_iter().map(move |base| EncodingVector {
+                label: label.clone(),
+                name: hex::encode(name.0),
+                base,
+                encoded: base.encode(&name),
+            })
+        })
+        .collect()
+}
+
+/// Checks that this crate writes the name of every vector as expected,
+/// and reads it back from what is expected.
+pub fn verify_vectors(vectors: &[EncodingVector]) -> Vec<VectorMismatch> {
+    vectors
+        .iter()
+        .filter_map(|vector| {
+            verify_vector(vector).err().map(|reason| VectorMismatch {
+                vector: vector.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+fn verify_vector(vector: &EncodingVector) -> Result<(), String> {
+    let mut name = XorName::default();
+    hex::decode_to_slice(&vector.name, &mut name.0)
+        .map_err(|err| format!("the name is not 32 bytes of hex: {err}"))?;
+    let encoded = vector.base.encode(&name);
+    if encoded != vector.encoded {
+        return Err(format!("written as {encoded:?}, not {:?}", vector.encoded));
+    }
+    match decode_name(&vector.encoded) {
+        Ok(decoded) if decoded == name => Ok(()),
+        Ok(decoded) => Err(format!("read back as {}", hex::encode(decoded.0))),
+        Err(err) => Err(format!("not read back: {err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::Result;
+
+    // Regenerate with `safe --encoding-vectors > <this file>`, only when changing
+    // the encoding on purpose, as other implementations check against it.
+    const GOLDEN: &str = include_str!("encoding_vectors.json");
+
+    #[test]
+    fn the_corpus_matches_the_golden_file() -> Result<()> {
+        let golden: Vec<EncodingVector> = serde_json::from_str(GOLDEN)?;
+        assert_eq!(encoding_vectors(), golden);
+        assert!(verify_vectors(&golden).is_empty());
+
+        let mut tampered = golden[5].clone();
+        tampered.encoded.push('a');
+        let mismatches = verify_vectors(&[tampered]);
+        assert_eq!(mismatches.len(), 1);
+        Ok(())
+    }
+}
@@ -15,7 +15,7 @@ use super::{
 use crate::network_transfers::Error as TransferError;
 
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, result};
+use std::{fmt::Debug, result, time::Duration};
 use thiserror::Error;
 
 /// A specialised `Result` type for protocol crate.
@@ -92,7 +92,28 @@ pub enum Error {
     /// Hex decoding error.
     #[error("Hex decoding error:: {0}")]
     HexDecoding(String),
+    /// An address that could not be read.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
     /// Failed to write file, likely due to a system Io error
     #[error("Failed to write file")]
     FailedToWriteFile,
+    /// The node is receiving too many requests from us, and refused this one.
+    #[error("Too many requests, retry after {retry_after:?}")]
+    RateLimited {
+        /// How long to back off before the node will accept a request again.
+        retry_after: Duration,
+    },
+    /// The node is receiving more requests than it can queue, and shed this one.
+    #[error("The node is overloaded, retry after {retry_after:?}")]
+    Overloaded {
+        /// How long to back off before retrying.
+        retry_after: Duration,
+    },
+    /// The node banned us for misbehaving, and refuses all our requests for a while.
+    #[error("Banned for misbehaving, retry after {retry_after:?}")]
+    Banned {
+        /// How long until the ban is lifted.
+        retry_after: Duration,
+    },
 }
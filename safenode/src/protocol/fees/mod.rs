@@ -43,6 +43,7 @@ mod fee_ciphers;
 mod priority;
 mod required_fee;
 mod required_fee_content;
+mod spend_fee_estimate;
 mod spend_queue;
 
 pub use self::{
@@ -51,6 +52,7 @@ pub use self::{
     priority::SpendPriority,
     required_fee::RequiredFee,
     required_fee_content::RequiredFeeContent,
+    spend_fee_estimate::SpendFeeEstimate,
     spend_queue::{SpendQ, SpendQSnapshot, SpendQStats},
 };
 
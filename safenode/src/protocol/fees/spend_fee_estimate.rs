@@ -0,0 +1,105 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_dbc::Token;
+
+use serde::{Deserialize, Serialize};
+
+/// An estimate of what it currently costs to spend a single input dbc,
+/// aggregated over the fees quoted by the close groups of the sampled dbcs.
+///
+/// The fee for spending an input is the sum of the fees required
+/// by each of the nodes in the close group of that input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SpendFeeEstimate {
+    /// The lowest fee quoted for spending an input.
+    pub min: Token,
+    /// The median fee quoted for spending an input.
+    pub median: Token,
+    /// The highest fee quoted for spending an input.
+    pub max: Token,
+    /// The number of inputs that quotes were received for.
+    pub samples: usize,
+}
+
+impl SpendFeeEstimate {
+    /// Aggregates the given per-input fees into an estimate.
+    /// Returns `None` if no fees were given.
+    pub fn from_fees(mut fees: Vec<Token>) -> Option<Self> {
+        if fees.is_empty() {
+            return None;
+        }
+        fees.sort();
+
+        let samples = fees.len();
+        let mid = samples / 2;
+        let median = if samples % 2 == 1 {
+            fees[mid]
+        } else {
+            // Averaging this way cannot overflow.
+            let (a, b) = (fees[mid - 1].as_nano(), fees[mid].as_nano());
+            Token::from_nano(a / 2 + b / 2 + (a % 2 + b % 2) / 2)
+        };
+
+        Some(Self {
+            min: fees[0],
+            median,
+            max: fees[samples - 1],
+            samples,
+        })
+    }
+
+    /// The expected cost of a spend using the given number of inputs,
+    /// taken at the median fee. Returns `None` on overflow.
+    pub fn median_cost_for(&self, num_inputs: u64) -> Option<Token> {
+        self.median
+            .as_nano()
+            .checked_mul(num_inputs)
+            .map(Token::from_nano)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpendFeeEstimate;
+
+    use sn_dbc::Token;
+
+    fn tokens(amounts: &[u64]) -> Vec<Token> {
+        amounts.iter().copied().map(Token::from_nano).collect()
+    }
+
+    #[test]
+    fn no_fees_gives_no_estimate() {
+        assert_eq!(SpendFeeEstimate::from_fees(vec![]), None);
+    }
+
+    #[test]
+    fn estimate_with_odd_number_of_fees() {
+        let estimate = SpendFeeEstimate::from_fees(tokens(&[30, 10, 20])).expect("some estimate");
+        assert_eq!(estimate.min, Token::from_nano(10));
+        assert_eq!(estimate.median, Token::from_nano(20));
+        assert_eq!(estimate.max, Token::from_nano(30));
+        assert_eq!(estimate.samples, 3);
+    }
+
+    #[test]
+    fn estimate_with_even_number_of_fees() {
+        let estimate = SpendFeeEstimate::from_fees(tokens(&[u64::MAX, 1, u64::MAX - 2, 7]))
+            .expect("some estimate");
+        assert_eq!(estimate.min, Token::from_nano(1));
+        assert_eq!(estimate.median, Token::from_nano(u64::MAX / 2 + 3));
+        assert_eq!(estimate.max, Token::from_nano(u64::MAX));
+        assert_eq!(estimate.median_cost_for(3), None);
+        assert_eq!(
+            SpendFeeEstimate::from_fees(tokens(&[4, 6]))
+                .and_then(|estimate| estimate.median_cost_for(3)),
+            Some(Token::from_nano(15))
+        );
+    }
+}
@@ -6,13 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::RegisterCmd;
+use super::{CmdResponse, RegisterCmd};
 
 use crate::{
     node::NodeId,
     protocol::{
         address::{dbc_address, ChunkAddress, DataAddress},
         chunk::Chunk,
+        error::Error,
         fees::FeeCiphers,
     },
 };
@@ -68,4 +69,14 @@ impl Cmd {
             }
         }
     }
+
+    /// Returns the response to this cmd, carrying the given error.
+    pub fn error(&self, error: Error) -> CmdResponse {
+        match self {
+            Cmd::StoreChunk(_) => CmdResponse::StoreChunk(Err(error)),
+            Cmd::Register(RegisterCmd::Create(_)) => CmdResponse::CreateRegister(Err(error)),
+            Cmd::Register(RegisterCmd::Edit(_)) => CmdResponse::EditRegister(Err(error)),
+            Cmd::SpendDbc { .. } => CmdResponse::Spend(Err(error)),
+        }
+    }
 }
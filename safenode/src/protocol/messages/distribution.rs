@@ -0,0 +1,120 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The most leading bits of a name that records can be bucketed by, i.e. at most 256 buckets.
+pub const MAX_DISTRIBUTION_PREFIX_BITS: u8 = 8;
+
+/// The number of records held, bucketed by the leading bits of their names.
+///
+/// Bucket `i` counts the records whose names start with the `prefix_bits` bits of `i`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordDistribution {
+    /// The number of leading bits the records are bucketed by.
+    pub prefix_bits: u8,
+    /// The number of chunks in each bucket.
+    pub chunks: Vec<u64>,
+    /// The number of registers in each bucket.
+    pub registers: Vec<u64>,
+}
+
+impl RecordDistribution {
+    /// An empty distribution with `2^prefix_bits` buckets.
+    /// The prefix is capped at [`MAX_DISTRIBUTION_PREFIX_BITS`].
+    pub fn new(prefix_bits: u8) -> Self {
+        let prefix_bits = prefix_bits.min(MAX_DISTRIBUTION_PREFIX_BITS);
+        let buckets = 1 << prefix_bits;
+        Self {
+            prefix_bits,
+            chunks: vec![0; buckets],
+            registers: vec![0; buckets],
+        }
+    }
+
+    /// The bucket that the given name falls in.
+    pub fn bucket(&self, name: &XorName) -> usize {
+        match self.prefix_bits {
+            0 => 0,
+            bits => (name.0[0] >> (8 - bits)) as usize,
+        }
+    }
+
+    /// A name at the start of the given bucket.
+    pub fn bucket_name(&self, bucket: usize) -> XorName {
+        let mut name = XorName::default();
+        if self.prefix_bits > 0 {
+            name.0[0] = (bucket << (8 - self.prefix_bits)) as u8;
+        }
+        name
+    }
+
+    /// Counts a chunk with the given name.
+    pub fn add_chunk(&mut self, name: &XorName) {
+        let bucket = self.bucket(name);
+        self.chunks[bucket] += 1;
+    }
+
+    /// Counts a register with the given name.
+    pub fn add_register(&mut self, name: &XorName) {
+        let bucket = self.bucket(name);
+        self.registers[bucket] += 1;
+    }
+
+    /// Adds the counts of the other distribution to this one.
+    /// Distributions bucketed by a different prefix are ignored.
+    pub fn merge(&mut self, other: &RecordDistribution) {
+        if other.prefix_bits != self.prefix_bits
+            || other.chunks.len() != self.chunks.len()
+            || other.registers.len() != self.registers.len()
+        {
+            return;
+        }
+        for (count, other) in self.chunks.iter_mut().zip(&other.chunks) {
+            *count += other;
+        }
+        for (count, other) in self.registers.iter_mut().zip(&other.registers) {
+            *count += other;
+        }
+    }
+
+    /// The total number of records held.
+    pub fn total(&self) -> u64 {
+        self.chunks.iter().chain(&self.registers).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_fall_in_the_bucket_of_their_prefix() {
+        let mut distribution = RecordDistribution::new(3);
+        assert_eq!(distribution.chunks.len(), 8);
+
+        let mut name = XorName::default();
+        name.0[0] = 0b1010_0000;
+        assert_eq!(distribution.bucket(&name), 0b101);
+        assert_eq!(distribution.bucket(&distribution.bucket_name(5)), 5);
+
+        distribution.add_chunk(&name);
+        distribution.add_register(&XorName::default());
+        let mut total = RecordDistribution::new(3);
+        total.merge(&distribution);
+        total.merge(&distribution);
+        total.merge(&RecordDistribution::new(2));
+        assert_eq!(total.chunks[5], 2);
+        assert_eq!(total.registers[0], 2);
+        assert_eq!(total.total(), 4);
+
+        assert_eq!(RecordDistribution::new(200).prefix_bits, 8);
+        assert_eq!(RecordDistribution::new(0).bucket(&name), 0);
+    }
+}
@@ -8,6 +8,7 @@
 
 //! Data messages and their possible responses.
 mod cmd;
+mod distribution;
 mod event;
 mod query;
 mod register;
@@ -16,6 +17,7 @@ mod spend;
 
 pub use self::{
     cmd::Cmd,
+    distribution::{RecordDistribution, MAX_DISTRIBUTION_PREFIX_BITS},
     event::Event,
     query::Query,
     register::{
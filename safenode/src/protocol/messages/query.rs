@@ -6,14 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::spend::SpendQuery;
+use super::{spend::SpendQuery, QueryResponse};
 
 use crate::protocol::{
     address::{ChunkAddress, DataAddress},
+    error::Error,
     messages::RegisterQuery,
 };
 
 use serde::{Deserialize, Serialize};
+use xor_name::XorName;
 
 /// Data queries - retrieving data and inspecting their structure.
 ///
@@ -39,6 +41,28 @@ pub enum Query {
     ///
     /// [`Spend`]: super::transfers::SpendQuery.
     Spend(SpendQuery),
+    /// Retrieve the [`RecordDistribution`] of the records held by the node at the given name,
+    /// bucketed by the given number of leading bits of their names.
+    ///
+    /// This should eventually lead to a [`GetRecordDistribution`] response.
+    ///
+    /// [`RecordDistribution`]: super::RecordDistribution
+    /// [`GetRecordDistribution`]: super::QueryResponse::GetRecordDistribution
+    GetRecordDistribution {
+        /// The name of the node asked.
+        node: XorName,
+        /// The number of leading bits to bucket by.
+        prefix_bits: u8,
+    },
+    /// Retrieve the time on the clock of the node at the given name, to check ours against.
+    ///
+    /// This should eventually lead to a [`GetTime`] response.
+    ///
+    /// [`GetTime`]: super::QueryResponse::GetTime
+    GetTime {
+        /// The name of the node asked.
+        node: XorName,
+    },
 }
 
 impl Query {
@@ -48,6 +72,31 @@ impl Query {
             Query::GetChunk(address) => DataAddress::Chunk(*address),
             Query::Register(query) => DataAddress::Register(query.dst()),
             Query::Spend(query) => DataAddress::Spend(query.dst()),
+            // Not a data query, but it is routed to the node like one.
+            Query::GetRecordDistribution { node, .. } | Query::GetTime { node } => {
+                DataAddress::chunk(*node)
+            }
+        }
+    }
+
+    /// Returns the response to this query, carrying the given error.
+    pub fn error(&self, error: Error) -> QueryResponse {
+        match self {
+            Query::GetChunk(_) => QueryResponse::GetChunk(Err(error)),
+            Query::Register(query) => match query {
+                RegisterQuery::Get(_) => QueryResponse::GetRegister(Err(error)),
+                RegisterQuery::Read(_) => QueryResponse::ReadRegister(Err(error)),
+                RegisterQuery::GetEntry { .. } => QueryResponse::GetRegisterEntry(Err(error)),
+                RegisterQuery::GetPolicy(_) => QueryResponse::GetRegisterPolicy(Err(error)),
+                RegisterQuery::GetUserPermissions { .. } => {
+                    QueryResponse::GetRegisterUserPermissions(Err(error))
+                }
+                RegisterQuery::GetOwner(_) => QueryResponse::GetRegisterOwner(Err(error)),
+            },
+            Query::Spend(SpendQuery::GetFees { .. }) => QueryResponse::GetFees(Err(error)),
+            Query::Spend(SpendQuery::GetDbcSpend(_)) => QueryResponse::GetDbcSpend(Err(error)),
+            Query::GetRecordDistribution { .. } => QueryResponse::GetRecordDistribution(Err(error)),
+            Query::GetTime { .. } => QueryResponse::GetTime(Err(error)),
         }
     }
 }
@@ -12,6 +12,7 @@ use crate::{
         chunk::Chunk,
         error::Result,
         fees::RequiredFee,
+        messages::RecordDistribution,
         register::{Entry, EntryHash, Permissions, Policy, Register, User},
     },
 };
@@ -67,6 +68,36 @@ pub enum QueryResponse {
     GetRegisterPolicy(Result<Policy>),
     /// Response to [`RegisterQuery::GetUserPermissions`].
     GetRegisterUserPermissions(Result<Permissions>),
+    //
+    // ===== Node =====
+    //
+    /// Response to [`GetRecordDistribution`].
+    ///
+    /// [`GetRecordDistribution`]: crate::protocol::messages::Query::GetRecordDistribution
+    GetRecordDistribution(Result<RecordDistribution>),
+    /// Response to [`GetTime`], in milliseconds since the unix epoch.
+    ///
+    /// [`GetTime`]: crate::protocol::messages::Query::GetTime
+    GetTime(Result<u64>),
+}
+
+impl QueryResponse {
+    /// Returns true if the query was successful.
+    pub fn is_success(&self) -> bool {
+        match self {
+            Self::GetFees(result) => result.is_ok(),
+            Self::GetDbcSpend(result) => result.is_ok(),
+            Self::GetChunk(result) => result.is_ok(),
+            Self::GetRegister(result) => result.is_ok(),
+            Self::GetRegisterEntry(result) => result.is_ok(),
+            Self::GetRegisterOwner(result) => result.is_ok(),
+            Self::ReadRegister(result) => result.is_ok(),
+            Self::GetRegisterPolicy(result) => result.is_ok(),
+            Self::GetRegisterUserPermissions(result) => result.is_ok(),
+            Self::GetRecordDistribution(result) => result.is_ok(),
+            Self::GetTime(result) => result.is_ok(),
+        }
+    }
 }
 
 /// The response to a Cmd, containing the query result.
@@ -90,3 +121,15 @@ pub enum CmdResponse {
     /// Response to RegisterCmd::Edit.
     EditRegister(Result<()>),
 }
+
+impl CmdResponse {
+    /// Returns true if the cmd was successful.
+    pub fn is_success(&self) -> bool {
+        match self {
+            Self::Spend(result) => result.is_ok(),
+            Self::StoreChunk(result) => result.is_ok(),
+            Self::CreateRegister(result) => result.is_ok(),
+            Self::EditRegister(result) => result.is_ok(),
+        }
+    }
+}
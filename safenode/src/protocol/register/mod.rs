@@ -37,6 +37,35 @@ const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
 /// Maximum number of entries of a register.
 const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
+/// A summary of the state of a Register, for inspecting it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterInfo {
+    /// The address of the Register.
+    pub address: RegisterAddress,
+    /// The owner of the Register.
+    pub owner: User,
+    /// The permissions of users other than the owner.
+    pub permissions: BTreeMap<User, Permissions>,
+    /// The number of entries in the Register.
+    pub size: u64,
+    /// The current entries, i.e. the heads of each branch.
+    /// There is more than one when entries were written concurrently.
+    pub heads: BTreeSet<EntryHash>,
+    /// Every entry of the Register, latest first.
+    pub entries: Vec<EntryInfo>,
+}
+
+/// The metadata of an entry of a Register.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryInfo {
+    /// The hash of the entry.
+    pub hash: EntryHash,
+    /// The size of the entry, in bytes.
+    pub size: usize,
+    /// The entries this entry was written atop.
+    pub children: BTreeSet<EntryHash>,
+}
+
 /// Register mutation operation to apply to Register.
 pub type RegisterOp<T> = CrdtOperation<T>;
 
@@ -122,6 +151,29 @@ impl Register {
         &self.policy
     }
 
+    /// Return a summary of the state of the Register, with the metadata of all its entries.
+    pub fn info(&self) -> RegisterInfo {
+        let heads = self.read().into_iter().map(|(hash, _)| hash).collect();
+        let entries = self
+            .crdt
+            .history()
+            .into_iter()
+            .map(|(hash, entry, children)| EntryInfo {
+                hash,
+                size: entry.len(),
+                children,
+            })
+            .collect();
+        RegisterInfo {
+            address: *self.address(),
+            owner: self.owner(),
+            permissions: self.policy.permissions.clone(),
+            size: self.size(),
+            heads,
+            entries,
+        }
+    }
+
     /// Write an entry to the Register, returning the generated unsigned
     /// CRDT operation so the caller can sign and broadcast it to other replicas,
     /// along with the hash of the entry just written.
@@ -263,6 +315,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_info_shows_branches_and_history() -> Result<()> {
+        let authority = User::Key(SecretKey::random().public_key());
+        let mut register = Register::new_owned(authority, xor_name::rand::random(), 43_000);
+
+        let (root, _) = register.write(vec![1], BTreeSet::new())?;
+        let (left, _) = register.write(vec![2, 2], BTreeSet::from([root]))?;
+        let (right, _) = register.write(vec![3, 3, 3], BTreeSet::from([root]))?;
+
+        let info = register.info();
+        assert_eq!(info.owner, authority);
+        assert_eq!(info.size, 3);
+        assert_eq!(info.heads, BTreeSet::from([left, right]));
+        assert_eq!(info.entries.len(), 3);
+
+        let root_info = info
+            .entries
+            .iter()
+            .find(|entry| entry.hash == root)
+            .expect("The root entry to be in the history.");
+        assert_eq!(root_info.size, 1);
+        assert!(root_info.children.is_empty());
+        assert!(info
+            .entries
+            .iter()
+            .filter(|entry| entry.hash != root)
+            .all(|entry| entry.children == BTreeSet::from([root])));
+
+        Ok(())
+    }
+
     #[test]
     fn register_concurrent_write_ops() -> Result<()> {
         let authority_sk1 = SecretKey::random();
@@ -151,6 +151,25 @@ impl RegisterCrdt {
         self.data.node(hash.0).map(|node| &node.value)
     }
 
+    /// Returns every entry reachable from the current ones, latest first,
+    /// along with the hashes of the entries each of them was written atop.
+    pub(crate) fn history(&self) -> Vec<(EntryHash, &Entry, BTreeSet<EntryHash>)> {
+        let mut pending: Vec<_> = self.data.read().hashes().into_iter().collect();
+        let mut visited = BTreeSet::new();
+        let mut history = vec![];
+        while let Some(hash) = pending.pop() {
+            if !visited.insert(hash) {
+                continue;
+            }
+            if let Some(node) = self.data.node(hash) {
+                pending.extend(node.children.iter().copied());
+                let children = node.children.iter().copied().map(EntryHash).collect();
+                history.push((EntryHash(hash), &node.value, children));
+            }
+        }
+        history
+    }
+
     /// Read current entries (multiple entries occur on concurrent writes).
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
@@ -36,9 +36,11 @@ pub(crate) use self::offline::create_transfer as create_offline_transfer;
 
 pub(crate) use self::{
     error::{Error, Result},
-    online::create_transfer as create_online_transfer,
+    online::{create_transfer as create_online_transfer, estimate_spend_fees},
 };
 
+use super::fees::RequiredFee;
+
 use sn_dbc::{Dbc, DbcIdSource, DerivedKey, PublicAddress, RevealedAmount, Token};
 
 /// The input details necessary to
@@ -52,6 +54,8 @@ pub struct Inputs {
     pub recipients: Vec<(Token, DbcIdSource)>,
     /// Any surplus amount after spending the necessary input dbcs.
     pub change: (Token, PublicAddress),
+    /// The fees quoted by the nodes, that are paid for spending the input dbcs.
+    pub fees: Vec<RequiredFee>,
 }
 
 /// The created dbcs and change dbc from a transfer
@@ -64,6 +68,9 @@ pub struct Outputs {
     /// The dbc holding surplus tokens after
     /// spending the necessary input dbcs.
     pub change_dbc: Option<Dbc>,
+    /// The fees quoted by the nodes, that were paid
+    /// for spending the input dbcs.
+    pub fees_paid: Vec<RequiredFee>,
 }
 
 /// A resulting dbc from a token transfer.
@@ -106,6 +106,7 @@ fn select_inputs(
         dbcs_to_spend,
         recipients,
         change: (change_amount, change_to),
+        fees: vec![],
     })
 }
 
@@ -130,6 +131,7 @@ fn transfer(send_inputs: Inputs) -> Result<Outputs> {
         dbcs_to_spend,
         recipients,
         change: (change, change_to),
+        fees: fees_paid,
     } = send_inputs;
 
     let mut inputs = vec![];
@@ -187,5 +189,6 @@ fn transfer(send_inputs: Inputs) -> Result<Outputs> {
     Ok(Outputs {
         created_dbcs,
         change_dbc,
+        fees_paid,
     })
 }
@@ -13,7 +13,7 @@ use crate::{
     network::close_group_majority,
     node::NodeId,
     protocol::{
-        fees::{RequiredFee, SpendPriority},
+        fees::{RequiredFee, SpendFeeEstimate, SpendPriority},
         messages::{Query, QueryResponse, Request, Response, SpendQuery},
     },
 };
@@ -51,6 +51,41 @@ pub(crate) async fn create_transfer(
     create_transfer_with(selected_inputs)
 }
 
+/// Queries the close groups of the given dbcs for the fees currently required to spend them,
+/// and aggregates the quotes into an estimate of the cost of spending a single input.
+///
+/// Nothing is spent, and the quoted fees are not reserved in any way,
+/// so the fee paid by a subsequent transfer may differ from the estimate.
+#[allow(clippy::result_large_err)]
+pub(crate) async fn estimate_spend_fees(
+    dbcs: Vec<(Dbc, DerivedKey)>,
+    client: &Client,
+) -> Result<SpendFeeEstimate> {
+    let mut fees_per_input = vec![];
+
+    for (dbc, derived_key) in dbcs {
+        let dbc_id = dbc.id();
+        // The quoted fees are kept, so that spending the dbc shortly after does not quote them again.
+        let node_fees = match get_decrypted_fees(dbc_id, &derived_key, client).await {
+            Some(fees) => fees,
+            None => continue,
+        };
+
+        let fee_per_input = node_fees
+            .iter()
+            .try_fold(Token::zero(), |total, (_, fee)| total.checked_add(*fee));
+
+        match fee_per_input {
+            Some(fee) => fees_per_input.push(fee),
+            None => warn!("Overflow occurred while summing the fees quoted for {dbc_id:?}"),
+        }
+    }
+
+    SpendFeeEstimate::from_fees(fees_per_input).ok_or_else(|| {
+        Error::CouldNotGetFees("No fee quotes could be obtained for any of the dbcs.".to_string())
+    })
+}
+
 /// Select the necessary number of dbcs from those that we were passed.
 #[allow(clippy::result_large_err)]
 async fn select_inputs(
@@ -80,6 +115,7 @@ async fn select_inputs(
     let mut change_amount = total_output_amount;
     let mut all_fee_cipher_params = BTreeMap::new();
     let mut fees_paid = Token::zero();
+    let mut fees = vec![];
 
     for (dbc, derived_key) in available_dbcs {
         let dbc_id = dbc.id();
@@ -96,41 +132,10 @@ async fn select_inputs(
         #[cfg(not(feature = "data-network"))]
         let fee_per_input = {
             // Each section will have CLOSE_GROUP_SIZE instances to pay individually.
-            let node_fees = match get_fees(dbc_id, client).await {
-                Ok(fees) => fees,
-                Err(error) => {
-                    error!("Could not get fees for input dbc: {dbc_id:?}: {error}");
-                    continue;
-                }
+            let decrypted_node_fees = match get_decrypted_fees(dbc_id, &derived_key, client).await {
+                Some(fees) => fees,
+                None => continue,
             };
-            let num_responses = node_fees.len();
-            let required_responses = close_group_majority();
-            if required_responses > num_responses {
-                warn!("Not enough nodes contacted for the section to spend the input. Got: {num_responses}, needed: {required_responses}");
-                continue;
-            }
-
-            // Fees that were not encrypted to us.
-            let mut invalid_fees = BTreeSet::new();
-            // As the nodes encrypt the amount to the dbc id to be spent, we need to decrypt it.
-            let mut decrypted_node_fees = vec![];
-
-            for (node_id, fee) in node_fees {
-                match fee.content.decrypt_amount(&derived_key) {
-                    Ok(amount) => decrypted_node_fees.push(((node_id, fee), amount)),
-                    Err(error) => {
-                        error!("Decrypting the fee content from {node_id:?} failed! {error}");
-                        let _ = invalid_fees.insert(fee.content.reward_address);
-                    }
-                }
-            }
-
-            let max_invalid_fees = num_responses - required_responses;
-            if invalid_fees.len() > max_invalid_fees {
-                let valid_responses = num_responses - invalid_fees.len();
-                warn!("Not enough valid fees received from nodes to spend the input. Found: {valid_responses}, needed: {required_responses}", );
-                continue;
-            }
 
             // Total fee paid to all recipients in the section for this input.
             let fee_per_input = decrypted_node_fees
@@ -154,6 +159,7 @@ async fn select_inputs(
                         .reward_address
                         .random_dbc_id_src(&mut rand::thread_rng());
                     recipients.push((*fee, dbc_id_src));
+                    fees.push(required_fee.clone());
                     let _ = fee_cipher_params.insert(*node_id, (required_fee.clone(), dbc_id_src));
                 });
 
@@ -224,6 +230,7 @@ async fn select_inputs(
         dbcs_to_spend,
         recipients,
         change: (change_amount, change_to),
+        fees,
     })
 }
 
@@ -248,6 +255,7 @@ fn create_transfer_with(selected_inputs: Inputs) -> Result<Outputs> {
         dbcs_to_spend,
         recipients,
         change: (change, change_to),
+        fees: fees_paid,
     } = selected_inputs;
 
     let mut inputs = vec![];
@@ -305,9 +313,75 @@ fn create_transfer_with(selected_inputs: Inputs) -> Result<Outputs> {
     Ok(Outputs {
         created_dbcs,
         change_dbc,
+        fees_paid,
     })
 }
 
+/// Gets the fees for spending the dbc, and decrypts them, if enough nodes quoted a valid fee.
+/// Fees quoted shortly before are reused, but if they are rejected, the fees are quoted anew.
+async fn get_decrypted_fees(
+    dbc_id: DbcId,
+    derived_key: &DerivedKey,
+    client: &Client,
+) -> Option<Vec<((NodeId, RequiredFee), Token)>> {
+    if let Some(node_fees) = client.fee_cache().get(&dbc_id).await {
+        trace!("Reusing the fees quoted for {dbc_id:?}");
+        if let Some(fees) = decrypt_fees(node_fees, derived_key) {
+            return Some(fees);
+        }
+        warn!("The reused fees for {dbc_id:?} were rejected, quoting them anew.");
+        client.fee_cache().remove(&dbc_id).await;
+    }
+
+    let node_fees = match get_fees(dbc_id, client).await {
+        Ok(fees) => fees,
+        Err(error) => {
+            error!("Could not get fees for input dbc: {dbc_id:?}: {error}");
+            return None;
+        }
+    };
+    let fees = decrypt_fees(node_fees.clone(), derived_key)?;
+    client.fee_cache().insert(dbc_id, node_fees).await;
+    Some(fees)
+}
+
+/// As the nodes encrypt the amount to the dbc id to be spent, we need to decrypt it.
+/// Returns None if not enough nodes quoted a fee that we could decrypt.
+fn decrypt_fees(
+    node_fees: BTreeMap<NodeId, RequiredFee>,
+    derived_key: &DerivedKey,
+) -> Option<Vec<((NodeId, RequiredFee), Token)>> {
+    let num_responses = node_fees.len();
+    let required_responses = close_group_majority();
+    if required_responses > num_responses {
+        warn!("Not enough nodes contacted for the section to spend the input. Got: {num_responses}, needed: {required_responses}");
+        return None;
+    }
+
+    // Fees that were not encrypted to us.
+    let mut invalid_fees = BTreeSet::new();
+    let mut decrypted_node_fees = vec![];
+
+    for (node_id, fee) in node_fees {
+        match fee.content.decrypt_amount(derived_key) {
+            Ok(amount) => decrypted_node_fees.push(((node_id, fee), amount)),
+            Err(error) => {
+                error!("Decrypting the fee content from {node_id:?} failed! {error}");
+                let _ = invalid_fees.insert(fee.content.reward_address);
+            }
+        }
+    }
+
+    let max_invalid_fees = num_responses - required_responses;
+    if invalid_fees.len() > max_invalid_fees {
+        let valid_responses = num_responses - invalid_fees.len();
+        warn!("Not enough valid fees received from nodes to spend the input. Found: {valid_responses}, needed: {required_responses}", );
+        return None;
+    }
+
+    Some(decrypted_node_fees)
+}
+
 async fn get_fees(dbc_id: DbcId, client: &Client) -> Result<BTreeMap<NodeId, RequiredFee>> {
     let request = Request::Query(Query::Spend(SpendQuery::GetFees {
         dbc_id,
@@ -0,0 +1,314 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    keys::{bls_secret_from_hex, get_main_key, remove_main_key, store_new_keypair},
+};
+
+use sn_dbc::MainKey;
+
+use argon2::Argon2;
+use async_trait::async_trait;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hex::encode;
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Filename for storing the main key encrypted with a passphrase.
+const ENCRYPTED_MAIN_KEY_FILENAME: &str = "main_key.enc";
+/// Name of the service the main key is filed under in the OS keyring.
+const KEYRING_SERVICE: &str = "safe-network";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Somewhere the main key of a wallet can be kept.
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    /// Returns the stored key, or None if there is none.
+    async fn load(&self) -> Result<Option<MainKey>>;
+
+    /// Stores the key, replacing any key already stored.
+    async fn store(&self, main_key: &MainKey) -> Result<()>;
+
+    /// Removes the stored key, if any.
+    async fn remove(&self) -> Result<()>;
+
+    /// The kind of the store, and the wallet dir it keeps the key of.
+    /// Two stores at the same location keep the very same key.
+    fn location(&self) -> (&'static str, &Path);
+}
+
+/// Keeps the main key hex-encoded in a plain file in the wallet dir.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    root_dir: PathBuf,
+}
+
+impl FileStore {
+    /// A store for the key of the wallet in `root_dir`.
+    pub fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialStore for FileStore {
+    async fn load(&self) -> Result<Option<MainKey>> {
+        get_main_key(&self.root_dir).await
+    }
+
+    async fn store(&self, main_key: &MainKey) -> Result<()> {
+        store_new_keypair(&self.root_dir, main_key).await
+    }
+
+    async fn remove(&self) -> Result<()> {
+        remove_main_key(&self.root_dir).await
+    }
+
+    fn location(&self) -> (&'static str, &Path) {
+        ("file", &self.root_dir)
+    }
+}
+
+/// Keeps the main key in a file in the wallet dir, encrypted with a key derived from a passphrase.
+#[derive(Clone)]
+pub struct EncryptedFileStore {
+    root_dir: PathBuf,
+    passphrase: String,
+}
+
+impl EncryptedFileStore {
+    /// A store for the key of the wallet in `root_dir`, encrypted with `passphrase`.
+    pub fn new(root_dir: &Path, passphrase: String) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            passphrase,
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialStore for EncryptedFileStore {
+    async fn load(&self) -> Result<Option<MainKey>> {
+        let path = self.root_dir.join(ENCRYPTED_MAIN_KEY_FILENAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&path).await?;
+        let secret_hex = decrypt_with_passphrase(&self.passphrase, &bytes)?;
+
+        Ok(Some(MainKey::new(bls_secret_from_hex(secret_hex)?)))
+    }
+
+    async fn store(&self, main_key: &MainKey) -> Result<()> {
+        let bytes =
+            encrypt_with_passphrase(&self.passphrase, encode(main_key.to_bytes()).as_bytes())?;
+        fs::write(self.root_dir.join(ENCRYPTED_MAIN_KEY_FILENAME), bytes).await?;
+        Ok(())
+    }
+
+    async fn remove(&self) -> Result<()> {
+        let path = self.root_dir.join(ENCRYPTED_MAIN_KEY_FILENAME);
+        if path.is_file() {
+            fs::remove_file(path).await?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> (&'static str, &Path) {
+        ("encrypted-file", &self.root_dir)
+    }
+}
+
+/// Encrypts the plaintext with a key derived from the passphrase.
+/// The returned bytes start with the salt and nonce that were used.
+pub(crate) fn encrypt_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    {
+        let mut rng = rand::thread_rng();
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+    }
+
+    let ciphertext = passphrase_cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| Error::FailedToEncryptKey)?;
+
+    let mut bytes = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    bytes.extend_from_slice(&salt);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend(ciphertext);
+    Ok(bytes)
+}
+
+/// Decrypts bytes encrypted by [`encrypt_with_passphrase`] with the same passphrase.
+pub(crate) fn decrypt_with_passphrase(passphrase: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() < SALT_LEN + NONCE_LEN {
+        return Err(Error::FailedToDecryptKey);
+    }
+    let (salt, rest) = bytes.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    passphrase_cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::FailedToDecryptKey)
+}
+
+fn passphrase_cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| Error::FailedToDeriveKey(err.to_string()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Keeps the main key in the credential store of the OS
+/// (Secret Service on Linux, Keychain on macOS, Credential Manager on Windows).
+#[derive(Clone, Debug)]
+pub struct KeyringStore {
+    root_dir: PathBuf,
+    user: String,
+}
+
+impl KeyringStore {
+    /// A store for the key of the wallet in `root_dir`.
+    /// Each wallet dir gets its own entry in the keyring.
+    pub fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            user: root_dir.display().to_string(),
+        }
+    }
+
+    fn entry(&self) -> Result<keyring::Entry> {
+        Ok(keyring::Entry::new(KEYRING_SERVICE, &self.user)?)
+    }
+}
+
+#[async_trait]
+impl CredentialStore for KeyringStore {
+    async fn load(&self) -> Result<Option<MainKey>> {
+        match self.entry()?.get_password() {
+            Ok(secret_hex) => Ok(Some(MainKey::new(bls_secret_from_hex(secret_hex)?))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn store(&self, main_key: &MainKey) -> Result<()> {
+        Ok(self.entry()?.set_password(&encode(main_key.to_bytes()))?)
+    }
+
+    async fn remove(&self) -> Result<()> {
+        match self.entry()?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn location(&self) -> (&'static str, &Path) {
+        ("keyring", &self.root_dir)
+    }
+}
+
+/// Moves the main key from one store to another.
+///
+/// The key is only removed from the old store once it can be read back from the new one.
+/// Migrating to the store the key is in is refused, as removing it would lose the key.
+pub async fn migrate_main_key(from: &dyn CredentialStore, to: &dyn CredentialStore) -> Result<()> {
+    if from.location() == to.location() {
+        return Err(Error::SameKeyStore);
+    }
+    let main_key = from.load().await?.ok_or(Error::NoKeyToMigrate)?;
+    to.store(&main_key).await?;
+
+    match to.load().await? {
+        Some(stored) if stored.public_address() == main_key.public_address() => from.remove().await,
+        _ => Err(Error::FailedToMigrateKey),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use eyre::{eyre, Result};
+    use tempfile::{tempdir, TempDir};
+
+    #[tokio::test]
+    async fn encrypted_key_to_and_from_file() -> Result<()> {
+        let dir = create_temp_dir()?;
+        let store = EncryptedFileStore::new(dir.path(), "correct horse".to_string());
+        assert!(store.load().await?.is_none());
+
+        let main_key = MainKey::random();
+        store.store(&main_key).await?;
+        let loaded = store.load().await?.expect("There to be a key on disk.");
+        assert_eq!(loaded.public_address(), main_key.public_address());
+
+        let wrong_passphrase = EncryptedFileStore::new(dir.path(), "battery staple".to_string());
+        assert!(matches!(
+            wrong_passphrase.load().await,
+            Err(Error::FailedToDecryptKey)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrating_moves_the_key() -> Result<()> {
+        let dir = create_temp_dir()?;
+        let plain = FileStore::new(dir.path());
+        let encrypted = EncryptedFileStore::new(dir.path(), "passphrase".to_string());
+
+        let main_key = MainKey::random();
+        plain.store(&main_key).await?;
+        migrate_main_key(&plain, &encrypted).await?;
+
+        assert!(plain.load().await?.is_none());
+        let migrated = encrypted.load().await?.expect("There to be a key on disk.");
+        assert_eq!(migrated.public_address(), main_key.public_address());
+
+        // There is nothing left to migrate.
+        assert!(matches!(
+            migrate_main_key(&plain, &encrypted).await,
+            Err(Error::NoKeyToMigrate)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrating_to_the_same_store_keeps_the_key() -> Result<()> {
+        let dir = create_temp_dir()?;
+        let from = FileStore::new(dir.path());
+        let to = FileStore::new(dir.path());
+
+        let main_key = MainKey::random();
+        from.store(&main_key).await?;
+        assert!(matches!(
+            migrate_main_key(&from, &to).await,
+            Err(Error::SameKeyStore)
+        ));
+
+        let kept = from.load().await?.expect("There to be a key on disk.");
+        assert_eq!(kept.public_address(), main_key.public_address());
+        Ok(())
+    }
+
+    fn create_temp_dir() -> Result<TempDir> {
+        tempdir().map_err(|e| eyre!("Failed to create temp dir: {}", e))
+    }
+}
@@ -20,6 +20,15 @@ pub enum Error {
     /// A general error when a transfer fails.
     #[error("Failed to send tokens due to {0}")]
     CouldNotSendTokens(String),
+    /// Failed to get the state of a dbc from the network.
+    #[error("Failed to get the spend status due to {0}")]
+    CouldNotGetSpendStatus(String),
+    /// The signature of a payment receipt, or of a quote in it, is invalid.
+    #[error("The payment receipt signature is invalid.")]
+    ReceiptSignatureInvalid,
+    /// A detached signature did not verify against the payload and public address.
+    #[error("The signature is invalid.")]
+    InvalidSignature,
     /// Failed to parse bytes into a bls key.
     #[error("Failed to parse bls key")]
     FailedToParseBlsKey,
@@ -29,6 +38,33 @@ pub enum Error {
     /// Failed to serialize a main key to hex.
     #[error("Could not serialize main key to hex: {0}")]
     FailedToHexEncodeKey(String),
+    /// Failed to derive an encryption key from the passphrase.
+    #[error("Could not derive a key from the passphrase: {0}")]
+    FailedToDeriveKey(String),
+    /// Failed to encrypt the main key.
+    #[error("Could not encrypt main key.")]
+    FailedToEncryptKey,
+    /// Failed to decrypt the main key, either the passphrase is wrong or the file is corrupted.
+    #[error("Could not decrypt main key, the passphrase may be wrong.")]
+    FailedToDecryptKey,
+    /// A wallet export could not be read.
+    #[error("Invalid wallet export: {0}")]
+    InvalidWalletExport(String),
+    /// The wallet to import into has another key than the imported one.
+    #[error("There is a wallet with another key already.")]
+    WalletKeyMismatch,
+    /// There was no main key in the store to migrate from.
+    #[error("There is no main key to migrate.")]
+    NoKeyToMigrate,
+    /// The main key could not be read back from the store it was migrated to.
+    #[error("The migrated main key could not be read back, the old one has been kept.")]
+    FailedToMigrateKey,
+    /// The main key was to be migrated to the store it is in.
+    #[error("The main key is already in that store, there is nothing to migrate.")]
+    SameKeyStore,
+    /// OS keyring error.
+    #[error("Keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
     /// Bls error.
     #[error("Bls error: {0}")]
     Bls(#[from] bls::error::Error),
@@ -0,0 +1,221 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    decrypt_with_passphrase, encrypt_with_passphrase,
+    error::{Error, Result},
+    keys::bls_secret_from_hex,
+    wallet_file::{get_wallet, store_wallet},
+    CredentialStore, KeyLessWallet, LocalWallet, PaymentReceipt, Wallet,
+};
+
+use crate::protocol::transfers::CreatedDbc;
+
+use sn_dbc::{Dbc, MainKey};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// The format of wallet exports, to tell them apart from any other json.
+const WALLET_EXPORT_FORMAT: &str = "safe-wallet-export";
+// The version of the format of wallet exports.
+const WALLET_EXPORT_VERSION: u16 = 1;
+
+/// A wallet, exported to be imported on another machine, or kept as a cold backup.
+///
+/// It is json, with everything but the address and time of export encrypted with a key
+/// derived from a passphrase, with argon2, and then chacha20poly1305.
+#[derive(Debug, Serialize, Deserialize)]
+struct WalletExport {
+    format: String,
+    version: u16,
+    /// The hex encoded public address of the wallet.
+    address: String,
+    /// When the wallet was exported, in seconds since the unix epoch.
+    exported_at: u64,
+    /// The hex encoded salt, nonce and ciphertext of the json serialised [`ExportedWallet`].
+    encrypted: String,
+}
+
+// What is encrypted in a wallet export.
+#[derive(Serialize, Deserialize)]
+struct ExportedWallet {
+    /// The hex encoded main key, which the keys of all the dbcs of the wallet derive from.
+    main_key: String,
+    available_dbcs: Vec<Dbc>,
+    spent_dbcs: Vec<Dbc>,
+    dbcs_created_for_others: Vec<CreatedDbc>,
+    receipts: Vec<PaymentReceipt>,
+}
+
+impl LocalWallet {
+    /// Exports the wallet, with its key and history, as json encrypted with the passphrase.
+    /// See [`LocalWallet::import`] for how to import it again.
+    pub fn export(&self, passphrase: &str) -> Result<String> {
+        let exported = ExportedWallet {
+            main_key: hex::encode(self.key.to_bytes()),
+            available_dbcs: self.wallet.available_dbcs.values().cloned().collect(),
+            spent_dbcs: self.wallet.spent_dbcs.values().cloned().collect(),
+            dbcs_created_for_others: self.wallet.dbcs_created_for_others.clone(),
+            receipts: self.wallet.receipts.clone(),
+        };
+        let plaintext = serde_json::to_vec(&exported).map_err(invalid_export)?;
+        let export = WalletExport {
+            format: WALLET_EXPORT_FORMAT.to_string(),
+            version: WALLET_EXPORT_VERSION,
+            address: hex::encode(self.address().to_bytes()),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            encrypted: hex::encode(encrypt_with_passphrase(passphrase, &plaintext)?),
+        };
+        serde_json::to_string_pretty(&export).map_err(invalid_export)
+    }
+
+    /// Imports an exported wallet into the wallet in `root_dir`, keeping its key in the
+    /// given store. The dbcs and history of the export are merged into those of the wallet
+    /// there, if any, which must then have the same key.
+    pub async fn import(
+        root_dir: &Path,
+        key_store: &dyn CredentialStore,
+        export: &str,
+        passphrase: &str,
+    ) -> Result<Self> {
+        let export: WalletExport = serde_json::from_str(export).map_err(invalid_export)?;
+        if export.format != WALLET_EXPORT_FORMAT {
+            return Err(Error::InvalidWalletExport(format!(
+                "the format is {:?}",
+                export.format
+            )));
+        }
+        if export.version != WALLET_EXPORT_VERSION {
+            return Err(Error::InvalidWalletExport(format!(
+                "version {} is not supported, only version {WALLET_EXPORT_VERSION} is",
+                export.version
+            )));
+        }
+        let encrypted = hex::decode(&export.encrypted).map_err(invalid_export)?;
+        let plaintext = decrypt_with_passphrase(passphrase, &encrypted)?;
+        let exported: ExportedWallet =
+            serde_json::from_slice(&plaintext).map_err(invalid_export)?;
+        let key = MainKey::new(bls_secret_from_hex(&exported.main_key)?);
+
+        match key_store.load().await? {
+            Some(existing) if existing.public_address() != key.public_address() => {
+                return Err(Error::WalletKeyMismatch)
+            }
+            Some(_) => {}
+            None => key_store.store(&key).await?,
+        }
+        let mut wallet = get_wallet(root_dir)
+            .await?
+            .unwrap_or_else(KeyLessWallet::new);
+        wallet.merge(exported, &key);
+        store_wallet(root_dir, &wallet).await?;
+        info!(
+            "Imported the wallet {}, exported at {}",
+            export.address, export.exported_at
+        );
+
+        Ok(Self {
+            key,
+            wallet,
+            root_dir: root_dir.to_path_buf(),
+        })
+    }
+}
+
+impl KeyLessWallet {
+    // Adds the dbcs and history of the exported wallet to ours.
+    fn merge(&mut self, exported: ExportedWallet, key: &MainKey) {
+        for dbc in exported.spent_dbcs {
+            let id = dbc.id();
+            let _ = self.available_dbcs.remove(&id);
+            let _ = self.spent_dbcs.insert(id, dbc);
+        }
+        for created in exported.dbcs_created_for_others {
+            let known = self
+                .dbcs_created_for_others
+                .iter()
+                .any(|ours| ours.dbc.id() == created.dbc.id());
+            if !known {
+                self.dbcs_created_for_others.push(created);
+            }
+        }
+        for receipt in exported.receipts {
+            if !self.receipts.contains(&receipt) {
+                self.receipts.push(receipt);
+            }
+        }
+        // Updates the balance, skipping the dbcs spent by either wallet.
+        self.deposit(exported.available_dbcs, key);
+    }
+}
+
+fn invalid_export(err: impl std::fmt::Display) -> Error {
+    Error::InvalidWalletExport(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{
+        dbc_genesis::{create_genesis_dbc, GENESIS_DBC_AMOUNT},
+        wallet::{DepositWallet, FileStore},
+    };
+
+    use eyre::Result;
+
+    #[tokio::test]
+    async fn exported_wallet_imports_with_the_passphrase_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut wallet = LocalWallet::load_from(dir.path()).await?;
+        let genesis = create_genesis_dbc(&wallet.key)?;
+        wallet.deposit(vec![genesis]);
+        let export = wallet.export("correct horse")?;
+        assert!(export.contains(&hex::encode(wallet.address().to_bytes())));
+
+        let other_dir = tempfile::tempdir()?;
+        let key_store = FileStore::new(other_dir.path());
+        assert!(matches!(
+            LocalWallet::import(other_dir.path(), &key_store, &export, "wrong").await,
+            Err(Error::FailedToDecryptKey)
+        ));
+        let imported =
+            LocalWallet::import(other_dir.path(), &key_store, &export, "correct horse").await?;
+        assert_eq!(imported.address(), wallet.address());
+        assert_eq!(imported.balance().as_nano(), GENESIS_DBC_AMOUNT);
+
+        // Importing it again changes nothing, and so does loading it.
+        let again =
+            LocalWallet::import(other_dir.path(), &key_store, &export, "correct horse").await?;
+        assert_eq!(again.balance().as_nano(), GENESIS_DBC_AMOUNT);
+        let loaded = LocalWallet::load_from(other_dir.path()).await?;
+        assert_eq!(loaded.balance().as_nano(), GENESIS_DBC_AMOUNT);
+
+        // A wallet with another key is not overwritten.
+        let third_dir = tempfile::tempdir()?;
+        let _ = LocalWallet::load_from(third_dir.path()).await?;
+        assert!(matches!(
+            LocalWallet::import(
+                third_dir.path(),
+                &FileStore::new(third_dir.path()),
+                &export,
+                "correct horse"
+            )
+            .await,
+            Err(Error::WalletKeyMismatch)
+        ));
+        Ok(())
+    }
+}
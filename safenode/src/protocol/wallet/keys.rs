@@ -8,7 +8,7 @@
 
 use super::error::{Error, Result};
 
-use sn_dbc::MainKey;
+use sn_dbc::{MainKey, PublicAddress, Signature};
 
 use hex::{decode, encode};
 use std::path::Path;
@@ -46,9 +46,18 @@ pub(super) async fn get_main_key(root_dir: &Path) -> Result<Option<MainKey>> {
     Ok(Some(MainKey::new(secret)))
 }
 
+/// Removes the main key file, if any. The public address is kept.
+pub(super) async fn remove_main_key(root_dir: &Path) -> Result<()> {
+    let path = root_dir.join(MAIN_KEY_FILENAME);
+    if path.is_file() {
+        fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
 /// Construct a BLS secret key from a hex-encoded string.
 #[allow(clippy::result_large_err)]
-fn bls_secret_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::SecretKey> {
+pub(super) fn bls_secret_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::SecretKey> {
     let bytes = decode(hex).map_err(|_| Error::FailedToDecodeHexToKey)?;
     let bytes_fixed_len: [u8; bls::SK_SIZE] = bytes
         .as_slice()
@@ -58,9 +67,44 @@ fn bls_secret_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::SecretKey> {
     Ok(sk)
 }
 
+/// Verifies a detached signature over the payload, as made by [`LocalWallet::sign`].
+///
+/// The signature is the hex encoding of the 96 bytes of a BLS signature, and the
+/// public address the hex encoding of the 48 bytes of a BLS public key.
+/// Surrounding whitespace, such as a trailing newline, is ignored in both.
+///
+/// [`LocalWallet::sign`]: super::LocalWallet::sign
+#[allow(clippy::result_large_err)]
+pub fn verify_detached(
+    payload: &[u8],
+    signature_hex: &str,
+    public_address_hex: &str,
+) -> Result<()> {
+    let signature = decode(signature_hex.trim()).map_err(|_| Error::FailedToDecodeHexToKey)?;
+    let signature: [u8; bls::SIG_SIZE] = signature
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::FailedToParseBlsKey)?;
+    let signature = Signature::from_bytes(signature)?;
+
+    let public_key =
+        decode(public_address_hex.trim()).map_err(|_| Error::FailedToDecodeHexToKey)?;
+    let public_key: [u8; bls::PK_SIZE] = public_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::FailedToParseBlsKey)?;
+    let public_address = PublicAddress::new(bls::PublicKey::from_bytes(public_key)?);
+
+    if public_address.verify(&signature, payload) {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{get_main_key, store_new_keypair, MainKey};
+    use super::{get_main_key, store_new_keypair, verify_detached, Error, MainKey};
 
     use eyre::{eyre, Result};
     use tempfile::{tempdir, TempDir};
@@ -78,6 +122,25 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn detached_signature_verifies_only_for_its_payload_and_key() -> Result<()> {
+        let main_key = MainKey::random();
+        let signature = hex::encode(main_key.sign(b"manifest").to_bytes());
+        let public_address = hex::encode(main_key.public_address().to_bytes());
+
+        verify_detached(b"manifest", &format!("{signature}\n"), &public_address)?;
+        assert!(matches!(
+            verify_detached(b"tampered", &signature, &public_address),
+            Err(Error::InvalidSignature)
+        ));
+        let other_address = hex::encode(MainKey::random().public_address().to_bytes());
+        assert!(matches!(
+            verify_detached(b"manifest", &signature, &other_address),
+            Err(Error::InvalidSignature)
+        ));
+        Ok(())
+    }
+
     fn create_temp_dir() -> Result<TempDir> {
         tempdir().map_err(|e| eyre!("Failed to create temp dir: {}", e))
     }
@@ -7,14 +7,14 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    keys::{get_main_key, store_new_keypair},
     wallet_file::{get_wallet, store_wallet},
-    DepositWallet, KeyLessWallet, Result, SendClient, SendWallet, Wallet,
+    CredentialStore, DepositWallet, FileStore, KeyLessWallet, PaymentReceipt, Result, SendClient,
+    SendWallet, Wallet,
 };
 
 use crate::protocol::transfers::{CreatedDbc, Outputs as TransferDetails};
 
-use sn_dbc::{Dbc, DbcIdSource, MainKey, PublicAddress, Token};
+use sn_dbc::{Dbc, DbcIdSource, DerivedKey, MainKey, PublicAddress, Signature, Token};
 
 use async_trait::async_trait;
 use std::{
@@ -26,11 +26,11 @@ use std::{
 pub struct LocalWallet {
     /// The secret key with which we can access
     /// all the tokens in the available_dbcs.
-    key: MainKey,
+    pub(super) key: MainKey,
     /// The wallet containing all data.
-    wallet: KeyLessWallet,
+    pub(super) wallet: KeyLessWallet,
     /// The dir of the wallet file.
-    root_dir: PathBuf,
+    pub(super) root_dir: PathBuf,
 }
 
 impl LocalWallet {
@@ -41,22 +41,41 @@ impl LocalWallet {
 
     /// Loads a serialized wallet from a path.
     pub async fn load_from(root_dir: &Path) -> Result<Self> {
-        let (key, wallet) = load_from_path(root_dir).await?;
+        Self::load_with(root_dir, &FileStore::new(root_dir)).await
+    }
+
+    /// Loads a serialized wallet from a path, with its key kept in the given store.
+    pub async fn load_with(root_dir: &Path, key_store: &dyn CredentialStore) -> Result<Self> {
+        let (key, wallet) = load_from_path(root_dir, key_store).await?;
         Ok(Self {
             key,
             wallet,
             root_dir: root_dir.to_path_buf(),
         })
     }
+
+    /// Signs the payload with the main key of the wallet, for a detached signature.
+    /// See [`verify_detached`](super::verify_detached) for how to check it.
+    pub fn sign(&self, payload: &[u8]) -> Signature {
+        self.key.sign(payload)
+    }
+
+    /// The receipts of all payments made from this wallet, oldest first.
+    pub fn receipts(&self) -> &[PaymentReceipt] {
+        &self.wallet.receipts
+    }
 }
 
 /// Loads a serialized wallet from a path.
-async fn load_from_path(root_dir: &Path) -> Result<(MainKey, KeyLessWallet)> {
-    let key = match get_main_key(root_dir).await? {
+async fn load_from_path(
+    root_dir: &Path,
+    key_store: &dyn CredentialStore,
+) -> Result<(MainKey, KeyLessWallet)> {
+    let key = match key_store.load().await? {
         Some(key) => key,
         None => {
             let key = MainKey::random();
-            store_new_keypair(root_dir, &key).await?;
+            key_store.store(&key).await?;
             key
         }
     };
@@ -73,12 +92,13 @@ async fn load_from_path(root_dir: &Path) -> Result<(MainKey, KeyLessWallet)> {
 }
 
 impl KeyLessWallet {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             balance: Token::zero(),
             spent_dbcs: BTreeMap::new(),
             available_dbcs: BTreeMap::new(),
             dbcs_created_for_others: vec![],
+            receipts: vec![],
         }
     }
 
@@ -86,7 +106,7 @@ impl KeyLessWallet {
         self.balance
     }
 
-    fn deposit(&mut self, dbcs: Vec<Dbc>, key: &MainKey) {
+    pub(super) fn deposit(&mut self, dbcs: Vec<Dbc>, key: &MainKey) {
         if dbcs.is_empty() {
             return;
         }
@@ -135,6 +155,21 @@ impl DepositWallet for LocalWallet {
 
 #[async_trait]
 impl SendWallet for LocalWallet {
+    fn spendable_dbcs(&self) -> Vec<(Dbc, DerivedKey)> {
+        let mut available_dbcs = vec![];
+        for dbc in self.wallet.available_dbcs.values() {
+            if let Ok(derived_key) = dbc.derived_key(&self.key) {
+                available_dbcs.push((dbc.clone(), derived_key));
+            } else {
+                warn!(
+                    "Skipping DBC {:?} because we don't have the key to spend it",
+                    dbc.id()
+                );
+            }
+        }
+        available_dbcs
+    }
+
     async fn send<C: SendClient>(
         &mut self,
         to: Vec<(Token, PublicAddress)>,
@@ -153,21 +188,12 @@ impl SendWallet for LocalWallet {
             return Ok(vec![]);
         }
 
-        let mut available_dbcs = vec![];
-        for dbc in self.wallet.available_dbcs.values() {
-            if let Ok(derived_key) = dbc.derived_key(&self.key) {
-                available_dbcs.push((dbc.clone(), derived_key));
-            } else {
-                warn!(
-                    "Skipping DBC {:?} because we don't have the key to spend it",
-                    dbc.id()
-                );
-            }
-        }
+        let available_dbcs = self.spendable_dbcs();
 
         let TransferDetails {
             change_dbc,
             created_dbcs,
+            fees_paid,
         } = client.send(available_dbcs, to, self.address()).await?;
 
         let spent_dbc_ids: BTreeSet<_> = created_dbcs
@@ -176,6 +202,17 @@ impl SendWallet for LocalWallet {
             .map(|spend| spend.dbc_id())
             .collect();
 
+        let paid_addresses = created_dbcs
+            .iter()
+            .map(|created| *created.dbc.public_address())
+            .collect();
+        let receipt = PaymentReceipt::new(
+            spent_dbc_ids.iter().map(|id| **id).collect(),
+            fees_paid,
+            paid_addresses,
+            &self.key,
+        )?;
+
         let mut spent_dbcs = spent_dbc_ids
             .into_iter()
             .filter_map(|id| self.wallet.available_dbcs.remove(id).map(|dbc| (*id, dbc)))
@@ -186,6 +223,7 @@ impl SendWallet for LocalWallet {
         self.wallet
             .dbcs_created_for_others
             .extend(created_dbcs.clone());
+        self.wallet.receipts.push(receipt);
 
         Ok(created_dbcs)
     }
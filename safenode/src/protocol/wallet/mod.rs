@@ -31,18 +31,26 @@
 //! We will already now pave for that, by mimicing that flow for the local storage of a Wallet.
 //! First though, a simpler local storage will be used. But after that a local register store can be implemented.
 
+mod credentials;
 mod error;
+mod export;
 mod keys;
 mod local_store;
 mod network_store;
+mod receipts;
 mod wallet_file;
 
 pub use self::{
+    credentials::{migrate_main_key, CredentialStore, EncryptedFileStore, FileStore, KeyringStore},
     error::{Error, Result},
+    keys::verify_detached,
     local_store::LocalWallet,
+    receipts::{PaymentReceipt, PaymentReceiptContent},
     // network_store::NetworkWallet,
 };
 
+pub(crate) use self::credentials::{decrypt_with_passphrase, encrypt_with_passphrase};
+
 use super::transfers::{CreatedDbc, Outputs as TransferDetails};
 
 use sn_dbc::{Dbc, DbcIdSource, DerivedKey, PublicAddress, Token};
@@ -89,6 +97,9 @@ pub trait Wallet {
 /// of a deposit wallet, can also send tokens to other addresses.
 #[async_trait]
 pub trait SendWallet: DepositWallet {
+    /// The dbcs held by this wallet that it has the keys to spend,
+    /// along with the derived key for each of them.
+    fn spendable_dbcs(&self) -> Vec<(Dbc, DerivedKey)>;
     /// Sends the given tokens to the given addresses.
     /// Returns the new dbcs that were created.
     /// Depending on the implementation of the send client, this may
@@ -124,4 +135,7 @@ pub(super) struct KeyLessWallet {
     /// keep them here so we can track our
     /// transfer history.
     dbcs_created_for_others: Vec<CreatedDbc>,
+    /// These are the receipts of the payments
+    /// made when sending tokens to other addresses.
+    receipts: Vec<PaymentReceipt>,
 }
@@ -0,0 +1,118 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Error, Result};
+
+use crate::protocol::fees::RequiredFee;
+
+use sn_dbc::{DbcId, MainKey, PublicAddress, Signature};
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A proof of a payment made from a wallet, kept by the wallet for accounting and disputes.
+///
+/// The receipt is signed by the paying wallet, and holds the fee quotes signed by each paid node,
+/// so that both the payer and the nodes can be held to what was paid.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    /// The content of the receipt.
+    pub content: PaymentReceiptContent,
+    /// The signature over the content, by the payer.
+    pub payer_sig: Signature,
+}
+
+/// What was paid, when, and by whom.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PaymentReceiptContent {
+    /// The time of the payment, in seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The address of the paying wallet.
+    pub payer: PublicAddress,
+    /// The ids of the dbcs that were spent by the payment.
+    pub spent_dbcs: Vec<DbcId>,
+    /// The fee quotes of the nodes that were paid for the spends.
+    pub quotes: Vec<RequiredFee>,
+    /// The addresses that were paid, i.e. the recipients as well as the nodes.
+    pub paid_addresses: Vec<PublicAddress>,
+}
+
+impl PaymentReceipt {
+    /// Creates a receipt, timestamped now, and signs it with the main key of the paying wallet.
+    pub fn new(
+        spent_dbcs: Vec<DbcId>,
+        quotes: Vec<RequiredFee>,
+        paid_addresses: Vec<PublicAddress>,
+        payer_key: &MainKey,
+    ) -> Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let content = PaymentReceiptContent {
+            timestamp,
+            payer: payer_key.public_address(),
+            spent_dbcs,
+            quotes,
+            paid_addresses,
+        };
+        let payer_sig = payer_key.sign(content.to_bytes()?);
+        Ok(Self { content, payer_sig })
+    }
+
+    /// Verifies that the receipt was signed by the payer, and that every quote was signed by its node.
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .content
+            .payer
+            .verify(&self.payer_sig, self.content.to_bytes()?)
+        {
+            return Err(Error::ReceiptSignatureInvalid);
+        }
+        for quote in &self.content.quotes {
+            quote.verify().map_err(|_| Error::ReceiptSignatureInvalid)?;
+        }
+        Ok(())
+    }
+}
+
+impl PaymentReceiptContent {
+    /// Represent the content as bytes, as signed by the payer.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tampered_receipt_does_not_verify() -> Result<()> {
+        let payer_key = MainKey::random();
+        let node_address = MainKey::random().public_address();
+        let receipt = PaymentReceipt::new(vec![], vec![], vec![node_address], &payer_key)?;
+        receipt.verify()?;
+
+        let mut tampered = receipt.clone();
+        tampered.content.paid_addresses = vec![MainKey::random().public_address()];
+        assert!(matches!(
+            tampered.verify(),
+            Err(Error::ReceiptSignatureInvalid)
+        ));
+
+        let mut forged = receipt;
+        forged.content.payer = MainKey::random().public_address();
+        assert!(matches!(
+            forged.verify(),
+            Err(Error::ReceiptSignatureInvalid)
+        ));
+
+        Ok(())
+    }
+}
@@ -6,18 +6,24 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::DataTypeHandler;
+
 use crate::protocol::{
-    address::ChunkAddress,
+    address::{ChunkAddress, DataAddress},
     chunk::Chunk,
     error::{Error, Result},
+    messages::{Cmd, CmdResponse, Query, QueryResponse},
 };
 
+use async_trait::async_trait;
 use clru::CLruCache;
 use std::{num::NonZeroUsize, sync::Arc};
 use tokio::sync::RwLock;
 use tracing::trace;
 
 const CHUNKS_CACHE_SIZE: usize = 20 * 1024 * 1024;
+// Chunks are replicated before Registers, as most of the data is held in them.
+const CHUNKS_REPLICATE_PRIORITY: u8 = 2;
 
 /// Operations on data chunks.
 #[derive(Clone)]
@@ -65,8 +71,7 @@ impl ChunkStorage {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub(super) async fn addrs(&self) -> Vec<ChunkAddress> {
+    pub(crate) async fn addrs(&self) -> Vec<ChunkAddress> {
         self.cache
             .read()
             .await
@@ -75,7 +80,6 @@ impl ChunkStorage {
             .collect()
     }
 
-    #[allow(dead_code)]
     pub(super) async fn remove_chunk(&self, address: &ChunkAddress) -> Result<()> {
         trace!("Removing Chunk: {address:?}");
         if self.cache.write().await.pop(address).is_some() {
@@ -86,6 +90,65 @@ impl ChunkStorage {
     }
 }
 
+#[async_trait]
+impl DataTypeHandler for ChunkStorage {
+    fn name(&self) -> &'static str {
+        "chunk"
+    }
+
+    fn replicate_priority(&self) -> u8 {
+        CHUNKS_REPLICATE_PRIORITY
+    }
+
+    fn handles_cmd(&self, cmd: &Cmd) -> bool {
+        matches!(cmd, Cmd::StoreChunk(_))
+    }
+
+    fn handles_query(&self, query: &Query) -> bool {
+        matches!(query, Query::GetChunk(_))
+    }
+
+    fn handles_address(&self, address: &DataAddress) -> bool {
+        matches!(address, DataAddress::Chunk(_))
+    }
+
+    async fn store(&self, cmd: &Cmd) -> CmdResponse {
+        match cmd {
+            Cmd::StoreChunk(chunk) => CmdResponse::StoreChunk(self.store(chunk).await),
+            other => other.error(Error::UnexpectedResponses),
+        }
+    }
+
+    async fn query(&self, query: &Query) -> QueryResponse {
+        match query {
+            Query::GetChunk(address) => QueryResponse::GetChunk(self.get(address).await),
+            other => other.error(Error::UnexpectedResponses),
+        }
+    }
+
+    async fn addrs(&self) -> Vec<DataAddress> {
+        self.addrs()
+            .await
+            .into_iter()
+            .map(DataAddress::Chunk)
+            .collect()
+    }
+
+    async fn replication_cmds(&self, address: &DataAddress) -> Result<Vec<Cmd>> {
+        match address {
+            DataAddress::Chunk(address) => Ok(vec![Cmd::StoreChunk(self.get(address).await?)]),
+            other => Err(Error::InvalidAddress(format!("{other:?} is not a chunk"))),
+        }
+    }
+
+    async fn remove(&self, address: &DataAddress) -> Result<()> {
+        match address {
+            DataAddress::Chunk(address) => self.remove_chunk(address).await,
+            other => Err(Error::InvalidAddress(format!("{other:?} is not a chunk"))),
+        }
+    }
+}
+
 impl Default for ChunkStorage {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,171 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::{
+    address::DataAddress,
+    error::Result,
+    messages::{Cmd, CmdResponse, Query, QueryResponse},
+};
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// The handling of a native data type by the node: which cmds and queries it answers,
+/// how it validates and stores the data, and how the data is replicated.
+///
+/// New data types are added by implementing this in their own module, and registering
+/// the handler in [`DataTypes`].
+#[async_trait]
+pub(crate) trait DataTypeHandler: Send + Sync {
+    /// The name of the data type, for logging.
+    fn name(&self) -> &'static str;
+
+    /// Handlers with a higher priority have their data replicated first.
+    fn replicate_priority(&self) -> u8;
+
+    /// Whether this handler stores the data of the cmd.
+    fn handles_cmd(&self, cmd: &Cmd) -> bool;
+
+    /// Whether this handler answers the query.
+    fn handles_query(&self, query: &Query) -> bool;
+
+    /// Whether the data at the address is of the type of this handler.
+    fn handles_address(&self, address: &DataAddress) -> bool;
+
+    /// Checks the cmd before it is stored, e.g. its signature.
+    async fn validate(&self, _cmd: &Cmd) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stores the data of the cmd, which was validated already.
+    async fn store(&self, cmd: &Cmd) -> CmdResponse;
+
+    /// Answers the query from the stored data.
+    async fn query(&self, query: &Query) -> QueryResponse;
+
+    /// The addresses of all the data stored by this handler.
+    async fn addrs(&self) -> Vec<DataAddress>;
+
+    /// The cmds to send to other nodes for them to store the data at the address.
+    async fn replication_cmds(&self, address: &DataAddress) -> Result<Vec<Cmd>>;
+
+    /// Deletes the data at the address, which the node is no longer responsible for.
+    async fn remove(&self, address: &DataAddress) -> Result<()>;
+}
+
+/// The data types handled by the node, see [`DataTypeHandler`].
+#[derive(Clone, Default)]
+pub(crate) struct DataTypes {
+    // Sorted by descending replicate priority.
+    handlers: Vec<Arc<dyn DataTypeHandler>>,
+}
+
+impl DataTypes {
+    /// Adds the handler of a data type.
+    pub(crate) fn register(&mut self, handler: Arc<dyn DataTypeHandler>) {
+        trace!("Registering the {} data type", handler.name());
+        self.handlers.push(handler);
+        self.handlers
+            .sort_by_key(|handler| std::cmp::Reverse(handler.replicate_priority()));
+    }
+
+    /// Validates and stores the data of the cmd, returning the response to it,
+    /// or `None` if no handler stores such data.
+    pub(crate) async fn handle_cmd(&self, cmd: &Cmd) -> Option<CmdResponse> {
+        let handler = self
+            .handlers
+            .iter()
+            .find(|handler| handler.handles_cmd(cmd))?;
+        if let Err(error) = handler.validate(cmd).await {
+            debug!("Invalid {} cmd to {:?}: {error}", handler.name(), cmd.dst());
+            return Some(cmd.error(error));
+        }
+        Some(handler.store(cmd).await)
+    }
+
+    /// Answers the query, or returns `None` if no handler answers it.
+    pub(crate) async fn handle_query(&self, query: &Query) -> Option<QueryResponse> {
+        let handler = self
+            .handlers
+            .iter()
+            .find(|handler| handler.handles_query(query))?;
+        Some(handler.query(query).await)
+    }
+
+    /// The addresses of all the stored data, in replication order.
+    pub(crate) async fn addrs(&self) -> Vec<DataAddress> {
+        let mut addrs = vec![];
+        for handler in &self.handlers {
+            addrs.extend(handler.addrs().await);
+        }
+        addrs
+    }
+
+    /// The cmds to send to other nodes for them to store the data at the address,
+    /// or `None` if no handler stores such data.
+    pub(crate) async fn replication_cmds(&self, address: &DataAddress) -> Option<Result<Vec<Cmd>>> {
+        let handler = self
+            .handlers
+            .iter()
+            .find(|handler| handler.handles_address(address))?;
+        Some(handler.replication_cmds(address).await)
+    }
+
+    /// Deletes the data at the address, or returns `None` if no handler stores such data.
+    pub(crate) async fn remove(&self, address: &DataAddress) -> Option<Result<()>> {
+        let handler = self
+            .handlers
+            .iter()
+            .find(|handler| handler.handles_address(address))?;
+        Some(handler.remove(address).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        protocol::{address::ChunkAddress, chunk::Chunk, error::Error},
+        storage::{ChunkStorage, RegisterStorage},
+    };
+
+    use bytes::Bytes;
+
+    #[tokio::test]
+    async fn cmds_and_queries_are_dispatched_to_their_handler() {
+        let mut data = DataTypes::default();
+        data.register(Arc::new(RegisterStorage::new()));
+        data.register(Arc::new(ChunkStorage::new()));
+
+        let chunk = Chunk::new(Bytes::from_static(b"some chunk"));
+        let address = *chunk.address();
+        let resp = data.handle_cmd(&Cmd::StoreChunk(chunk.clone())).await;
+        assert!(matches!(resp, Some(CmdResponse::StoreChunk(Ok(())))));
+
+        let resp = data.handle_query(&Query::GetChunk(address)).await;
+        assert!(matches!(resp, Some(QueryResponse::GetChunk(Ok(stored))) if stored == chunk));
+        let missing = ChunkAddress::new(xor_name::XorName::random(&mut rand::thread_rng()));
+        let resp = data.handle_query(&Query::GetChunk(missing)).await;
+        assert!(matches!(
+            resp,
+            Some(QueryResponse::GetChunk(Err(Error::ChunkNotFound(_))))
+        ));
+
+        assert_eq!(data.addrs().await, vec![DataAddress::Chunk(address)]);
+        let cmds = data.replication_cmds(&DataAddress::Chunk(address)).await;
+        assert!(matches!(cmds, Some(Ok(cmds)) if cmds == vec![Cmd::StoreChunk(chunk)]));
+
+        // Queries about the node itself are left to it.
+        let query = Query::GetRecordDistribution {
+            node: xor_name::XorName::random(&mut rand::thread_rng()),
+            prefix_bits: 1,
+        };
+        assert!(data.handle_query(&query).await.is_none());
+    }
+}
@@ -7,9 +7,16 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod chunks;
+mod data_types;
 mod register_store;
 mod registers;
 mod spends;
 mod used_space;
 
-pub(crate) use self::{chunks::ChunkStorage, registers::RegisterStorage, spends::SpendStorage};
+pub use self::registers::RegisterMaintenanceReport;
+pub(crate) use self::{
+    chunks::ChunkStorage,
+    data_types::{DataTypeHandler, DataTypes},
+    registers::RegisterStorage,
+    spends::SpendStorage,
+};
@@ -16,9 +16,11 @@ use crate::protocol::{
 use clru::CLruCache;
 use std::{num::NonZeroUsize, sync::Arc};
 use tokio::sync::RwLock;
-use tracing::trace;
+use tracing::{trace, warn};
 
 const REGISTERS_CACHE_SIZE: usize = 20 * 1024 * 1024;
+// The number of corrupt Registers kept for inspection, the oldest being dropped first.
+const QUARANTINE_SIZE: usize = 128;
 
 pub(super) type RegisterLog = Vec<RegisterCmd>;
 
@@ -32,20 +34,24 @@ pub(super) struct StoredRegister {
 #[derive(Clone)]
 pub(super) struct RegisterStore {
     cache: Arc<RwLock<CLruCache<RegisterAddress, StoredRegister>>>,
+    // Registers found to be corrupt, kept aside for inspection.
+    quarantined: Arc<RwLock<CLruCache<RegisterAddress, StoredRegister>>>,
 }
 
 impl Default for RegisterStore {
     fn default() -> Self {
         let capacity = NonZeroUsize::new(REGISTERS_CACHE_SIZE)
             .expect("Failed to create in-memory Registers storage");
+        let quarantine_capacity =
+            NonZeroUsize::new(QUARANTINE_SIZE).expect("Failed to create the Registers quarantine");
         Self {
             cache: Arc::new(RwLock::new(CLruCache::new(capacity))),
+            quarantined: Arc::new(RwLock::new(CLruCache::new(quarantine_capacity))),
         }
     }
 }
 
 impl RegisterStore {
-    #[cfg(test)]
     pub(super) async fn addrs(&self) -> Vec<RegisterAddress> {
         self.cache
             .read()
@@ -55,7 +61,6 @@ impl RegisterStore {
             .collect()
     }
 
-    #[allow(dead_code)]
     pub(super) async fn remove(&self, address: &RegisterAddress) -> Result<()> {
         trace!("Removing Register: {address:?}");
         if self.cache.write().await.pop(address).is_some() {
@@ -65,6 +70,40 @@ impl RegisterStore {
         }
     }
 
+    /// Moves a Register out of the store, into the quarantine.
+    pub(super) async fn quarantine(&self, address: &RegisterAddress) {
+        warn!("Quarantining Register: {address:?}");
+        if let Some(stored_reg) = self.cache.write().await.pop(address) {
+            let mut quarantined = self.quarantined.write().await;
+            if quarantined.is_full() && !quarantined.contains(address) {
+                if let Some((dropped, _)) = quarantined.pop_back() {
+                    warn!("Dropping quarantined Register, as the quarantine is full: {dropped:?}");
+                }
+            }
+            let _ = quarantined.put(*address, stored_reg);
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) async fn quarantined(&self) -> Vec<RegisterAddress> {
+        self.quarantined
+            .read()
+            .await
+            .iter()
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Modifies the stored Register in place, if there is one,
+    /// without letting any other access to the store interleave.
+    pub(super) async fn modify<R>(
+        &self,
+        address: &RegisterAddress,
+        f: impl FnOnce(&mut StoredRegister) -> R,
+    ) -> Option<R> {
+        self.cache.write().await.peek_mut(address).map(f)
+    }
+
     /// Opens the log of RegisterCmds for a given Register address.
     /// Creates a new log if no data is found
     pub(super) async fn get(&self, address: &RegisterAddress) -> StoredRegister {
@@ -6,19 +6,54 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::register_store::{RegisterStore, StoredRegister};
+use super::{
+    register_store::{RegisterStore, StoredRegister},
+    DataTypeHandler,
+};
 
 use crate::protocol::{
-    address::RegisterAddress,
+    address::{DataAddress, RegisterAddress},
     error::{Error, Result},
     messages::{
-        EditRegister, QueryResponse, RegisterCmd, RegisterQuery, ReplicatedRegisterLog,
-        SignedRegisterCreate, SignedRegisterEdit,
+        Cmd, CmdResponse, EditRegister, Query, QueryResponse, RegisterCmd, RegisterQuery,
+        ReplicatedRegisterLog, SignedRegisterCreate, SignedRegisterEdit,
     },
     register::{Action, EntryHash, Register, User},
 };
 
+use async_trait::async_trait;
 use bincode::serialize;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use xor_name::XorName;
+
+// Registers are replicated after Chunks.
+const REGISTERS_REPLICATE_PRIORITY: u8 = 1;
+
+/// The outcome of a maintenance pass over the stored Registers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterMaintenanceReport {
+    /// The number of Registers in the store.
+    pub registers: usize,
+    /// The number of duplicated cmds removed from the Register logs.
+    pub duplicate_cmds: usize,
+    /// The number of Registers whose cmd signatures were verified.
+    pub verified: usize,
+    /// The number of verified Registers from which invalid cmds were removed.
+    pub repaired: usize,
+    /// The number of verified Registers which could not be repaired, and were quarantined.
+    pub quarantined: usize,
+}
+
+// The result of verifying a stored Register.
+#[derive(Debug, PartialEq, Eq)]
+enum Integrity {
+    Unchecked,
+    Valid,
+    Repaired,
+    Corrupt,
+}
 
 /// Operations over the Register data type and its storage.
 #[derive(Clone, Default)]
@@ -75,6 +110,69 @@ impl RegisterStorage {
             .await
     }
 
+    /// --- Maintenance ---
+
+    /// Compacts the logs of all stored Registers, removing duplicated cmds, and verifies
+    /// the signatures of all cmds of a random sample of `sample_size` Registers.
+    /// Cmds with invalid signatures are removed, and Registers which can then not be
+    /// reconstructed from their remaining cmds are quarantined.
+    ///
+    /// The address of every Register that was compacted, repaired or quarantined is handed
+    /// to `on_changed`, e.g. to drop what was cached of it.
+    pub(crate) async fn maintain(
+        &self,
+        sample_size: usize,
+        mut on_changed: impl FnMut(RegisterAddress),
+    ) -> RegisterMaintenanceReport {
+        let addrs = self.register_store.addrs().await;
+        let sample: Vec<_> = {
+            let mut rng = rand::thread_rng();
+            addrs
+                .choose_multiple(&mut rng, sample_size)
+                .copied()
+                .collect()
+        };
+
+        let mut report = RegisterMaintenanceReport {
+            registers: addrs.len(),
+            ..Default::default()
+        };
+        for addr in addrs {
+            let verify = sample.contains(&addr);
+            let outcome = self
+                .register_store
+                .modify(&addr, |stored_reg| {
+                    maintain_register(&addr, stored_reg, verify)
+                })
+                .await;
+            let (duplicates, integrity) = match outcome {
+                Some(outcome) => outcome,
+                // It was evicted meanwhile.
+                None => continue,
+            };
+
+            report.duplicate_cmds += duplicates;
+            if duplicates > 0 || matches!(integrity, Integrity::Repaired | Integrity::Corrupt) {
+                on_changed(addr);
+            }
+            match integrity {
+                Integrity::Unchecked => {}
+                Integrity::Valid => report.verified += 1,
+                Integrity::Repaired => {
+                    report.verified += 1;
+                    report.repaired += 1;
+                }
+                Integrity::Corrupt => {
+                    report.verified += 1;
+                    report.quarantined += 1;
+                    self.register_store.quarantine(&addr).await;
+                }
+            }
+        }
+
+        report
+    }
+
     /// --- Reading ---
     pub(crate) async fn read(&self, read: &RegisterQuery, requester: User) -> QueryResponse {
         trace!("Reading register: {:?}", read.dst());
@@ -270,16 +368,12 @@ impl RegisterStorage {
         Ok(stored_reg)
     }
 
-    #[cfg(test)]
-    async fn addrs(&self) -> Vec<RegisterAddress> {
+    pub(crate) async fn addrs(&self) -> Vec<RegisterAddress> {
         self.register_store.addrs().await
     }
 
-    /// Used for replication of data to new nodes.
-    /// Currently only used by the tests.
-    /// TODO: to be used by replication logic.
-    #[cfg(test)]
-    async fn get_register_replica(
+    /// Used for replication of data to other nodes.
+    pub(crate) async fn get_register_replica(
         &self,
         address: &RegisterAddress,
     ) -> Result<ReplicatedRegisterLog> {
@@ -293,6 +387,166 @@ impl RegisterStorage {
     }
 }
 
+#[async_trait]
+impl DataTypeHandler for RegisterStorage {
+    fn name(&self) -> &'static str {
+        "register"
+    }
+
+    fn replicate_priority(&self) -> u8 {
+        REGISTERS_REPLICATE_PRIORITY
+    }
+
+    fn handles_cmd(&self, cmd: &Cmd) -> bool {
+        matches!(cmd, Cmd::Register(_))
+    }
+
+    fn handles_query(&self, query: &Query) -> bool {
+        matches!(query, Query::Register(_))
+    }
+
+    fn handles_address(&self, address: &DataAddress) -> bool {
+        matches!(address, DataAddress::Register(_))
+    }
+
+    // Edits are only checked against the permissions of the Register once it is
+    // stored, but their signature can be checked right away.
+    async fn validate(&self, cmd: &Cmd) -> Result<()> {
+        match cmd {
+            Cmd::Register(cmd) => verify_cmd(&cmd.dst(), cmd),
+            _ => Ok(()),
+        }
+    }
+
+    async fn store(&self, cmd: &Cmd) -> CmdResponse {
+        match cmd {
+            Cmd::Register(cmd) => {
+                let result = self.write(cmd).await;
+                match cmd {
+                    RegisterCmd::Create(_) => CmdResponse::CreateRegister(result),
+                    RegisterCmd::Edit(_) => CmdResponse::EditRegister(result),
+                }
+            }
+            other => other.error(Error::UnexpectedResponses),
+        }
+    }
+
+    async fn query(&self, query: &Query) -> QueryResponse {
+        match query {
+            Query::Register(query) => self.read(query, User::Anyone).await,
+            other => other.error(Error::UnexpectedResponses),
+        }
+    }
+
+    async fn addrs(&self) -> Vec<DataAddress> {
+        self.addrs()
+            .await
+            .into_iter()
+            .map(DataAddress::Register)
+            .collect()
+    }
+
+    async fn replication_cmds(&self, address: &DataAddress) -> Result<Vec<Cmd>> {
+        match address {
+            DataAddress::Register(address) => {
+                let replica = self.get_register_replica(address).await?;
+                Ok(replica.op_log.into_iter().map(Cmd::Register).collect())
+            }
+            other => Err(Error::InvalidAddress(format!(
+                "{other:?} is not a register"
+            ))),
+        }
+    }
+
+    async fn remove(&self, address: &DataAddress) -> Result<()> {
+        match address {
+            DataAddress::Register(address) => self.register_store.remove(address).await,
+            other => Err(Error::InvalidAddress(format!(
+                "{other:?} is not a register"
+            ))),
+        }
+    }
+}
+
+// Removes duplicated cmds from the log of the Register and, if `verify` is set,
+// removes the cmds with invalid signatures and reconstructs the Register from the rest.
+// Returns the number of duplicates removed, and the integrity of the Register.
+fn maintain_register(
+    addr: &RegisterAddress,
+    stored_reg: &mut StoredRegister,
+    verify: bool,
+) -> (usize, Integrity) {
+    let len = stored_reg.op_log.len();
+    let mut seen = HashSet::with_capacity(len);
+    stored_reg.op_log.retain(|cmd| match serialize(cmd) {
+        Ok(bytes) => seen.insert(XorName::from_content(&bytes)),
+        // Not told apart from the others, so it is kept.
+        Err(_) => true,
+    });
+    let duplicates = len - stored_reg.op_log.len();
+
+    if !verify {
+        return (duplicates, Integrity::Unchecked);
+    }
+
+    let len = stored_reg.op_log.len();
+    stored_reg.op_log.retain(|cmd| {
+        let result = verify_cmd(addr, cmd);
+        if let Err(err) = &result {
+            warn!("Removing invalid cmd from Register {addr:?}: {err:?}");
+        }
+        result.is_ok()
+    });
+    if stored_reg.op_log.len() == len {
+        return (duplicates, Integrity::Valid);
+    }
+
+    // Without a valid 'Register create' cmd we only hold edits, which cannot be applied anyway.
+    if stored_reg.state.is_none() {
+        return (duplicates, Integrity::Repaired);
+    }
+    let create = stored_reg.op_log.iter().find_map(|cmd| match cmd {
+        RegisterCmd::Create(SignedRegisterCreate { op, .. }) => Some(op),
+        RegisterCmd::Edit(_) => None,
+    });
+    let integrity = match create {
+        Some(op) => {
+            let mut register =
+                Register::new(*op.policy.owner(), op.name, op.tag, op.policy.clone());
+            let rebuilt = stored_reg.op_log.iter().all(|cmd| match cmd {
+                RegisterCmd::Create(_) => true,
+                RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                    register.apply_op(op.edit.clone()).is_ok()
+                }
+            });
+            if rebuilt {
+                stored_reg.state = Some(register);
+                Integrity::Repaired
+            } else {
+                Integrity::Corrupt
+            }
+        }
+        None => Integrity::Corrupt,
+    };
+
+    (duplicates, integrity)
+}
+
+// Verifies that the cmd targets the given Register, and is signed by the key it claims.
+fn verify_cmd(addr: &RegisterAddress, cmd: &RegisterCmd) -> Result<()> {
+    if &cmd.dst() != addr {
+        return Err(Error::RegisterAddrMismatch {
+            cmd_dst_addr: cmd.dst(),
+            reg_addr: *addr,
+        });
+    }
+    let (payload, auth) = match cmd {
+        RegisterCmd::Create(SignedRegisterCreate { op, auth }) => (serialize(op), auth),
+        RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => (serialize(op), auth),
+    };
+    auth.verify_authority(payload.map_err(|e| Error::Bincode(e.to_string()))?)
+}
+
 #[cfg(test)]
 mod test {
     use super::RegisterStorage;
@@ -666,6 +920,107 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_register_maintenance_removes_duplicates() -> Result<()> {
+        let store = RegisterStorage::default();
+
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let addr = cmd_create.dst();
+        let mut register = Register::new(*policy.owner(), name, 0, policy);
+        store.write(&cmd_create).await?;
+        let cmd_edit = edit_register(&mut register, &sk)?;
+        store.write(&cmd_edit).await?;
+        store.write(&cmd_edit).await?;
+        assert_eq!(store.try_load_stored_register(&addr).await?.op_log.len(), 3);
+
+        let mut changed = vec![];
+        let report = store.maintain(0, |addr| changed.push(addr)).await;
+        assert_eq!(changed, [addr]);
+        assert_eq!(report.registers, 1);
+        assert_eq!(report.duplicate_cmds, 1);
+        assert_eq!(report.verified, 0);
+
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.op_log, vec![cmd_create, cmd_edit]);
+        assert_eq!(stored_reg.state.as_ref(), Some(&register));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_maintenance_repairs_and_quarantines() -> Result<()> {
+        let store = RegisterStorage::default();
+
+        // A register with an edit signed by someone else than it claims.
+        let (cmd_create, _, sk, name, policy) = create_register()?;
+        let addr = cmd_create.dst();
+        let mut register = Register::new(*policy.owner(), name, 0, policy);
+        store.write(&cmd_create).await?;
+        let cmd_edit = edit_register(&mut register, &sk)?;
+        let forged_edit = match cmd_edit {
+            RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
+                RegisterCmd::Edit(SignedRegisterEdit {
+                    auth: DataAuthority {
+                        signature: SecretKey::random().sign(serialize(&op)?),
+                        ..auth
+                    },
+                    op,
+                })
+            }
+            cmd => bail!("Unexpected cmd {cmd:?}"),
+        };
+        let mut stored_reg = store.try_load_stored_register(&addr).await?;
+        stored_reg.op_log.push(forged_edit);
+        store
+            .register_store
+            .store_register_ops_log(&vec![], stored_reg, addr)
+            .await?;
+
+        // A register with a forged create cmd.
+        let (forged_create, _, _, _, _) = create_register()?;
+        let forged_addr = forged_create.dst();
+        let forged_create = match forged_create {
+            RegisterCmd::Create(SignedRegisterCreate { op, auth }) => {
+                RegisterCmd::Create(SignedRegisterCreate {
+                    auth: DataAuthority {
+                        signature: SecretKey::random().sign(serialize(&op)?),
+                        ..auth
+                    },
+                    op,
+                })
+            }
+            cmd => bail!("Unexpected cmd {cmd:?}"),
+        };
+        let mut stored_forged = store.try_load_stored_register(&forged_addr).await?;
+        let CreateRegister { name, tag, policy } = match &forged_create {
+            RegisterCmd::Create(SignedRegisterCreate { op, .. }) => op.clone(),
+            cmd => bail!("Unexpected cmd {cmd:?}"),
+        };
+        stored_forged.state = Some(Register::new(*policy.owner(), name, tag, policy));
+        stored_forged.op_log.push(forged_create);
+        store
+            .register_store
+            .store_register_ops_log(&vec![], stored_forged, forged_addr)
+            .await?;
+
+        let mut changed = vec![];
+        let report = store.maintain(2, |addr| changed.push(addr)).await;
+        changed.sort();
+        let mut expected = vec![addr, forged_addr];
+        expected.sort();
+        assert_eq!(changed, expected);
+        assert_eq!(report.verified, 2);
+        assert_eq!(report.repaired, 1);
+        assert_eq!(report.quarantined, 1);
+
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.op_log, vec![cmd_create]);
+        assert_eq!(store.addrs().await, vec![addr]);
+        assert_eq!(store.register_store.quarantined().await, vec![forged_addr]);
+
+        Ok(())
+    }
+
     fn random_user() -> (User, SecretKey) {
         let sk = SecretKey::random();
         let authority = User::Key(sk.public_key());
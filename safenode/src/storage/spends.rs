@@ -58,10 +58,16 @@ impl SpendStorage {
     }
 
     // Read Spend from local store.
+    // If the dbc was double spent, both spends are returned in a `DoubleSpendAttempt` error.
     pub(crate) async fn get(&self, address: DbcAddress) -> Result<SignedSpend> {
         trace!("Getting Spend: {address:?}");
         if let Some(spend) = self.valid_spends.read().await.get(&address) {
             Ok(spend.clone())
+        } else if let Some((existing, new)) = self.double_spends.read().await.get(&address) {
+            Err(Error::DoubleSpendAttempt {
+                new: Box::new(new.clone()),
+                existing: Box::new(existing.clone()),
+            })
         } else {
             Err(Error::SpendNotFound(address))
         }
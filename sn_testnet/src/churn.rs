@@ -0,0 +1,421 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+use color_eyre::{eyre::eyre, Result};
+#[cfg(test)]
+use mockall::automock;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info};
+
+/// Churn nodes get their own directory prefix, so that they are not counted as regular testnet
+/// nodes when joining an existing network.
+const CHURN_NODE_DIR_PREFIX: &str = "churn-node-";
+
+/// Starts and stops node processes.
+///
+/// Unlike the `NodeLauncher`, which fires and forgets, the nodes started here can be stopped
+/// again. This trait also exists for unit testing.
+#[cfg_attr(test, automock)]
+pub trait NodeController {
+    /// Starts a node process, returning its process id.
+    fn start(&mut self, node_bin_path: &Path, args: Vec<String>) -> Result<u32>;
+    /// Stops the node process with the given process id.
+    fn stop(&mut self, pid: u32) -> Result<()>;
+}
+
+/// Controls nodes as child processes of the current process.
+#[derive(Default)]
+pub struct ChildProcessController {
+    children: BTreeMap<u32, Child>,
+}
+
+impl NodeController for ChildProcessController {
+    fn start(&mut self, node_bin_path: &Path, args: Vec<String>) -> Result<u32> {
+        debug!("Running {:#?} with args: {:#?}", node_bin_path, args);
+        let child = Command::new(node_bin_path)
+            .args(args)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let pid = child.id();
+        self.children.insert(pid, child);
+        Ok(pid)
+    }
+
+    fn stop(&mut self, pid: u32) -> Result<()> {
+        let mut child = self
+            .children
+            .remove(&pid)
+            .ok_or_else(|| eyre!("There is no node process with pid {pid}"))?;
+        child.kill()?;
+        child.wait()?;
+        Ok(())
+    }
+}
+
+/// An action taken by the `ChurnScheduler`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChurnAction {
+    Started { node_name: String, pid: u32 },
+    Stopped { node_name: String, pid: u32 },
+}
+
+/// A churn action and the time at which it was taken.
+#[derive(Clone, Debug)]
+pub struct ChurnEvent {
+    pub at: SystemTime,
+    pub action: ChurnAction,
+}
+
+type Verification = Box<dyn FnMut() -> Result<()>>;
+
+/// Continuously stops and starts nodes alongside a running testnet, for soak testing.
+///
+/// Only the nodes started by the scheduler are churned; the nodes of the testnet itself are
+/// left alone.
+pub struct ChurnScheduler {
+    node_bin_path: PathBuf,
+    nodes_dir_path: PathBuf,
+    node_args: Vec<String>,
+    churn_interval: Duration,
+    min_population: usize,
+    controller: Box<dyn NodeController>,
+    verifications: Vec<Verification>,
+    running_nodes: VecDeque<(String, u32)>,
+    next_node_index: usize,
+    events: Vec<ChurnEvent>,
+}
+
+impl ChurnScheduler {
+    /// Create a new `ChurnScheduler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_bin_path` - The `safenode` binary to launch nodes with.
+    /// * `nodes_dir_path` - The directory under which each churn node will get its own directory.
+    /// * `churn_interval` - The time between each node being replaced.
+    /// * `min_population` - The number of churn nodes that must be running at all times.
+    /// * `controller` - Used to start and stop the node processes.
+    pub fn new(
+        node_bin_path: PathBuf,
+        nodes_dir_path: PathBuf,
+        churn_interval: Duration,
+        min_population: usize,
+        controller: Box<dyn NodeController>,
+    ) -> Self {
+        Self {
+            node_bin_path,
+            nodes_dir_path,
+            node_args: Vec::new(),
+            churn_interval,
+            min_population,
+            controller,
+            verifications: Vec::new(),
+            running_nodes: VecDeque::new(),
+            next_node_index: 1,
+            events: Vec::new(),
+        }
+    }
+
+    /// Set additional arguments to pass to each node process, e.g., --json-logs.
+    pub fn node_args(&mut self, node_args: Vec<String>) -> &mut Self {
+        self.node_args = node_args;
+        self
+    }
+
+    /// Add a check to run after every round of churn, e.g., that previously stored test data is
+    /// still retrievable.
+    ///
+    /// Churn stops as soon as a check fails, and `run` returns the error of that check.
+    pub fn add_verification(
+        &mut self,
+        verification: impl FnMut() -> Result<()> + 'static,
+    ) -> &mut Self {
+        self.verifications.push(Box::new(verification));
+        self
+    }
+
+    /// The actions taken so far, oldest first.
+    pub fn events(&self) -> &[ChurnEvent] {
+        &self.events
+    }
+
+    /// The number of churn nodes currently running.
+    pub fn population(&self) -> usize {
+        self.running_nodes.len()
+    }
+
+    /// Churns nodes for the given duration.
+    ///
+    /// The population is first brought up to the minimum. Then on every tick of the churn
+    /// interval, a new node is started and the longest running node is stopped, so the population
+    /// never drops below the minimum. The verifications are run once the minimum population has
+    /// been reached, and again after every tick.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * A node data directory cannot be created
+    /// * A node process cannot be started or stopped
+    /// * A verification fails
+    pub fn run(&mut self, duration: Duration) -> Result<()> {
+        while self.running_nodes.len() < self.min_population {
+            self.start_node()?;
+        }
+        self.verify()?;
+
+        let ticks = duration.as_millis() / self.churn_interval.as_millis().max(1);
+        info!(
+            "Churning {} nodes every {:?} for {:?}",
+            self.min_population, self.churn_interval, duration
+        );
+        for _ in 0..ticks {
+            std::thread::sleep(self.churn_interval);
+            self.start_node()?;
+            if self.running_nodes.len() > self.min_population {
+                self.stop_oldest_node()?;
+            }
+            self.verify()?;
+        }
+        Ok(())
+    }
+
+    fn start_node(&mut self) -> Result<()> {
+        let node_name = format!("{CHURN_NODE_DIR_PREFIX}{}", self.next_node_index);
+        self.next_node_index += 1;
+
+        let node_data_dir_path = self.nodes_dir_path.join(&node_name);
+        std::fs::create_dir_all(&node_data_dir_path)?;
+        let node_data_dir_path = node_data_dir_path
+            .to_str()
+            .ok_or_else(|| eyre!("Unable to obtain node data directory path"))?
+            .to_string();
+        // Each node keeps its own identity, as two nodes cannot run with the same one.
+        let mut launch_args = vec![
+            "--root-dir".to_string(),
+            node_data_dir_path.clone(),
+            "--log-dir".to_string(),
+            node_data_dir_path,
+        ];
+        launch_args.extend(self.node_args.clone());
+
+        let pid = self.controller.start(&self.node_bin_path, launch_args)?;
+        info!("Started churn node {node_name} with pid {pid}");
+        self.running_nodes.push_back((node_name.clone(), pid));
+        self.events.push(ChurnEvent {
+            at: SystemTime::now(),
+            action: ChurnAction::Started { node_name, pid },
+        });
+        Ok(())
+    }
+
+    fn stop_oldest_node(&mut self) -> Result<()> {
+        let (node_name, pid) = self
+            .running_nodes
+            .pop_front()
+            .ok_or_else(|| eyre!("There are no churn nodes running"))?;
+        self.controller.stop(pid)?;
+        info!("Stopped churn node {node_name} with pid {pid}");
+        self.events.push(ChurnEvent {
+            at: SystemTime::now(),
+            action: ChurnAction::Stopped { node_name, pid },
+        });
+        Ok(())
+    }
+
+    fn verify(&mut self) -> Result<()> {
+        for verification in self.verifications.iter_mut() {
+            verification()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_fs::prelude::*;
+    use color_eyre::Result;
+    use mockall::predicate::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const CHURN_INTERVAL: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn run_should_start_the_minimum_population_before_churning() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let mut controller = MockNodeController::new();
+        let mut next_pid = 0;
+        controller.expect_start().times(3).returning(move |_, _| {
+            next_pid += 1;
+            Ok(next_pid)
+        });
+        controller.expect_stop().never();
+
+        let mut scheduler = ChurnScheduler::new(
+            PathBuf::from("safenode"),
+            tmp_data_dir.to_path_buf(),
+            CHURN_INTERVAL,
+            3,
+            Box::new(controller),
+        );
+        scheduler.run(Duration::ZERO)?;
+
+        assert_eq!(scheduler.population(), 3);
+        tmp_data_dir
+            .child("churn-node-1")
+            .assert(predicates::path::is_dir());
+        tmp_data_dir
+            .child("churn-node-3")
+            .assert(predicates::path::is_dir());
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_should_replace_the_oldest_node_on_each_tick() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let mut controller = MockNodeController::new();
+        let mut next_pid = 0;
+        controller.expect_start().times(4).returning(move |_, _| {
+            next_pid += 1;
+            Ok(next_pid)
+        });
+        controller
+            .expect_stop()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(()));
+        controller
+            .expect_stop()
+            .with(eq(2))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut scheduler = ChurnScheduler::new(
+            PathBuf::from("safenode"),
+            tmp_data_dir.to_path_buf(),
+            CHURN_INTERVAL,
+            2,
+            Box::new(controller),
+        );
+        scheduler.run(CHURN_INTERVAL * 2)?;
+
+        assert_eq!(scheduler.population(), 2);
+        let actions: Vec<_> = scheduler
+            .events()
+            .iter()
+            .map(|event| event.action.clone())
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ChurnAction::Started {
+                    node_name: "churn-node-1".to_string(),
+                    pid: 1
+                },
+                ChurnAction::Started {
+                    node_name: "churn-node-2".to_string(),
+                    pid: 2
+                },
+                ChurnAction::Started {
+                    node_name: "churn-node-3".to_string(),
+                    pid: 3
+                },
+                ChurnAction::Stopped {
+                    node_name: "churn-node-1".to_string(),
+                    pid: 1
+                },
+                ChurnAction::Started {
+                    node_name: "churn-node-4".to_string(),
+                    pid: 4
+                },
+                ChurnAction::Stopped {
+                    node_name: "churn-node-2".to_string(),
+                    pid: 2
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_should_pass_the_node_args_to_each_node() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let node_data_dir = tmp_data_dir.child("churn-node-1");
+        let mut controller = MockNodeController::new();
+        controller
+            .expect_start()
+            .times(1)
+            .with(
+                eq(PathBuf::from("safenode")),
+                eq(vec![
+                    "--root-dir".to_string(),
+                    node_data_dir.path().to_str().unwrap().to_string(),
+                    "--log-dir".to_string(),
+                    node_data_dir.path().to_str().unwrap().to_string(),
+                    "--json-logs".to_string(),
+                ]),
+            )
+            .returning(|_, _| Ok(1));
+
+        let mut scheduler = ChurnScheduler::new(
+            PathBuf::from("safenode"),
+            tmp_data_dir.to_path_buf(),
+            CHURN_INTERVAL,
+            1,
+            Box::new(controller),
+        );
+        scheduler
+            .node_args(vec!["--json-logs".to_string()])
+            .run(Duration::ZERO)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_should_stop_churning_when_a_verification_fails() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let mut controller = MockNodeController::new();
+        let mut next_pid = 0;
+        controller.expect_start().times(2).returning(move |_, _| {
+            next_pid += 1;
+            Ok(next_pid)
+        });
+        controller.expect_stop().times(1).returning(|_| Ok(()));
+
+        let verification_runs = Rc::new(Cell::new(0));
+        let runs = verification_runs.clone();
+        let mut scheduler = ChurnScheduler::new(
+            PathBuf::from("safenode"),
+            tmp_data_dir.to_path_buf(),
+            CHURN_INTERVAL,
+            1,
+            Box::new(controller),
+        );
+        let result = scheduler
+            .add_verification(move || {
+                runs.set(runs.get() + 1);
+                if runs.get() > 1 {
+                    return Err(eyre!("Stored data could not be retrieved"));
+                }
+                Ok(())
+            })
+            .run(CHURN_INTERVAL * 10);
+
+        assert!(result.is_err());
+        assert_eq!(verification_runs.get(), 2);
+        assert_eq!(scheduler.events().len(), 3);
+
+        Ok(())
+    }
+}
@@ -5,9 +5,16 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+mod churn;
+mod node_spec;
+
+pub use churn::{ChildProcessController, ChurnAction, ChurnEvent, ChurnScheduler, NodeController};
+pub use node_spec::NodeSpec;
+
 use color_eyre::{eyre::eyre, Result};
 #[cfg(test)]
 use mockall::automock;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -27,16 +34,27 @@ const TESTNET_DIR_NAME: &str = "local-test-network";
 /// launching processes.
 #[cfg_attr(test, automock)]
 pub trait NodeLauncher {
-    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<()>;
+    fn launch(
+        &self,
+        node_bin_path: &Path,
+        args: Vec<String>,
+        env: Vec<(String, String)>,
+    ) -> Result<()>;
 }
 
 #[derive(Default)]
 pub struct SafeNodeLauncher {}
 impl NodeLauncher for SafeNodeLauncher {
-    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<()> {
+    fn launch(
+        &self,
+        node_bin_path: &Path,
+        args: Vec<String>,
+        env: Vec<(String, String)>,
+    ) -> Result<()> {
         debug!("Running {:#?} with args: {:#?}", node_bin_path, args);
         Command::new(node_bin_path)
             .args(args)
+            .envs(env)
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
             .spawn()?;
@@ -51,6 +69,7 @@ pub struct TestnetBuilder {
     nodes_dir_path: Option<PathBuf>,
     clear_nodes_dir: bool,
     flamegraph_mode: bool,
+    node_specs: BTreeMap<usize, NodeSpec>,
 }
 
 impl TestnetBuilder {
@@ -96,6 +115,14 @@ impl TestnetBuilder {
         self
     }
 
+    /// Set the environment variables and resource limits of a node.
+    ///
+    /// Nodes are numbered from 1, which is the genesis node, in the order they are launched.
+    pub fn node_spec(&mut self, node: usize, spec: NodeSpec) -> &mut Self {
+        self.node_specs.insert(node, spec);
+        self
+    }
+
     /// Construct a `Testnet` instance using the options specified.
     ///
     /// The testnet instance and the path to the network contacts will be returned.
@@ -115,7 +142,7 @@ impl TestnetBuilder {
         }
 
         let node_launcher = SafeNodeLauncher::default();
-        let testnet = Testnet::new(
+        let mut testnet = Testnet::new(
             self.node_bin_path
                 .as_ref()
                 .unwrap_or(&PathBuf::from(SAFENODE_BIN_NAME))
@@ -126,6 +153,7 @@ impl TestnetBuilder {
             self.flamegraph_mode,
             Box::new(node_launcher),
         )?;
+        testnet.node_specs = self.node_specs.clone();
         let network_contacts_path = nodes_dir_path
             .join(GENESIS_NODE_DIR_NAME)
             .join("section_tree");
@@ -140,6 +168,8 @@ pub struct Testnet {
     pub flamegraph_mode: bool,
     pub node_count: usize,
     pub launcher: Box<dyn NodeLauncher>,
+    /// The settings of the nodes launched, by node number, starting from 1 for the genesis node.
+    pub node_specs: BTreeMap<usize, NodeSpec>,
 }
 
 impl Testnet {
@@ -180,6 +210,7 @@ impl Testnet {
             flamegraph_mode,
             node_count,
             launcher,
+            node_specs: BTreeMap::new(),
         })
     }
 
@@ -220,8 +251,7 @@ impl Testnet {
         let node_data_dir_path = self.nodes_dir_path.join("safenode-1");
         std::fs::create_dir_all(node_data_dir_path)?;
 
-        let launch_bin = self.get_launch_bin();
-        self.launcher.launch(&launch_bin, launch_args)?;
+        self.launch_node(1, launch_args)?;
         info!(
             "Delaying for {} seconds before launching other nodes",
             self.node_launch_interval / 1000
@@ -267,8 +297,7 @@ impl Testnet {
                 Some(network_contacts_path),
                 node_args.clone(),
             )?;
-            let launch_bin = self.get_launch_bin();
-            self.launcher.launch(&launch_bin, launch_args)?;
+            self.launch_node(i, launch_args)?;
 
             if i < end {
                 info!(
@@ -282,6 +311,13 @@ impl Testnet {
         Ok(())
     }
 
+    // Launches the node with the given number, applying its spec.
+    fn launch_node(&self, node: usize, launch_args: Vec<String>) -> Result<()> {
+        let spec = self.node_specs.get(&node).cloned().unwrap_or_default();
+        let (launch_bin, launch_args) = spec.wrap_command(self.get_launch_bin(), launch_args)?;
+        self.launcher.launch(&launch_bin, launch_args, spec.env)
+    }
+
     fn get_launch_args(
         &self,
         node_name: String,
@@ -311,6 +347,9 @@ impl Testnet {
             .to_str()
             .ok_or_else(|| eyre!("Unable to obtain node data directory path"))?
             .to_string();
+        // Each node keeps its own identity, as two nodes cannot run with the same one.
+        launch_args.push("--root-dir".to_string());
+        launch_args.push(node_data_dir_path.clone());
         launch_args.push("--log-dir".to_string());
         launch_args.push(node_data_dir_path);
         launch_args.extend(node_args);
@@ -339,7 +378,7 @@ mod test {
     #[test]
     fn new_should_create_a_testnet_with_zero_nodes_when_no_previous_network_exists() -> Result<()> {
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
@@ -371,7 +410,7 @@ mod test {
         }
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
             30000,
@@ -404,7 +443,7 @@ mod test {
         random_dir.create_dir_all()?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
@@ -453,8 +492,9 @@ mod test {
                     genesis_data_dir,
                     "--json-logs".to_string(),
                 ]),
+                eq(vec![]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
@@ -502,8 +542,9 @@ mod test {
                     genesis_data_dir,
                     "--json-logs".to_string(),
                 ]),
+                eq(vec![]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
@@ -527,7 +568,7 @@ mod test {
         nodes_dir.create_dir_all()?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -555,7 +596,7 @@ mod test {
         let nodes_dir = tmp_data_dir.child(TESTNET_DIR_NAME);
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -618,8 +659,9 @@ mod test {
                     genesis_data_dir_str,
                     "--json-logs".to_string(),
                 ]),
+                eq(vec![]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
@@ -651,7 +693,7 @@ mod test {
         }
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
 
         let testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
@@ -708,8 +750,9 @@ mod test {
                         node_data_dir,
                         "--json-logs".to_string(),
                     ]),
+                    eq(vec![]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _, _| Ok(()));
         }
 
         let mut testnet = Testnet::new(
@@ -741,7 +784,7 @@ mod test {
         network_contacts_file.write_str("section tree content")?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
         let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -774,7 +817,7 @@ mod test {
         network_contacts_file.write_str("section tree content")?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _, _| Ok(()));
         let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -840,8 +883,9 @@ mod test {
                         node_data_dir,
                         "--json-logs".to_string(),
                     ]),
+                    eq(vec![]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _, _| Ok(()));
         }
 
         let mut testnet = Testnet::new(
@@ -892,8 +936,9 @@ mod test {
                         node_data_dir,
                         "--json-logs".to_string(),
                     ]),
+                    eq(vec![]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _, _| Ok(()));
         }
 
         let mut testnet = Testnet::new(
@@ -30,17 +30,23 @@
 #[cfg(feature = "verify-nodes")]
 mod check_testnet;
 
-use sn_testnet::{Testnet, DEFAULT_NODE_LAUNCH_INTERVAL, SAFENODE_BIN_NAME};
+use sn_testnet::{
+    ChildProcessController, ChurnScheduler, NodeSpec, Testnet, DEFAULT_NODE_LAUNCH_INTERVAL,
+    SAFENODE_BIN_NAME,
+};
 
 use clap::Parser;
 use color_eyre::{eyre::eyre, Help, Result};
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
     process::{Command, Stdio},
+    time::Duration,
 };
 use tracing::{debug, info};
 
 const DEFAULT_NODE_COUNT: u32 = 25;
+const DEFAULT_CHURN_NODE_COUNT: usize = 5;
 
 #[derive(Debug, clap::StructOpt)]
 #[clap(name = "testnet", version)]
@@ -92,6 +98,40 @@ struct Cmd {
     #[clap(short = 'c', long, env = "NODE_COUNT")]
     node_count: Option<u32>,
 
+    /// Churn nodes once the testnet is running, replacing a node every this many ms.
+    ///
+    /// Extra nodes are launched alongside the testnet, and on every interval the longest running
+    /// of them is killed and a new one is launched. Requires --churn-duration.
+    #[clap(long)]
+    churn_interval: Option<u64>,
+
+    /// The number of seconds to keep churning nodes for.
+    #[clap(long)]
+    churn_duration: Option<u64>,
+
+    /// The minimum number of churn nodes to keep running. Defaults to 5.
+    #[clap(long)]
+    churn_node_count: Option<usize>,
+
+    /// Set an environment variable for a single node, e.g. `3:RUST_LOG=safenode=trace`.
+    ///
+    /// Nodes are numbered from 1, the genesis node, in the order they are launched. This can be
+    /// used several times.
+    #[clap(long, value_name = "NODE:KEY=VALUE")]
+    node_env: Vec<String>,
+
+    /// Run a single node at a nice level, e.g. `3:19` for a slow node.
+    ///
+    /// Windows is not supported.
+    #[clap(long, value_name = "NODE:LEVEL")]
+    node_nice: Vec<String>,
+
+    /// Cap the memory of a single node, in MiB, e.g. `3:128` for a memory-starved node.
+    ///
+    /// This runs the node in its own cgroup with `systemd-run`, so only Linux is supported.
+    #[clap(long, value_name = "NODE:MIB")]
+    node_memory: Vec<String>,
+
     /// Specify any additional arguments to pass to safenode on launch, e.g., --json-logs.
     ///
     /// Any arguments must be valid safenode arguments.
@@ -105,6 +145,7 @@ async fn main() -> Result<()> {
     init_tracing()?;
 
     let args = Cmd::from_args();
+    let node_specs = parse_node_specs(&args)?;
 
     if args.flame {
         #[cfg(not(target_os = "windows"))]
@@ -137,21 +178,38 @@ async fn main() -> Result<()> {
             node_count,
             args.network_contacts_path,
             args.node_args,
+            node_specs,
         )
         .await?;
         return Ok(());
     }
 
     run_network(
-        node_bin_path,
+        node_bin_path.clone(),
         args.node_launch_interval
             .unwrap_or(DEFAULT_NODE_LAUNCH_INTERVAL),
         args.node_count.unwrap_or(DEFAULT_NODE_COUNT),
-        args.node_args,
+        args.node_args.clone(),
         args.flame,
+        node_specs,
     )
     .await?;
 
+    if let Some(churn_interval) = args.churn_interval {
+        let churn_duration = args.churn_duration.ok_or_else(|| {
+            eyre!("A churn duration must be specified for churning nodes")
+                .suggestion("Please try again using the --churn-duration argument")
+        })?;
+        churn_network(
+            node_bin_path,
+            Duration::from_millis(churn_interval),
+            Duration::from_secs(churn_duration),
+            args.churn_node_count.unwrap_or(DEFAULT_CHURN_NODE_COUNT),
+            args.node_args,
+        )
+        .await?;
+    }
+
     Ok(())
 }
 
@@ -182,7 +240,8 @@ async fn check_flamegraph_prerequisites() -> Result<()> {
 }
 
 async fn build_node() -> Result<()> {
-    let mut args = vec!["build", "--release"];
+    // The nodes of a local testnet find each other with mDNS.
+    let mut args = vec!["build", "--release", "--features", "local-discovery"];
 
     // Keep features consistent to avoid recompiling.
     if cfg!(feature = "chaos") {
@@ -218,13 +277,18 @@ async fn run_network(
     node_count: u32,
     node_args: Vec<String>,
     flamegraph_mode: bool,
+    node_specs: BTreeMap<usize, NodeSpec>,
 ) -> Result<()> {
-    let (mut testnet, network_contacts_path) = Testnet::configure()
+    let mut builder = Testnet::configure();
+    let _ = builder
         .node_bin_path(node_bin_path)
         .node_launch_interval(node_launch_interval)
         .clear_nodes_dir()
-        .flamegraph_mode(flamegraph_mode)
-        .build()?;
+        .flamegraph_mode(flamegraph_mode);
+    for (node, spec) in node_specs {
+        let _ = builder.node_spec(node, spec);
+    }
+    let (mut testnet, network_contacts_path) = builder.build()?;
     testnet.launch_genesis(None, node_args.clone())?;
     testnet.launch_nodes(node_count as usize, &network_contacts_path, node_args)?;
 
@@ -246,16 +310,81 @@ async fn join_network(
     node_count: u32,
     network_contacts_path: Option<PathBuf>,
     node_args: Vec<String>,
+    node_specs: BTreeMap<usize, NodeSpec>,
 ) -> Result<()> {
-    let (mut testnet, default_network_contacts_path) = Testnet::configure()
+    let mut builder = Testnet::configure();
+    let _ = builder
         .node_bin_path(node_bin_path)
-        .node_launch_interval(node_launch_interval)
-        .build()?;
+        .node_launch_interval(node_launch_interval);
+    for (node, spec) in node_specs {
+        let _ = builder.node_spec(node, spec);
+    }
+    let (mut testnet, default_network_contacts_path) = builder.build()?;
     let network_contacts_path = network_contacts_path.unwrap_or(default_network_contacts_path);
     testnet.launch_nodes(node_count as usize, &network_contacts_path, node_args)?;
     Ok(())
 }
 
+async fn churn_network(
+    node_bin_path: PathBuf,
+    churn_interval: Duration,
+    churn_duration: Duration,
+    churn_node_count: usize,
+    node_args: Vec<String>,
+) -> Result<()> {
+    let (testnet, _) = Testnet::configure().node_bin_path(node_bin_path).build()?;
+    let mut scheduler = ChurnScheduler::new(
+        testnet.node_bin_path,
+        testnet.nodes_dir_path,
+        churn_interval,
+        churn_node_count,
+        Box::<ChildProcessController>::default(),
+    );
+    scheduler.node_args(node_args).run(churn_duration)?;
+    info!(
+        "Churn completed after {} node starts and stops",
+        scheduler.events().len()
+    );
+    Ok(())
+}
+
+/// Collects the `--node-env`, `--node-nice` and `--node-memory` settings by node.
+fn parse_node_specs(args: &Cmd) -> Result<BTreeMap<usize, NodeSpec>> {
+    let mut specs: BTreeMap<usize, NodeSpec> = BTreeMap::new();
+    for setting in &args.node_env {
+        let (node, var) = split_node_setting(setting)?;
+        let (key, value) = var
+            .split_once('=')
+            .ok_or_else(|| eyre!("Expected KEY=VALUE after the node number in {setting:?}"))?;
+        specs
+            .entry(node)
+            .or_default()
+            .env
+            .push((key.to_string(), value.to_string()));
+    }
+    for setting in &args.node_nice {
+        let (node, level) = split_node_setting(setting)?;
+        specs.entry(node).or_default().nice = Some(level.parse()?);
+    }
+    for setting in &args.node_memory {
+        let (node, memory_limit_mb) = split_node_setting(setting)?;
+        specs.entry(node).or_default().memory_limit_mb = Some(memory_limit_mb.parse()?);
+    }
+    Ok(specs)
+}
+
+/// Splits a `NODE:VALUE` setting into the node number and the value.
+fn split_node_setting(setting: &str) -> Result<(usize, &str)> {
+    let (node, value) = setting
+        .split_once(':')
+        .ok_or_else(|| eyre!("Expected NODE:VALUE, got {setting:?}"))?;
+    let node = node.parse()?;
+    if node == 0 {
+        return Err(eyre!("Nodes are numbered from 1, got {setting:?}"));
+    }
+    Ok((node, value))
+}
+
 fn init_tracing() -> Result<()> {
     tracing_subscriber::fmt().init();
 
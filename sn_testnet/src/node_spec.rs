@@ -0,0 +1,118 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+use color_eyre::{eyre::eyre, Result};
+use std::path::PathBuf;
+
+/// Settings for a single node of the testnet, to construct networks of unequal nodes, e.g. with a
+/// slow node or a memory-starved one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeSpec {
+    /// Environment variables to set for the node process, e.g. `RUST_LOG`.
+    pub env: Vec<(String, String)>,
+    /// The nice level to run the node process at.
+    ///
+    /// This uses `nice`, so it is not supported on Windows.
+    pub nice: Option<i32>,
+    /// The most memory the node process can use, in MiB.
+    ///
+    /// The process is run in its own cgroup with `systemd-run`, so this is only supported on Linux
+    /// hosts running systemd.
+    pub memory_limit_mb: Option<u64>,
+}
+
+impl NodeSpec {
+    /// Wraps the command launching a node in the commands that apply the resource limits.
+    ///
+    /// The command is returned as is if there are no limits.
+    pub(crate) fn wrap_command(
+        &self,
+        bin: PathBuf,
+        args: Vec<String>,
+    ) -> Result<(PathBuf, Vec<String>)> {
+        let (mut bin, mut args) = (bin, args);
+        let bin_arg = |bin: &PathBuf| {
+            bin.to_str()
+                .map(|bin| bin.to_string())
+                .ok_or_else(|| eyre!("Unable to obtain the path of {bin:?}"))
+        };
+
+        if let Some(nice) = self.nice {
+            if cfg!(target_os = "windows") {
+                return Err(eyre!("Nice levels are not supported on Windows"));
+            }
+            let mut wrapped = vec!["-n".to_string(), nice.to_string(), bin_arg(&bin)?];
+            wrapped.extend(args);
+            (bin, args) = (PathBuf::from("nice"), wrapped);
+        }
+
+        if let Some(memory_limit_mb) = self.memory_limit_mb {
+            if !cfg!(target_os = "linux") {
+                return Err(eyre!("Memory limits are only supported on Linux"));
+            }
+            let mut wrapped = vec![
+                "--user".to_string(),
+                "--scope".to_string(),
+                "--quiet".to_string(),
+                "-p".to_string(),
+                format!("MemoryMax={memory_limit_mb}M"),
+                bin_arg(&bin)?,
+            ];
+            wrapped.extend(args);
+            (bin, args) = (PathBuf::from("systemd-run"), wrapped);
+        }
+
+        Ok((bin, args))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_command_should_leave_the_command_as_is_without_limits() -> Result<()> {
+        let spec = NodeSpec {
+            env: vec![("RUST_LOG".to_string(), "safenode=trace".to_string())],
+            ..Default::default()
+        };
+        let (bin, args) =
+            spec.wrap_command(PathBuf::from("safenode"), vec!["--json-logs".to_string()])?;
+        assert_eq!(bin, PathBuf::from("safenode"));
+        assert_eq!(args, vec!["--json-logs".to_string()]);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn wrap_command_should_apply_the_nice_level_and_memory_limit() -> Result<()> {
+        let spec = NodeSpec {
+            nice: Some(10),
+            memory_limit_mb: Some(256),
+            ..Default::default()
+        };
+        let (bin, args) =
+            spec.wrap_command(PathBuf::from("safenode"), vec!["--json-logs".to_string()])?;
+        assert_eq!(bin, PathBuf::from("systemd-run"));
+        assert_eq!(
+            args,
+            vec![
+                "--user".to_string(),
+                "--scope".to_string(),
+                "--quiet".to_string(),
+                "-p".to_string(),
+                "MemoryMax=256M".to_string(),
+                "nice".to_string(),
+                "-n".to_string(),
+                "10".to_string(),
+                "safenode".to_string(),
+                "--json-logs".to_string(),
+            ]
+        );
+        Ok(())
+    }
+}